@@ -0,0 +1,104 @@
+use anyhow::Context;
+use aptos_crypto::ValidCryptoMaterialStringExt;
+use aptos_vm_genesis::GENESIS_KEYPAIR;
+use block_execution_bench::{BlockSet, GenerateParams};
+use clap::{Parser, Subcommand};
+use maptos_dof_execution::v1::Executor;
+use maptos_execution_util::config::Config;
+use maptos_opt_executor::executor::TxExecutionResult;
+use movement_signer_loader::identifiers::{local::Local, SignerIdentifier};
+use std::path::PathBuf;
+use tokio::sync::mpsc::unbounded_channel;
+
+#[derive(Parser, Debug)]
+#[clap(rename_all = "kebab-case", about = "Benchmarks the block execution on a fixed block set")]
+struct BlockExecutionBench {
+	#[clap(subcommand)]
+	command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+	/// Generate a block set and save it to a file.
+	Generate {
+		#[clap(long)]
+		out: PathBuf,
+		/// Number of accounts sending transfers.
+		#[clap(long, default_value_t = 1000)]
+		accounts: usize,
+		/// Number of transfer blocks.
+		#[clap(long, default_value_t = 20)]
+		blocks: usize,
+		#[clap(long, default_value_t = 1000)]
+		transactions_per_block: usize,
+		#[clap(long, default_value_t = 0)]
+		seed: u64,
+	},
+	/// Execute a block set on a fresh chain and print the throughput as JSON.
+	/// The execution settings are read from the maptos config environment variables
+	/// and can be overridden with the options.
+	Run {
+		#[clap(long)]
+		block_set: PathBuf,
+		/// Number of threads of the parallel execution, 0 for the number of CPUs.
+		#[clap(long)]
+		concurrency_level: Option<u16>,
+	},
+}
+
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+	use tracing_subscriber::EnvFilter;
+
+	tracing_subscriber::fmt()
+		.with_env_filter(
+			EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+		)
+		.init();
+
+	match BlockExecutionBench::parse().command {
+		Command::Generate { out, accounts, blocks, transactions_per_block, seed } => {
+			let chain_id = Config::default().chain.maptos_chain_id;
+			let params = GenerateParams { accounts, blocks, transactions_per_block, seed };
+			let block_set = BlockSet::generate(chain_id, params)?;
+			block_set.save(&out)?;
+			println!(
+				"Generated {} blocks, {} setup blocks, in {out:?}",
+				block_set.blocks.len(),
+				block_set.setup_blocks
+			);
+		}
+		Command::Run { block_set, concurrency_level } => {
+			let block_set = BlockSet::load(&block_set)
+				.with_context(|| format!("Failed to load the block set {block_set:?}"))?;
+
+			let tempdir = tempfile::tempdir()?;
+			let mut config = Config::default();
+			config.chain.maptos_chain_id = block_set.chain_id;
+			config.chain.maptos_db_path.replace(tempdir.path().to_path_buf());
+			let private_key_hex = GENESIS_KEYPAIR.0.to_encoded_string()?;
+			config.chain.maptos_private_key_signer_identifier = SignerIdentifier::Local(Local {
+				private_key_hex_bytes: private_key_hex.trim_start_matches("0x").to_string(),
+			});
+			if let Some(concurrency_level) = concurrency_level {
+				config.execution.concurrency_level = concurrency_level;
+			}
+			let execution_config = config.execution.clone();
+
+			let (result_sender, mut result_receiver) =
+				unbounded_channel::<Vec<TxExecutionResult>>();
+			let mut executor = Executor::try_from_config(config, result_sender).await?;
+			let report =
+				block_execution_bench::run(&mut executor, &mut result_receiver, &block_set)?;
+
+			println!(
+				"{}",
+				serde_json::to_string_pretty(&serde_json::json!({
+					"concurrency_level": execution_config.concurrency_level(),
+					"report": report,
+				}))?
+			);
+		}
+	}
+	Ok(())
+}
@@ -0,0 +1,201 @@
+//! Throughput benchmark of the block execution.
+//!
+//! A block set is a list of DA blocks generated once and saved to a file, so the same blocks
+//! can be executed with different execution settings on a fresh chain and the throughputs
+//! compared. The first blocks of a set create and fund the accounts, the next ones transfer
+//! coins between them. Only the transfer blocks are measured.
+
+use aptos_crypto::HashValue;
+use aptos_sdk::transaction_builder::TransactionFactory;
+use aptos_sdk::types::LocalAccount;
+use aptos_types::account_config::aptos_test_root_address;
+use aptos_types::chain_id::ChainId;
+use aptos_types::transaction::{
+	signature_verified_transaction::SignatureVerifiedTransaction, SignedTransaction, Transaction,
+	TransactionStatus,
+};
+use aptos_vm_genesis::GENESIS_KEYPAIR;
+use maptos_dof_execution::{DynOptFinExecutor, ExecutableBlock, ExecutableTransactions};
+use maptos_opt_executor::executor::TxExecutionResult;
+use movement_types::block::{Block, BlockMetadata, Id};
+use movement_types::transaction::Transaction as DaTransaction;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+/// Expiration of the generated transactions, relative to the generation time.
+const TRANSACTION_EXPIRATION_SECS: u64 = 60 * 60 * 24 * 365;
+/// Coins minted to each account.
+const ACCOUNT_FUNDING: u64 = 100_000_000_000;
+
+/// Parameters of a generated block set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerateParams {
+	pub accounts: usize,
+	pub blocks: usize,
+	pub transactions_per_block: usize,
+	pub seed: u64,
+}
+
+/// DA blocks to execute on a fresh chain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockSet {
+	pub params: GenerateParams,
+	pub chain_id: ChainId,
+	/// Number of blocks creating and funding the accounts at the start of the set.
+	pub setup_blocks: usize,
+	pub blocks: Vec<Block>,
+}
+
+impl BlockSet {
+	/// Generate the block set. The transactions are signed with the genesis root key,
+	/// which is the key of the chains created by the benchmark.
+	pub fn generate(chain_id: ChainId, params: GenerateParams) -> Result<Self, anyhow::Error> {
+		let mut rng = StdRng::seed_from_u64(params.seed);
+		let tx_factory = TransactionFactory::new(chain_id)
+			.with_transaction_expiration_time(TRANSACTION_EXPIRATION_SECS);
+		let root = LocalAccount::new(aptos_test_root_address(), GENESIS_KEYPAIR.0.clone(), 0);
+		let accounts: Vec<LocalAccount> =
+			(0..params.accounts.max(1)).map(|_| LocalAccount::generate(&mut rng)).collect();
+		let per_block = params.transactions_per_block.max(1);
+
+		let mut setup_transactions = Vec::with_capacity(accounts.len() * 2);
+		for account in &accounts {
+			setup_transactions.push(root.sign_with_transaction_builder(
+				tx_factory.create_user_account(account.public_key()),
+			));
+			setup_transactions.push(root.sign_with_transaction_builder(
+				tx_factory.mint(account.address(), ACCOUNT_FUNDING),
+			));
+		}
+
+		let mut blocks = Vec::new();
+		let mut parent = Id::test();
+		for chunk in setup_transactions.chunks(per_block) {
+			let block = da_block(parent, chunk)?;
+			parent = block.id();
+			blocks.push(block);
+		}
+		let setup_blocks = blocks.len();
+
+		for block_index in 0..params.blocks {
+			let transactions: Vec<SignedTransaction> = (0..per_block)
+				.map(|index| {
+					let sender = &accounts[(block_index * per_block + index) % accounts.len()];
+					let recipient = &accounts[rng.gen_range(0, accounts.len())];
+					sender
+						.sign_with_transaction_builder(tx_factory.transfer(recipient.address(), 1))
+				})
+				.collect();
+			let block = da_block(parent, &transactions)?;
+			parent = block.id();
+			blocks.push(block);
+		}
+
+		Ok(BlockSet { params, chain_id, setup_blocks, blocks })
+	}
+
+	pub fn save(&self, path: &Path) -> Result<(), anyhow::Error> {
+		std::fs::write(path, bcs::to_bytes(self)?)?;
+		Ok(())
+	}
+
+	pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+		Ok(bcs::from_bytes(&std::fs::read(path)?)?)
+	}
+}
+
+fn da_block(parent: Id, transactions: &[SignedTransaction]) -> Result<Block, anyhow::Error> {
+	let transactions = transactions
+		.iter()
+		.map(|transaction| {
+			Ok(DaTransaction::new(bcs::to_bytes(transaction)?, 0, transaction.sequence_number()))
+		})
+		.collect::<Result<BTreeSet<_>, anyhow::Error>>()?;
+	Ok(Block::new(BlockMetadata::BlockMetadata, parent, transactions))
+}
+
+/// Result of the execution of the transfer blocks of a set.
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+	pub blocks: usize,
+	pub transactions: usize,
+	/// Transactions discarded or not executed because of the block gas limit.
+	pub failed_transactions: usize,
+	pub elapsed_ms: u128,
+	pub transactions_per_second: f64,
+}
+
+/// Execute the block set, the executor must be on a fresh chain.
+pub fn run<E: DynOptFinExecutor>(
+	executor: &mut E,
+	results: &mut UnboundedReceiver<Vec<TxExecutionResult>>,
+	block_set: &BlockSet,
+) -> Result<Report, anyhow::Error> {
+	let mut elapsed = Duration::ZERO;
+	let mut transactions = 0;
+	let mut failed_transactions = 0;
+	let mut last_timestamp = 0;
+	for (index, block) in block_set.blocks.iter().enumerate() {
+		// Block timestamps must increase.
+		let timestamp = block.timestamp().max(last_timestamp + 1);
+		last_timestamp = timestamp;
+		let executable_block = executable_block(executor, block, timestamp)?;
+
+		let start = Instant::now();
+		executor.execute_block_opt(executable_block)?;
+		let block_elapsed = start.elapsed();
+
+		let block_results = results.try_recv().unwrap_or_default();
+		let failed = block_results
+			.iter()
+			.filter(|result| !matches!(result.status, TransactionStatus::Keep(_)))
+			.count();
+		if index < block_set.setup_blocks {
+			anyhow::ensure!(failed == 0, "{failed} transactions of the setup block {index} failed");
+			continue;
+		}
+		elapsed += block_elapsed;
+		transactions += block.transactions().len();
+		failed_transactions += failed;
+	}
+
+	let transactions_per_second = match elapsed.as_secs_f64() {
+		secs if secs > 0.0 => transactions as f64 / secs,
+		_ => 0.0,
+	};
+	Ok(Report {
+		blocks: block_set.blocks.len() - block_set.setup_blocks,
+		transactions,
+		failed_transactions,
+		elapsed_ms: elapsed.as_millis(),
+		transactions_per_second,
+	})
+}
+
+/// Build the executable block the way the full node does for a DA block.
+fn executable_block<E: DynOptFinExecutor>(
+	executor: &E,
+	block: &Block,
+	timestamp: u64,
+) -> Result<ExecutableBlock, anyhow::Error> {
+	let block_id = block.id();
+	let block_metadata = executor
+		.build_block_metadata(HashValue::sha3_256_of(block_id.as_bytes().as_slice()), timestamp)?;
+	let mut transactions =
+		vec![SignatureVerifiedTransaction::Valid(Transaction::BlockMetadata(block_metadata))];
+	for transaction in block.transactions() {
+		let signed_transaction: SignedTransaction = bcs::from_bytes(transaction.data())?;
+		transactions.push(SignatureVerifiedTransaction::Valid(Transaction::UserTransaction(
+			signed_transaction,
+		)));
+	}
+	Ok(ExecutableBlock::new(
+		HashValue::from_slice(block_id)?,
+		ExecutableTransactions::Unsharded(transactions),
+	))
+}
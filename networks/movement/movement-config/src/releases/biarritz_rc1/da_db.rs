@@ -9,6 +9,10 @@ pub struct Config {
 	pub start_sync_height: u64,
 	#[serde(default = "default_allow_sync_from_zero")]
 	pub allow_sync_from_zero: bool,
+	/// Roll back to the last height agreed with the main node and re-execute the DA blocks
+	/// when a state divergence is detected, instead of stopping the node.
+	#[serde(default = "default_auto_recover_state_divergence")]
+	pub auto_recover_state_divergence: bool,
 }
 
 impl Default for Config {
@@ -17,6 +21,7 @@ impl Default for Config {
 			da_db_path: default_da_db_path(),
 			start_sync_height: default_start_sync_height(),
 			allow_sync_from_zero: default_allow_sync_from_zero(),
+			auto_recover_state_divergence: default_auto_recover_state_divergence(),
 		}
 	}
 }
@@ -24,3 +29,9 @@ impl Default for Config {
 env_default!(default_da_db_path, "SUZUKA_DA_DB_PATH", String, "movement-da-db".to_string());
 env_default!(default_start_sync_height, "MOVEMENT_START_SYNC_HEIGHT", u64, 0);
 env_default!(default_allow_sync_from_zero, "MOVEMENT_ALLOW_SYNC_FROM_ZERO", bool, false);
+env_default!(
+	default_auto_recover_state_divergence,
+	"MOVEMENT_AUTO_RECOVER_STATE_DIVERGENCE",
+	bool,
+	false
+);
@@ -0,0 +1,28 @@
+use crate::common_args::MovementArgs;
+use crate::node::checkpoint::NodeCheckpoints;
+use clap::Parser;
+
+#[derive(Debug, Parser, Clone)]
+#[clap(rename_all = "kebab-case", about = "Snapshots the ledger at the block head under a name.")]
+pub struct Create {
+	#[clap(flatten)]
+	pub movement_args: MovementArgs,
+	/// The name of the checkpoint.
+	pub name: String,
+}
+
+impl Create {
+	pub async fn execute(&self) -> Result<(), anyhow::Error> {
+		let config = self.movement_args.config().await?;
+		let checkpoints = NodeCheckpoints::try_from_config(&config)?;
+		let name = self.name.clone();
+		let checkpoint = tokio::task::spawn_blocking(move || checkpoints.create(&name)).await??;
+
+		// Use println as this is standard (non-logging output)
+		println!(
+			"Checkpoint {} created at block height {} (ledger version {})",
+			checkpoint.name, checkpoint.block_height, checkpoint.ledger_version
+		);
+		Ok(())
+	}
+}
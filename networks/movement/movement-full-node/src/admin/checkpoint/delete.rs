@@ -0,0 +1,23 @@
+use crate::common_args::MovementArgs;
+use crate::node::checkpoint::NodeCheckpoints;
+use clap::Parser;
+
+#[derive(Debug, Parser, Clone)]
+#[clap(rename_all = "kebab-case", about = "Deletes a checkpoint of the ledger.")]
+pub struct Delete {
+	#[clap(flatten)]
+	pub movement_args: MovementArgs,
+	/// The name of the checkpoint.
+	pub name: String,
+}
+
+impl Delete {
+	pub async fn execute(&self) -> Result<(), anyhow::Error> {
+		let config = self.movement_args.config().await?;
+		NodeCheckpoints::try_from_config(&config)?.delete(&self.name)?;
+
+		// Use println as this is standard (non-logging output)
+		println!("Checkpoint {} deleted", self.name);
+		Ok(())
+	}
+}
@@ -0,0 +1,24 @@
+use crate::common_args::MovementArgs;
+use crate::node::checkpoint::NodeCheckpoints;
+use clap::Parser;
+
+#[derive(Debug, Parser, Clone)]
+#[clap(rename_all = "kebab-case", about = "Lists the checkpoints of the ledger.")]
+pub struct List {
+	#[clap(flatten)]
+	pub movement_args: MovementArgs,
+}
+
+impl List {
+	pub async fn execute(&self) -> Result<(), anyhow::Error> {
+		let config = self.movement_args.config().await?;
+		let checkpoints = NodeCheckpoints::try_from_config(&config)?;
+
+		// Use println as this is standard (non-logging output)
+		if let Some(pending) = checkpoints.pending_restore()? {
+			println!("The restore of checkpoint {} is not finished", pending.name);
+		}
+		println!("{}", serde_json::to_string_pretty(&checkpoints.list()?)?);
+		Ok(())
+	}
+}
@@ -0,0 +1,29 @@
+pub mod create;
+pub mod delete;
+pub mod list;
+pub mod restore;
+
+use clap::Subcommand;
+
+#[derive(Subcommand, Debug)]
+#[clap(
+	rename_all = "kebab-case",
+	about = "Commands for the named checkpoints of the ledger. The node must be stopped."
+)]
+pub enum Checkpoint {
+	Create(create::Create),
+	List(list::List),
+	Restore(restore::Restore),
+	Delete(delete::Delete),
+}
+
+impl Checkpoint {
+	pub async fn execute(&self) -> Result<(), anyhow::Error> {
+		match self {
+			Checkpoint::Create(create) => create.execute().await,
+			Checkpoint::List(list) => list.execute().await,
+			Checkpoint::Restore(restore) => restore.execute().await,
+			Checkpoint::Delete(delete) => delete.execute().await,
+		}
+	}
+}
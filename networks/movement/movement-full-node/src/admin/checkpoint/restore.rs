@@ -0,0 +1,32 @@
+use crate::common_args::MovementArgs;
+use crate::node::checkpoint::NodeCheckpoints;
+use clap::Parser;
+
+#[derive(Debug, Parser, Clone)]
+#[clap(
+	rename_all = "kebab-case",
+	about = "Restores the ledger and the DA synced height at a checkpoint. An interrupted restore can be run again."
+)]
+pub struct Restore {
+	#[clap(flatten)]
+	pub movement_args: MovementArgs,
+	/// The name of the checkpoint.
+	pub name: String,
+}
+
+impl Restore {
+	pub async fn execute(&self) -> Result<(), anyhow::Error> {
+		let config = self.movement_args.config().await?;
+		let checkpoints = NodeCheckpoints::try_from_config(&config)?;
+		let name = self.name.clone();
+		let (checkpoint, da_height) =
+			tokio::task::spawn_blocking(move || checkpoints.restore(&name)).await??;
+
+		// Use println as this is standard (non-logging output)
+		println!(
+			"Checkpoint {} restored at block height {}, the node syncs from DA height {}",
+			checkpoint.name, checkpoint.block_height, da_height
+		);
+		Ok(())
+	}
+}
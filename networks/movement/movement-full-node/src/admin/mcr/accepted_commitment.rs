@@ -0,0 +1,37 @@
+use super::output::{print_json, print_table, OutputFormat};
+use crate::common_args::MovementArgs;
+use clap::Parser;
+use mcr_settlement_client::McrSettlementClientOperations;
+
+#[derive(Debug, Parser, Clone)]
+#[clap(rename_all = "kebab-case", about = "Shows the commitment accepted by MCR at a height.")]
+pub struct AcceptedCommitment {
+	#[clap(flatten)]
+	pub movement_args: MovementArgs,
+	pub height: u64,
+	#[clap(long, value_enum, default_value_t = OutputFormat::Table)]
+	pub output: OutputFormat,
+}
+
+impl AcceptedCommitment {
+	pub async fn execute(&self) -> Result<(), anyhow::Error> {
+		let client = super::settlement_client(&self.movement_args).await?;
+		let accepted = client.get_commitment_at_height(self.height).await?;
+		match (self.output, accepted) {
+			(OutputFormat::Json, accepted) => print_json(&accepted)?,
+			(OutputFormat::Table, Some(accepted)) => print_table(
+				&["HEIGHT", "BLOCK ID", "COMMITMENT"],
+				&[vec![
+					accepted.height().to_string(),
+					accepted.block_id().to_string(),
+					accepted.commitment().to_string(),
+				]],
+			),
+			(OutputFormat::Table, None) => {
+				// Use println as this is standard (non-logging output)
+				println!("No commitment accepted at height {}", self.height);
+			}
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,84 @@
+use super::output::{print_json, print_table, OutputFormat};
+use crate::common_args::MovementArgs;
+use clap::Parser;
+use mcr_settlement_client::McrSettlementClientOperations;
+use movement_types::block::BlockCommitment;
+use serde::Serialize;
+
+#[derive(Debug, Parser, Clone)]
+#[clap(
+	rename_all = "kebab-case",
+	about = "Compares the commitment posted by this node at a height with the accepted one."
+)]
+pub struct CommitmentStatus {
+	#[clap(flatten)]
+	pub movement_args: MovementArgs,
+	pub height: u64,
+	#[clap(long, value_enum, default_value_t = OutputFormat::Table)]
+	pub output: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+	/// The commitment of the node is the accepted one.
+	Accepted,
+	/// Another commitment is accepted.
+	Rejected,
+	/// No commitment is accepted at the height yet.
+	AwaitingQuorum,
+	/// The node has not posted a commitment at the height.
+	NotPosted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommitmentStatusReport {
+	pub height: u64,
+	pub status: Status,
+	pub posted: Option<BlockCommitment>,
+	pub accepted: Option<BlockCommitment>,
+}
+
+pub async fn commitment_status<C: McrSettlementClientOperations>(
+	client: &C,
+	height: u64,
+) -> Result<CommitmentStatusReport, anyhow::Error> {
+	let posted = client.get_posted_commitment_at_height(height).await?;
+	let accepted = client.get_commitment_at_height(height).await?;
+	let status = match (&posted, &accepted) {
+		(None, _) => Status::NotPosted,
+		(Some(_), None) => Status::AwaitingQuorum,
+		(Some(posted), Some(accepted)) if posted.commitment() == accepted.commitment() => {
+			Status::Accepted
+		}
+		(Some(_), Some(_)) => Status::Rejected,
+	};
+	Ok(CommitmentStatusReport { height, status, posted, accepted })
+}
+
+impl CommitmentStatus {
+	pub async fn execute(&self) -> Result<(), anyhow::Error> {
+		let client = super::settlement_client(&self.movement_args).await?;
+		let report = commitment_status(&client, self.height).await?;
+		match self.output {
+			OutputFormat::Json => print_json(&report)?,
+			OutputFormat::Table => {
+				let row = |name: &str, commitment: &Option<BlockCommitment>| match commitment {
+					Some(commitment) => vec![
+						name.to_string(),
+						commitment.block_id().to_string(),
+						commitment.commitment().to_string(),
+					],
+					None => vec![name.to_string(), "-".to_string(), "-".to_string()],
+				};
+				// Use println as this is standard (non-logging output)
+				println!("Height {}: {:?}", report.height, report.status);
+				print_table(
+					&["", "BLOCK ID", "COMMITMENT"],
+					&[row("posted", &report.posted), row("accepted", &report.accepted)],
+				);
+			}
+		}
+		Ok(())
+	}
+}
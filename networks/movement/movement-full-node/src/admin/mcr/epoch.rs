@@ -0,0 +1,53 @@
+use super::output::{print_json, print_table, OutputFormat};
+use crate::common_args::MovementArgs;
+use clap::Parser;
+use mcr_settlement_client::McrSettlementClientOperations;
+use serde::Serialize;
+
+#[derive(Debug, Parser, Clone)]
+#[clap(
+	rename_all = "kebab-case",
+	about = "Shows the current epoch of MCR and the range of heights that can be committed."
+)]
+pub struct Epoch {
+	#[clap(flatten)]
+	pub movement_args: MovementArgs,
+	#[clap(long, value_enum, default_value_t = OutputFormat::Table)]
+	pub output: OutputFormat,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EpochReport {
+	pub current_epoch: u64,
+	pub last_accepted_block_height: u64,
+	pub max_tolerable_block_height: u64,
+}
+
+pub async fn epoch_report<C: McrSettlementClientOperations>(
+	client: &C,
+) -> Result<EpochReport, anyhow::Error> {
+	Ok(EpochReport {
+		current_epoch: client.get_current_epoch().await?,
+		last_accepted_block_height: client.get_last_accepted_block_height().await?,
+		max_tolerable_block_height: client.get_max_tolerable_block_height().await?,
+	})
+}
+
+impl Epoch {
+	pub async fn execute(&self) -> Result<(), anyhow::Error> {
+		let client = super::settlement_client(&self.movement_args).await?;
+		let report = epoch_report(&client).await?;
+		match self.output {
+			OutputFormat::Json => print_json(&report)?,
+			OutputFormat::Table => print_table(
+				&["EPOCH", "LAST ACCEPTED HEIGHT", "MAX TOLERABLE HEIGHT"],
+				&[vec![
+					report.current_epoch.to_string(),
+					report.last_accepted_block_height.to_string(),
+					report.max_tolerable_block_height.to_string(),
+				]],
+			),
+		}
+		Ok(())
+	}
+}
@@ -1,17 +1,46 @@
+pub mod accepted_commitment;
+pub mod commitment_status;
+pub mod epoch;
 pub mod force_commitment;
+pub mod output;
+pub mod pending;
+pub mod stakes;
 
+use crate::common_args::MovementArgs;
+use anyhow::Context;
 use clap::Subcommand;
+use mcr_settlement_client::{McrSettlementClient, McrSettlementClientOperations};
 
 #[derive(Subcommand, Debug)]
 #[clap(rename_all = "kebab-case", about = "Commands for administrating MCR from the node.")]
 pub enum Mcr {
 	ForceCommitment(force_commitment::ForceCommitment),
+	Epoch(epoch::Epoch),
+	Stakes(stakes::Stakes),
+	AcceptedCommitment(accepted_commitment::AcceptedCommitment),
+	CommitmentStatus(commitment_status::CommitmentStatus),
+	Pending(pending::Pending),
 }
 
 impl Mcr {
 	pub async fn execute(&self) -> Result<(), anyhow::Error> {
 		match self {
 			Mcr::ForceCommitment(force_commitment) => force_commitment.execute().await,
+			Mcr::Epoch(epoch) => epoch.execute().await,
+			Mcr::Stakes(stakes) => stakes.execute().await,
+			Mcr::AcceptedCommitment(accepted_commitment) => accepted_commitment.execute().await,
+			Mcr::CommitmentStatus(commitment_status) => commitment_status.execute().await,
+			Mcr::Pending(pending) => pending.execute().await,
 		}
 	}
 }
+
+/// Build the settlement client of the node, to read the state of the MCR contract.
+pub(crate) async fn settlement_client(
+	movement_args: &MovementArgs,
+) -> Result<impl McrSettlementClientOperations, anyhow::Error> {
+	let config = movement_args.config().await?;
+	McrSettlementClient::build_with_config(&config.mcr)
+		.await
+		.context("Failed to build MCR settlement client with config")
+}
@@ -0,0 +1,38 @@
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// Output format of the MCR inspection commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+	Table,
+	Json,
+}
+
+pub fn print_json<T: Serialize>(value: &T) -> Result<(), anyhow::Error> {
+	// Use println as this is standard (non-logging output)
+	println!("{}", serde_json::to_string_pretty(value)?);
+	Ok(())
+}
+
+/// Print the rows in columns aligned on their widest cell.
+pub fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+	let mut widths: Vec<usize> = headers.iter().map(|header| header.len()).collect();
+	for row in rows {
+		for (width, cell) in widths.iter_mut().zip(row) {
+			*width = (*width).max(cell.len());
+		}
+	}
+	let format_row = |cells: Vec<&str>| {
+		let cells: Vec<String> = cells
+			.iter()
+			.zip(&widths)
+			.map(|(cell, width)| format!("{cell:<width$}"))
+			.collect();
+		cells.join("  ").trim_end().to_string()
+	};
+	// Use println as this is standard (non-logging output)
+	println!("{}", format_row(headers.to_vec()));
+	for row in rows {
+		println!("{}", format_row(row.iter().map(String::as_str).collect()));
+	}
+}
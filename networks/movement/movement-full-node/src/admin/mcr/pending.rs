@@ -0,0 +1,147 @@
+use super::output::{print_json, print_table, OutputFormat};
+use super::stakes::supermajority_stake;
+use crate::common_args::MovementArgs;
+use clap::Parser;
+use mcr_settlement_client::{AttesterCommitment, McrSettlementClientOperations};
+use serde::Serialize;
+
+#[derive(Debug, Parser, Clone)]
+#[clap(
+	rename_all = "kebab-case",
+	about = "Shows the heights committed after the last accepted one, awaiting a quorum."
+)]
+pub struct Pending {
+	#[clap(flatten)]
+	pub movement_args: MovementArgs,
+	#[clap(long, value_enum, default_value_t = OutputFormat::Table)]
+	pub output: OutputFormat,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PendingHeight {
+	pub height: u64,
+	pub commitments: Vec<AttesterCommitment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PendingReport {
+	pub last_accepted_block_height: u64,
+	pub max_tolerable_block_height: u64,
+	/// The stake a commitment must exceed to be accepted, with the stakes of the current epoch.
+	pub supermajority_stake: u128,
+	pub heights: Vec<PendingHeight>,
+}
+
+/// The commitments of the heights after the last accepted one, up to the max tolerable height.
+pub async fn pending_report<C: McrSettlementClientOperations>(
+	client: &C,
+) -> Result<PendingReport, anyhow::Error> {
+	let last_accepted_block_height = client.get_last_accepted_block_height().await?;
+	let max_tolerable_block_height = client.get_max_tolerable_block_height().await?;
+	let total_stake = client.get_attester_stakes().await?.iter().map(|a| a.stake).sum();
+	let mut heights = Vec::new();
+	for height in last_accepted_block_height + 1..=max_tolerable_block_height {
+		let commitments = client.get_attester_commitments_at_height(height).await?;
+		if !commitments.is_empty() {
+			heights.push(PendingHeight { height, commitments });
+		}
+	}
+	Ok(PendingReport {
+		last_accepted_block_height,
+		max_tolerable_block_height,
+		supermajority_stake: supermajority_stake(total_stake),
+		heights,
+	})
+}
+
+impl Pending {
+	pub async fn execute(&self) -> Result<(), anyhow::Error> {
+		let client = super::settlement_client(&self.movement_args).await?;
+		let report = pending_report(&client).await?;
+		match self.output {
+			OutputFormat::Json => print_json(&report)?,
+			OutputFormat::Table => {
+				let rows: Vec<Vec<String>> = report
+					.heights
+					.iter()
+					.flat_map(|pending| {
+						pending.commitments.iter().map(|commitment| {
+							vec![
+								pending.height.to_string(),
+								commitment.attester.clone(),
+								commitment.commitment.commitment().to_string(),
+								commitment.commitment_stake.to_string(),
+							]
+						})
+					})
+					.collect();
+				// Use println as this is standard (non-logging output)
+				println!(
+					"Last accepted height {}, max tolerable height {}, supermajority above {}",
+					report.last_accepted_block_height,
+					report.max_tolerable_block_height,
+					report.supermajority_stake
+				);
+				print_table(&["HEIGHT", "ATTESTER", "COMMITMENT", "COMMITMENT STAKE"], &rows);
+			}
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::super::commitment_status::{commitment_status, Status};
+	use super::*;
+	use alloy_primitives::Address;
+	use mcr_settlement_client::simulator::{McrSimulator, McrSimulatorParams};
+	use movement_types::block::{BlockCommitment, Commitment, Id};
+
+	#[tokio::test]
+	async fn test_pending_heights_and_commitment_status() -> Result<(), anyhow::Error> {
+		let mcr = McrSimulator::new(McrSimulatorParams::default());
+		let clients: Vec<_> = [40, 30, 30]
+			.into_iter()
+			.enumerate()
+			.map(|(i, stake)| {
+				let attester = Address::with_last_byte(i as u8 + 1);
+				mcr.stake(attester, stake);
+				mcr.grant_trusted_attester(attester);
+				mcr.client(attester)
+			})
+			.collect();
+		mcr.accept_genesis_ceremony();
+		let commitment = |height, byte| {
+			BlockCommitment::new(height, Id::new([byte; 32]), Commitment::new([byte; 32]))
+		};
+
+		// Height 1 is accepted, the attesters disagree on height 2.
+		clients[0]
+			.post_block_commitment_batch(vec![commitment(1, 1), commitment(2, 2)])
+			.await?;
+		clients[1]
+			.post_block_commitment_batch(vec![commitment(1, 1), commitment(2, 3)])
+			.await?;
+
+		let report = pending_report(&clients[0]).await?;
+		assert_eq!(report.last_accepted_block_height, 1);
+		assert_eq!(report.max_tolerable_block_height, 11);
+		assert_eq!(report.supermajority_stake, 66);
+		assert_eq!(report.heights.len(), 1);
+		assert_eq!(report.heights[0].height, 2);
+		let stakes: Vec<u128> =
+			report.heights[0].commitments.iter().map(|c| c.commitment_stake).collect();
+		assert_eq!(stakes, vec![40, 30]);
+
+		assert_eq!(commitment_status(&clients[0], 1).await?.status, Status::Accepted);
+		assert_eq!(commitment_status(&clients[0], 2).await?.status, Status::AwaitingQuorum);
+		assert_eq!(commitment_status(&clients[2], 2).await?.status, Status::NotPosted);
+
+		// The last attester settles height 2 with the first one.
+		clients[2].post_block_commitment(commitment(2, 2)).await?;
+		assert_eq!(commitment_status(&clients[0], 2).await?.status, Status::Accepted);
+		assert_eq!(commitment_status(&clients[1], 2).await?.status, Status::Rejected);
+		assert!(pending_report(&clients[0]).await?.heights.is_empty());
+		Ok(())
+	}
+}
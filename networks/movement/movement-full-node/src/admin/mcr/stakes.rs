@@ -0,0 +1,76 @@
+use super::output::{print_json, print_table, OutputFormat};
+use crate::common_args::MovementArgs;
+use clap::Parser;
+use mcr_settlement_client::{AttesterStake, McrSettlementClientOperations};
+use serde::Serialize;
+
+#[derive(Debug, Parser, Clone)]
+#[clap(rename_all = "kebab-case", about = "Shows the stake of each attester in the current epoch.")]
+pub struct Stakes {
+	#[clap(flatten)]
+	pub movement_args: MovementArgs,
+	#[clap(long, value_enum, default_value_t = OutputFormat::Table)]
+	pub output: OutputFormat,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StakesReport {
+	pub epoch: u64,
+	pub total_stake: u128,
+	/// A commitment is accepted once its stake is above this one.
+	pub supermajority_stake: u128,
+	pub attesters: Vec<AttesterStake>,
+}
+
+pub async fn stakes_report<C: McrSettlementClientOperations>(
+	client: &C,
+) -> Result<StakesReport, anyhow::Error> {
+	let attesters = client.get_attester_stakes().await?;
+	let total_stake = attesters.iter().map(|attester| attester.stake).sum();
+	Ok(StakesReport {
+		epoch: client.get_current_epoch().await?,
+		total_stake,
+		supermajority_stake: supermajority_stake(total_stake),
+		attesters,
+	})
+}
+
+/// The stake a commitment must exceed to be accepted, as computed by the contract.
+pub fn supermajority_stake(total_stake: u128) -> u128 {
+	(2 * total_stake) / 3
+}
+
+impl Stakes {
+	pub async fn execute(&self) -> Result<(), anyhow::Error> {
+		let client = super::settlement_client(&self.movement_args).await?;
+		let report = stakes_report(&client).await?;
+		match self.output {
+			OutputFormat::Json => print_json(&report)?,
+			OutputFormat::Table => {
+				let rows: Vec<Vec<String>> = report
+					.attesters
+					.iter()
+					.map(|attester| {
+						let share = if report.total_stake == 0 {
+							0.0
+						} else {
+							attester.stake as f64 * 100.0 / report.total_stake as f64
+						};
+						vec![
+							attester.attester.clone(),
+							attester.stake.to_string(),
+							format!("{share:.2}%"),
+						]
+					})
+					.collect();
+				// Use println as this is standard (non-logging output)
+				println!(
+					"Epoch {}, total stake {}, supermajority above {}",
+					report.epoch, report.total_stake, report.supermajority_stake
+				);
+				print_table(&["ATTESTER", "STAKE", "SHARE"], &rows);
+			}
+		}
+		Ok(())
+	}
+}
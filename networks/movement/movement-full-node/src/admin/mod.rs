@@ -1,4 +1,5 @@
 pub mod bring_up;
+pub mod checkpoint;
 pub mod config;
 pub mod framework;
 pub mod governed_gas_pool;
@@ -6,6 +7,7 @@ pub mod l1_migration;
 pub mod mcr;
 pub mod ops;
 pub mod rotate_key;
+pub mod state_audit;
 pub mod testkey;
 
 use clap::Subcommand;
@@ -31,6 +33,10 @@ pub enum Admin {
 	TestKey(testkey::TestKey),
 	#[clap(subcommand)]
 	L1Migration(l1_migration::L1Migration),
+	#[clap(subcommand)]
+	StateAudit(state_audit::StateAudit),
+	#[clap(subcommand)]
+	Checkpoint(checkpoint::Checkpoint),
 }
 
 impl Admin {
@@ -45,6 +51,8 @@ impl Admin {
 			Admin::Config(config) => config.execute().await,
 			Admin::TestKey(key) => key.execute().await,
 			Admin::L1Migration(l1_migration) => l1_migration.execute().await,
+			Admin::StateAudit(state_audit) => state_audit.execute().await,
+			Admin::Checkpoint(checkpoint) => checkpoint.execute().await,
 		}
 	}
 }
@@ -0,0 +1,36 @@
+use crate::common_args::MovementArgs;
+use crate::node::da_db::DaDB;
+use anyhow::Context;
+use clap::Parser;
+use tracing::info;
+
+#[derive(Debug, Parser, Clone)]
+#[clap(
+	rename_all = "kebab-case",
+	about = "Reports the first height where the local state and the main node state diverge, with both states."
+)]
+pub struct FirstDivergence {
+	#[clap(flatten)]
+	pub movement_args: MovementArgs,
+	/// The height to start the search from.
+	#[clap(long, default_value_t = 0)]
+	pub from_height: u64,
+}
+
+impl FirstDivergence {
+	pub async fn execute(&self) -> Result<(), anyhow::Error> {
+		let config = self.movement_args.config().await?;
+		info!("Reading node states from {:?}", config.da_db.da_db_path);
+
+		// The DB is opened read only, so the audit can run while the node is running.
+		let da_db =
+			DaDB::open_read_only(&config.da_db.da_db_path).context("Failed to open the DA DB")?;
+		match da_db.find_first_divergence(self.from_height)? {
+			// Use println as this is standard (non-logging output)
+			Some(divergence) => println!("{}", serde_json::to_string_pretty(&divergence)?),
+			None => println!("No state divergence found from height {}", self.from_height),
+		}
+
+		Ok(())
+	}
+}
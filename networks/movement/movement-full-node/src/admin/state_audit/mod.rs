@@ -0,0 +1,17 @@
+pub mod first_divergence;
+
+use clap::Subcommand;
+
+#[derive(Subcommand, Debug)]
+#[clap(rename_all = "kebab-case", about = "Commands for auditing the node state verification.")]
+pub enum StateAudit {
+	FirstDivergence(first_divergence::FirstDivergence),
+}
+
+impl StateAudit {
+	pub async fn execute(&self) -> Result<(), anyhow::Error> {
+		match self {
+			StateAudit::FirstDivergence(first_divergence) => first_divergence.execute().await,
+		}
+	}
+}
@@ -0,0 +1,213 @@
+use crate::node::da_db::DaDB;
+use crate::node::tasks::state_verifier::StateOrigin;
+use maptos_opt_executor::checkpoint::{CheckpointStore, LedgerCheckpoint};
+use movement_config::Config;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+const RESTORE_JOURNAL: &str = "restore-in-progress.json";
+
+/// A restore started and not finished yet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingRestore {
+	pub name: String,
+	pub block_height: u64,
+}
+
+/// Named checkpoints of the full node.
+///
+/// A checkpoint is a snapshot of the AptosDB. Restoring it also rolls the DA DB back to the
+/// checkpoint height, so the blocks after it are streamed from the DA and executed again.
+/// The restore is recorded in a journal until both DBs are restored, and the node doesn't start
+/// while a restore is pending. The node must be stopped to create or restore a checkpoint.
+#[derive(Debug, Clone)]
+pub struct NodeCheckpoints {
+	ledger: CheckpointStore,
+	da_db_path: PathBuf,
+}
+
+impl NodeCheckpoints {
+	pub fn try_from_config(config: &Config) -> Result<Self, anyhow::Error> {
+		Ok(Self {
+			ledger: CheckpointStore::try_from_config(&config.execution_config.maptos_config)?,
+			da_db_path: PathBuf::from(&config.da_db.da_db_path),
+		})
+	}
+
+	fn journal_path(&self) -> PathBuf {
+		self.ledger.root().join(RESTORE_JOURNAL)
+	}
+
+	pub fn create(&self, name: &str) -> Result<LedgerCheckpoint, anyhow::Error> {
+		if let Some(pending) = self.pending_restore()? {
+			anyhow::bail!("The restore of checkpoint {} is not finished", pending.name);
+		}
+		let checkpoint = self.ledger.create(name)?;
+		let da_db = DaDB::open(&self.da_db_path)?;
+		if da_db.get_executed_block(checkpoint.block_height)?.is_none() {
+			warn!(
+				"No DA block recorded for block height {}, the checkpoint {name} can't be restored",
+				checkpoint.block_height
+			);
+		}
+		Ok(checkpoint)
+	}
+
+	pub fn list(&self) -> Result<Vec<LedgerCheckpoint>, anyhow::Error> {
+		self.ledger.list()
+	}
+
+	pub fn delete(&self, name: &str) -> Result<(), anyhow::Error> {
+		if self.pending_restore()?.is_some_and(|pending| pending.name == name) {
+			anyhow::bail!("The restore of checkpoint {name} is not finished");
+		}
+		self.ledger.delete(name)
+	}
+
+	/// Restore the AptosDB and the DA DB at the checkpoint height.
+	/// Returns the checkpoint and the DA height the node streams the blocks from.
+	pub fn restore(&self, name: &str) -> Result<(LedgerCheckpoint, u64), anyhow::Error> {
+		if let Some(pending) = self.pending_restore()? {
+			if pending.name != name {
+				anyhow::bail!(
+					"The restore of checkpoint {} is not finished, restore it first",
+					pending.name
+				);
+			}
+			info!("Resume the restore of checkpoint {name}");
+		}
+		let checkpoint = self.ledger.get(name)?;
+		let da_db = DaDB::open(&self.da_db_path)?;
+		if da_db.get_executed_block(checkpoint.block_height)?.is_none() {
+			anyhow::bail!(
+				"No DA block recorded for block height {}, the DA DB can't be rolled back",
+				checkpoint.block_height
+			);
+		}
+
+		let pending =
+			PendingRestore { name: name.to_string(), block_height: checkpoint.block_height };
+		fs::write(self.journal_path(), serde_json::to_vec_pretty(&pending)?)?;
+
+		self.ledger.restore(name)?;
+		let da_height = da_db.rollback_executed_blocks_to(checkpoint.block_height)?;
+		da_db.remove_node_states_above(StateOrigin::Local, checkpoint.block_height)?;
+
+		fs::remove_file(self.journal_path())?;
+		Ok((checkpoint, da_height))
+	}
+
+	pub fn pending_restore(&self) -> Result<Option<PendingRestore>, anyhow::Error> {
+		match fs::read(self.journal_path()) {
+			Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+			Err(e) => Err(e.into()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::node::tasks::state_verifier::NodeState;
+	use maptos_dof_execution::{
+		v1::Executor, DynOptFinExecutor, ExecutableBlock, ExecutableTransactions, HashValue,
+		SignatureVerifiedTransaction, Transaction,
+	};
+	use maptos_opt_executor::executor::TxExecutionResult;
+	use tokio::sync::mpsc::unbounded_channel;
+
+	const DA_HEIGHT_OFFSET: u64 = 100;
+
+	/// Execute `count` empty blocks and record them in the DA DB as the node does.
+	async fn execute_blocks(
+		config: &Config,
+		count: usize,
+	) -> Result<Vec<HashValue>, anyhow::Error> {
+		let (mempool_tx_exec_result_sender, _mempool_commit_tx_receiver) =
+			unbounded_channel::<Vec<TxExecutionResult>>();
+		let mut executor = Executor::try_from_config(
+			config.execution_config.maptos_config.clone(),
+			mempool_tx_exec_result_sender,
+		)
+		.await?;
+		let da_db = DaDB::open(&config.da_db.da_db_path)?;
+		let mut block_ids = vec![];
+		for _ in 0..count {
+			let block_id = HashValue::random();
+			let block_metadata = executor
+				.build_block_metadata(block_id, chrono::Utc::now().timestamp_micros() as u64)?;
+			let transactions =
+				ExecutableTransactions::Unsharded(vec![SignatureVerifiedTransaction::Valid(
+					Transaction::BlockMetadata(block_metadata),
+				)]);
+			let (commitment, _) =
+				executor.execute_block_opt(ExecutableBlock::new(block_id, transactions))?;
+			let block_height = commitment.height();
+			let da_height = block_height + DA_HEIGHT_OFFSET;
+			da_db.add_executed_block(block_id.to_vec())?;
+			da_db.set_executed_block_height(block_height, da_height, block_id.to_vec())?;
+			da_db.set_synced_height(da_height)?;
+			da_db.add_node_state(
+				StateOrigin::Local,
+				&NodeState { block_height, ledger_timestamp: block_height, ledger_version: 0 },
+			)?;
+			block_ids.push(block_id);
+		}
+		Ok(block_ids)
+	}
+
+	async fn block_head_height(config: &Config) -> Result<u64, anyhow::Error> {
+		let (mempool_tx_exec_result_sender, _mempool_commit_tx_receiver) =
+			unbounded_channel::<Vec<TxExecutionResult>>();
+		let executor = Executor::try_from_config(
+			config.execution_config.maptos_config.clone(),
+			mempool_tx_exec_result_sender,
+		)
+		.await?;
+		executor.get_block_head_height()
+	}
+
+	#[tokio::test]
+	async fn test_restore_rolls_back_the_ledger_and_the_da_db() -> Result<(), anyhow::Error> {
+		let dir = tempfile::tempdir()?;
+		let mut config = Config::default();
+		config.execution_config.maptos_config.chain.maptos_db_path =
+			Some(dir.path().join("maptos-db"));
+		config.da_db.da_db_path = dir.path().join("da-db").to_string_lossy().to_string();
+		let checkpoints = NodeCheckpoints::try_from_config(&config)?;
+
+		let kept = execute_blocks(&config, 2).await?;
+		let checkpoint = checkpoints.create("before-upgrade")?;
+		assert_eq!(checkpoint.block_height, block_head_height(&config).await?);
+
+		let rolled_back = execute_blocks(&config, 2).await?;
+		assert_eq!(block_head_height(&config).await?, checkpoint.block_height + 2);
+
+		let (restored, da_height) = checkpoints.restore("before-upgrade")?;
+		assert_eq!(restored, checkpoint);
+		assert_eq!(da_height, checkpoint.block_height + DA_HEIGHT_OFFSET);
+		assert_eq!(checkpoints.pending_restore()?, None);
+		assert_eq!(block_head_height(&config).await?, checkpoint.block_height);
+
+		let da_db = DaDB::open(&config.da_db.da_db_path)?;
+		assert_eq!(da_db.get_synced_height()?, da_height);
+		assert_eq!(da_db.get_executed_block(checkpoint.block_height)?, Some(da_height));
+		assert_eq!(da_db.get_executed_block(checkpoint.block_height + 1)?, None);
+		for block_id in kept {
+			assert!(da_db.has_executed_block(block_id.to_vec())?);
+		}
+		for block_id in rolled_back {
+			assert!(!da_db.has_executed_block(block_id.to_vec())?);
+		}
+		assert!(da_db.get_node_state(StateOrigin::Local, checkpoint.block_height)?.is_some());
+		assert!(da_db.get_node_state(StateOrigin::Local, checkpoint.block_height + 1)?.is_none());
+		drop(da_db);
+
+		// The checkpoint is kept, so it can be restored again.
+		assert_eq!(checkpoints.list()?, vec![checkpoint]);
+		Ok(())
+	}
+}
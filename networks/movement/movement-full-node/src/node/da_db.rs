@@ -1,4 +1,6 @@
-use rocksdb::{ColumnFamilyDescriptor, Options, DB};
+use crate::node::tasks::state_verifier::{NodeState, StateDivergence, StateOrigin};
+use rocksdb::{ColumnFamilyDescriptor, Direction, IteratorMode, Options, WriteBatch, DB};
+use serde::{Deserialize, Serialize};
 
 use std::path::Path;
 use std::sync::Arc;
@@ -6,9 +8,29 @@ use std::sync::Arc;
 mod column_families {
 	pub const EXECUTED_BLOCKS: &str = "executed_blocks";
 	pub const SYNCED_HEIGHT: &str = "synced_height";
+	pub const LOCAL_STATES: &str = "local_states";
+	pub const MAIN_NODE_STATES: &str = "main_node_states";
+	pub const EXECUTED_BLOCK_HEIGHTS: &str = "executed_block_heights";
 }
 use column_families::*;
 
+const COLUMN_FAMILIES: [&str; 5] =
+	[SYNCED_HEIGHT, EXECUTED_BLOCKS, LOCAL_STATES, MAIN_NODE_STATES, EXECUTED_BLOCK_HEIGHTS];
+
+/// The DA block that produced the ledger block at some height.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ExecutedBlockRef {
+	da_height: u64,
+	block_id: Vec<u8>,
+}
+
+fn state_column_family(origin: StateOrigin) -> &'static str {
+	match origin {
+		StateOrigin::Local => LOCAL_STATES,
+		StateOrigin::MainNode => MAIN_NODE_STATES,
+	}
+}
+
 /// Simple data store for locally recorded DA events.
 ///
 /// An async access API is provided to avoid blocking async tasks.
@@ -24,14 +46,22 @@ impl DaDB {
 		options.create_if_missing(true);
 		options.create_missing_column_families(true);
 
-		let synced_height = ColumnFamilyDescriptor::new(SYNCED_HEIGHT, Options::default());
-		let executed_blocks = ColumnFamilyDescriptor::new(EXECUTED_BLOCKS, Options::default());
+		let column_families = COLUMN_FAMILIES
+			.iter()
+			.map(|name| ColumnFamilyDescriptor::new(*name, Options::default()));
 
-		let db = DB::open_cf_descriptors(&options, path, vec![synced_height, executed_blocks])
+		let db = DB::open_cf_descriptors(&options, path, column_families)
 			.map_err(|e| anyhow::anyhow!("Failed to open DA DB: {:?}", e))?;
 		Ok(Self { inner: Arc::new(db) })
 	}
 
+	/// Open the DB in read only mode, so it can be inspected while the node is running.
+	pub fn open_read_only(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+		let db = DB::open_cf_for_read_only(&Options::default(), path, COLUMN_FAMILIES, false)
+			.map_err(|e| anyhow::anyhow!("Failed to open DA DB in read only mode: {:?}", e))?;
+		Ok(Self { inner: Arc::new(db) })
+	}
+
 	pub fn add_executed_block(&self, id: Vec<u8>) -> Result<(), anyhow::Error> {
 		let da_db = self.inner.clone();
 		let cf = da_db
@@ -110,4 +140,206 @@ impl DaDB {
 		}
 		Ok(())
 	}
+
+	/// Record the ledger block height produced by the execution of the DA block.
+	pub fn set_executed_block_height(
+		&self,
+		block_height: u64,
+		da_height: u64,
+		block_id: Vec<u8>,
+	) -> Result<(), anyhow::Error> {
+		let da_db = self.inner.clone();
+		let cf = da_db
+			.cf_handle(EXECUTED_BLOCK_HEIGHTS)
+			.ok_or(anyhow::anyhow!("No executed_block_heights column family"))?;
+		let block_ref = serde_json::to_vec(&ExecutedBlockRef { da_height, block_id })
+			.map_err(|e| anyhow::anyhow!("Failed to serialize executed block: {:?}", e))?;
+		da_db
+			.put_cf(&cf, block_height.to_be_bytes(), block_ref)
+			.map_err(|e| anyhow::anyhow!("Failed to set executed block height: {:?}", e))?;
+		Ok(())
+	}
+
+	/// The DA height of the block that produced the ledger block at the height, if recorded.
+	pub fn get_executed_block(&self, block_height: u64) -> Result<Option<u64>, anyhow::Error> {
+		let da_db = self.inner.clone();
+		let cf = da_db
+			.cf_handle(EXECUTED_BLOCK_HEIGHTS)
+			.ok_or(anyhow::anyhow!("No executed_block_heights column family"))?;
+		da_db
+			.get_cf(&cf, block_height.to_be_bytes())
+			.map_err(|e| anyhow::anyhow!("Failed to get executed block height: {:?}", e))?
+			.map(|value| {
+				serde_json::from_slice::<ExecutedBlockRef>(&value)
+					.map(|block_ref| block_ref.da_height)
+					.map_err(|e| anyhow::anyhow!("Failed to deserialize executed block: {:?}", e))
+			})
+			.transpose()
+	}
+
+	/// Forget the DA blocks executed after the ledger block height, so they are executed again
+	/// when the DA is streamed from the returned DA height.
+	pub fn rollback_executed_blocks_to(&self, block_height: u64) -> Result<u64, anyhow::Error> {
+		let da_db = self.inner.clone();
+		let heights_cf = da_db
+			.cf_handle(EXECUTED_BLOCK_HEIGHTS)
+			.ok_or(anyhow::anyhow!("No executed_block_heights column family"))?;
+		let blocks_cf = da_db
+			.cf_handle(EXECUTED_BLOCKS)
+			.ok_or(anyhow::anyhow!("No executed_blocks column family"))?;
+
+		let decode = |value: &[u8]| {
+			serde_json::from_slice::<ExecutedBlockRef>(value)
+				.map_err(|e| anyhow::anyhow!("Failed to deserialize executed block: {:?}", e))
+		};
+		let agreed_block = da_db
+			.get_cf(&heights_cf, block_height.to_be_bytes())
+			.map_err(|e| anyhow::anyhow!("Failed to get executed block height: {:?}", e))?
+			.ok_or(anyhow::anyhow!("No DA block recorded for block height {block_height}"))?;
+		let agreed_block = decode(&agreed_block)?;
+
+		let synced_cf = da_db
+			.cf_handle(SYNCED_HEIGHT)
+			.ok_or(anyhow::anyhow!("No synced_height column family"))?;
+		let height_str = serde_json::to_string(&agreed_block.da_height)
+			.map_err(|e| anyhow::anyhow!("Failed to serialize synced height: {:?}", e))?;
+
+		// Remove the entries and move the synced height back in one write, so an interrupted
+		// rollback leaves the DB unchanged.
+		let mut batch = WriteBatch::default();
+		let start = (block_height + 1).to_be_bytes();
+		for entry in da_db.iterator_cf(&heights_cf, IteratorMode::From(&start, Direction::Forward))
+		{
+			let (key, value) =
+				entry.map_err(|e| anyhow::anyhow!("Failed to iterate executed blocks: {:?}", e))?;
+			let block_ref = decode(&value)?;
+			batch.delete_cf(&blocks_cf, block_ref.block_id);
+			batch.delete_cf(&heights_cf, key);
+		}
+		batch.put_cf(&synced_cf, "synced_height", height_str);
+		da_db
+			.write(batch)
+			.map_err(|e| anyhow::anyhow!("Failed to roll back executed blocks: {:?}", e))?;
+
+		Ok(agreed_block.da_height)
+	}
+
+	/// Persist a node state. Only the first state recorded at a height is kept.
+	pub fn add_node_state(
+		&self,
+		origin: StateOrigin,
+		state: &NodeState,
+	) -> Result<(), anyhow::Error> {
+		if self.get_node_state(origin, state.block_height)?.is_some() {
+			return Ok(());
+		}
+		let da_db = self.inner.clone();
+		let cf = da_db
+			.cf_handle(state_column_family(origin))
+			.ok_or(anyhow::anyhow!("No {origin} states column family"))?;
+		let value = serde_json::to_vec(state)
+			.map_err(|e| anyhow::anyhow!("Failed to serialize node state: {:?}", e))?;
+		da_db
+			.put_cf(&cf, state.block_height.to_be_bytes(), value)
+			.map_err(|e| anyhow::anyhow!("Failed to add node state: {:?}", e))?;
+		Ok(())
+	}
+
+	pub fn get_node_state(
+		&self,
+		origin: StateOrigin,
+		block_height: u64,
+	) -> Result<Option<NodeState>, anyhow::Error> {
+		let da_db = self.inner.clone();
+		let cf = da_db
+			.cf_handle(state_column_family(origin))
+			.ok_or(anyhow::anyhow!("No {origin} states column family"))?;
+		da_db
+			.get_cf(&cf, block_height.to_be_bytes())
+			.map_err(|e| anyhow::anyhow!("Failed to get node state: {:?}", e))?
+			.map(|value| {
+				serde_json::from_slice(&value)
+					.map_err(|e| anyhow::anyhow!("Failed to deserialize node state: {:?}", e))
+			})
+			.transpose()
+	}
+
+	/// Remove the node states recorded above the block height.
+	pub fn remove_node_states_above(
+		&self,
+		origin: StateOrigin,
+		block_height: u64,
+	) -> Result<(), anyhow::Error> {
+		let da_db = self.inner.clone();
+		let cf = da_db
+			.cf_handle(state_column_family(origin))
+			.ok_or(anyhow::anyhow!("No {origin} states column family"))?;
+		let start = (block_height + 1).to_be_bytes();
+		for entry in da_db.iterator_cf(&cf, IteratorMode::From(&start, Direction::Forward)) {
+			let (key, _) =
+				entry.map_err(|e| anyhow::anyhow!("Failed to iterate node states: {:?}", e))?;
+			da_db
+				.delete_cf(&cf, key)
+				.map_err(|e| anyhow::anyhow!("Failed to remove node state: {:?}", e))?;
+		}
+		Ok(())
+	}
+
+	/// Find the first height, starting at `from_height`, where the local state and
+	/// the main node state disagree.
+	pub fn find_first_divergence(
+		&self,
+		from_height: u64,
+	) -> Result<Option<StateDivergence>, anyhow::Error> {
+		let da_db = self.inner.clone();
+		let cf = da_db
+			.cf_handle(MAIN_NODE_STATES)
+			.ok_or(anyhow::anyhow!("No main_node_states column family"))?;
+		let start = from_height.to_be_bytes();
+		for entry in da_db.iterator_cf(&cf, IteratorMode::From(&start, Direction::Forward)) {
+			let (_, value) =
+				entry.map_err(|e| anyhow::anyhow!("Failed to iterate node states: {:?}", e))?;
+			let main_node: NodeState = serde_json::from_slice(&value)
+				.map_err(|e| anyhow::anyhow!("Failed to deserialize node state: {:?}", e))?;
+			let Some(local) = self.get_node_state(StateOrigin::Local, main_node.block_height)?
+			else {
+				continue;
+			};
+			if !local.matches(&main_node) {
+				let last_agreed_height = self.find_last_agreed_height(main_node.block_height)?;
+				return Ok(Some(StateDivergence {
+					block_height: main_node.block_height,
+					local,
+					main_node,
+					last_agreed_height,
+				}));
+			}
+		}
+		Ok(None)
+	}
+
+	/// Find the highest height below `block_height` where the local state and the main node state agree.
+	pub fn find_last_agreed_height(&self, block_height: u64) -> Result<Option<u64>, anyhow::Error> {
+		let da_db = self.inner.clone();
+		let cf = da_db
+			.cf_handle(LOCAL_STATES)
+			.ok_or(anyhow::anyhow!("No local_states column family"))?;
+		let Some(below) = block_height.checked_sub(1) else {
+			return Ok(None);
+		};
+		let start = below.to_be_bytes();
+		for entry in da_db.iterator_cf(&cf, IteratorMode::From(&start, Direction::Reverse)) {
+			let (_, value) =
+				entry.map_err(|e| anyhow::anyhow!("Failed to iterate node states: {:?}", e))?;
+			let local: NodeState = serde_json::from_slice(&value)
+				.map_err(|e| anyhow::anyhow!("Failed to deserialize node state: {:?}", e))?;
+			if self
+				.get_node_state(StateOrigin::MainNode, local.block_height)?
+				.is_some_and(|main_node| main_node.matches(&local))
+			{
+				return Ok(Some(local.block_height));
+			}
+		}
+		Ok(None)
+	}
 }
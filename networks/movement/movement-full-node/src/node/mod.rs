@@ -1,4 +1,5 @@
+pub mod checkpoint;
 pub mod da_db;
 pub mod manager;
 pub mod partial;
-mod tasks;
+pub mod tasks;
@@ -1,4 +1,4 @@
-use crate::node::{da_db::DaDB, tasks};
+use crate::node::{checkpoint::NodeCheckpoints, da_db::DaDB, tasks};
 use maptos_dof_execution::MakeOptFinServices;
 use maptos_dof_execution::{v1::Executor, DynOptFinExecutor};
 use maptos_opt_executor::executor::TxExecutionResult;
@@ -56,6 +56,25 @@ where
 			self.config.mcr.clone(),
 		);
 
+		// Streamed blocks are verified if the sequencer block signing key is configured.
+		let block_verifying_key = self
+			.config
+			.execution_config
+			.maptos_config
+			.da_sequencer
+			.block_verifying_key
+			.as_deref()
+			.map(|key| {
+				let bytes = hex::decode(key.strip_prefix("0x").unwrap_or(key))
+					.context("Invalid DA Sequencer block verifying key hex")?;
+				let bytes: [u8; 32] = bytes.try_into().map_err(|_| {
+					anyhow::anyhow!("Invalid DA Sequencer block verifying key length")
+				})?;
+				ed25519_dalek::VerifyingKey::from_bytes(&bytes)
+					.context("Invalid DA Sequencer block verifying key")
+			})
+			.transpose()?;
+
 		let (result, _index, _remaining) = futures::future::select_all(vec![
 			tokio::spawn({
 				let da_signer = self
@@ -88,7 +107,9 @@ where
 							da_sequencer_url,
 							stream_heartbeat_interval_sec,
 							self.config.da_db.allow_sync_from_zero,
+							self.config.da_db.auto_recover_state_divergence,
 							propagate_execution_state,
+							block_verifying_key,
 							&da_signer,
 							stop_rx,
 						)
@@ -122,6 +143,14 @@ impl MovementPartialNode<Executor> {
 		config: Config,
 		mempool_tx_exec_result_sender: UnboundedSender<Vec<TxExecutionResult>>,
 	) -> Result<Self, anyhow::Error> {
+		if let Some(pending) = NodeCheckpoints::try_from_config(&config)?.pending_restore()? {
+			anyhow::bail!(
+				"The restore of checkpoint {} is not finished, run `movement-full-node admin checkpoint restore {}` again",
+				pending.name,
+				pending.name
+			);
+		}
+
 		debug!("Creating the executor");
 		let executor = Executor::try_from_config(
 			config.execution_config.maptos_config.clone(),
@@ -136,7 +165,8 @@ impl MovementPartialNode<Executor> {
 				.await
 				.context("Failed to build MCR settlement client with config")?;
 			let (settlement_manager, commitment_events) =
-				McrSettlementManager::new(settlement_client, &config.mcr);
+				McrSettlementManager::new(settlement_client, &config.mcr)
+					.context("Failed to create the MCR settlement manager")?;
 			(Some(settlement_manager), Some(commitment_events))
 		} else {
 			(None, None)
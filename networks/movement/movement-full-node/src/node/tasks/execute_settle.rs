@@ -1,6 +1,6 @@
 //! Task module to execute blocks from the DA and process settlement.
 use crate::node::da_db::DaDB;
-use crate::node::tasks::state_verifier::StateVerifier;
+use crate::node::tasks::state_verifier::{NodeState, StateOrigin, StateVerifier};
 use anyhow::Context;
 use futures::{future::Either, stream};
 use maptos_dof_execution::{
@@ -17,7 +17,7 @@ use movement_signer_loader::{identifiers::SignerIdentifier, Load, LoadedSigner};
 use movement_types::block::{Block, BlockCommitment, BlockCommitmentEvent};
 use tokio::select;
 use tokio_stream::{Stream, StreamExt};
-use tracing::{debug, error, info, info_span, Instrument};
+use tracing::{debug, error, info, info_span, warn, Instrument};
 use url::Url;
 
 pub struct Task<E, S> {
@@ -69,7 +69,9 @@ where
 		da_connection_url: Url,
 		stream_heartbeat_interval_sec: u64,
 		allow_sync_from_zero: bool,
+		auto_recover_state_divergence: bool,
 		propagate_execution_state: bool,
+		block_verifying_key: Option<ed25519_dalek::VerifyingKey>,
 		da_batch_signer: &SignerIdentifier,
 		mut stop_rx: tokio::sync::watch::Receiver<()>,
 	) -> anyhow::Result<()> {
@@ -79,13 +81,19 @@ where
 			return Err(anyhow::anyhow!("Da Sync from height zero is not allowed."));
 		}
 
-		let mut node_local_state_verifier = StateVerifier::new();
-		let mut node_main_state_verifier = StateVerifier::new();
+		// The local state verifier holds the main node states to verify the local ones,
+		// the main state verifier holds the local states. Both are persisted for the audit.
+		let mut node_local_state_verifier =
+			StateVerifier::with_log(self.da_db.clone(), StateOrigin::MainNode);
+		let mut node_main_state_verifier =
+			StateVerifier::with_log(self.da_db.clone(), StateOrigin::Local);
+		let mut last_recovered_height = None;
 
 		info!("DA synced height: {:?}", synced_height);
 		let mut da_client =
 			GrpcDaSequencerClient::try_connect(&da_connection_url, stream_heartbeat_interval_sec)
-				.await?;
+				.await?
+				.with_block_verifying_key(block_verifying_key);
 		// TODO manage alert_channel in the issue #1169
 		let (mut blocks_from_da, mut alert_channel) = da_client
 			.stream_read_from_height(StreamReadFromHeightRequest { height: synced_height })
@@ -107,50 +115,66 @@ where
 							let response = res.context("failed to get next block from DA")?;
 							let span = info_span!(target: "movement_timing", "process_block_from_da", block_id = %hex::encode(response.block_id.clone()));
 							tracing::info!("Receive state from DA: {:?}",response.node_state);
-							if let Some(main_state) = response.node_state {
+							let mut divergent_height = None;
+							if let Some(main_state) = response.node_state.as_ref() {
+								let main_state = NodeState::from(main_state);
+								node_local_state_verifier.add_state(main_state.clone())?;
 								// validate received state with local node executed state
-								if !node_main_state_verifier.validate(&(&main_state).into()) {
-									let main_node_state = node_main_state_verifier.get_state(main_state.block_height.into());
-									tracing::error!("Main State from Da verification failed, local node state: {main_state:?} main_node_state:{main_node_state:?}");
-									break;
+								if !node_main_state_verifier.validate(&main_state)? {
+									let local_state = node_main_state_verifier.get_state(main_state.block_height)?;
+									tracing::error!("Main State from Da verification failed, main node state: {main_state:?} local node state:{local_state:?}");
+									divergent_height = Some(main_state.block_height);
 								}
-								node_local_state_verifier.add_state((&main_state).into());
 							}
-							let new_state = self.process_block_from_da(response).instrument(span).await?;
-							tracing::info!("New state after execution: {new_state:?}");
-							if let Some(new_state) = new_state {
-								if !node_local_state_verifier.validate(&(&new_state).into()) {
-									let main_node_state = node_local_state_verifier.get_state(new_state.block_height.into());
-									tracing::error!("Local state from Da verification failed, local node state: {new_state:?} main_node_state:{main_node_state:?}");
-									break;
-								}
-								node_main_state_verifier.add_state((&new_state).into());
-
-								// If main node send new execution result state
-								if propagate_execution_state {
-									tokio::spawn({
-										let mut client = da_client.clone();
-										let signer: LoadedSigner<Ed25519> = match da_batch_signer.load().await {
-											Ok(signer) => signer,
-											Err(err) => {
-												tracing::error!("Failed to load DA batch signer: {err}");
-												break;
-											}
-										};
-										let state = movement_da_sequencer_proto::MainNodeState {
-											block_height: new_state.block_height,
-											ledger_timestamp:  new_state.ledger_timestamp,
-											ledger_version: new_state.ledger_version,
-
-										};
-										async move {
-											if let Err(err) = client.send_state(&signer, state).await {
-												tracing::error!("Send execution state to da sequencer failed : {err}");
+							if divergent_height.is_none() {
+								let da_height = response.height;
+								let block_id = response.block_id.clone();
+								let new_state = self.process_block_from_da(response).instrument(span).await?;
+								tracing::info!("New state after execution: {new_state:?}");
+								if let Some(new_state) = new_state {
+									self.da_db.set_executed_block_height(new_state.block_height, da_height, block_id)?;
+									let local_state = NodeState::from(&new_state);
+									node_main_state_verifier.add_state(local_state.clone())?;
+									if !node_local_state_verifier.validate(&local_state)? {
+										let main_node_state = node_local_state_verifier.get_state(new_state.block_height)?;
+										tracing::error!("Local state from Da verification failed, local node state: {new_state:?} main_node_state:{main_node_state:?}");
+										divergent_height = Some(new_state.block_height);
+									} else if propagate_execution_state {
+										// If main node send new execution result state
+										tokio::spawn({
+											let mut client = da_client.clone();
+											let signer: LoadedSigner<Ed25519> = match da_batch_signer.load().await {
+												Ok(signer) => signer,
+												Err(err) => {
+													tracing::error!("Failed to load DA batch signer: {err}");
+													break;
+												}
+											};
+											let state = movement_da_sequencer_proto::MainNodeState {
+												block_height: new_state.block_height,
+												ledger_timestamp:  new_state.ledger_timestamp,
+												ledger_version: new_state.ledger_version,
+
+											};
+											async move {
+												if let Err(err) = client.send_state(&signer, state).await {
+													tracing::error!("Send execution state to da sequencer failed : {err}");
+												}
 											}
-										}
-									});
-
+										});
+									}
+								}
+							}
+							if let Some(divergent_height) = divergent_height {
+								// Recover only once from a divergence at the same height.
+								if !auto_recover_state_divergence || last_recovered_height == Some(divergent_height) {
+									break;
 								}
+								let da_height = self.recover_from_divergence(divergent_height, &mut node_main_state_verifier).await?;
+								last_recovered_height = Some(divergent_height);
+								(blocks_from_da, alert_channel) = da_client
+									.stream_read_from_height(StreamReadFromHeightRequest { height: da_height })
+									.await?;
 							}
 						}
 					}
@@ -172,6 +196,34 @@ where
 		Err(anyhow::anyhow!("Block execution loop break. Node need to be restarted."))
 	}
 
+	/// Roll back the ledger to the last height where the local state agrees with the main node
+	/// state, below the divergent height. Return the DA height to stream the blocks from.
+	async fn recover_from_divergence(
+		&self,
+		divergent_height: u64,
+		local_states: &mut StateVerifier,
+	) -> anyhow::Result<u64> {
+		let agreed_height =
+			self.da_db.find_last_agreed_height(divergent_height)?.ok_or(anyhow::anyhow!(
+				"No height agreed with the main node below the divergent height {divergent_height}"
+			))?;
+		let executor = match self.executor {
+			Some(ref executor) => executor,
+			None => anyhow::bail!("State recovery failed, executor not present."),
+		};
+		warn!("State divergence at height {divergent_height}, roll back to height {agreed_height}");
+		executor
+			.revert_block_head_to(agreed_height)
+			.await
+			.context(format!("failed to revert to block height {agreed_height}"))?;
+		let da_height = self.da_db.rollback_executed_blocks_to(agreed_height)?;
+		local_states.truncate_above(agreed_height)?;
+		info!(
+			"Ledger rolled back to height {agreed_height}, re-execute from DA height {da_height}"
+		);
+		Ok(da_height)
+	}
+
 	async fn process_block_from_da(
 		&mut self,
 		da_block: BlockV1,
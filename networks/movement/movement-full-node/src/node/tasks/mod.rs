@@ -1,4 +1,4 @@
 //! Modules to separate full node processing into actor-like tasks.
 
 pub mod execute_settle;
-mod state_verifier;
+pub mod state_verifier;
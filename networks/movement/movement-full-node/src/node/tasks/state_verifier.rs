@@ -2,30 +2,44 @@
 /// Without on-chain consensus, we need a “source of truth” node to decide which state is correct.
 /// We call this the main node (to avoid confusion with the leader node, which has a different role); it broadcasts its state to all other nodes.
 /// Each node verifies the state received from the DA-Sequencer against its own computed state after execution.
-/// If the states diverge, the node stops processing and must restore its state to recover.
+/// If the states diverge, the node stops processing and must restore its state to recover,
+/// or rolls back to the last agreed height if the automatic recovery is enabled.
 /// The structs in this module are used for state verification.
 /// Both the locally computed state and the main node’s published state are verified.
-/// Each comparison (local vs. main and main vs. local) uses a `StateVerifier`; the last `MAX_STATE_ENTRY` states (both received and computed) are stored.
+/// Each comparison (local vs. main and main vs. local) uses a `StateVerifier`; the last `MAX_STATE_ENTRY` states (both received and computed) are stored in memory.
+/// When a `StateVerifier` has a log, every state is also persisted in the `DaDB` and states older
+/// than the in-memory window are verified against the log. The log is used to audit divergences.
 /// When a new state is published or computed, verification occurs for that state’s height.
 /// States at the same height must have the same ledger timestamp and version.
+use crate::node::da_db::DaDB;
 use maptos_opt_executor::executor::ExecutionState;
 use movement_da_sequencer_proto::MainNodeState;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
+use std::fmt;
 
 // We produce at most 2 blocks per second.
 // With a `MAX_HISTORY_SIZE` of 120, a node that falls more than 60 s behind
-// will not detect state divergence.
+// will only detect state divergence from the persisted log.
 // We chose 120 (60 seconds) because any node lagging by more than 60 s
 // likely has other issues and cannot be considered in sync.
 const MAX_STATE_ENTRY: usize = 120;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct NodeState {
 	pub block_height: u64,
 	pub ledger_timestamp: u64,
 	pub ledger_version: u64,
 }
 
+impl NodeState {
+	/// Returns true if both states have the same ledger timestamp and version.
+	pub fn matches(&self, other: &NodeState) -> bool {
+		self.ledger_timestamp == other.ledger_timestamp
+			&& self.ledger_version == other.ledger_version
+	}
+}
+
 impl From<&MainNodeState> for NodeState {
 	fn from(main_node_state: &MainNodeState) -> Self {
 		NodeState {
@@ -46,29 +60,65 @@ impl From<&ExecutionState> for NodeState {
 	}
 }
 
+/// Which node computed a state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateOrigin {
+	/// The state computed by this node.
+	Local,
+	/// The state published by the main node.
+	MainNode,
+}
+
+impl fmt::Display for StateOrigin {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			StateOrigin::Local => write!(f, "local"),
+			StateOrigin::MainNode => write!(f, "main_node"),
+		}
+	}
+}
+
+/// The local and the main node states at the first height where they disagree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StateDivergence {
+	pub block_height: u64,
+	pub local: NodeState,
+	pub main_node: NodeState,
+	/// The highest height below the divergence where both states agree.
+	pub last_agreed_height: Option<u64>,
+}
+
 pub struct StateVerifier {
 	states: BTreeMap<u64, NodeState>,
+	log: Option<(DaDB, StateOrigin)>,
 }
 
 impl StateVerifier {
+	/// Create a verifier that only keeps the last `MAX_STATE_ENTRY` states in memory.
 	pub fn new() -> Self {
-		StateVerifier { states: BTreeMap::new() }
+		StateVerifier { states: BTreeMap::new(), log: None }
+	}
+
+	/// Create a verifier that also persists the added states in the `DaDB`.
+	/// `origin` is the node that computed the added states.
+	pub fn with_log(da_db: DaDB, origin: StateOrigin) -> Self {
+		StateVerifier { states: BTreeMap::new(), log: Some((da_db, origin)) }
 	}
 
-	pub fn validate(&self, local_state: &NodeState) -> bool {
+	pub fn validate(&self, local_state: &NodeState) -> Result<bool, anyhow::Error> {
 		//if the height is not present, return true.
-		self.states
-			.get(&local_state.block_height.into())
-			.map(|s| {
-				s.ledger_timestamp == local_state.ledger_timestamp
-					&& s.ledger_version == local_state.ledger_version
-			})
-			.unwrap_or(true)
+		Ok(self
+			.get_state(local_state.block_height)?
+			.map(|s| s.matches(local_state))
+			.unwrap_or(true))
 	}
 
-	pub fn add_state(&mut self, new_state: NodeState) {
+	pub fn add_state(&mut self, new_state: NodeState) -> Result<(), anyhow::Error> {
 		// State can only be added once
 		if !self.states.contains_key(&new_state.block_height) {
+			if let Some((da_db, origin)) = &self.log {
+				da_db.add_node_state(*origin, &new_state)?;
+			}
 			// If the number of stored states exceeds the maximum allowed entries,
 			// remove the oldest entry (smallest key) to maintain a fixed-size cache.
 			// This ensures that the most recent states are retained.
@@ -77,10 +127,24 @@ impl StateVerifier {
 			}
 			self.states.insert(new_state.block_height, new_state);
 		}
+		Ok(())
 	}
 
-	pub fn get_state(&self, block_height: u64) -> Option<&NodeState> {
-		self.states.get(&block_height)
+	pub fn get_state(&self, block_height: u64) -> Result<Option<NodeState>, anyhow::Error> {
+		match (self.states.get(&block_height), &self.log) {
+			(Some(state), _) => Ok(Some(state.clone())),
+			(None, Some((da_db, origin))) => da_db.get_node_state(*origin, block_height),
+			(None, None) => Ok(None),
+		}
+	}
+
+	/// Remove the states above the block height, after the node rolled back its ledger.
+	pub fn truncate_above(&mut self, block_height: u64) -> Result<(), anyhow::Error> {
+		self.states.retain(|height, _| *height <= block_height);
+		if let Some((da_db, origin)) = &self.log {
+			da_db.remove_node_states_above(*origin, block_height)?;
+		}
+		Ok(())
 	}
 }
 #[cfg(test)]
@@ -96,7 +160,7 @@ mod test {
 		// Verify with no state stored. Validation true any state.
 		let state1 = ExecutionState { block_height: 1, ledger_timestamp: 2, ledger_version: 3 };
 		assert!(
-			state_verifier.validate(&(&state1).into()),
+			state_verifier.validate(&(&state1).into()).unwrap(),
 			"Empty state verifier validate a state"
 		);
 	}
@@ -107,9 +171,12 @@ mod test {
 		let mut state_verifier = StateVerifier::new();
 		// Add the same state and validate it
 		let new_state = MainNodeState { block_height: 1, ledger_timestamp: 2, ledger_version: 3 };
-		state_verifier.add_state((&new_state).into());
+		state_verifier.add_state((&new_state).into()).unwrap();
 		let state1 = ExecutionState { block_height: 1, ledger_timestamp: 2, ledger_version: 3 };
-		assert!(state_verifier.validate(&(&state1).into()), "Same state added doesn't valid.");
+		assert!(
+			state_verifier.validate(&(&state1).into()).unwrap(),
+			"Same state added doesn't valid."
+		);
 	}
 	// Add a different state for same height and validate it
 	#[test]
@@ -117,13 +184,16 @@ mod test {
 		let mut state_verifier = StateVerifier::new();
 		// Add initial state
 		let new_state = MainNodeState { block_height: 1, ledger_timestamp: 2, ledger_version: 3 };
-		state_verifier.add_state((&new_state).into());
+		state_verifier.add_state((&new_state).into()).unwrap();
 
 		// Add a different state for same height and validate it
 		let state2 = ExecutionState { block_height: 1, ledger_timestamp: 3, ledger_version: 3 };
-		assert!(!state_verifier.validate(&(&state2).into()), "Diff ts and state valid");
+		assert!(!state_verifier.validate(&(&state2).into()).unwrap(), "Diff ts and state valid");
 		let state3 = ExecutionState { block_height: 1, ledger_timestamp: 2, ledger_version: 4 };
-		assert!(!state_verifier.validate(&(&state3).into()), "Diff version and state valid");
+		assert!(
+			!state_verifier.validate(&(&state3).into()).unwrap(),
+			"Diff version and state valid"
+		);
 	}
 
 	// Add a different state with same height. First added still valid.
@@ -131,20 +201,23 @@ mod test {
 	fn test_state_validate_old_state_after_update() {
 		let mut state_verifier = StateVerifier::new();
 		let new_state = MainNodeState { block_height: 1, ledger_timestamp: 2, ledger_version: 3 };
-		state_verifier.add_state((&new_state).into());
+		state_verifier.add_state((&new_state).into()).unwrap();
 
 		// Add a different state with same height
 		let new_state = MainNodeState { block_height: 1, ledger_timestamp: 3, ledger_version: 3 };
-		state_verifier.add_state((&new_state).into());
+		state_verifier.add_state((&new_state).into()).unwrap();
 		// Old state still validate.
 		let state1 = ExecutionState { block_height: 1, ledger_timestamp: 2, ledger_version: 3 };
 		assert!(
-			state_verifier.validate(&(&state1).into()),
+			state_verifier.validate(&(&state1).into()).unwrap(),
 			"State updated, old one doesn't validate"
 		);
 		//New one invalid.
 		let state2 = ExecutionState { block_height: 1, ledger_timestamp: 3, ledger_version: 3 };
-		assert!(!state_verifier.validate(&(&state2).into()), "State updated, new one is valid");
+		assert!(
+			!state_verifier.validate(&(&state2).into()).unwrap(),
+			"State updated, new one is valid"
+		);
 	}
 
 	// Fill the state, oldest height should be removed.
@@ -153,11 +226,14 @@ mod test {
 		let mut state_verifier = StateVerifier::new();
 		// Add the first state that should be removed with nely added state.
 		let new_state = MainNodeState { block_height: 1, ledger_timestamp: 2, ledger_version: 3 };
-		state_verifier.add_state((&new_state).into());
+		state_verifier.add_state((&new_state).into()).unwrap();
 
 		//State change detection works before adding new state.
 		let state2 = ExecutionState { block_height: 1, ledger_timestamp: 3, ledger_version: 3 };
-		assert!(!state_verifier.validate(&(&state2).into()), "State updated, new one is valid");
+		assert!(
+			!state_verifier.validate(&(&state2).into()).unwrap(),
+			"State updated, new one is valid"
+		);
 
 		// Fill the state, oldest height should be removed.
 		for index in 0u64..MAX_STATE_ENTRY as u64 {
@@ -166,10 +242,63 @@ mod test {
 				ledger_timestamp: index + 3,
 				ledger_version: index + 4,
 			};
-			state_verifier.add_state((&state).into());
+			state_verifier.add_state((&state).into()).unwrap();
 		}
 		// Any state should validate at height 1 now because the first state has been removed
 		let state1 = ExecutionState { block_height: 1, ledger_timestamp: 3, ledger_version: 4 };
-		assert!(state_verifier.validate(&(&state1).into()), "Previous state3 not valid");
+		assert!(state_verifier.validate(&(&state1).into()).unwrap(), "Previous state3 not valid");
+	}
+
+	fn node_state(block_height: u64, ledger_version: u64) -> NodeState {
+		NodeState { block_height, ledger_timestamp: block_height, ledger_version }
+	}
+
+	// States out of the in-memory window are verified against the persisted log.
+	#[test]
+	fn test_state_validate_from_log() {
+		let db_dir = tempfile::tempdir().expect("failed to create temp dir");
+		let da_db = DaDB::open(db_dir.path()).unwrap();
+		let mut state_verifier = StateVerifier::with_log(da_db.clone(), StateOrigin::MainNode);
+
+		for height in 1..=MAX_STATE_ENTRY as u64 + 1 {
+			state_verifier.add_state(node_state(height, height)).unwrap();
+		}
+		// Height 1 is out of the in-memory window but still verified.
+		assert!(state_verifier.validate(&node_state(1, 1)).unwrap());
+		assert!(!state_verifier.validate(&node_state(1, 2)).unwrap());
+
+		state_verifier.truncate_above(10).unwrap();
+		assert!(state_verifier.validate(&node_state(11, 100)).unwrap());
+		assert_eq!(da_db.get_node_state(StateOrigin::MainNode, 11).unwrap(), None);
+	}
+
+	// The audit reports the first divergent height and the last height where both states agree.
+	#[test]
+	fn test_find_first_divergence() {
+		let db_dir = tempfile::tempdir().expect("failed to create temp dir");
+		let da_db = DaDB::open(db_dir.path()).unwrap();
+		let mut local_states = StateVerifier::with_log(da_db.clone(), StateOrigin::Local);
+		let mut main_node_states = StateVerifier::with_log(da_db.clone(), StateOrigin::MainNode);
+
+		for height in 1..=6 {
+			local_states.add_state(node_state(height, height)).unwrap();
+		}
+		// The main node state of height 4 is missing, its local state can't be verified.
+		for height in [1, 2, 3, 5, 6] {
+			let version = if height >= 5 { height + 1 } else { height };
+			main_node_states.add_state(node_state(height, version)).unwrap();
+		}
+
+		let divergence = da_db.find_first_divergence(0).unwrap().expect("no divergence found");
+		assert_eq!(
+			divergence,
+			StateDivergence {
+				block_height: 5,
+				local: node_state(5, 5),
+				main_node: node_state(5, 6),
+				last_agreed_height: Some(3),
+			}
+		);
+		assert_eq!(da_db.find_first_divergence(7).unwrap(), None);
 	}
 }
@@ -104,6 +104,28 @@ impl Local {
 
 		Ok(config)
 	}
+
+	async fn setup_mcr_config(
+		&self,
+		dot_movement: DotMovement,
+		mut config: movement_config::Config,
+	) -> Result<movement_config::Config, anyhow::Error> {
+		// keep the commitments not settled yet across restarts
+		if config.mcr.transactions.commitment_outbox_path.is_none() {
+			let outbox_path = dot_movement.get_path().join("mcr-commitment-outbox.json");
+			config.mcr.transactions.commitment_outbox_path = Some(
+				outbox_path
+					.to_str()
+					.ok_or(anyhow::anyhow!(
+						"Failed to convert outbox path to string: {:?}",
+						outbox_path
+					))?
+					.to_string(),
+			);
+		}
+
+		Ok(config)
+	}
 }
 
 impl MovementFullNodeSetupOperations for Local {
@@ -125,6 +147,9 @@ impl MovementFullNodeSetupOperations for Local {
 		// run the da_db config setup
 		let config = self.setup_da_db_config(dot_movement.clone(), config).await?;
 
+		// run the mcr config setup
+		let config = self.setup_mcr_config(dot_movement.clone(), config).await?;
+
 		// Placeholder for returning the actual configuration.
 		Ok((config, join_handle))
 	}
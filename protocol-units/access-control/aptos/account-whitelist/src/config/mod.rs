@@ -1,27 +1,78 @@
-use crate::file::{Whitelist, WhitelistOperations};
+use crate::file::{EntryFunctionAllowlist, Whitelist, WhitelistOperations};
+use crate::AllowedEntryFunction;
 use aptos_types::account_address::AccountAddress;
 use godfig::env_default;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
 env_default!(default_aptos_account_whitelist, "APTOS_ACCOUNT_WHITELIST", String);
+env_default!(default_aptos_account_denylist, "APTOS_ACCOUNT_DENYLIST", String);
+env_default!(default_aptos_entry_function_allowlist, "APTOS_ENTRY_FUNCTION_ALLOWLIST", String);
+env_default!(default_max_in_flight_per_sender, "APTOS_MAX_IN_FLIGHT_PER_SENDER", u64);
+env_default!(default_ingress_reload_interval_sec, "APTOS_INGRESS_RELOAD_INTERVAL_SEC", u64, 60);
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Config {
 	/// The whitelist (path) for the mempool
 	#[serde(default = "default_aptos_account_whitelist")]
 	pub ingress_account_whitelist: Option<String>,
+
+	/// The list (path) of the accounts whose transactions are refused by the mempool.
+	#[serde(default = "default_aptos_account_denylist")]
+	pub ingress_account_denylist: Option<String>,
+
+	/// The list (path) of the entry functions accepted by the mempool, one
+	/// `<address>::<module>` or `<address>::<module>::<function>` per line.
+	/// Only entry function payloads are accepted when it is set.
+	#[serde(default = "default_aptos_entry_function_allowlist")]
+	pub ingress_entry_function_allowlist: Option<String>,
+
+	/// Max number of transactions of a sender in flight in the mempool.
+	#[serde(default = "default_max_in_flight_per_sender")]
+	pub max_in_flight_per_sender: Option<u64>,
+
+	/// Interval in seconds between two reloads of the list files. 0 disables the reload.
+	#[serde(default = "default_ingress_reload_interval_sec")]
+	pub ingress_reload_interval_sec: u64,
 }
 
 impl Default for Config {
 	fn default() -> Self {
-		Self { ingress_account_whitelist: default_aptos_account_whitelist() }
+		Self {
+			ingress_account_whitelist: default_aptos_account_whitelist(),
+			ingress_account_denylist: default_aptos_account_denylist(),
+			ingress_entry_function_allowlist: default_aptos_entry_function_allowlist(),
+			max_in_flight_per_sender: default_max_in_flight_per_sender(),
+			ingress_reload_interval_sec: default_ingress_reload_interval_sec(),
+		}
 	}
 }
 
 impl Config {
 	pub fn whitelisted_accounts(&self) -> Result<Option<HashSet<AccountAddress>>, anyhow::Error> {
-		match &self.ingress_account_whitelist {
+		Self::read_accounts(&self.ingress_account_whitelist)
+	}
+
+	pub fn denied_accounts(&self) -> Result<Option<HashSet<AccountAddress>>, anyhow::Error> {
+		Self::read_accounts(&self.ingress_account_denylist)
+	}
+
+	pub fn allowed_entry_functions(
+		&self,
+	) -> Result<Option<HashSet<AllowedEntryFunction>>, anyhow::Error> {
+		match &self.ingress_entry_function_allowlist {
+			Some(allowlist_path) => {
+				let allowlist = EntryFunctionAllowlist::try_new(allowlist_path.as_str())?;
+				Ok(Some(allowlist.try_into_set()?))
+			}
+			None => Ok(None),
+		}
+	}
+
+	fn read_accounts(
+		path: &Option<String>,
+	) -> Result<Option<HashSet<AccountAddress>>, anyhow::Error> {
+		match path {
 			Some(whitelist_path) => {
 				let whitelist = Whitelist::try_new(whitelist_path.as_str())?;
 				let whitelisted = whitelist.try_into_set()?;
@@ -1,4 +1,4 @@
-use crate::WhitelistedAccountAddress;
+use crate::{AllowedEntryFunction, WhitelistedAccountAddress};
 use aptos_types::account_address::AccountAddress;
 pub use whitelist::file::Whitelist as GenericWhitelist;
 use whitelist::file::{Error, TryFromFileLine};
@@ -14,3 +14,25 @@ impl TryFromFileLine for WhitelistedAccountAddress {
 }
 
 pub type Whitelist = GenericWhitelist<WhitelistedAccountAddress>;
+
+impl TryFromFileLine for AllowedEntryFunction {
+	fn try_from_file_line(line: &str) -> Result<Self, Error>
+	where
+		Self: Sized,
+	{
+		let mut parts = line.trim().split("::");
+		let (Some(address), Some(module)) = (parts.next(), parts.next()) else {
+			return Err(Error::Internal(format!("Invalid entry function line: {line}")));
+		};
+		let function = parts.next().map(str::to_string);
+		if parts.next().is_some() || module.is_empty() || function.as_deref() == Some("") {
+			return Err(Error::Internal(format!("Invalid entry function line: {line}")));
+		}
+		let address = AccountAddress::from_hex_literal(address)
+			.or_else(|_| AccountAddress::from_hex(address))
+			.map_err(|e| Error::Internal(e.to_string()))?;
+		Ok(Self { address, module: module.to_string(), function })
+	}
+}
+
+pub type EntryFunctionAllowlist = GenericWhitelist<AllowedEntryFunction>;
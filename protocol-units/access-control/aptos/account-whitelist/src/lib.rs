@@ -14,3 +14,23 @@ impl WhitelistedAccountAddress {
 		self.0
 	}
 }
+
+/// An entry of the entry function allow-list, read from a `<address>::<module>` line to allow
+/// all the entry functions of a module, or from a `<address>::<module>::<function>` line.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AllowedEntryFunction {
+	pub address: AccountAddress,
+	pub module: String,
+	pub function: Option<String>,
+}
+
+impl AllowedEntryFunction {
+	/// Returns true if the entry allows the function of the module.
+	pub fn allows(&self, address: &AccountAddress, module: &str, function: &str) -> bool {
+		let function_allowed = match &self.function {
+			Some(allowed) => allowed == function,
+			None => true,
+		};
+		self.address == *address && self.module == module && function_allowed
+	}
+}
@@ -6,7 +6,9 @@ use movement_da_sequencer_proto::BatchWriteResponse;
 use movement_da_sequencer_proto::BlockResponse;
 use movement_da_sequencer_proto::BlockV1;
 use movement_da_sequencer_proto::ReadAtHeightResponse;
+use movement_da_sequencer_proto::RejectionCode;
 use movement_da_sequencer_proto::StreamReadFromHeightRequest;
+use movement_da_sequencer_proto::StreamReadRangeRequest;
 use movement_signer::cryptography::ed25519::PUBLIC_KEY_SIZE;
 use movement_signer::cryptography::ed25519::SIGNATURE_SIZE;
 use movement_signer::{
@@ -14,6 +16,7 @@ use movement_signer::{
 	Signing,
 };
 use movement_signer_loader::LoadedSigner;
+use movement_types::block::Block;
 use std::{
 	future::Future,
 	sync::Arc,
@@ -30,11 +33,77 @@ use url::Url;
 pub enum ClientDaSequencerError {
 	#[error("Failed to open block stream: {0}")]
 	FailToOpenBlockStream(String),
+	#[error("Block range stream failed: {0}")]
+	BlockRangeStream(String),
+	#[error("Block at height {height} failed verification: {reason}")]
+	InvalidBlock { height: u64, reason: String },
+}
+
+/// Errors returned by `DaSequencerClient::batch_write`.
+#[derive(Debug, thiserror::Error)]
+pub enum BatchWriteError {
+	#[error("Batch write request failed: {0}")]
+	Transport(#[from] tonic::Status),
+	#[error("Batch is malformed: {0}")]
+	MalformedBatch(String),
+	#[error("Batch signature is invalid: {0}")]
+	InvalidSignature(String),
+	#[error("Batch signer is not whitelisted: {0}")]
+	NotWhitelisted(String),
+	#[error("Batch transactions at indices {indices:?} are invalid: {reason}")]
+	InvalidTransactions { indices: Vec<u32>, reason: String },
+	#[error("Batch rate limit exceeded: {0}")]
+	RateLimited(String),
+	#[error("Da Sequencer pending pool is full: {0}")]
+	PendingPoolFull(String),
+	#[error("Da Sequencer internal error: {0}")]
+	Internal(String),
+	#[error("Batch rejected: {0}")]
+	Rejected(String),
+}
+
+impl BatchWriteError {
+	/// Returns true if the rejection is transient and the same batch can be sent again later.
+	pub fn is_retryable(&self) -> bool {
+		matches!(
+			self,
+			BatchWriteError::Transport(_)
+				| BatchWriteError::RateLimited(_)
+				| BatchWriteError::PendingPoolFull(_)
+				| BatchWriteError::Internal(_)
+		)
+	}
+}
+
+/// Converts a `BatchWriteResponse` into the accepted response or the typed rejection.
+/// Responses from sequencers that don't send a rejection code are returned as `Rejected`.
+pub fn check_batch_write_response(
+	response: BatchWriteResponse,
+) -> Result<BatchWriteResponse, BatchWriteError> {
+	if response.answer {
+		return Ok(response);
+	}
+	let reason = response.reason;
+	Err(match response.code() {
+		RejectionCode::Unspecified => BatchWriteError::Rejected(reason),
+		RejectionCode::MalformedBatch => BatchWriteError::MalformedBatch(reason),
+		RejectionCode::InvalidSignature => BatchWriteError::InvalidSignature(reason),
+		RejectionCode::NotWhitelisted => BatchWriteError::NotWhitelisted(reason),
+		RejectionCode::InvalidTransactions => {
+			BatchWriteError::InvalidTransactions { indices: response.invalid_tx_indices, reason }
+		}
+		RejectionCode::RateLimited => BatchWriteError::RateLimited(reason),
+		RejectionCode::PendingPoolFull => BatchWriteError::PendingPoolFull(reason),
+		RejectionCode::InternalError => BatchWriteError::Internal(reason),
+	})
 }
 
 pub type StreamReadBlockFromHeight =
 	std::pin::Pin<Box<dyn Stream<Item = Result<BlockV1, ClientDaSequencerError>> + Send + 'static>>;
 
+/// Finite stream of the blocks of a height range.
+pub type StreamReadBlockRange = StreamReadBlockFromHeight;
+
 pub trait DaSequencerClient: Clone + Send {
 	/// Stream reads from a given height.
 	fn stream_read_from_height(
@@ -44,11 +113,18 @@ pub trait DaSequencerClient: Clone + Send {
 		Output = Result<(StreamReadBlockFromHeight, UnboundedReceiver<()>), ClientDaSequencerError>,
 	> + Send;
 
-	/// Writes a batch of transactions to the Da Sequencer node
+	/// Stream reads the blocks of a height range. The stream ends after the last block of the range.
+	fn stream_read_range(
+		&mut self,
+		request: StreamReadRangeRequest,
+	) -> impl Future<Output = Result<StreamReadBlockRange, ClientDaSequencerError>> + Send;
+
+	/// Writes a batch of transactions to the Da Sequencer node.
+	/// A batch refused by the Da Sequencer is returned as a `BatchWriteError`.
 	fn batch_write(
 		&mut self,
 		request: movement_da_sequencer_proto::BatchWriteRequest,
-	) -> impl Future<Output = Result<BatchWriteResponse, tonic::Status>> + Send;
+	) -> impl Future<Output = Result<BatchWriteResponse, BatchWriteError>> + Send;
 	fn send_state(
 		&mut self,
 		signer: &LoadedSigner<Ed25519>,
@@ -61,11 +137,62 @@ pub trait DaSequencerClient: Clone + Send {
 	) -> impl Future<Output = Result<ReadAtHeightResponse, tonic::Status>> + Send;
 }
 
+/// Checks that the received blocks are signed by the sequencer and chained to each other.
+#[derive(Debug, Clone)]
+pub struct BlockVerifier {
+	verifying_key: VerifyingKey,
+	// Height and id of the last verified block.
+	last_block: Option<(u64, Vec<u8>)>,
+}
+
+impl BlockVerifier {
+	pub fn new(verifying_key: VerifyingKey) -> Self {
+		BlockVerifier { verifying_key, last_block: None }
+	}
+
+	/// Verify the block signature. If the block data is present, verify that it matches the block id
+	/// and that its parent is the previously verified block when both heights are consecutive.
+	pub fn verify(&mut self, block: &BlockV1) -> Result<(), ClientDaSequencerError> {
+		let invalid =
+			|reason: String| ClientDaSequencerError::InvalidBlock { height: block.height, reason };
+
+		let signature = ed25519_dalek::Signature::from_slice(&block.signature)
+			.map_err(|_| invalid("missing or malformed sequencer signature".to_string()))?;
+		self.verifying_key
+			.verify(&serialize_block_header(block.height, &block.block_id), &signature)
+			.map_err(|_| invalid("invalid sequencer signature".to_string()))?;
+
+		// Headers only blocks have no data to check.
+		if !block.data.is_empty() {
+			let inner: Block = bcs::from_bytes(&block.data)
+				.map_err(|err| invalid(format!("block data can't be deserialized: {err}")))?;
+			if !inner.verify_id() || inner.id().as_bytes()[..] != block.block_id[..] {
+				return Err(invalid("block data doesn't match the block id".to_string()));
+			}
+			match &self.last_block {
+				Some((last_height, last_id))
+					if *last_height + 1 == block.height
+						&& inner.parent().as_bytes()[..] != last_id[..] =>
+				{
+					return Err(invalid(format!(
+						"parent id doesn't match the block at height {last_height}"
+					)));
+				}
+				_ => (),
+			}
+		}
+		self.last_block = Some((block.height, block.block_id.clone()));
+		Ok(())
+	}
+}
+
 /// Grpc implementation of the DA Sequencer client
 #[derive(Debug, Clone)]
 pub struct GrpcDaSequencerClient {
 	client: DaSequencerNodeServiceClient<Channel>,
 	pub stream_heartbeat_interval_sec: u64,
+	// If set, the received blocks must be signed with this key.
+	block_verifying_key: Option<VerifyingKey>,
 }
 
 impl GrpcDaSequencerClient {
@@ -77,7 +204,11 @@ impl GrpcDaSequencerClient {
 		for _ in 0..5 {
 			match GrpcDaSequencerClient::connect(connection_url.clone()).await {
 				Ok(client) => {
-					return Ok(GrpcDaSequencerClient { client, stream_heartbeat_interval_sec });
+					return Ok(GrpcDaSequencerClient {
+						client,
+						stream_heartbeat_interval_sec,
+						block_verifying_key: None,
+					});
 				}
 				Err(err) => {
 					tracing::warn!(
@@ -94,6 +225,13 @@ impl GrpcDaSequencerClient {
 		))
 	}
 
+	/// Verify the signature and the parent chaining of the received blocks
+	/// with the sequencer block verifying key.
+	pub fn with_block_verifying_key(mut self, block_verifying_key: Option<VerifyingKey>) -> Self {
+		self.block_verifying_key = block_verifying_key;
+		self
+	}
+
 	/// Connects to a da sequencer node service using the given connection string.
 	async fn connect(
 		connection_url: Url,
@@ -154,6 +292,7 @@ impl DaSequencerClient for GrpcDaSequencerClient {
 			}
 		});
 
+		let mut verifier = self.block_verifying_key.map(BlockVerifier::new);
 		let output = async_stream::try_stream! {
 			// Block da height is monotonic.
 			let mut expected_height = start_height;
@@ -174,6 +313,12 @@ impl DaSequencerClient for GrpcDaSequencerClient {
 										// The client re connection will detect end of heartbeat and reconnect.
 										break;
 									} else {
+										if let Some(verifier) = verifier.as_mut() {
+											if let Err(err) = verifier.verify(&block) {
+												tracing::error!("Da sequencer block rejected: {err}");
+												Err(err)?;
+											}
+										}
 										expected_height +=1;
 										*last_heartbeat_time.lock().await = Instant::now();
 										yield block;
@@ -206,13 +351,43 @@ impl DaSequencerClient for GrpcDaSequencerClient {
 		Ok((Box::pin(output) as StreamReadBlockFromHeight, alert_rx))
 	}
 
+	/// Stream reads the blocks of a height range.
+	async fn stream_read_range(
+		&mut self,
+		request: StreamReadRangeRequest,
+	) -> Result<StreamReadBlockRange, ClientDaSequencerError> {
+		let response = self
+			.client
+			.stream_read_range(request)
+			.await
+			.map_err(|err| ClientDaSequencerError::FailToOpenBlockStream(err.to_string()))?;
+
+		let mut verifier = self.block_verifying_key.map(BlockVerifier::new);
+		let output = response.into_inner().filter_map(move |response| match response {
+			Ok(response) => match response.response.and_then(|response| response.block_type) {
+				Some(block_response::BlockType::BlockV1(block)) => match verifier.as_mut() {
+					Some(verifier) => Some(verifier.verify(&block).map(|_| block)),
+					None => Some(Ok(block)),
+				},
+				// Range streams don't send heartbeats.
+				Some(block_response::BlockType::Heartbeat(_)) => None,
+				None => Some(Err(ClientDaSequencerError::BlockRangeStream(
+					"Da sequencer range stream returned an empty response".to_string(),
+				))),
+			},
+			Err(err) => Some(Err(ClientDaSequencerError::BlockRangeStream(err.to_string()))),
+		});
+
+		Ok(Box::pin(output) as StreamReadBlockRange)
+	}
+
 	/// Writes a batch of transactions to the Da Sequencer node
 	async fn batch_write(
 		&mut self,
 		request: movement_da_sequencer_proto::BatchWriteRequest,
-	) -> Result<BatchWriteResponse, tonic::Status> {
+	) -> Result<BatchWriteResponse, BatchWriteError> {
 		let response = self.client.batch_write(request).await?;
-		Ok(response.into_inner())
+		check_batch_write_response(response.into_inner())
 	}
 
 	async fn send_state(
@@ -235,8 +410,16 @@ impl DaSequencerClient for GrpcDaSequencerClient {
 
 	async fn read_at_height(&mut self, height: u64) -> Result<ReadAtHeightResponse, tonic::Status> {
 		let request = movement_da_sequencer_proto::ReadAtHeightRequest { height };
-		let response = self.client.read_at_height(request).await?;
-		Ok(response.into_inner())
+		let response = self.client.read_at_height(request).await?.into_inner();
+		if let (Some(verifying_key), Some(block_response::BlockType::BlockV1(block))) = (
+			self.block_verifying_key,
+			response.response.as_ref().and_then(|response| response.block_type.as_ref()),
+		) {
+			BlockVerifier::new(verifying_key)
+				.verify(block)
+				.map_err(|err| tonic::Status::data_loss(err.to_string()))?;
+		}
+		Ok(response)
 	}
 }
 
@@ -248,6 +431,14 @@ pub fn serialize_node_state(state: &movement_da_sequencer_proto::MainNodeState)
 	serialized
 }
 
+/// Serializes the block header signed by the DA Sequencer.
+pub fn serialize_block_header(height: u64, block_id: &[u8]) -> Vec<u8> {
+	let mut serialized: Vec<u8> = Vec::with_capacity(8 + block_id.len());
+	serialized.extend_from_slice(&height.to_le_bytes());
+	serialized.extend_from_slice(block_id);
+	serialized
+}
+
 /// Signs and encodes a batch for submission to the DA Sequencer.
 pub async fn sign_and_encode_batch(
 	batch_data: Vec<u8>,
@@ -317,19 +508,27 @@ impl DaSequencerClient for EmptyDaSequencerClient {
 		Ok((Box::pin(never_ending_stream), alert_rx))
 	}
 
+	/// Stream reads the blocks of a height range.
+	async fn stream_read_range(
+		&mut self,
+		_request: StreamReadRangeRequest,
+	) -> Result<StreamReadBlockRange, ClientDaSequencerError> {
+		Ok(Box::pin(stream::empty()))
+	}
+
 	/// Writes a batch of transactions to the Da Sequencer node
 	async fn batch_write(
 		&mut self,
 		_request: movement_da_sequencer_proto::BatchWriteRequest,
-	) -> Result<BatchWriteResponse, tonic::Status> {
-		Ok(BatchWriteResponse { answer: true })
+	) -> Result<BatchWriteResponse, BatchWriteError> {
+		Ok(BatchWriteResponse::accepted())
 	}
 	async fn send_state(
 		&mut self,
 		_signer: &LoadedSigner<Ed25519>,
 		_state: movement_da_sequencer_proto::MainNodeState,
 	) -> Result<BatchWriteResponse, tonic::Status> {
-		Ok(BatchWriteResponse { answer: true })
+		Ok(BatchWriteResponse::accepted())
 	}
 
 	async fn read_at_height(
@@ -1,8 +1,11 @@
 use ed25519_dalek::VerifyingKey;
 use godfig::env_default;
 use hex::FromHex;
+use movement_signer::key::TryFromCanonicalString;
+use movement_signer_loader::identifiers::SignerIdentifier;
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
+use std::path::PathBuf;
 
 pub const DA_SEQUENCER_DIR: &str = "da-sequencer";
 
@@ -32,8 +35,177 @@ pub struct DaSequencerConfig {
 	#[serde(default)]
 	pub main_node_verifying_key: Option<String>,
 
+	/// Ed25519 key used to sign the header of each produced block.
+	/// If it's not set, the canonical string in `MOVEMENT_DA_SEQUENCER_BLOCK_SIGNER_IDENTIFIER`
+	/// is used, and blocks are not signed if neither is set.
+	#[serde(default)]
+	pub block_signer_identifier: Option<SignerIdentifier>,
+
 	#[serde(default = "default_healthcheck_bind_port")]
 	pub healthcheck_bind_port: u16,
+
+	/// Number of produced blocks and heartbeats buffered for each follower stream.
+	/// A follower that falls behind the buffer is caught up from the storage.
+	#[serde(default = "default_follower_buffer_size")]
+	pub follower_buffer_size: usize,
+
+	#[serde(default)]
+	pub external_da: ExternalDaConfig,
+
+	#[serde(default)]
+	pub block_assembly: BlockAssemblyConfig,
+
+	/// Limits applied to `BatchWrite` requests. Reloaded from the config file while running.
+	#[serde(default)]
+	pub batch_write_limits: BatchWriteLimitsConfig,
+
+	/// Pruning of the old blocks once they are confirmed on the external DA.
+	#[serde(default)]
+	pub retention: RetentionConfig,
+}
+
+/// Retention policy of the produced blocks.
+/// Only blocks confirmed on the external DA are pruned, the most recent ones are always kept.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RetentionConfig {
+	/// Number of most recent blocks kept in the storage. 0 disables the pruning.
+	#[serde(default = "default_retention_keep_blocks")]
+	pub keep_blocks: u64,
+
+	/// Interval between two pruning passes.
+	#[serde(default = "default_retention_prune_interval_sec")]
+	pub prune_interval_sec: u64,
+
+	/// Maximum number of blocks pruned in one pass. It is also the size of an archive segment.
+	#[serde(default = "default_retention_max_blocks_per_prune")]
+	pub max_blocks_per_prune: u64,
+
+	/// If set, pruned blocks are first exported to compressed archive segments in this directory.
+	/// A relative path is resolved from the `.movement` directory.
+	#[serde(default)]
+	pub archive_dir: Option<PathBuf>,
+}
+
+impl Default for RetentionConfig {
+	fn default() -> Self {
+		Self {
+			keep_blocks: default_retention_keep_blocks(),
+			prune_interval_sec: default_retention_prune_interval_sec(),
+			max_blocks_per_prune: default_retention_max_blocks_per_prune(),
+			archive_dir: None,
+		}
+	}
+}
+
+/// Backpressure settings for batches written by the whitelisted full nodes.
+/// A value of 0 disables the corresponding limit.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BatchWriteLimitsConfig {
+	/// Sustained batch bytes per second accepted from one verifying key.
+	#[serde(default = "default_max_batch_bytes_per_sec")]
+	pub max_bytes_per_sec: u64,
+
+	/// Sustained batches per second accepted from one verifying key.
+	#[serde(default = "default_max_batches_per_sec")]
+	pub max_batches_per_sec: u64,
+
+	/// Maximum number of transactions waiting in the pending pool for a block.
+	#[serde(default = "default_max_pending_transactions")]
+	pub max_pending_transactions: u64,
+}
+
+impl Default for BatchWriteLimitsConfig {
+	fn default() -> Self {
+		Self {
+			max_bytes_per_sec: default_max_batch_bytes_per_sec(),
+			max_batches_per_sec: default_max_batches_per_sec(),
+			max_pending_transactions: default_max_pending_transactions(),
+		}
+	}
+}
+
+/// Defines how pending transactions are selected when a block is produced.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BlockAssemblyConfig {
+	#[serde(default)]
+	pub policy: BlockAssemblyPolicy,
+
+	/// Maximum number of transactions in one block.
+	#[serde(default = "default_max_transactions_per_block")]
+	pub max_transactions_per_block: u64,
+
+	/// Number of transactions taken from each submitter per round with the round robin policy.
+	#[serde(default = "default_round_robin_quota")]
+	pub round_robin_quota: u64,
+
+	/// The policies other than Fifo choose among the oldest pending transactions, up to
+	/// `scan_factor` times the limits of a block.
+	#[serde(default = "default_block_assembly_scan_factor")]
+	pub scan_factor: u64,
+}
+
+impl Default for BlockAssemblyConfig {
+	fn default() -> Self {
+		Self {
+			policy: BlockAssemblyPolicy::default(),
+			max_transactions_per_block: default_max_transactions_per_block(),
+			round_robin_quota: default_round_robin_quota(),
+			scan_factor: default_block_assembly_scan_factor(),
+		}
+	}
+}
+
+/// All policies are deterministic: the same pending set always produces the same block.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockAssemblyPolicy {
+	/// Pending transactions are taken in batch arrival order.
+	/// Only the head of the pending pool is read, the other policies read `scan_factor` blocks.
+	#[default]
+	Fifo,
+	/// Submitters (whitelisted batch signing keys) are served in turn,
+	/// each one in batch arrival order, up to `round_robin_quota` transactions per round.
+	RoundRobin,
+	/// Highest Aptos gas unit price first across senders, batch arrival order between equal
+	/// prices. The transactions of a sender stay in sequence number order.
+	GasPrice,
+}
+
+/// Selects the external DA the sequencer anchors its block ids to.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExternalDaConfig {
+	/// In-memory mock that accepts every block and never confirms anything.
+	#[default]
+	Mock,
+	/// A Celestia node reached through its RPC endpoint.
+	Celestia {
+		#[serde(default = "default_celestia_connection_url")]
+		connection_url: String,
+		#[serde(default)]
+		auth_token: Option<String>,
+		/// Hex encoded v0 namespace id, with or without the 0x prefix. Defaults to `movement`.
+		#[serde(default = "default_celestia_namespace")]
+		namespace: String,
+	},
+	/// A local stand-in DA stored on disk, for devnets and operators without Celestia access.
+	Local {
+		#[serde(default)]
+		backend: LocalDaBackend,
+		#[serde(default = "default_local_da_relative_path")]
+		db_relative_path: String,
+	},
+}
+
+/// Storage provider used by the local stand-in DA.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LocalDaBackend {
+	/// Blobs are appended at increasing heights by the `disk-fifo` provider.
+	#[default]
+	DiskFifo,
+	/// Blobs are kept in the `digest-store` provider on top of a `disk-fifo` DA.
+	DigestStore,
 }
 
 impl DaSequencerConfig {
@@ -52,6 +224,20 @@ impl DaSequencerConfig {
 			})
 			.transpose()
 	}
+
+	/// The block signer, from the config or else from its canonical string in the environment,
+	/// e.g. `local::<private key hex>`.
+	pub fn get_block_signer_identifier(&self) -> Result<Option<SignerIdentifier>, anyhow::Error> {
+		if let Some(identifier) = &self.block_signer_identifier {
+			return Ok(Some(identifier.clone()));
+		}
+		match std::env::var(BLOCK_SIGNER_IDENTIFIER_ENV) {
+			Ok(val) => SignerIdentifier::try_from_canonical_string(&val)
+				.map(Some)
+				.map_err(|e| anyhow::anyhow!("Invalid {BLOCK_SIGNER_IDENTIFIER_ENV}: {e}")),
+			Err(_) => Ok(None),
+		}
+	}
 }
 
 env_default!(
@@ -75,7 +261,10 @@ env_default!(
 	u64,
 	10
 );
+const BLOCK_SIGNER_IDENTIFIER_ENV: &str = "MOVEMENT_DA_SEQUENCER_BLOCK_SIGNER_IDENTIFIER";
+
 env_default!(default_healthcheck_bind_port, "MOVEMENT_DA_HEALTHCHECK_PORT", u16, 30931);
+env_default!(default_follower_buffer_size, "MOVEMENT_DA_FOLLOWER_BUFFER_SIZE", usize, 256);
 
 env_default!(
 	default_whitelist_relative_path,
@@ -89,6 +278,58 @@ env_default!(
 	String,
 	"da-store".to_string()
 );
+env_default!(
+	default_celestia_connection_url,
+	"MOVEMENT_DA_CELESTIA_CONNECTION_URL",
+	String,
+	"http://127.0.0.1:26658".to_string()
+);
+env_default!(
+	default_celestia_namespace,
+	"MOVEMENT_DA_CELESTIA_NAMESPACE",
+	String,
+	"0x6d6f76656d656e74".to_string()
+);
+env_default!(
+	default_local_da_relative_path,
+	"MOVEMENT_DA_LOCAL_DA_RELATIVE_PATH",
+	String,
+	"local-da-store".to_string()
+);
+env_default!(
+	default_max_transactions_per_block,
+	"MOVEMENT_DA_MAX_TRANSACTIONS_PER_BLOCK",
+	u64,
+	10_000
+);
+env_default!(default_round_robin_quota, "MOVEMENT_DA_ROUND_ROBIN_QUOTA", u64, 100);
+env_default!(default_block_assembly_scan_factor, "MOVEMENT_DA_BLOCK_ASSEMBLY_SCAN_FACTOR", u64, 4);
+env_default!(
+	default_max_batch_bytes_per_sec,
+	"MOVEMENT_DA_MAX_BATCH_BYTES_PER_SEC",
+	u64,
+	32 * 1024 * 1024
+);
+env_default!(default_max_batches_per_sec, "MOVEMENT_DA_MAX_BATCHES_PER_SEC", u64, 20);
+env_default!(
+	default_max_pending_transactions,
+	"MOVEMENT_DA_MAX_PENDING_TRANSACTIONS",
+	u64,
+	1_000_000
+);
+env_default!(default_retention_keep_blocks, "MOVEMENT_DA_RETENTION_KEEP_BLOCKS", u64, 0);
+env_default!(
+	default_retention_prune_interval_sec,
+	"MOVEMENT_DA_RETENTION_PRUNE_INTERVAL_SEC",
+	u64,
+	60
+);
+env_default!(
+	default_retention_max_blocks_per_prune,
+	"MOVEMENT_DA_RETENTION_MAX_BLOCKS_PER_PRUNE",
+	u64,
+	1_000
+);
 
 impl Default for DaSequencerConfig {
 	fn default() -> Self {
@@ -99,7 +340,13 @@ impl Default for DaSequencerConfig {
 			whitelist_relative_path: default_whitelist_relative_path(),
 			db_storage_relative_path: default_db_storage_relative_path(),
 			main_node_verifying_key: None,
+			block_signer_identifier: None,
 			healthcheck_bind_port: default_healthcheck_bind_port(),
+			follower_buffer_size: default_follower_buffer_size(),
+			external_da: ExternalDaConfig::default(),
+			block_assembly: BlockAssemblyConfig::default(),
+			batch_write_limits: BatchWriteLimitsConfig::default(),
+			retention: RetentionConfig::default(),
 		}
 	}
 }
@@ -0,0 +1,300 @@
+use aptos_types::account_address::AccountAddress;
+use aptos_types::transaction::SignedTransaction as AptosTransaction;
+use movement_da_sequencer_config::{BlockAssemblyConfig, BlockAssemblyPolicy};
+use movement_types::transaction::Transaction;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, VecDeque};
+
+/// Submitter used for pending transactions stored without their batch signer.
+pub const UNKNOWN_SUBMITTER: [u8; 32] = [0; 32];
+
+/// A pending transaction that can be included in the next block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingCandidate {
+	/// The pending transaction storage key. Candidates are provided in key order.
+	pub key: Vec<u8>,
+	/// The verifying key of the whitelisted full node that submitted the batch.
+	pub submitter: [u8; 32],
+	pub tx: Transaction,
+	/// The serialized size of the transaction.
+	pub size: u64,
+}
+
+impl PendingCandidate {
+	/// The Aptos sender, sequence number and gas unit price of the transaction,
+	/// None if the payload is not an Aptos transaction.
+	fn aptos_ordering(&self) -> Option<(AccountAddress, u64, u64)> {
+		bcs::from_bytes::<AptosTransaction>(self.tx.data())
+			.map(|tx| (tx.sender(), tx.sequence_number(), tx.gas_unit_price()))
+			.ok()
+	}
+}
+
+/// Tracks the limits of the block being assembled.
+struct BlockLimits {
+	max_size: u64,
+	max_count: u64,
+	size: u64,
+	count: u64,
+}
+
+impl BlockLimits {
+	fn new(max_size: u64, max_count: u64) -> Self {
+		BlockLimits { max_size, max_count, size: 0, count: 0 }
+	}
+
+	fn is_full(&self) -> bool {
+		self.count >= self.max_count
+	}
+
+	fn fits(&self, candidate: &PendingCandidate) -> bool {
+		!self.is_full() && self.size + candidate.size <= self.max_size
+	}
+
+	fn add(&mut self, candidate: &PendingCandidate) {
+		self.size += candidate.size;
+		self.count += 1;
+	}
+}
+
+/// Select the transactions of the next block from the pending candidates, given in storage key order.
+///
+/// The result only depends on the candidates and the config, so any follower can audit the selection.
+pub fn select_transactions(
+	candidates: Vec<PendingCandidate>,
+	config: &BlockAssemblyConfig,
+	max_block_size: u64,
+) -> Vec<PendingCandidate> {
+	let mut limits = BlockLimits::new(max_block_size, config.max_transactions_per_block);
+	match config.policy {
+		BlockAssemblyPolicy::Fifo => select_fifo(candidates, &mut limits),
+		BlockAssemblyPolicy::RoundRobin => {
+			select_round_robin(candidates, &mut limits, config.round_robin_quota.max(1))
+		}
+		BlockAssemblyPolicy::GasPrice => select_gas_price(candidates, &mut limits),
+	}
+}
+
+fn select_fifo(
+	candidates: Vec<PendingCandidate>,
+	limits: &mut BlockLimits,
+) -> Vec<PendingCandidate> {
+	let mut selected = vec![];
+	for candidate in candidates {
+		if !limits.fits(&candidate) {
+			break;
+		}
+		limits.add(&candidate);
+		selected.push(candidate);
+	}
+	selected
+}
+
+fn select_round_robin(
+	candidates: Vec<PendingCandidate>,
+	limits: &mut BlockLimits,
+	quota: u64,
+) -> Vec<PendingCandidate> {
+	// Submitters are served in key order, each queue keeps the batch arrival order.
+	let mut queues: BTreeMap<[u8; 32], VecDeque<PendingCandidate>> = BTreeMap::new();
+	for candidate in candidates {
+		queues.entry(candidate.submitter).or_default().push_back(candidate);
+	}
+
+	let mut selected = vec![];
+	while !queues.is_empty() && !limits.is_full() {
+		queues.retain(|_, queue| {
+			let mut taken = 0;
+			while taken < quota {
+				match queue.front() {
+					Some(candidate) if limits.fits(candidate) => {
+						let candidate = queue.pop_front().expect("front exists");
+						limits.add(&candidate);
+						selected.push(candidate);
+						taken += 1;
+					}
+					// A transaction that doesn't fit ends the submitter turn for this block
+					// so its transactions are never reordered.
+					Some(_) => {
+						queue.clear();
+						break;
+					}
+					None => break,
+				}
+			}
+			!queue.is_empty()
+		});
+	}
+	selected
+}
+
+fn select_gas_price(
+	candidates: Vec<PendingCandidate>,
+	limits: &mut BlockLimits,
+) -> Vec<PendingCandidate> {
+	// Queue the transactions per sender in sequence number order, so the price only orders
+	// transactions across senders. Payloads that are not Aptos transactions get their own
+	// queue at price 0.
+	let mut queues: BTreeMap<Option<AccountAddress>, Vec<(u64, u64, PendingCandidate)>> =
+		BTreeMap::new();
+	let mut standalone = vec![];
+	for candidate in candidates {
+		match candidate.aptos_ordering() {
+			Some((sender, sequence_number, price)) => queues
+				.entry(Some(sender))
+				.or_default()
+				.push((sequence_number, price, candidate)),
+			None => standalone.push(vec![(0, 0, candidate)]),
+		}
+	}
+	let mut queues: Vec<VecDeque<(u64, u64, PendingCandidate)>> = queues
+		.into_values()
+		.chain(standalone)
+		.map(|mut queue| {
+			// The sort is stable so a resubmitted sequence number stays in key order.
+			queue.sort_by_key(|(sequence_number, _, _)| *sequence_number);
+			queue.into()
+		})
+		.collect();
+
+	// Take the head with the highest price, the one with the lowest key between equal prices.
+	let mut heads = BinaryHeap::new();
+	for (index, queue) in queues.iter().enumerate() {
+		if let Some((_, price, candidate)) = queue.front() {
+			heads.push((*price, Reverse(candidate.key.clone()), index));
+		}
+	}
+
+	let mut selected = vec![];
+	while let Some((_, _, index)) = heads.pop() {
+		if limits.is_full() {
+			break;
+		}
+		let queue = &mut queues[index];
+		let (_, _, candidate) = queue.pop_front().expect("queue has a head");
+		// A transaction that doesn't fit ends the sender turn for this block,
+		// the next sequence numbers would fail without it.
+		if !limits.fits(&candidate) {
+			continue;
+		}
+		limits.add(&candidate);
+		selected.push(candidate);
+		if let Some((_, price, candidate)) = queue.front() {
+			heads.push((*price, Reverse(candidate.key.clone()), index));
+		}
+	}
+	selected
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tests::{
+		create_aptos_transaction_with_gas_price, create_aptos_transaction_with_sender,
+	};
+
+	fn candidate(index: u8, submitter: u8, size: u64) -> PendingCandidate {
+		PendingCandidate {
+			key: vec![index],
+			submitter: [submitter; 32],
+			tx: Transaction::test_only_new(vec![index], 0, index as u64),
+			size,
+		}
+	}
+
+	fn keys(selected: &[PendingCandidate]) -> Vec<u8> {
+		selected.iter().map(|c| c.key[0]).collect()
+	}
+
+	fn config(policy: BlockAssemblyPolicy, max_count: u64, quota: u64) -> BlockAssemblyConfig {
+		BlockAssemblyConfig {
+			policy,
+			max_transactions_per_block: max_count,
+			round_robin_quota: quota,
+			scan_factor: 1,
+		}
+	}
+
+	#[test]
+	fn test_fifo_stops_at_size_and_count_limits() {
+		let candidates: Vec<_> = (0..5).map(|i| candidate(i, 1, 10)).collect();
+
+		let selected =
+			select_transactions(candidates.clone(), &config(BlockAssemblyPolicy::Fifo, 3, 1), 1000);
+		assert_eq!(keys(&selected), vec![0, 1, 2]);
+
+		let selected =
+			select_transactions(candidates, &config(BlockAssemblyPolicy::Fifo, 100, 1), 25);
+		assert_eq!(keys(&selected), vec![0, 1]);
+	}
+
+	#[test]
+	fn test_round_robin_shares_block_between_submitters() {
+		// Submitter 1 floods the pending pool before submitter 2 sends anything.
+		let mut candidates: Vec<_> = (0..10).map(|i| candidate(i, 1, 10)).collect();
+		candidates.extend((10..13).map(|i| candidate(i, 2, 10)));
+
+		let selected =
+			select_transactions(candidates, &config(BlockAssemblyPolicy::RoundRobin, 6, 2), 1000);
+		assert_eq!(keys(&selected), vec![0, 1, 10, 11, 2, 3]);
+	}
+
+	#[test]
+	fn test_round_robin_never_reorders_a_submitter() {
+		let candidates = vec![candidate(0, 1, 10), candidate(1, 1, 100), candidate(2, 1, 10)];
+		let selected =
+			select_transactions(candidates, &config(BlockAssemblyPolicy::RoundRobin, 10, 10), 50);
+		assert_eq!(keys(&selected), vec![0]);
+	}
+
+	#[test]
+	fn test_gas_price_orders_by_price_then_arrival() {
+		let candidates: Vec<_> = [5, 20, 5, 10]
+			.into_iter()
+			.enumerate()
+			.map(|(i, price)| {
+				let data = bcs::to_bytes(&create_aptos_transaction_with_gas_price(price)).unwrap();
+				PendingCandidate {
+					key: vec![i as u8],
+					submitter: UNKNOWN_SUBMITTER,
+					tx: Transaction::test_only_new(data, 0, i as u64),
+					size: 10,
+				}
+			})
+			.collect();
+
+		let selected =
+			select_transactions(candidates, &config(BlockAssemblyPolicy::GasPrice, 3, 1), 1000);
+		assert_eq!(keys(&selected), vec![1, 3, 0]);
+	}
+
+	#[test]
+	fn test_gas_price_keeps_the_sequence_number_order_of_a_sender() {
+		let sender = AccountAddress::random();
+		let transaction = |i: u8, sender, sequence_number, price| {
+			let data = bcs::to_bytes(&create_aptos_transaction_with_sender(
+				sender,
+				sequence_number,
+				price,
+			))
+			.unwrap();
+			PendingCandidate {
+				key: vec![i],
+				submitter: UNKNOWN_SUBMITTER,
+				tx: Transaction::test_only_new(data, 0, i as u64),
+				size: 10,
+			}
+		};
+		let other = AccountAddress::random();
+		// The sender bumped the price of its second transaction above the other sender.
+		let candidates = vec![
+			transaction(0, sender, 0, 5),
+			transaction(1, sender, 1, 50),
+			transaction(2, other, 0, 10),
+		];
+
+		let selected =
+			select_transactions(candidates, &config(BlockAssemblyPolicy::GasPrice, 10, 1), 1000);
+		assert_eq!(keys(&selected), vec![2, 0, 1]);
+	}
+}
@@ -22,18 +22,28 @@ impl FullNodeTxs {
 	}
 
 	// Validate all batch Txs signatures.
-	// Return an error if any signature is invalid.
+	// Return an error with the index of every Tx that can't be deserialized or has an invalid signature.
 	// If any Tx is wrong, the whole batch is rejected.
 	fn validate_txs(&self) -> Result<(), DaSequencerError> {
-		self.0.iter().try_for_each(|tx| {
-			//Validate batch Tx signature
-			let aptos_transaction: AptosTransaction = bcs::from_bytes(&tx.data())
-				.map_err(|_| DaSequencerError::DeserializationFailure)?;
-
-			aptos_transaction
-				.verify_signature()
-				.map_err(|_| DaSequencerError::InvalidSignature)
-		})
+		let invalid_indices: Vec<u32> = self
+			.0
+			.iter()
+			.enumerate()
+			.filter(|(_, tx)| {
+				//Validate batch Tx signature
+				match bcs::from_bytes::<AptosTransaction>(&tx.data()) {
+					Ok(aptos_transaction) => aptos_transaction.verify_signature().is_err(),
+					Err(_) => true,
+				}
+			})
+			.map(|(index, _)| index as u32)
+			.collect();
+
+		if invalid_indices.is_empty() {
+			Ok(())
+		} else {
+			Err(DaSequencerError::InvalidTransactions(invalid_indices))
+		}
 	}
 }
 
@@ -61,15 +61,32 @@ impl<T: Into<i64>> Add<T> for BlockHeight {
 pub struct SequencerBlock {
 	height: BlockHeight,
 	block: Block,
+	/// Sequencer signature of the block header. Stored apart from the block.
+	#[serde(skip)]
+	signature: Option<Vec<u8>>,
 }
 
 impl SequencerBlock {
 	/// Try to construct a SequencerBlock, but fail if it exceeds the max encoded size.
 	pub fn try_new(height: BlockHeight, block: Block) -> Result<Self, DaSequencerError> {
-		let sb = SequencerBlock { height, block };
+		let sb = SequencerBlock { height, block, signature: None };
 		Ok(sb)
 	}
 
+	/// The block header signed by the sequencer.
+	pub fn header_bytes(&self) -> Vec<u8> {
+		movement_da_sequencer_client::serialize_block_header(self.height.0, self.id().as_bytes())
+	}
+
+	pub fn signature(&self) -> Option<&[u8]> {
+		self.signature.as_deref()
+	}
+
+	pub fn with_signature(mut self, signature: Option<Vec<u8>>) -> Self {
+		self.signature = signature;
+		self
+	}
+
 	pub fn id(&self) -> block::Id {
 		self.block.id()
 	}
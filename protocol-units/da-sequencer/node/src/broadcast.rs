@@ -0,0 +1,225 @@
+//! Fan-out of the produced blocks and heartbeats to the followers streaming from the sequencer.
+//!
+//! The main loop publishes each produced block once. Every follower reads it from a bounded
+//! buffer, so a slow follower can't make the sequencer memory grow. A follower that falls out
+//! of the buffer is caught up from the storage by its stream.
+
+use crate::block::BlockHeight;
+use crate::server::ProducedData;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{
+	atomic::{AtomicU64, Ordering},
+	Arc, Mutex,
+};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Lag of a connected follower.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FollowerLag {
+	pub follower_id: u64,
+	/// Height of the last block sent to the follower.
+	pub last_sent_height: u64,
+	/// Number of produced blocks not sent to the follower yet.
+	pub lag: u64,
+	/// Number of times the follower fell out of its buffer and was caught up from the storage.
+	pub catch_ups: u64,
+}
+
+#[derive(Debug, Default)]
+struct FollowerState {
+	last_sent_height: u64,
+	catch_ups: u64,
+}
+
+type FollowerRegistry = Arc<Mutex<BTreeMap<u64, FollowerState>>>;
+
+/// Publishes the produced data to all followers.
+#[derive(Debug, Clone)]
+pub struct Broadcaster {
+	sender: broadcast::Sender<ProducedData>,
+	followers: FollowerRegistry,
+	next_follower_id: Arc<AtomicU64>,
+	last_published_height: Arc<AtomicU64>,
+}
+
+impl Broadcaster {
+	/// Create a broadcaster buffering `buffer_size` items per follower.
+	/// `current_height` is the height of the last block in the storage.
+	pub fn new(buffer_size: usize, current_height: BlockHeight) -> Self {
+		let (sender, _) = broadcast::channel(buffer_size.max(1));
+		Broadcaster {
+			sender,
+			followers: Arc::new(Mutex::new(BTreeMap::new())),
+			next_follower_id: Arc::new(AtomicU64::new(0)),
+			last_published_height: Arc::new(AtomicU64::new(current_height.0)),
+		}
+	}
+
+	/// Send the data to all connected followers. Never blocks.
+	pub fn publish(&self, data: ProducedData) {
+		if let ProducedData::Block(block, _) = &data {
+			self.last_published_height.fetch_max(block.height().0, Ordering::Relaxed);
+		}
+		// Fails only if no follower is connected.
+		let _ = self.sender.send(data);
+	}
+
+	/// Height of the last published block.
+	pub fn last_published_height(&self) -> BlockHeight {
+		BlockHeight(self.last_published_height.load(Ordering::Relaxed))
+	}
+
+	/// Register a new follower. It receives the data published after this call.
+	pub fn subscribe(&self) -> Follower {
+		let id = self.next_follower_id.fetch_add(1, Ordering::Relaxed);
+		self.followers
+			.lock()
+			.expect("Follower registry lock poisoned")
+			.insert(id, FollowerState::default());
+		Follower {
+			id,
+			receiver: self.sender.subscribe(),
+			followers: Arc::clone(&self.followers),
+			last_published_height: Arc::clone(&self.last_published_height),
+		}
+	}
+
+	pub fn follower_count(&self) -> usize {
+		self.followers.lock().expect("Follower registry lock poisoned").len()
+	}
+
+	/// The lag of each connected follower, in follower id order.
+	pub fn followers_lag(&self) -> Vec<FollowerLag> {
+		let current_height = self.last_published_height.load(Ordering::Relaxed);
+		self.followers
+			.lock()
+			.expect("Follower registry lock poisoned")
+			.iter()
+			.map(|(id, state)| FollowerLag {
+				follower_id: *id,
+				last_sent_height: state.last_sent_height,
+				lag: current_height.saturating_sub(state.last_sent_height),
+				catch_ups: state.catch_ups,
+			})
+			.collect()
+	}
+}
+
+/// What a follower receives from the broadcaster.
+#[derive(Debug)]
+pub enum FollowerEvent {
+	Data(ProducedData),
+	/// The follower fell out of its buffer. The missed blocks must be read from the storage.
+	Lagged,
+	/// The broadcaster is dropped, the sequencer stops.
+	Closed,
+}
+
+/// A follower registration. It is unregistered when dropped.
+#[derive(Debug)]
+pub struct Follower {
+	id: u64,
+	receiver: broadcast::Receiver<ProducedData>,
+	followers: FollowerRegistry,
+	last_published_height: Arc<AtomicU64>,
+}
+
+impl Follower {
+	pub fn id(&self) -> u64 {
+		self.id
+	}
+
+	/// Wait for the next published data.
+	pub async fn recv(&mut self) -> FollowerEvent {
+		match self.receiver.recv().await {
+			Ok(data) => FollowerEvent::Data(data),
+			Err(RecvError::Lagged(skipped)) => {
+				tracing::warn!(
+					follower_id = self.id,
+					"Follower missed {skipped} produced items, catch up from the storage."
+				);
+				if let Some(state) = self
+					.followers
+					.lock()
+					.expect("Follower registry lock poisoned")
+					.get_mut(&self.id)
+				{
+					state.catch_ups += 1;
+				}
+				FollowerEvent::Lagged
+			}
+			Err(RecvError::Closed) => FollowerEvent::Closed,
+		}
+	}
+
+	/// Record the height of the last block sent to the follower.
+	pub fn set_last_sent_height(&self, height: u64) {
+		if let Some(state) = self
+			.followers
+			.lock()
+			.expect("Follower registry lock poisoned")
+			.get_mut(&self.id)
+		{
+			state.last_sent_height = height;
+		}
+	}
+
+	/// Height of the last published block.
+	pub fn last_published_height(&self) -> BlockHeight {
+		BlockHeight(self.last_published_height.load(Ordering::Relaxed))
+	}
+}
+
+impl Drop for Follower {
+	fn drop(&mut self) {
+		if let Ok(mut followers) = self.followers.lock() {
+			followers.remove(&self.id);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::block::SequencerBlock;
+	use movement_types::block::Block;
+
+	fn produced_block(height: u64) -> ProducedData {
+		ProducedData::Block(
+			SequencerBlock::try_new(BlockHeight(height), Block::test()).unwrap(),
+			None,
+		)
+	}
+
+	#[tokio::test]
+	async fn test_lagging_follower_is_reported_and_unregistered() {
+		let broadcaster = Broadcaster::new(2, BlockHeight(0));
+		let mut fast = broadcaster.subscribe();
+		let mut slow = broadcaster.subscribe();
+		assert_eq!(broadcaster.follower_count(), 2);
+
+		for height in 1..=4 {
+			broadcaster.publish(produced_block(height));
+			if let FollowerEvent::Data(ProducedData::Block(block, _)) = fast.recv().await {
+				fast.set_last_sent_height(block.height().0);
+			} else {
+				panic!("Fast follower should receive every block");
+			}
+		}
+
+		// The slow follower buffer only kept the last 2 blocks.
+		assert!(matches!(slow.recv().await, FollowerEvent::Lagged));
+		assert_eq!(slow.last_published_height(), BlockHeight(4));
+		assert_eq!(
+			broadcaster.followers_lag(),
+			vec![
+				FollowerLag { follower_id: 0, last_sent_height: 4, lag: 0, catch_ups: 0 },
+				FollowerLag { follower_id: 1, last_sent_height: 0, lag: 4, catch_ups: 1 },
+			]
+		);
+
+		drop(slow);
+		assert_eq!(broadcaster.follower_count(), 1);
+	}
+}
@@ -1,6 +1,7 @@
-use super::submit::BlobSubmitter;
+use super::submit::{BlobSubmitter, CelestiaBlobSink};
 use super::{BlockSource, CelestiaBlob, CelestiaClientOps, CelestiaHeight, ExternalDaNotification};
 use crate::error::DaSequencerError;
+use celestia_rpc::prelude::*;
 use celestia_rpc::Client as RpcClient;
 use celestia_types::nmt::Namespace;
 use movement_types::block;
@@ -16,7 +17,8 @@ pub enum Error {
 
 #[derive(Clone)]
 pub struct CelestiaClient {
-	_rpc_client: Arc<RpcClient>,
+	rpc_client: Arc<RpcClient>,
+	celestia_namespace: Namespace,
 	_notifier: mpsc::Sender<ExternalDaNotification>,
 	// The sender end of the channel for the background sender task.
 	id_sender: mpsc::Sender<(block::Id, BlockSource)>,
@@ -33,15 +35,13 @@ impl CelestiaClient {
 		let rpc_client = RpcClient::new(&connection_url.to_string(), auth_token).await?;
 		let rpc_client = Arc::new(rpc_client);
 		let (digest_sender, digest_receiver) = mpsc::channel(8);
-		let blob_submitter = BlobSubmitter::new(
-			Arc::clone(&rpc_client),
-			celestia_namespace,
-			digest_receiver,
-			notifier.clone(),
-		);
+		let sink =
+			CelestiaBlobSink { celestia_client: Arc::clone(&rpc_client), celestia_namespace };
+		let blob_submitter = BlobSubmitter::new(sink, digest_receiver, notifier.clone());
 		tokio::spawn(blob_submitter.run());
 		Ok(CelestiaClient {
-			_rpc_client: rpc_client,
+			rpc_client,
+			celestia_namespace,
 			_notifier: notifier,
 			id_sender: digest_sender,
 		})
@@ -51,9 +51,30 @@ impl CelestiaClient {
 impl CelestiaClientOps for CelestiaClient {
 	async fn get_blob_at_height(
 		&self,
-		_height: CelestiaHeight,
+		height: CelestiaHeight,
 	) -> Result<Option<CelestiaBlob>, DaSequencerError> {
-		todo!()
+		let blobs = self
+			.rpc_client
+			.blob_get_all(height.into(), &[self.celestia_namespace])
+			.await
+			.map_err(|e| {
+			DaSequencerError::ExternalDaBootstrap(format!(
+				"Failed to get blobs at Celestia height {height}: {e}"
+			))
+		})?;
+
+		// Blobs submitted in the same Celestia block are merged in inclusion order.
+		let blobs = match blobs {
+			Some(blobs) if !blobs.is_empty() => blobs,
+			_ => return Ok(None),
+		};
+		let mut block_ids = vec![];
+		for blob in blobs {
+			let blob: CelestiaBlob = bcs::from_bytes(&blob.data)
+				.map_err(|e| DaSequencerError::Deserialization(e.to_string()))?;
+			block_ids.extend(blob);
+		}
+		Ok(Some(CelestiaBlob::from(block_ids)))
 	}
 
 	async fn send_block(
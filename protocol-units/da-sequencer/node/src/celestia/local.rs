@@ -0,0 +1,162 @@
+//! A local stand-in for Celestia backed by the movement DA providers.
+//!
+//! Block ids are stored as digest blobs at increasing heights, so the bootstrap algorithm and the
+//! committed height tracking behave the same way they do with a real Celestia network.
+
+use super::submit::{BlobSink, BlobSubmitter};
+use super::{BlockSource, CelestiaBlob, CelestiaClientOps, CelestiaHeight, ExternalDaNotification};
+use crate::error::DaSequencerError;
+use movement_da_light_node_da::{Certificate, CertificateStream, DaOperations};
+use movement_da_util::blob::ir::blob::DaBlob;
+use movement_signer::cryptography::secp256k1::Secp256k1;
+use movement_types::block;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+use tokio_stream::StreamExt;
+
+/// Curve of the DA providers. Only digest blobs are stored, so it is never used to sign.
+pub type LocalDaCurve = Secp256k1;
+
+#[derive(Clone)]
+pub struct LocalDaClient<D> {
+	da: Arc<D>,
+	// The sender end of the channel for the background submitter task.
+	id_sender: mpsc::Sender<(block::Id, BlockSource)>,
+}
+
+impl<D> LocalDaClient<D>
+where
+	D: DaOperations<LocalDaCurve> + 'static,
+{
+	/// Create the local DA client and spawn the task that submits block ids to the DA.
+	pub fn new(da: D, notifier: mpsc::Sender<ExternalDaNotification>) -> Self {
+		let da = Arc::new(da);
+		let (id_sender, id_receiver) = mpsc::channel(8);
+		tokio::spawn(run_submitter(Arc::clone(&da), id_receiver, notifier));
+		LocalDaClient { da, id_sender }
+	}
+}
+
+impl<D> CelestiaClientOps for LocalDaClient<D>
+where
+	D: DaOperations<LocalDaCurve> + 'static,
+{
+	async fn get_blob_at_height(
+		&self,
+		height: CelestiaHeight,
+	) -> Result<Option<CelestiaBlob>, DaSequencerError> {
+		let blobs = self
+			.da
+			.get_da_blobs_at_height(height.into())
+			.await
+			.map_err(|e| DaSequencerError::ExternalDa(e.to_string()))?;
+
+		if blobs.is_empty() {
+			return Ok(None);
+		}
+		let mut block_ids = vec![];
+		for blob in blobs {
+			let blob = match blob {
+				DaBlob::DigestV1(data) => bcs::from_bytes::<CelestiaBlob>(&data)
+					.map_err(|e| DaSequencerError::Deserialization(e.to_string()))?,
+				DaBlob::SignedV1(_) => {
+					return Err(DaSequencerError::ExternalDa(format!(
+						"Unexpected signed blob at local DA height {height}"
+					)))
+				}
+			};
+			block_ids.extend(blob);
+		}
+		Ok(Some(CelestiaBlob::from(block_ids)))
+	}
+
+	async fn send_block(
+		&self,
+		block_id: block::Id,
+		source: BlockSource,
+	) -> Result<(), DaSequencerError> {
+		self.id_sender
+			.send((block_id, source))
+			.await
+			.map_err(|_| DaSequencerError::SendFailure)
+	}
+}
+
+/// Posts the blobs to the DA, the height of a blob is the height of the next DA certificate.
+struct LocalBlobSink<'a, D> {
+	da: &'a D,
+	certificates: Mutex<CertificateStream<'a>>,
+}
+
+impl<D> BlobSink for LocalBlobSink<'_, D>
+where
+	D: DaOperations<LocalDaCurve>,
+{
+	async fn submit(&self, block_ids: Vec<block::Id>) -> Result<CelestiaHeight, anyhow::Error> {
+		let data = bcs::to_bytes(&CelestiaBlob::from(block_ids))?;
+		// Hold the certificates while submitting, so the certificate is the one of this blob.
+		let mut certificates = self.certificates.lock().await;
+		self.da.submit_blob(DaBlob::DigestV1(data)).await?;
+		loop {
+			match certificates.next().await {
+				Some(Ok(Certificate::Height(height))) => return Ok(CelestiaHeight::from(height)),
+				Some(Ok(Certificate::Nolo)) => continue,
+				Some(Err(e)) => return Err(e.into()),
+				None => anyhow::bail!("Local DA certificate stream closed"),
+			}
+		}
+	}
+}
+
+/// Submit the received block ids to the DA with the same submitter as Celestia.
+async fn run_submitter<D>(
+	da: Arc<D>,
+	id_receiver: mpsc::Receiver<(block::Id, BlockSource)>,
+	notifier: mpsc::Sender<ExternalDaNotification>,
+) -> Result<(), anyhow::Error>
+where
+	D: DaOperations<LocalDaCurve>,
+{
+	// Subscribe before the first submission so no certificate is missed.
+	let certificates = da.stream_certificates().await?;
+	let sink = LocalBlobSink { da: da.as_ref(), certificates: Mutex::new(certificates) };
+	BlobSubmitter::new(sink, id_receiver, notifier).run().await
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use movement_da_light_node_disk_fifo::da::Da as DiskFifoDa;
+	use movement_types::block::Block;
+
+	#[tokio::test]
+	async fn test_local_da_commits_block_ids_at_increasing_heights() {
+		let temp_dir = tempfile::tempdir().expect("failed to create temp dir");
+		let da = DiskFifoDa::<LocalDaCurve>::try_new(temp_dir.path()).unwrap();
+		let (notifier, mut notification_rx) = mpsc::channel(8);
+		let client = LocalDaClient::new(da, notifier);
+
+		let first_id = Block::default().id();
+		client.send_block(first_id, BlockSource::Input).await.unwrap();
+		let Some(ExternalDaNotification::BlocksCommitted { block_ids, celestia_height }) =
+			notification_rx.recv().await
+		else {
+			panic!("Expected a blocks committed notification");
+		};
+		assert_eq!(block_ids, vec![first_id]);
+		assert_eq!(celestia_height, CelestiaHeight::from(0));
+
+		let second_id = block::Id::new([1; 32]);
+		client.send_block(second_id, BlockSource::Bootstrap).await.unwrap();
+		let Some(ExternalDaNotification::BlocksCommitted { celestia_height, .. }) =
+			notification_rx.recv().await
+		else {
+			panic!("Expected a blocks committed notification");
+		};
+		assert_eq!(celestia_height, CelestiaHeight::from(1));
+
+		let blob = client.get_blob_at_height(1.into()).await.unwrap().unwrap();
+		assert_eq!(blob.to_vec(), vec![second_id]);
+		assert!(client.get_blob_at_height(2.into()).await.unwrap().is_none());
+	}
+}
@@ -1,6 +1,7 @@
 pub mod blob;
 pub mod client;
 pub mod height;
+pub mod local;
 pub mod mock;
 pub mod submit;
 
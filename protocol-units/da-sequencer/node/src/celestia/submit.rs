@@ -12,6 +12,7 @@ use tokio::sync::mpsc;
 use tracing::debug;
 
 use movement_types::block;
+use std::future::Future;
 use std::mem;
 use std::sync::Arc;
 
@@ -19,85 +20,97 @@ use std::sync::Arc;
 // https://docs.celestia.org/how-to-guides/submit-data#maximum-blob-size
 const MAX_CELESTIA_BLOB_SIZE: usize = 512 * 1024;
 
-pub(crate) struct BlobSubmitter {
+/// Where the blob submitter posts the blobs of block ids.
+pub(crate) trait BlobSink: Sync {
+	/// Submit a blob with the block ids and return the height it was included at.
+	fn submit(
+		&self,
+		block_ids: Vec<block::Id>,
+	) -> impl Future<Output = Result<CelestiaHeight, anyhow::Error>> + Send;
+}
+
+/// Posts the blobs to Celestia.
+pub(crate) struct CelestiaBlobSink {
 	// The Celestia RPC client
-	celestia_client: Arc<Client>,
+	pub(crate) celestia_client: Arc<Client>,
 	// The Celestia namespace
-	celestia_namespace: Namespace,
+	pub(crate) celestia_namespace: Namespace,
+}
+
+impl BlobSink for CelestiaBlobSink {
+	async fn submit(&self, block_ids: Vec<block::Id>) -> Result<CelestiaHeight, anyhow::Error> {
+		submit_blob(&self.celestia_client, self.celestia_namespace.clone(), block_ids).await
+	}
+}
+
+pub(crate) struct BlobSubmitter<S> {
+	// Where the blobs are posted
+	sink: S,
 	// Channel to receive digests from foreground
 	id_receiver: mpsc::Receiver<(block::Id, BlockSource)>,
 	// Channel to send notifications from Celestia layer
 	notifier: mpsc::Sender<ExternalDaNotification>,
 }
 
-impl BlobSubmitter {
+impl<S: BlobSink> BlobSubmitter<S> {
 	pub(crate) fn new(
-		celestia_client: Arc<Client>,
-		celestia_namespace: Namespace,
+		sink: S,
 		id_receiver: mpsc::Receiver<(block::Id, BlockSource)>,
 		notifier: mpsc::Sender<ExternalDaNotification>,
 	) -> Self {
-		BlobSubmitter { celestia_client, celestia_namespace, id_receiver, notifier }
+		BlobSubmitter { sink, id_receiver, notifier }
 	}
 
-	pub(crate) async fn run(mut self) -> Result<(), anyhow::Error> {
+	pub(crate) async fn run(self) -> Result<(), anyhow::Error> {
+		let BlobSubmitter { sink, mut id_receiver, notifier } = self;
 		// Digests accumulated while waiting for client to submit
 		let mut buffered_ids: Vec<block::Id> = vec![];
 		// Digests accumulated on bootstrap
 		let mut bootstrap_ids: Vec<block::Id> = vec![];
 		// Size of the accumulated blob data
 		let mut total_data_size = 0;
+		// The digests of the pending submit request
+		let mut submitted_ids: Vec<block::Id> = vec![];
 		let mut submit_request = None;
 		loop {
-			match &mut submit_request {
-				None => {
-					// No request is currently pending.
-					// Grab the accumulated digests, if there are any, and submit them in a blob.
-					// Bootstrap digests should be sent ahead of the digests that arrived with
-					// submit requests.
-					if !buffered_ids.is_empty() || !bootstrap_ids.is_empty() {
-						let mut digests = mem::replace(&mut bootstrap_ids, vec![]);
-						digests.append(&mut buffered_ids);
-						total_data_size = 0;
-						submit_request = Some(Box::pin(submit_blob(
-							&self.celestia_client,
-							self.celestia_namespace.clone(),
-							digests,
-						)));
+			if submit_request.is_none() && (!buffered_ids.is_empty() || !bootstrap_ids.is_empty()) {
+				// No request is currently pending.
+				// Grab the accumulated digests and submit them in a blob.
+				// Bootstrap digests should be sent ahead of the digests that arrived with
+				// submit requests.
+				submitted_ids = mem::take(&mut bootstrap_ids);
+				submitted_ids.append(&mut buffered_ids);
+				total_data_size = 0;
+				submit_request = Some(Box::pin(sink.submit(submitted_ids.clone())));
+			}
+
+			// Always keep draining the channel, so senders never block on an idle submitter.
+			// While a submit request is pending, accumulate blobs and provide back-pressure if
+			// the data size is pushing against the Celestia sanity limit.
+			select! {
+				res = async { submit_request.as_mut().expect("checked by the precondition").await },
+						if submit_request.is_some() => {
+					submit_request = None;
+					let celestia_height = res?;
+					let block_ids = mem::take(&mut submitted_ids);
+					let notification =
+						ExternalDaNotification::BlocksCommitted { block_ids, celestia_height };
+					if notifier.send(notification).await.is_err() {
+						debug!("failed to send notification, shutting down");
+						break;
 					}
 				}
-				Some(pending_request) => {
-					// While a submit request is pending, accumulate blobs.
-					// Provide back-pressure if the data size is pushing
-					// against the Celestia sanity limit.
-					select! {
-						res = pending_request => {
-							let (block_ids, celestia_height) = res?;
-							if self
-								.notifier
-								.send(
-									ExternalDaNotification::BlocksCommitted { block_ids, celestia_height }
-								)
-								.await
-								.is_err() {
-								debug!("failed to send notification, shutting down");
-								break;
-							}
-							submit_request = None;
+				next = id_receiver.recv(),
+						if total_data_size + block::Id::SIZE <= MAX_CELESTIA_BLOB_SIZE => {
+					match next {
+						None => break,
+						Some((id, BlockSource::Input)) => {
+							total_data_size += block::Id::SIZE;
+							buffered_ids.push(id);
 						}
-						next = self.id_receiver.recv(),
-								if total_data_size + block::Id::SIZE <= MAX_CELESTIA_BLOB_SIZE => {
-							match next {
-								None => break,
-								Some((id, BlockSource::Input)) => {
-									total_data_size += block::Id::SIZE;
-									buffered_ids.push(id);
-								}
-								Some((id, BlockSource::Bootstrap)) => {
-									total_data_size += block::Id::SIZE;
-									bootstrap_ids.push(id);
-								}
-							}
+						Some((id, BlockSource::Bootstrap)) => {
+							total_data_size += block::Id::SIZE;
+							bootstrap_ids.push(id);
 						}
 					}
 				}
@@ -111,8 +124,8 @@ async fn submit_blob(
 	celestia_client: &Client,
 	namespace: Namespace,
 	ids: Vec<block::Id>,
-) -> Result<(Vec<block::Id>, CelestiaHeight), anyhow::Error> {
-	let data = CelestiaBlob::from(ids.clone());
+) -> Result<CelestiaHeight, anyhow::Error> {
+	let data = CelestiaBlob::from(ids);
 	let serialized_data = bcs::to_bytes(&data)?;
 	let blob = Blob::new(namespace, serialized_data, AppVersion::V2)?;
 	let config = TxConfig::default();
@@ -121,5 +134,52 @@ async fn submit_blob(
 		.blob_submit(&[blob], config)
 		.await
 		.context("failed to submit the blob")?;
-	Ok((ids, CelestiaHeight::from(celestia_height)))
+	Ok(CelestiaHeight::from(celestia_height))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::atomic::{AtomicU64, Ordering};
+	use std::time::Duration;
+
+	struct CountingSink {
+		height: AtomicU64,
+	}
+
+	impl BlobSink for CountingSink {
+		async fn submit(
+			&self,
+			_block_ids: Vec<block::Id>,
+		) -> Result<CelestiaHeight, anyhow::Error> {
+			Ok(CelestiaHeight::from(self.height.fetch_add(1, Ordering::SeqCst)))
+		}
+	}
+
+	#[tokio::test]
+	async fn test_idle_submitter_drains_the_channel() -> Result<(), anyhow::Error> {
+		let (id_sender, id_receiver) = mpsc::channel(2);
+		let (notifier, mut notification_rx) = mpsc::channel(64);
+		let sink = CountingSink { height: AtomicU64::new(0) };
+		tokio::spawn(BlobSubmitter::new(sink, id_receiver, notifier).run());
+
+		// More ids than the channel holds, sent while the submitter has nothing to submit.
+		let ids: Vec<_> = (0..16u8).map(|i| block::Id::new([i; 32])).collect();
+		for id in &ids {
+			tokio::time::timeout(Duration::from_secs(5), id_sender.send((*id, BlockSource::Input)))
+				.await??;
+		}
+
+		let mut committed = vec![];
+		while committed.len() < ids.len() {
+			match tokio::time::timeout(Duration::from_secs(5), notification_rx.recv()).await? {
+				Some(ExternalDaNotification::BlocksCommitted { block_ids, .. }) => {
+					committed.extend(block_ids)
+				}
+				other => panic!("Unexpected notification {other:?}"),
+			}
+		}
+		assert_eq!(committed, ids);
+		Ok(())
+	}
 }
@@ -25,10 +25,20 @@ pub enum DaSequencerError {
 	InvalidSignature,
 	#[error("Non-whitelisted signer")]
 	UnauthorizedSigner,
+	#[error("Invalid transactions at batch indices {0:?}")]
+	InvalidTransactions(Vec<u32>),
+	#[error("External DA error: {0}")]
+	ExternalDa(String),
 	#[error("Error during bootstrapping the external DA: {0}")]
 	ExternalDaBootstrap(String),
 	#[error("Error during requesting a block: {0}")]
 	BlockRetrieval(String),
+	#[error("Block at height {0} is pruned, fetch it from the archive")]
+	BlockPruned(u64),
+	#[error("Error during block archival: {0}")]
+	Archive(String),
+	#[error("Error during block signing: {0}")]
+	BlockSigning(String),
 	#[error("failed to send block digest to external DA")]
 	SendFailure,
 	#[error("Error during channel messaging: {0}")]
@@ -0,0 +1,117 @@
+use aptos_crypto::HashValue;
+use aptos_types::account_address::AccountAddress;
+use aptos_types::transaction::SignedTransaction as AptosTransaction;
+use movement_da_sequencer_proto::BlockFilter;
+use movement_types::transaction::Transaction;
+use std::collections::HashSet;
+
+/// Server side selection of the transactions of a streamed block.
+///
+/// A transaction matches if its sender or its committed hash is in the filter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionFilter {
+	senders: HashSet<AccountAddress>,
+	hashes: HashSet<HashValue>,
+}
+
+impl TransactionFilter {
+	/// Build the filter from the request one. Return an error message if an address or a hash is malformed.
+	pub fn try_from_proto(filter: BlockFilter) -> Result<Self, String> {
+		let senders = filter
+			.sender_addresses
+			.iter()
+			.map(|address| {
+				AccountAddress::from_bytes(address).map_err(|err| {
+					format!("Invalid sender address 0x{}: {err}", hex::encode(address))
+				})
+			})
+			.collect::<Result<_, _>>()?;
+		let hashes = filter
+			.transaction_hashes
+			.iter()
+			.map(|hash| {
+				HashValue::from_slice(hash).map_err(|err| {
+					format!("Invalid transaction hash 0x{}: {err}", hex::encode(hash))
+				})
+			})
+			.collect::<Result<_, _>>()?;
+		Ok(TransactionFilter { senders, hashes })
+	}
+
+	/// Returns true if no sender and no hash is set. An empty filter is not applied.
+	pub fn is_empty(&self) -> bool {
+		self.senders.is_empty() && self.hashes.is_empty()
+	}
+
+	/// Returns true if the transaction matches the filter.
+	/// Transactions that are not Aptos transactions never match.
+	pub fn matches(&self, tx: &Transaction) -> bool {
+		match bcs::from_bytes::<AptosTransaction>(tx.data()) {
+			Ok(aptos_tx) => {
+				self.senders.contains(&aptos_tx.sender())
+					|| (!self.hashes.is_empty() && self.hashes.contains(&aptos_tx.committed_hash()))
+			}
+			Err(_) => false,
+		}
+	}
+
+	/// Indices in the block order of the transactions that match the filter.
+	pub fn matched_indices<'a>(&self, txs: impl Iterator<Item = &'a Transaction>) -> Vec<u32> {
+		txs.enumerate()
+			.filter(|(_, tx)| self.matches(tx))
+			.map(|(index, _)| index as u32)
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tests::create_aptos_transaction;
+
+	fn to_transaction(aptos_tx: &AptosTransaction, sequence_number: u64) -> Transaction {
+		Transaction::test_only_new(bcs::to_bytes(aptos_tx).unwrap(), 0, sequence_number)
+	}
+
+	#[test]
+	fn test_filter_matches_sender_or_hash() {
+		let aptos_tx = create_aptos_transaction();
+		let other_tx = Transaction::test_only_new(b"not an aptos tx".to_vec(), 0, 1);
+		let txs = vec![other_tx, to_transaction(&aptos_tx, 2)];
+
+		let by_sender = TransactionFilter::try_from_proto(BlockFilter {
+			sender_addresses: vec![aptos_tx.sender().to_vec()],
+			transaction_hashes: vec![],
+		})
+		.unwrap();
+		assert_eq!(by_sender.matched_indices(txs.iter()), vec![1]);
+
+		let by_hash = TransactionFilter::try_from_proto(BlockFilter {
+			sender_addresses: vec![],
+			transaction_hashes: vec![aptos_tx.committed_hash().to_vec()],
+		})
+		.unwrap();
+		assert_eq!(by_hash.matched_indices(txs.iter()), vec![1]);
+
+		let no_match = TransactionFilter::try_from_proto(BlockFilter {
+			sender_addresses: vec![AccountAddress::ONE.to_vec()],
+			transaction_hashes: vec![HashValue::zero().to_vec()],
+		})
+		.unwrap();
+		assert!(no_match.matched_indices(txs.iter()).is_empty());
+	}
+
+	#[test]
+	fn test_filter_rejects_malformed_values() {
+		assert!(TransactionFilter::try_from_proto(BlockFilter {
+			sender_addresses: vec![vec![1, 2, 3]],
+			transaction_hashes: vec![],
+		})
+		.is_err());
+		assert!(TransactionFilter::try_from_proto(BlockFilter {
+			sender_addresses: vec![],
+			transaction_hashes: vec![vec![0; 31]],
+		})
+		.is_err());
+	}
+}
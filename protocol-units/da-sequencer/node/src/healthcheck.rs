@@ -1,9 +1,14 @@
+use crate::broadcast::FollowerLag;
 use anyhow::Error;
 use futures::prelude::*;
 use poem::{
-	get, handler, listener::TcpListener, middleware::Tracing, web::Data, EndpointExt, IntoResponse,
-	Response, Route, Server,
+	get, handler,
+	listener::TcpListener,
+	middleware::Tracing,
+	web::{Data, Json},
+	EndpointExt, IntoResponse, Response, Route, Server,
 };
+use serde::Serialize;
 use std::future::Future;
 use std::sync::Arc;
 use tokio::sync::mpsc;
@@ -12,8 +17,17 @@ use tracing::info;
 
 pub const DEFAULT_REST_LISTENER_HOSTNAME: &str = "0.0.0.0";
 
+/// State of the sequencer main loop sent back to a health check.
+#[derive(Debug, Clone, Serialize)]
+pub struct SequencerHealth {
+	/// Height of the last produced block.
+	pub current_height: u64,
+	/// Lag of each follower streaming blocks.
+	pub followers: Vec<FollowerLag>,
+}
+
 struct HealthCheckContext {
-	check_request_tx: mpsc::Sender<oneshot::Sender<bool>>,
+	check_request_tx: mpsc::Sender<oneshot::Sender<SequencerHealth>>,
 }
 
 pub struct HealthCheckRest {
@@ -24,7 +38,7 @@ pub struct HealthCheckRest {
 impl HealthCheckRest {
 	pub fn new(
 		rest_listener_url: String,
-		check_request_tx: mpsc::Sender<oneshot::Sender<bool>>,
+		check_request_tx: mpsc::Sender<oneshot::Sender<SequencerHealth>>,
 	) -> Result<Self, anyhow::Error> {
 		let context = HealthCheckContext { check_request_tx };
 		Ok(Self { url: rest_listener_url, context: Arc::new(context) })
@@ -39,15 +53,32 @@ impl HealthCheckRest {
 	}
 
 	pub fn create_routes(&self) -> impl EndpointExt {
-		Route::new().at("/health", get(health)).with(Tracing).data(self.context.clone())
+		Route::new()
+			.at("/health", get(health))
+			.at("/health/followers", get(followers))
+			.with(Tracing)
+			.data(self.context.clone())
 	}
 }
 
-#[handler]
-async fn health(context: Data<&Arc<HealthCheckContext>>) -> Result<Response, anyhow::Error> {
+/// Ask the main loop for its state. Fails if the main loop doesn't answer in time.
+async fn request_health(context: &HealthCheckContext) -> Result<SequencerHealth, anyhow::Error> {
 	let (check_tx, check_rx) = oneshot::channel();
 	context.check_request_tx.send(check_tx).await?;
-	let check_resp = tokio::time::timeout(std::time::Duration::from_secs(2), check_rx).await??;
-	let res = if check_resp { "OK".to_string() } else { format!("NOK") };
-	Ok(res.into_response())
+	Ok(tokio::time::timeout(std::time::Duration::from_secs(2), check_rx).await??)
+}
+
+#[handler]
+async fn health(context: Data<&Arc<HealthCheckContext>>) -> Result<Response, anyhow::Error> {
+	//Basic monitoring, always OK if the main loop answers.
+	request_health(&context).await?;
+	Ok("OK".into_response())
+}
+
+/// Report the lag of each follower.
+#[handler]
+async fn followers(
+	context: Data<&Arc<HealthCheckContext>>,
+) -> Result<Json<SequencerHealth>, anyhow::Error> {
+	Ok(Json(request_health(&context).await?))
 }
@@ -1,7 +1,14 @@
 use crate::block::SequencerBlock;
+use crate::broadcast::Broadcaster;
+use crate::celestia::client::CelestiaClient;
+use crate::celestia::local::{LocalDaClient, LocalDaCurve};
 use crate::celestia::mock::CelestiaMock;
+use crate::celestia::CelestiaExternalDa;
 use crate::celestia::DaSequencerExternalDa;
+use crate::celestia::ExternalDaNotification;
 use crate::error::DaSequencerError;
+use crate::healthcheck::SequencerHealth;
+use crate::limiter::BatchWriteLimiter;
 use crate::server::run_server;
 use crate::server::GrpcRequests;
 use crate::server::ProducedData;
@@ -9,21 +16,32 @@ use crate::storage::DaSequencerStorage;
 use crate::storage::Storage;
 use crate::whitelist::Whitelist;
 use anyhow::Context;
+use celestia_types::nmt::Namespace;
 use futures::future::Either;
 use futures::stream::FuturesUnordered;
 use godfig::{backend::config_file::ConfigFile, Godfig};
-use movement_da_sequencer_config::DaSequencerConfig;
+use movement_da_light_node_digest_store::da::Da as DigestStoreDa;
+use movement_da_light_node_disk_fifo::da::Da as DiskFifoDa;
+use movement_da_sequencer_config::{DaSequencerConfig, ExternalDaConfig, LocalDaBackend};
+use movement_signer::{cryptography::ed25519::Ed25519, Signing};
+use movement_signer_loader::{Load, LoadedSigner};
 use tokio::signal::unix::signal;
 use tokio::signal::unix::SignalKind;
 use tokio::sync::{mpsc, oneshot};
 use tokio::task::JoinHandle;
 use tokio_stream::StreamExt;
+use url::Url;
 
+pub mod assembly;
 pub mod batch;
 pub mod block;
+pub mod broadcast;
 pub mod celestia;
 pub mod error;
-mod healthcheck;
+pub mod filter;
+pub mod healthcheck;
+pub mod limiter;
+pub mod pruning;
 pub mod server;
 pub mod storage;
 #[cfg(test)]
@@ -42,21 +60,39 @@ pub async fn start(mut dot_movement: dot_movement::DotMovement) -> Result<(), an
 	// Get a matching godfig object
 	let godfig: Godfig<DaSequencerConfig, ConfigFile> =
 		Godfig::new(ConfigFile::new(config_file), vec![]);
-	let da_sequencer_config: DaSequencerConfig = godfig.try_wait_for_ready().await?;
+	let mut da_sequencer_config: DaSequencerConfig = godfig.try_wait_for_ready().await?;
 
 	let dotmovement_path = dot_movement.get_path().to_path_buf();
 
+	// Pruned blocks archive segments
+	if let Some(archive_dir) = da_sequencer_config.retention.archive_dir.as_mut() {
+		*archive_dir = dotmovement_path.join(&archive_dir);
+	}
+
 	// Initialize whitelist
 	let whitelist_path = dotmovement_path.join(&da_sequencer_config.whitelist_relative_path);
 	let whitelist = Whitelist::from_file_and_spawn_reload_thread(whitelist_path)?;
 
+	// Open the storage first, the batch write limiter follows its pending transaction count.
+	let db_storage_path = dotmovement_path.join(&da_sequencer_config.db_storage_relative_path);
+
+	let storage = Storage::try_new(&db_storage_path)?
+		.with_block_assembly(da_sequencer_config.block_assembly.clone());
+
+	// Initialize batch write limits
+	let limiter = BatchWriteLimiter::from_config_file_and_spawn_reload_thread(
+		da_sequencer_config.batch_write_limits.clone(),
+		storage.pending_transactions(),
+		dot_movement.get_config_json_path(),
+	);
+
 	let (request_tx, request_rx) = mpsc::channel(GRPC_REQUEST_CHANNEL_SIZE);
 	// Start gprc server
 	let grpc_address = da_sequencer_config.grpc_listen_address;
 	let verifying_key = da_sequencer_config.get_main_node_verifying_key()?;
 
 	let grpc_jh = tokio::spawn(async move {
-		run_server(grpc_address, request_tx, whitelist, verifying_key).await
+		run_server(grpc_address, request_tx, whitelist, limiter, verifying_key).await
 	});
 
 	// Start healthcheck entry point
@@ -71,14 +107,50 @@ pub async fn start(mut dot_movement: dot_movement::DotMovement) -> Result<(), an
 	let rest_jh = tokio::spawn(rest_service_future);
 
 	//Start the main loop
-	let db_storage_path = dotmovement_path.join(&da_sequencer_config.db_storage_relative_path);
-
-	let storage = Storage::try_new(&db_storage_path)?;
-
-	// TODO Use Celestia Mock for now
-	let celestia_mock = CelestiaMock::new();
-	let loop_jh =
-		tokio::spawn(run(da_sequencer_config, request_rx, rest_health_rx, storage, celestia_mock));
+	let (external_da_tx, external_da_rx) = mpsc::channel(GRPC_REQUEST_CHANNEL_SIZE);
+	let main_loop = MainLoop {
+		config: da_sequencer_config.clone(),
+		request_rx,
+		check_request_rx: rest_health_rx,
+		external_da_rx,
+		storage,
+	};
+	let loop_jh = match da_sequencer_config.external_da {
+		ExternalDaConfig::Mock => {
+			tracing::warn!("Da Sequencer uses the Celestia mock, blocks are not anchored.");
+			main_loop.spawn(CelestiaMock::new())
+		}
+		ExternalDaConfig::Celestia { connection_url, auth_token, namespace } => {
+			let connection_url =
+				Url::parse(&connection_url).context("Invalid Celestia connection url.")?;
+			let namespace = parse_celestia_namespace(&namespace)?;
+			tracing::info!("Da Sequencer connects to Celestia at {connection_url}.");
+			let client = CelestiaClient::new(
+				connection_url,
+				auth_token.as_deref(),
+				namespace,
+				external_da_tx.clone(),
+			)
+			.await?;
+			main_loop.spawn(CelestiaExternalDa::with_notifier(external_da_tx, client))
+		}
+		ExternalDaConfig::Local { backend, db_relative_path } => {
+			let local_da_path = dotmovement_path.join(db_relative_path);
+			tracing::info!("Da Sequencer uses the local {backend:?} DA in {local_da_path:?}.");
+			let fifo_da = DiskFifoDa::<LocalDaCurve>::try_new(local_da_path.join("fifo"))?;
+			match backend {
+				LocalDaBackend::DiskFifo => {
+					let client = LocalDaClient::new(fifo_da, external_da_tx.clone());
+					main_loop.spawn(CelestiaExternalDa::with_notifier(external_da_tx, client))
+				}
+				LocalDaBackend::DigestStore => {
+					let digest_da = DigestStoreDa::try_new(fifo_da, local_da_path.join("digests"))?;
+					let client = LocalDaClient::new(digest_da, external_da_tx.clone());
+					main_loop.spawn(CelestiaExternalDa::with_notifier(external_da_tx, client))
+				}
+			}
+		}
+	};
 
 	let (stop_tx, mut stop_rx) = tokio::sync::watch::channel(());
 	tokio::spawn({
@@ -117,6 +189,31 @@ pub async fn start(mut dot_movement: dot_movement::DotMovement) -> Result<(), an
 	Ok(())
 }
 
+/// The inputs of the DA sequencing loop, spawned once the external DA is selected.
+struct MainLoop {
+	config: DaSequencerConfig,
+	request_rx: mpsc::Receiver<GrpcRequests>,
+	check_request_rx: mpsc::Receiver<oneshot::Sender<SequencerHealth>>,
+	external_da_rx: mpsc::Receiver<ExternalDaNotification>,
+	storage: Storage,
+}
+
+impl MainLoop {
+	fn spawn<D>(self, celestia: D) -> JoinHandle<Result<(), DaSequencerError>>
+	where
+		D: DaSequencerExternalDa + Clone + Send + 'static,
+	{
+		tokio::spawn(run(
+			self.config,
+			self.request_rx,
+			self.check_request_rx,
+			self.external_da_rx,
+			self.storage,
+			celestia,
+		))
+	}
+}
+
 /// Run Da sequencing loop.
 /// Runs the DA sequencing loop.
 ///
@@ -124,7 +221,8 @@ pub async fn start(mut dot_movement: dot_movement::DotMovement) -> Result<(), an
 pub async fn run<D, S>(
 	config: DaSequencerConfig,
 	mut request_rx: mpsc::Receiver<GrpcRequests>,
-	mut check_request_rx: mpsc::Receiver<oneshot::Sender<bool>>,
+	mut check_request_rx: mpsc::Receiver<oneshot::Sender<SequencerHealth>>,
+	mut external_da_rx: mpsc::Receiver<ExternalDaNotification>,
 	storage: S,
 	celestia: D,
 ) -> Result<(), DaSequencerError>
@@ -142,11 +240,71 @@ where
 	let mut produce_block = false;
 	let mut produce_block_jh: Option<JoinHandle<Result<Option<SequencerBlock>, DaSequencerError>>> =
 		None; //get_pending_future();
-	let mut connected_grpc_sender = vec![];
 	let mut current_node_state = None;
 	// Batch timestamp should always be greater strict to the last one.
 	let mut last_batch_timestamp = chrono::Utc::now().timestamp_micros() as u64;
 
+	// Sign the header of the produced blocks if a block signer is configured.
+	let block_signer_identifier = config
+		.get_block_signer_identifier()
+		.map_err(|e| DaSequencerError::BlockSigning(e.to_string()))?;
+	let block_signer: Option<LoadedSigner<Ed25519>> = match block_signer_identifier {
+		Some(identifier) => Some(
+			identifier
+				.load()
+				.await
+				.map_err(|e| DaSequencerError::BlockSigning(e.to_string()))?,
+		),
+		None => None,
+	};
+
+	// Synchronize the external DA with the stored blocks before accepting new ones.
+	// Grpc requests stay queued in the request channel until the bootstrap is done.
+	let (current_block_height, last_finalized_celestia_height) = tokio::task::spawn_blocking({
+		let storage = storage.clone();
+		move || {
+			let current_block_height = storage.get_current_block_height()?;
+			let last_finalized = storage.get_last_celestia_finalized_block()?;
+			Ok::<_, DaSequencerError>((current_block_height, last_finalized.map(|(_, h)| h)))
+		}
+	})
+	.await
+	.map_err(|e| DaSequencerError::ExternalDaBootstrap(e.to_string()))??;
+	tracing::info!(
+		"Bootstrap external DA at block height:{} last finalized Celestia height:{:?}",
+		current_block_height.0,
+		last_finalized_celestia_height
+	);
+	{
+		let bootstrap = celestia.bootstrap(current_block_height, last_finalized_celestia_height);
+		tokio::pin!(bootstrap);
+		loop {
+			tokio::select! {
+				res = &mut bootstrap => {
+					res?;
+					break;
+				}
+				// The bootstrap requests blocks and reports commits through the notification channel.
+				Some(notification) = external_da_rx.recv() => {
+					spawn_result_futures.push(handle_external_da_notification(&storage, notification));
+				}
+				Some(Ok(res)) = spawn_result_futures.next() =>  {
+					if let Err(err) = res {
+						tracing::error!(error = %err, "Error during future execution.");
+					}
+				}
+			}
+		}
+	}
+	tracing::info!("External DA bootstrap done.");
+
+	// Prune the old blocks confirmed on the external DA, outside of the main loop.
+	spawn_result_futures
+		.push(tokio::spawn(pruning::run_pruning(storage.clone(), config.retention.clone())));
+
+	// Fan-out of the produced blocks to the followers, outside of the main loop.
+	let broadcaster = Broadcaster::new(config.follower_buffer_size, current_block_height);
+
 	let pending = futures::future::pending();
 	tokio::pin!(pending);
 
@@ -160,8 +318,12 @@ where
 		tokio::select! {
 			// Manage health check request.
 			Some(oneshot_tx) = check_request_rx.recv() => {
-				//Basic monitoring, always true if the loop run.
-				if let Err(err) = oneshot_tx.send(true){
+				//Basic monitoring, the loop is healthy if it answers.
+				let health = SequencerHealth {
+					current_height: broadcaster.last_published_height().0,
+					followers: broadcaster.followers_lag(),
+				};
+				if let Err(err) = oneshot_tx.send(health){
 					tracing::warn!("Heal check oneshot channel closed abnormally :{err:?}");
 				}
 			}
@@ -169,15 +331,16 @@ where
 			// Manage grpc request.
 			Some(grpc_request) = request_rx.recv() => {
 				match grpc_request {
-					GrpcRequests::StartBlockStream(proposed_block_tx, curent_height_callback) => {
-						connected_grpc_sender.push(proposed_block_tx);
+					GrpcRequests::StartBlockStream(follower_callback) => {
+						// Subscribe before reading the current height so no block is missed.
+						let follower = broadcaster.subscribe();
 
-						// Send back the current height.
+						// Send back the follower with the current height.
 						let start_jh = tokio::task::spawn_blocking({
 							let storage = storage.clone();
 							move || {
 								let current_height = storage.get_current_block_height()?;
-								let _ = curent_height_callback.send(current_height);
+								let _ = follower_callback.send((follower, current_height));
 								Ok::<(), DaSequencerError>(())
 						}});
 						spawn_result_futures.push(start_jh);
@@ -187,26 +350,46 @@ where
 							let storage = storage.clone();
 							move || {storage.get_block_at_height(block_height)}
 						});
+						let storage = storage.clone();
+						let block_signer = block_signer.clone();
 						tokio::spawn(async move {
 							let result = get_block_jh.await;
 							// Manage result.
 							let to_send = match result {
 								Err(err) => {
 									tracing::error!(error = %err, "spawn_blocking task failed.");
-									None
+									Err(DaSequencerError::BlockRetrieval(err.to_string()))
 								}
+								// A pruned block is expected, the requester fetches it from the archive.
+								Ok(Err(err @ DaSequencerError::BlockPruned(_))) => Err(err),
 								Ok(Err(err)) => {
 									tracing::error!(error = %err, "Storage get_block_at_height return an error.");
-									None
-
+									Err(err)
+								}
+								Ok(Ok(block)) => Ok(block),
+							};
+							// Blocks produced before a signer was configured are signed when read.
+							let to_send = match (to_send, &block_signer) {
+								(Ok(Some(block)), Some(signer)) if block.signature().is_none() => {
+									sign_block(&storage, signer, block).await.map(Some)
 								}
-								Ok(Ok(block)) => block,
+								(to_send, _) => to_send,
 							};
 
 							let _ = callback.send(to_send);
 						});
 					},
-					GrpcRequests::WriteBatch(batch) => {
+					GrpcRequests::GetCurrentHeight(callback) => {
+						let current_height_jh = tokio::task::spawn_blocking({
+							let storage = storage.clone();
+							move || {
+								let current_height = storage.get_current_block_height()?;
+								let _ = callback.send(current_height);
+								Ok::<(), DaSequencerError>(())
+						}});
+						spawn_result_futures.push(current_height_jh);
+					},
+					GrpcRequests::WriteBatch(batch, reservation) => {
 						// Create an unique batch data
 						let batch = batch.unique(last_batch_timestamp);
 						last_batch_timestamp = batch.data().timestamp;
@@ -214,7 +397,12 @@ where
 						//send batch to the storage.
 						let write_batch_jh = tokio::task::spawn_blocking({
 							let storage = storage.clone();
-							move || {storage.write_batch(batch)}
+							move || {
+								let res = storage.write_batch(batch);
+								// The written transactions are now counted by the storage.
+								drop(reservation);
+								res
+							}
 						});
 						spawn_result_futures.push(write_batch_jh);
 					},
@@ -222,14 +410,30 @@ where
 
 				}
 			}
+			// Manage external DA notifications.
+			Some(notification) = external_da_rx.recv() => {
+				spawn_result_futures.push(handle_external_da_notification(&storage, notification));
+			}
+
 			// Every tick product a new block.
 			_ = produce_block_interval.tick() => {
 				// Produce only one block at a time.
 				// If some is already in production, wait next tick.
 				if !produce_block {
+					// The block is signed before it is saved, so a failed signing leaves the
+					// transactions pending for the next block.
 					let produce_block_batch_jh = tokio::task::spawn_blocking({
 						let storage = storage.clone();
-						move || {storage.produce_next_block()}
+						let block_signer = block_signer.clone();
+						let runtime = tokio::runtime::Handle::current();
+						move || {
+							storage.produce_next_block(|block| match &block_signer {
+								Some(signer) => {
+									runtime.block_on(sign_header(signer, block)).map(Some)
+								}
+								None => Ok(None),
+							})
+						}
 					});
 					produce_block_jh = Some(produce_block_batch_jh);
 					produce_block = true;
@@ -244,10 +448,9 @@ where
 					Ok(Ok(Some(block))) => {
 						let block_id = block.id();
 						// Send the block to all registered follower
-						// For now send to the main loop because there are very few followers (<100).
-						tracing::info!(sender_len = %connected_grpc_sender.len(), block_height= %block.height().0, "New block produced, sent to fullnodes.");
+						tracing::info!(follower_count = %broadcaster.follower_count(), block_height= %block.height().0, "New block produced, sent to fullnodes.");
 
-						stream_block_to_sender(&mut connected_grpc_sender, ProducedData::Block(block, current_node_state.clone())).await;
+						broadcaster.publish(ProducedData::Block(block, current_node_state.clone()));
 
 						//send the block to Celestia.
 						let celestia_send_jh = tokio::spawn({
@@ -270,8 +473,8 @@ where
 			}
 			// Every tick will produce a heartbeat.
 			_ = da_stream_heartbeat_interval.tick() => {
-				tracing::info!(sender_len = %connected_grpc_sender.len(), "Produced a heartbeat, sent to fullnodes");
-				stream_block_to_sender(&mut connected_grpc_sender, ProducedData::HeartBeat).await;
+				tracing::info!(follower_count = %broadcaster.follower_count(), "Produced a heartbeat, sent to fullnodes");
+				broadcaster.publish(ProducedData::HeartBeat);
 
 			}
 
@@ -286,18 +489,78 @@ where
 	}
 }
 
-async fn stream_block_to_sender(
-	senders: &mut Vec<mpsc::UnboundedSender<ProducedData>>,
-	data: ProducedData,
-) {
-	let mut new_sender = vec![];
-	for sender in senders.drain(..) {
-		// Remove the sender in error because it means the client was disconnected.
-		if let Err(err) = sender.send(data.clone()) {
-			tracing::warn!("Failed to send block to grpc client. Client disconnected. remove connection :{err}");
-		} else {
-			new_sender.push(sender);
+/// Sign the block header with the sequencer block signer.
+async fn sign_header(
+	signer: &LoadedSigner<Ed25519>,
+	block: &SequencerBlock,
+) -> Result<Vec<u8>, DaSequencerError> {
+	Ok(signer
+		.sign(&block.header_bytes())
+		.await
+		.map_err(|e| DaSequencerError::BlockSigning(e.to_string()))?
+		.as_bytes()
+		.to_vec())
+}
+
+/// Sign the header of a stored block and save the signature in the storage.
+async fn sign_block<S>(
+	storage: &S,
+	signer: &LoadedSigner<Ed25519>,
+	block: SequencerBlock,
+) -> Result<SequencerBlock, DaSequencerError>
+where
+	S: DaSequencerStorage + Clone + Send + 'static,
+{
+	let signature = sign_header(signer, &block).await?;
+	tokio::task::spawn_blocking({
+		let storage = storage.clone();
+		let height = block.height();
+		let signature = signature.clone();
+		move || storage.set_block_signature(height, signature)
+	})
+	.await
+	.map_err(|e| DaSequencerError::StorageAccess(e.to_string()))??;
+	Ok(block.with_signature(Some(signature)))
+}
+
+/// Serve an external DA notification from the storage on a blocking task.
+fn handle_external_da_notification<S>(
+	storage: &S,
+	notification: ExternalDaNotification,
+) -> JoinHandle<Result<(), DaSequencerError>>
+where
+	S: DaSequencerStorage + Clone + Send + 'static,
+{
+	let storage = storage.clone();
+	tokio::task::spawn_blocking(move || match notification {
+		ExternalDaNotification::BlocksCommitted { block_ids, celestia_height } => {
+			// Persist the Celestia height of each committed block.
+			for block_id in block_ids {
+				let block = storage.get_block_with_id(block_id)?.ok_or_else(|| {
+					DaSequencerError::BlockRetrieval(format!(
+						"Committed block {block_id:?} not found in storage"
+					))
+				})?;
+				storage.set_block_celestia_height(block.height(), celestia_height)?;
+			}
+			Ok(())
 		}
-	}
-	*senders = new_sender;
+		ExternalDaNotification::RequestBlockAtHeight { height, callback } => {
+			let block = storage.get_block_at_height(height)?;
+			let _ = callback.send(block);
+			Ok(())
+		}
+		ExternalDaNotification::RequestBlockForId { id, callback } => {
+			let block = storage.get_block_with_id(id)?;
+			let _ = callback.send(block);
+			Ok(())
+		}
+	})
+}
+
+/// Parse a hex encoded v0 Celestia namespace id.
+fn parse_celestia_namespace(namespace: &str) -> Result<Namespace, anyhow::Error> {
+	let namespace = namespace.strip_prefix("0x").unwrap_or(namespace);
+	let id = hex::decode(namespace).context("Invalid hex Celestia namespace.")?;
+	Namespace::new_v0(&id).context("Invalid Celestia namespace id.")
 }
@@ -0,0 +1,303 @@
+use std::{
+	collections::HashMap,
+	fmt, fs,
+	path::Path,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc, Mutex, RwLock,
+	},
+	thread,
+	time::{Duration, Instant},
+};
+
+use ed25519_dalek::VerifyingKey;
+use movement_da_sequencer_config::{BatchWriteLimitsConfig, DaSequencerConfig};
+use movement_da_sequencer_proto::RejectionCode;
+
+/// Why a batch was refused by the limiter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchWriteRejection {
+	/// The verifying key sent more bytes per second than allowed.
+	BytesRateExceeded,
+	/// The verifying key sent more batches per second than allowed.
+	BatchRateExceeded,
+	/// The pending transaction pool is full.
+	PendingPoolFull,
+}
+
+impl BatchWriteRejection {
+	/// The protocol rejection code sent back to the full node.
+	pub fn code(&self) -> RejectionCode {
+		match self {
+			BatchWriteRejection::BytesRateExceeded | BatchWriteRejection::BatchRateExceeded => {
+				RejectionCode::RateLimited
+			}
+			BatchWriteRejection::PendingPoolFull => RejectionCode::PendingPoolFull,
+		}
+	}
+}
+
+impl fmt::Display for BatchWriteRejection {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			BatchWriteRejection::BytesRateExceeded => write!(f, "batch bytes rate limit exceeded"),
+			BatchWriteRejection::BatchRateExceeded => write!(f, "batch rate limit exceeded"),
+			BatchWriteRejection::PendingPoolFull => write!(f, "pending transaction pool is full"),
+		}
+	}
+}
+
+/// A token bucket holding one second worth of tokens.
+#[derive(Debug, Clone)]
+struct TokenBucket {
+	tokens: f64,
+	last_refill: Instant,
+}
+
+impl TokenBucket {
+	fn new(rate: u64) -> Self {
+		TokenBucket { tokens: rate as f64, last_refill: Instant::now() }
+	}
+
+	fn refill(&mut self, rate: u64, now: Instant) {
+		let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+		self.tokens = (self.tokens + elapsed * rate as f64).min(rate as f64);
+		self.last_refill = now;
+	}
+
+	/// A full bucket always accepts, so a single batch bigger than the rate is not refused forever.
+	/// The bucket then goes in debt and refills before accepting again.
+	fn can_take(&self, rate: u64, cost: u64) -> bool {
+		rate == 0 || self.tokens >= cost as f64 || self.tokens >= rate as f64
+	}
+
+	fn take(&mut self, rate: u64, cost: u64) {
+		if rate != 0 {
+			self.tokens -= cost as f64;
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+struct KeyBuckets {
+	bytes: TokenBucket,
+	batches: TokenBucket,
+}
+
+/// Pending pool slots reserved for an accepted batch that the storage has not counted yet.
+/// The slots are released when the reservation is dropped, so it must be kept until the batch
+/// is written to the storage or the write has failed.
+#[derive(Debug)]
+pub struct PendingReservation {
+	reserved: Arc<AtomicU64>,
+	count: u64,
+}
+
+impl Drop for PendingReservation {
+	fn drop(&mut self) {
+		self.reserved.fetch_sub(self.count, Ordering::AcqRel);
+	}
+}
+
+/// Applies the `BatchWrite` limits: a token bucket per verifying key for bytes and batches
+/// per second, and a global cap on the transactions waiting in the pending pool.
+#[derive(Clone)]
+pub struct BatchWriteLimiter {
+	limits: Arc<RwLock<BatchWriteLimitsConfig>>,
+	buckets: Arc<Mutex<HashMap<VerifyingKey, KeyBuckets>>>,
+	pending_transactions: Arc<AtomicU64>,
+	// Transactions of accepted batches not yet counted in `pending_transactions`.
+	reserved_transactions: Arc<AtomicU64>,
+}
+
+impl BatchWriteLimiter {
+	/// Create a limiter. `pending_transactions` is the shared count of pending transactions
+	/// maintained by the storage.
+	pub fn new(limits: BatchWriteLimitsConfig, pending_transactions: Arc<AtomicU64>) -> Self {
+		BatchWriteLimiter {
+			limits: Arc::new(RwLock::new(limits)),
+			buckets: Arc::new(Mutex::new(HashMap::new())),
+			pending_transactions,
+			reserved_transactions: Arc::new(AtomicU64::new(0)),
+		}
+	}
+
+	/// A limiter that accepts everything.
+	pub fn unlimited() -> Self {
+		Self::new(
+			BatchWriteLimitsConfig {
+				max_bytes_per_sec: 0,
+				max_batches_per_sec: 0,
+				max_pending_transactions: 0,
+			},
+			Arc::new(AtomicU64::new(0)),
+		)
+	}
+
+	/// Create a limiter and spawn a thread that reloads the limits from the sequencer config file.
+	pub fn from_config_file_and_spawn_reload_thread(
+		limits: BatchWriteLimitsConfig,
+		pending_transactions: Arc<AtomicU64>,
+		config_path: impl AsRef<Path> + std::marker::Send + 'static,
+	) -> Self {
+		let limiter = Self::new(limits, pending_transactions);
+		let shared_limits = limiter.limits.clone();
+		thread::spawn(move || loop {
+			thread::sleep(Duration::from_secs(60));
+			match Self::load(&config_path) {
+				Ok(updated) => {
+					if let Ok(mut guard) = shared_limits.write() {
+						if *guard != updated {
+							tracing::info!("[limiter] Batch write limits updated: {:?}", updated);
+							*guard = updated;
+						}
+					} else {
+						tracing::error!("[limiter] Failed to acquire write lock");
+					}
+				}
+				Err(err) => {
+					tracing::error!("[limiter] Reload failed: {}", err);
+				}
+			}
+		});
+		limiter
+	}
+
+	fn load(path: impl AsRef<Path>) -> Result<BatchWriteLimitsConfig, anyhow::Error> {
+		let content = fs::read_to_string(path)?;
+		let config: DaSequencerConfig = serde_json::from_str(&content)?;
+		Ok(config.batch_write_limits)
+	}
+
+	/// Atomically reserve `tx_count` pending pool slots, so concurrent batches cannot together
+	/// go over the cap.
+	fn reserve(&self, max_pending: u64, tx_count: u64) -> Option<PendingReservation> {
+		self.reserved_transactions
+			.fetch_update(Ordering::AcqRel, Ordering::Acquire, |reserved| {
+				let pending = self.pending_transactions.load(Ordering::Acquire);
+				let total = pending.saturating_add(reserved).saturating_add(tx_count);
+				(max_pending == 0 || total <= max_pending).then_some(reserved + tx_count)
+			})
+			.ok()
+			.map(|_| PendingReservation {
+				reserved: self.reserved_transactions.clone(),
+				count: tx_count,
+			})
+	}
+
+	/// Check that a batch of `batch_bytes` bytes holding `tx_count` transactions can be accepted
+	/// from `key`. Tokens are consumed only if the batch is accepted, and the returned
+	/// reservation holds the batch pending pool slots until it is dropped.
+	pub fn check(
+		&self,
+		key: &VerifyingKey,
+		batch_bytes: u64,
+		tx_count: u64,
+	) -> Result<PendingReservation, BatchWriteRejection> {
+		let limits = self.limits.read().expect("Batch write limits lock poisoned").clone();
+
+		let reservation = self
+			.reserve(limits.max_pending_transactions, tx_count)
+			.ok_or(BatchWriteRejection::PendingPoolFull)?;
+
+		let now = Instant::now();
+		let mut buckets = self.buckets.lock().expect("Batch write buckets lock poisoned");
+		let key_buckets = buckets.entry(*key).or_insert_with(|| KeyBuckets {
+			bytes: TokenBucket::new(limits.max_bytes_per_sec),
+			batches: TokenBucket::new(limits.max_batches_per_sec),
+		});
+		key_buckets.bytes.refill(limits.max_bytes_per_sec, now);
+		key_buckets.batches.refill(limits.max_batches_per_sec, now);
+
+		if !key_buckets.batches.can_take(limits.max_batches_per_sec, 1) {
+			return Err(BatchWriteRejection::BatchRateExceeded);
+		}
+		if !key_buckets.bytes.can_take(limits.max_bytes_per_sec, batch_bytes) {
+			return Err(BatchWriteRejection::BytesRateExceeded);
+		}
+		key_buckets.batches.take(limits.max_batches_per_sec, 1);
+		key_buckets.bytes.take(limits.max_bytes_per_sec, batch_bytes);
+		Ok(reservation)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tests::generate_signing_key;
+
+	fn limits(bytes: u64, batches: u64, pending: u64) -> BatchWriteLimitsConfig {
+		BatchWriteLimitsConfig {
+			max_bytes_per_sec: bytes,
+			max_batches_per_sec: batches,
+			max_pending_transactions: pending,
+		}
+	}
+
+	#[test]
+	fn test_batch_rate_is_limited_per_key() {
+		let limiter = BatchWriteLimiter::new(limits(0, 2, 0), Arc::new(AtomicU64::new(0)));
+		let key = generate_signing_key().verifying_key();
+		let other_key = generate_signing_key().verifying_key();
+
+		assert!(limiter.check(&key, 10, 1).is_ok());
+		assert!(limiter.check(&key, 10, 1).is_ok());
+		assert_eq!(limiter.check(&key, 10, 1).err(), Some(BatchWriteRejection::BatchRateExceeded));
+		// Another key has its own bucket.
+		assert!(limiter.check(&other_key, 10, 1).is_ok());
+	}
+
+	#[test]
+	fn test_bytes_rate_is_limited_per_key() {
+		let limiter = BatchWriteLimiter::new(limits(100, 0, 0), Arc::new(AtomicU64::new(0)));
+		let key = generate_signing_key().verifying_key();
+
+		assert!(limiter.check(&key, 60, 1).is_ok());
+		assert_eq!(limiter.check(&key, 60, 1).err(), Some(BatchWriteRejection::BytesRateExceeded));
+		assert!(limiter.check(&key, 40, 1).is_ok());
+	}
+
+	#[test]
+	fn test_oversized_batch_is_accepted_with_a_full_bucket() {
+		let limiter = BatchWriteLimiter::new(limits(100, 0, 0), Arc::new(AtomicU64::new(0)));
+		let key = generate_signing_key().verifying_key();
+
+		assert!(limiter.check(&key, 500, 1).is_ok());
+		assert_eq!(limiter.check(&key, 1, 1).err(), Some(BatchWriteRejection::BytesRateExceeded));
+	}
+
+	#[test]
+	fn test_pending_pool_cap() {
+		let pending = Arc::new(AtomicU64::new(8));
+		let limiter = BatchWriteLimiter::new(limits(0, 0, 10), pending.clone());
+		let key = generate_signing_key().verifying_key();
+
+		let reservation = limiter.check(&key, 10, 2).expect("batch should be accepted");
+		// The accepted batch holds its slots until the storage counts it.
+		assert_eq!(limiter.check(&key, 10, 1).err(), Some(BatchWriteRejection::PendingPoolFull));
+		pending.store(10, Ordering::Relaxed);
+		drop(reservation);
+		assert_eq!(limiter.check(&key, 10, 1).err(), Some(BatchWriteRejection::PendingPoolFull));
+		pending.store(0, Ordering::Relaxed);
+		assert!(limiter.check(&key, 10, 3).is_ok());
+	}
+
+	#[test]
+	fn test_concurrent_batches_do_not_exceed_pending_pool_cap() {
+		let limiter = BatchWriteLimiter::new(limits(0, 0, 100), Arc::new(AtomicU64::new(0)));
+		let key = generate_signing_key().verifying_key();
+
+		let reservations: Vec<_> = (0..8)
+			.map(|_| {
+				let limiter = limiter.clone();
+				thread::spawn(move || {
+					(0..10).filter_map(|_| limiter.check(&key, 10, 3).ok()).collect::<Vec<_>>()
+				})
+			})
+			.flat_map(|handle| handle.join().expect("limiter thread panicked"))
+			.collect();
+		assert_eq!(reservations.len(), 33);
+		drop(reservations);
+		assert_eq!(limiter.reserved_transactions.load(Ordering::Acquire), 0);
+	}
+}
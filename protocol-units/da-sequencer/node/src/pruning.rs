@@ -0,0 +1,198 @@
+//! Pruning of the old blocks confirmed on the external DA.
+//!
+//! Blocks are optionally exported to a compressed archive segment before being deleted.
+//! A segment holds one file per block, named after the block height and containing the
+//! serialized `SequencerBlock`. The sequencer signature of a signed block is kept next to it,
+//! in a file with the `signature` extension, so the archived block can still be verified.
+
+use crate::block::{BlockHeight, SequencerBlock};
+use crate::error::DaSequencerError;
+use crate::storage::DaSequencerStorage;
+use movement_da_sequencer_config::RetentionConfig;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use syncador::backend::archive::gzip::push::Push;
+use syncador::backend::PushOperations;
+use syncador::files::package::{Package, PackageElement};
+
+/// Prune the storage at each `prune_interval_sec` interval. Never returns if the pruning is enabled.
+pub async fn run_pruning<S>(storage: S, retention: RetentionConfig) -> Result<(), DaSequencerError>
+where
+	S: DaSequencerStorage + Clone + Send + 'static,
+{
+	if retention.keep_blocks == 0 {
+		return Ok(());
+	}
+	let mut prune_interval =
+		tokio::time::interval(Duration::from_secs(retention.prune_interval_sec.max(1)));
+	loop {
+		prune_interval.tick().await;
+		// A failed pass leaves the blocks in the storage, they are pruned by the next one.
+		if let Err(err) = prune_once(&storage, &retention).await {
+			tracing::error!(error = %err, "Block pruning failed.");
+		}
+	}
+}
+
+/// Prune, and archive if configured, the blocks that can be pruned.
+/// Return the new pruned height if some blocks have been pruned.
+pub async fn prune_once<S>(
+	storage: &S,
+	retention: &RetentionConfig,
+) -> Result<Option<BlockHeight>, DaSequencerError>
+where
+	S: DaSequencerStorage + Clone + Send + 'static,
+{
+	let blocks = tokio::task::spawn_blocking({
+		let storage = storage.clone();
+		let keep_blocks = retention.keep_blocks;
+		let max_blocks = retention.max_blocks_per_prune.max(1);
+		move || storage.get_prunable_blocks(keep_blocks, max_blocks)
+	})
+	.await
+	.map_err(|e| DaSequencerError::StorageAccess(e.to_string()))??;
+
+	let Some(last_height) = blocks.last().map(|block| block.height()) else {
+		return Ok(None);
+	};
+
+	if let Some(archive_dir) = &retention.archive_dir {
+		let segment = archive_blocks(archive_dir, blocks).await?;
+		tracing::info!("Archived blocks up to height:{} in {segment:?}", last_height.0);
+	}
+
+	tokio::task::spawn_blocking({
+		let storage = storage.clone();
+		move || storage.prune_blocks_up_to(last_height)
+	})
+	.await
+	.map_err(|e| DaSequencerError::StorageAccess(e.to_string()))??;
+
+	Ok(Some(last_height))
+}
+
+/// Directory of the archive segment holding the blocks from `from` to `to` included.
+pub fn segment_dir(archive_dir: &Path, from: BlockHeight, to: BlockHeight) -> PathBuf {
+	archive_dir.join(format!("segment-{:020}-{:020}", from.0, to.0))
+}
+
+/// Write the blocks in a new archive segment and return the segment directory.
+async fn archive_blocks(
+	archive_dir: &Path,
+	blocks: Vec<SequencerBlock>,
+) -> Result<PathBuf, DaSequencerError> {
+	let (Some(first), Some(last)) = (blocks.first(), blocks.last()) else {
+		return Err(DaSequencerError::Archive("No block to archive".into()));
+	};
+	let segment_dir = segment_dir(archive_dir, first.height(), last.height());
+	let staging_dir = archive_dir.join(format!(".staging-{:020}", first.height().0));
+
+	// Write the block files on a blocking task.
+	let package = tokio::task::spawn_blocking({
+		let staging_dir = staging_dir.clone();
+		let segment_dir = segment_dir.clone();
+		move || {
+			let io_error = |e: std::io::Error| DaSequencerError::Archive(e.to_string());
+			std::fs::create_dir_all(&staging_dir).map_err(io_error)?;
+			std::fs::create_dir_all(&segment_dir).map_err(io_error)?;
+			let mut element = PackageElement::new(staging_dir.clone());
+			for block in blocks {
+				let path = staging_dir.join(format!("{:020}.block", block.height().0));
+				if let Some(signature) = block.signature() {
+					let signature_path = path.with_extension("signature");
+					std::fs::write(&signature_path, signature).map_err(io_error)?;
+					element.add_sync_file(signature_path);
+				}
+				let bytes: Vec<u8> = block.try_into()?;
+				std::fs::write(&path, bytes).map_err(io_error)?;
+				element.add_sync_file(path);
+			}
+			Ok::<_, DaSequencerError>(Package(vec![element]))
+		}
+	})
+	.await
+	.map_err(|e| DaSequencerError::Archive(e.to_string()))??;
+
+	let result = Push::new(segment_dir.clone()).push(package).await;
+	if let Err(err) = tokio::fs::remove_dir_all(&staging_dir).await {
+		tracing::warn!("Failed to remove the archive staging directory {staging_dir:?}: {err}");
+	}
+	result.map_err(|e| DaSequencerError::Archive(e.to_string()))?;
+
+	Ok(segment_dir)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::celestia::CelestiaHeight;
+	use crate::tests::mock::StorageMock;
+	use movement_types::block::Block;
+	use syncador::backend::archive::gzip::pull::Pull;
+	use syncador::backend::PullOperations;
+
+	#[tokio::test]
+	async fn test_prune_once_archives_then_prunes_confirmed_blocks() {
+		let archive_dir = tempfile::tempdir().expect("failed to create temp dir");
+		let storage = StorageMock::new();
+		{
+			let mut inner = storage.inner.lock().unwrap();
+			for height in 1..=6 {
+				let block = SequencerBlock::try_new(BlockHeight(height), Block::test())
+					.unwrap()
+					.with_signature(Some(vec![height as u8; 64]));
+				inner.produced_blocks.push(block);
+				inner.celestia_heights.insert(BlockHeight(height), CelestiaHeight::from(height));
+			}
+			inner.current_height = 6;
+		}
+		let retention = RetentionConfig {
+			keep_blocks: 2,
+			prune_interval_sec: 1,
+			max_blocks_per_prune: 10,
+			archive_dir: Some(archive_dir.path().to_path_buf()),
+		};
+
+		let pruned = prune_once(&storage, &retention).await.expect("prune_once failed");
+		assert_eq!(pruned, Some(BlockHeight(4)));
+		assert!(matches!(
+			storage.get_block_at_height(BlockHeight(3)),
+			Err(DaSequencerError::BlockPruned(3))
+		));
+		assert!(storage.get_block_at_height(BlockHeight(5)).unwrap().is_some());
+
+		let segment = segment_dir(archive_dir.path(), BlockHeight(1), BlockHeight(4));
+		assert!(segment.join("0.tar.gz").is_file());
+		// Only the segment is left in the archive directory.
+		assert_eq!(std::fs::read_dir(archive_dir.path()).unwrap().count(), 1);
+
+		// The archived blocks keep their signature.
+		let unpacked_dir = tempfile::tempdir().expect("failed to create temp dir");
+		let mut archive = PackageElement::new(segment.clone());
+		archive.add_sync_file(segment.join("0.tar.gz"));
+		let unpacked = Pull::new(unpacked_dir.path().to_path_buf())
+			.pull(Some(Package(vec![archive])))
+			.await
+			.expect("failed to unpack the segment")
+			.expect("no unpacked package");
+		let files: Vec<PathBuf> = unpacked
+			.into_manifests()
+			.into_iter()
+			.flat_map(|element| element.sync_files)
+			.collect();
+		let file = |name: String| {
+			files
+				.iter()
+				.find(|path| path.file_name().unwrap().to_string_lossy() == name)
+				.unwrap_or_else(|| panic!("{name} not archived"))
+		};
+		let block =
+			SequencerBlock::try_from(&std::fs::read(file(format!("{:020}.block", 2))).unwrap()[..])
+				.unwrap();
+		assert_eq!(block.height(), BlockHeight(2));
+		assert_eq!(std::fs::read(file(format!("{:020}.signature", 2))).unwrap(), vec![2u8; 64]);
+
+		// Nothing more to prune.
+		assert_eq!(prune_once(&storage, &retention).await.unwrap(), None);
+	}
+}
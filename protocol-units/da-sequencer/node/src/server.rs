@@ -2,6 +2,9 @@ use crate::batch::FullNodeTxs;
 use crate::batch::{validate_batch, DaBatch, RawData};
 use crate::block::NodeState;
 use crate::block::{BlockHeight, SequencerBlock};
+use crate::broadcast::{Follower, FollowerEvent};
+use crate::filter::TransactionFilter;
+use crate::limiter::{BatchWriteLimiter, PendingReservation};
 use crate::whitelist::Whitelist;
 use crate::DaSequencerError;
 use ed25519_dalek::{Verifier, VerifyingKey};
@@ -11,8 +14,8 @@ use movement_da_sequencer_proto::da_sequencer_node_service_server::{
 };
 use movement_da_sequencer_proto::{
 	block_response::BlockType, BatchWriteRequest, BatchWriteResponse, BlockResponse, BlockV1,
-	ReadAtHeightRequest, ReadAtHeightResponse, StreamReadFromHeightRequest,
-	StreamReadFromHeightResponse,
+	ReadAtHeightRequest, ReadAtHeightResponse, RejectionCode, StreamReadFromHeightRequest,
+	StreamReadFromHeightResponse, StreamReadRangeRequest, StreamReadRangeResponse,
 };
 use movement_da_sequencer_proto::{MainNodeState, MainNodeStateRequest};
 use std::net::SocketAddr;
@@ -25,6 +28,7 @@ pub async fn run_server(
 	address: SocketAddr,
 	request_tx: mpsc::Sender<GrpcRequests>,
 	whitelist: Whitelist,
+	limiter: BatchWriteLimiter,
 	main_node_verifying_key: Option<VerifyingKey>,
 ) -> Result<(), anyhow::Error> {
 	tracing::info!("Server listening on: {}", address);
@@ -44,6 +48,7 @@ pub async fn run_server(
 		.add_service(DaSequencerNodeServiceServer::new(DaSequencerNode {
 			request_tx,
 			whitelist,
+			limiter,
 			main_node_verifying_key,
 		}))
 		.add_service(reflection)
@@ -61,15 +66,18 @@ pub enum ProducedData {
 
 #[derive(Debug)]
 pub enum GrpcRequests {
-	StartBlockStream(mpsc::UnboundedSender<ProducedData>, oneshot::Sender<BlockHeight>),
-	GetBlockHeight(BlockHeight, oneshot::Sender<Option<SequencerBlock>>),
-	WriteBatch(DaBatch<FullNodeTxs>),
+	StartBlockStream(oneshot::Sender<(Follower, BlockHeight)>),
+	GetBlockHeight(BlockHeight, oneshot::Sender<Result<Option<SequencerBlock>, DaSequencerError>>),
+	GetCurrentHeight(oneshot::Sender<BlockHeight>),
+	WriteBatch(DaBatch<FullNodeTxs>, PendingReservation),
 	SendState(NodeState),
 }
 
 pub struct DaSequencerNode {
 	request_tx: mpsc::Sender<GrpcRequests>,
 	whitelist: Whitelist,
+	// Per verifying key rate limits and pending pool cap applied to written batches.
+	limiter: BatchWriteLimiter,
 	// State propagation is not a main functionality of the da-sequencer. It's used to detect instabilities.
 	// `main_node_verifying_key` contains the main node signing public key for state propagation.
 	// It's optional because issue on the mainnode can stop the network.
@@ -93,104 +101,94 @@ impl DaSequencerNodeService for DaSequencerNode {
 	) -> Result<tonic::Response<Self::StreamReadFromHeightStream>, tonic::Status> {
 		tracing::info!(request = ?request, "Stream read from height request");
 
-		// Register the new produced block channel to get lastest block.
-		// Use `unbounded_channel` to avoid filling the channel during the fetching of an old block.
-		let (current_produced_block_tx, mut current_produced_block_rx) = mpsc::unbounded_channel();
-		let (first_produced_block_height_tx, first_produced_block_height_rx) = oneshot::channel();
-		if let Err(err) = self
-			.request_tx
-			.send(GrpcRequests::StartBlockStream(
-				current_produced_block_tx,
-				first_produced_block_height_tx,
-			))
-			.await
-		{
+		// Register as a follower of the produced blocks.
+		// The follower is registered before the current height is read, so no block is missed between both.
+		let (follower_tx, follower_rx) = oneshot::channel();
+		if let Err(err) = self.request_tx.send(GrpcRequests::StartBlockStream(follower_tx)).await {
 			tracing::warn!(error = %err, "Internal grpc request channel closed, can't stream blocks");
 			return Err(tonic::Status::internal("Internal error. Retry later"));
 		}
-
-		// Wait from the supervisor main loop the height of the first produced block that will be sent in the `current_produced_block_rx` channel.
-		// Until this block height, block are fetch from the DB and after from the channel.
-		// This way blocks are fetch in order from start height until the last produced block.
-		// New procured block will arrive from the channel.
-		let mut first_produced_block_height = match first_produced_block_height_rx.await {
-			Ok(h) => h,
+		let (mut follower, current_height) = match follower_rx.await {
+			Ok(res) => res,
 			Err(err) => {
 				tracing::warn!("start stream channel closed: {err}");
 				return Err(tonic::Status::internal("Internal error. Retry later"));
 			}
 		};
-		let mut current_block_height = request.into_inner().height;
 
 		//The genesis block can't be retrieved so set min height to 1.
 		//In the DB block height start as 1 and the genesis block is not present.
-		if current_block_height == 0 {
-			current_block_height = 1;
-		}
+		let mut next_block_height = request.into_inner().height.max(1);
+		// Blocks until this height are read from the storage, the next ones come from the follower buffer.
+		let mut catch_up_height = current_height.0;
 
 		let request_tx = self.request_tx.clone();
 		let output = async_stream::try_stream! {
 			loop {
-				// Needed block height is before the first produced block in the produced block channel.
-				let response_content = if current_block_height <= first_produced_block_height.0 {
-					//get all block until the current produced height
-					let block_v1 = match get_block_at_height(current_block_height.into(), &request_tx).await {
+				// Catch up from the storage.
+				while next_block_height <= catch_up_height {
+					let block_v1 = match get_block_at_height(next_block_height, &request_tx).await {
 						Ok(None) => {
-							tracing::error!("Streamed block, get block: {} from DB is missing. Close the stream.",current_block_height);
+							tracing::error!("Streamed block, get block: {} from DB is missing. Close the stream.", next_block_height);
 							return;
 						}
 						Ok(Some(block)) => block,
+						Err(DaSequencerError::BlockPruned(height)) => {
+							tracing::info!("Streamed block: block {height} is pruned. Close the stream.");
+							Err(pruned_block_status(height))?
+						}
 						Err(err) => {
 							tracing::warn!(error = %err, "Streamed block serialization failed.");
 							return;
 						}
 					};
-					current_block_height +=1;
-					BlockResponse { block_type: Some(BlockType::BlockV1(block_v1)) }
+					follower.set_last_sent_height(next_block_height);
+					next_block_height += 1;
+					yield StreamReadFromHeightResponse {
+						response: Some(BlockResponse { block_type: Some(BlockType::BlockV1(block_v1)) })
+					};
+				}
 
 				// Fetch new produced block.
-				} else {
-					//send block in produced channel
-					let received_content = match current_produced_block_rx.recv().await {
-						Some(block) => block,
-						None => {
-							tracing::warn!("Streamed block: produced block channel closed.");
-							return;
+				let response_content = match follower.recv().await {
+					FollowerEvent::Data(ProducedData::HeartBeat) => {
+						// send heartbeat.
+						BlockResponse { block_type: Some(BlockType::Heartbeat(true)) }
+					}
+					FollowerEvent::Data(ProducedData::Block(new_block, state)) => {
+						let new_block_height = new_block.height().0;
+						// Already sent from the storage.
+						if new_block_height < next_block_height {
+							continue;
 						}
-
-					};
-
-					match received_content {
-						ProducedData::HeartBeat => {
-							// send heartbeat.
-							BlockResponse { block_type: Some(BlockType::Heartbeat(true)) }
+						// Some blocks are missing, fetch them from the storage up to this one.
+						if new_block_height > next_block_height {
+							tracing::warn!("Streamed block: next block height:{next_block_height} produced block height:{new_block_height}. Fetch them from the DB.");
+							catch_up_height = new_block_height;
+							continue;
 						}
-						ProducedData::Block(new_block, state) => {
-							// If the new produced height is not the next one it means that someway we miss blocks.
-							// Use the DB fetching mechanism to request them.
-							// Set the first_produced_block_height the to missing block height.
-							if current_block_height + 1 < new_block.height().0 {
-								// we missed a block request it.
-								first_produced_block_height = new_block.height();
-								tracing::warn!("Streamed block: Produced block fetching miss some blocks");
-								tracing::warn!("current_block_height:{current_block_height} produced block height:{}.", new_block.height().0);
-								tracing::warn!("Fetch them from the DB.");
-								continue;
+
+						let mut block_v1: BlockV1 = match new_block.try_into() {
+							Ok(b) => b,
+							Err(err) => {
+								tracing::warn!(error = %err, "Stream block: block serialization failed.");
+								return;
 							}
-							current_block_height = new_block.height().0;
-
-							let mut block_v1: BlockV1 = match new_block.try_into() {
-								Ok(b) => b,
-								Err(err) => {
-									tracing::warn!(error = %err, "Stream block: block serialization failed.");
-									return;
-
-								}
-							};
-							// send newly produced block.
-							block_v1.node_state = state.map(|s| s.into());
-							BlockResponse { block_type: Some(BlockType::BlockV1(block_v1)) }
-						}
+						};
+						follower.set_last_sent_height(new_block_height);
+						next_block_height += 1;
+						// send newly produced block.
+						block_v1.node_state = state.map(|s| s.into());
+						BlockResponse { block_type: Some(BlockType::BlockV1(block_v1)) }
+					}
+					FollowerEvent::Lagged => {
+						// The follower buffer overflowed, read the missed blocks from the storage.
+						catch_up_height = follower.last_published_height().0;
+						continue;
+					}
+					FollowerEvent::Closed => {
+						tracing::warn!("Streamed block: produced block channel closed.");
+						return;
 					}
 				};
 				let response = StreamReadFromHeightResponse {
@@ -198,13 +196,103 @@ impl DaSequencerNodeService for DaSequencerNode {
 				};
 
 				yield response;
-
 			}
 		};
 
 		Ok(tonic::Response::new(Box::pin(output) as Self::StreamReadFromHeightStream))
 	}
 
+	/// Server streaming response type for the StreamReadRange method.
+	type StreamReadRangeStream = std::pin::Pin<
+		Box<dyn Stream<Item = Result<StreamReadRangeResponse, tonic::Status>> + Send + 'static>,
+	>;
+
+	/// Stream the blocks of a bounded height range, then close the stream.
+	async fn stream_read_range(
+		&self,
+		request: tonic::Request<StreamReadRangeRequest>,
+	) -> Result<tonic::Response<Self::StreamReadRangeStream>, tonic::Status> {
+		tracing::info!(request = ?request, "Stream read range request");
+		let request = request.into_inner();
+
+		//The genesis block is not in the DB, so the range start at 1 at least.
+		let from_height = request.from_height.max(1);
+		if request.to_height < from_height {
+			return Err(tonic::Status::invalid_argument(format!(
+				"Invalid block range [{}, {}]",
+				request.from_height, request.to_height
+			)));
+		}
+		let filter = request
+			.filter
+			.map(TransactionFilter::try_from_proto)
+			.transpose()
+			.map_err(tonic::Status::invalid_argument)?
+			.filter(|filter| !filter.is_empty());
+		let headers_only = request.headers_only;
+
+		// Blocks above the current height are not waited for.
+		let to_height = match get_current_height(&self.request_tx).await {
+			Ok(current_height) => request.to_height.min(current_height.0),
+			Err(err) => {
+				tracing::warn!(error = %err, "Stream read range failed to get the current height.");
+				return Err(tonic::Status::internal("Internal error. Retry later"));
+			}
+		};
+
+		let request_tx = self.request_tx.clone();
+		let output = async_stream::try_stream! {
+			for height in from_height..=to_height {
+				let block = match get_sequencer_block_at_height(height, &request_tx).await {
+					Ok(Some(block)) => block,
+					Ok(None) => {
+						tracing::error!("Stream range, get block: {height} from DB is missing. Close the stream.");
+						Err(tonic::Status::not_found(format!("Block at height {height} is missing")))?
+					}
+					Err(DaSequencerError::BlockPruned(height)) => Err(pruned_block_status(height))?,
+					Err(err) => {
+						tracing::warn!(error = %err, "Stream range, get block failed.");
+						Err(tonic::Status::internal("Internal error. Retry later"))?
+					}
+				};
+
+				let matched_tx_indices = match &filter {
+					Some(filter) => {
+						let matched_tx_indices = filter.matched_indices(block.transactions());
+						if matched_tx_indices.is_empty() {
+							continue;
+						}
+						matched_tx_indices
+					}
+					None => vec![],
+				};
+
+				let mut block_v1 = if headers_only {
+					BlockV1 {
+						block_id: block.id().to_vec(),
+						height: block.height().into(),
+						data: vec![],
+						node_state: None,
+						matched_tx_indices: vec![],
+						signature: block.signature().map(<[u8]>::to_vec).unwrap_or_default(),
+					}
+				} else {
+					BlockV1::try_from(block).map_err(|err| {
+						tracing::warn!(error = %err, "Stream range: block serialization failed.");
+						tonic::Status::internal("Internal error. Retry later")
+					})?
+				};
+				block_v1.matched_tx_indices = matched_tx_indices;
+
+				yield StreamReadRangeResponse {
+					response: Some(BlockResponse { block_type: Some(BlockType::BlockV1(block_v1)) }),
+				};
+			}
+		};
+
+		Ok(tonic::Response::new(Box::pin(output) as Self::StreamReadRangeStream))
+	}
+
 	/// Read one block at a specified height.
 	async fn read_at_height(
 		&self,
@@ -214,6 +302,7 @@ impl DaSequencerNodeService for DaSequencerNode {
 		let block_v1 = match get_block_at_height(height, &self.request_tx).await {
 			Ok(None) => None,
 			Ok(Some(block_v1)) => Some(BlockType::BlockV1(block_v1)),
+			Err(DaSequencerError::BlockPruned(height)) => return Err(pruned_block_status(height)),
 			Err(err) => {
 				tracing::warn!(error = %err, "read_at_height get block failed because:{err}.");
 				None
@@ -232,43 +321,62 @@ impl DaSequencerNodeService for DaSequencerNode {
 
 		// Try to deserialize the batch
 		let (public_key, signature, bytes) =
-			match movement_da_sequencer_client::deserialize_full_node_batch(batch_data).map_or_else(
-				|err| {
+			match movement_da_sequencer_client::deserialize_full_node_batch(batch_data) {
+				Ok(res) => res,
+				Err(err) => {
 					tracing::warn!("Invalid batch send: deserialization failed: {err}");
-					None
-				},
-				|res| Some(res),
-			) {
-				Some(res) => res,
-				None => return Ok(tonic::Response::new(BatchWriteResponse { answer: false })),
+					return Ok(tonic::Response::new(BatchWriteResponse::rejected(
+						RejectionCode::MalformedBatch,
+						format!("deserialization failed: {err}"),
+					)));
+				}
 			};
+		let batch_size = bytes.len() as u64;
 
 		// Validate the batch
 		let validated = {
 			let raw_batch = DaBatch::<RawData>::new(public_key, signature, bytes);
-			match validate_batch(raw_batch, &self.whitelist).map_or_else(
-				|err| {
+			match validate_batch(raw_batch, &self.whitelist) {
+				Ok(validated) => validated,
+				Err(err) => {
 					tracing::warn!(
 						"Invalid batch send from sender:0x{}.  validation failed: {err}",
 						hex::encode(&public_key.to_bytes())
 					);
-					None
-				},
-				|validated| Some(validated),
-			) {
-				Some(validated) => validated,
-				None => return Ok(tonic::Response::new(BatchWriteResponse { answer: false })),
+					return Ok(tonic::Response::new(validation_rejection(err)));
+				}
 			}
 		};
 
-		if let Err(err) = self.request_tx.send(GrpcRequests::WriteBatch(validated)).await {
+		// Apply backpressure once the sender is known to be whitelisted.
+		let reservation =
+			match self.limiter.check(&public_key, batch_size, validated.data().len() as u64) {
+				Ok(reservation) => reservation,
+				Err(rejection) => {
+					tracing::warn!(
+						"Batch from sender:0x{} rejected: {rejection}",
+						hex::encode(&public_key.to_bytes())
+					);
+					return Ok(tonic::Response::new(BatchWriteResponse::rejected(
+						rejection.code(),
+						rejection.to_string(),
+					)));
+				}
+			};
+
+		if let Err(err) =
+			self.request_tx.send(GrpcRequests::WriteBatch(validated, reservation)).await
+		{
 			tracing::error!(
 				"Internal grpc request channel closed, no more batches will be processed: {err}"
 			);
-			return Ok(tonic::Response::new(BatchWriteResponse { answer: false }));
+			return Ok(tonic::Response::new(BatchWriteResponse::rejected(
+				RejectionCode::InternalError,
+				"internal error",
+			)));
 		}
 
-		Ok(tonic::Response::new(BatchWriteResponse { answer: true }))
+		Ok(tonic::Response::new(BatchWriteResponse::accepted()))
 	}
 
 	async fn send_state(
@@ -286,7 +394,10 @@ impl DaSequencerNodeService for DaSequencerNode {
 			Some(state) => state,
 			None => {
 				tracing::warn!("Bad node state data, no state in it.");
-				return Ok(tonic::Response::new(BatchWriteResponse { answer: false }));
+				return Ok(tonic::Response::new(BatchWriteResponse::rejected(
+					RejectionCode::MalformedBatch,
+					"no state",
+				)));
 			}
 		};
 
@@ -311,9 +422,12 @@ impl DaSequencerNodeService for DaSequencerNode {
 			tracing::error!(
 				"Internal grpc request channel closed, no more state will be processed: {err}"
 			);
-			return Ok(tonic::Response::new(BatchWriteResponse { answer: false }));
+			return Ok(tonic::Response::new(BatchWriteResponse::rejected(
+				RejectionCode::InternalError,
+				"internal error",
+			)));
 		}
-		Ok(tonic::Response::new(BatchWriteResponse { answer: true }))
+		Ok(tonic::Response::new(BatchWriteResponse::accepted()))
 	}
 }
 
@@ -321,12 +435,15 @@ impl TryFrom<SequencerBlock> for BlockV1 {
 	type Error = DaSequencerError;
 
 	fn try_from(block: SequencerBlock) -> Result<Self, Self::Error> {
+		let signature = block.signature().map(<[u8]>::to_vec).unwrap_or_default();
 		Ok(BlockV1 {
 			block_id: block.id().to_vec(),
 			height: block.height().into(),
 			data: bcs::to_bytes(&block.inner_block())
 				.map_err(|e| DaSequencerError::Deserialization(e.to_string()))?,
 			node_state: None,
+			matched_tx_indices: vec![],
+			signature,
 		})
 	}
 }
@@ -341,17 +458,64 @@ impl From<NodeState> for MainNodeState {
 	}
 }
 
+/// Build the response of a batch refused by `validate_batch`.
+fn validation_rejection(err: DaSequencerError) -> BatchWriteResponse {
+	let reason = format!("validation failed: {err}");
+	match err {
+		DaSequencerError::UnauthorizedSigner => {
+			BatchWriteResponse::rejected(RejectionCode::NotWhitelisted, reason)
+		}
+		DaSequencerError::InvalidSignature => {
+			BatchWriteResponse::rejected(RejectionCode::InvalidSignature, reason)
+		}
+		DaSequencerError::InvalidTransactions(indices) => {
+			BatchWriteResponse::rejected_transactions(indices, reason)
+		}
+		DaSequencerError::DeserializationFailure => {
+			BatchWriteResponse::rejected(RejectionCode::MalformedBatch, reason)
+		}
+		_ => BatchWriteResponse::rejected(RejectionCode::InternalError, reason),
+	}
+}
+
 async fn get_block_at_height(
 	height: u64,
 	request_tx: &mpsc::Sender<GrpcRequests>,
 ) -> Result<Option<BlockV1>, DaSequencerError> {
+	let block = get_sequencer_block_at_height(height, request_tx).await?;
+	block.map(|block| block.try_into()).transpose()
+}
+
+async fn get_sequencer_block_at_height(
+	height: u64,
+	request_tx: &mpsc::Sender<GrpcRequests>,
+) -> Result<Option<SequencerBlock>, DaSequencerError> {
 	let (get_height_tx, get_height_rx) = oneshot::channel();
 	request_tx
 		.send(GrpcRequests::GetBlockHeight(height.into(), get_height_tx))
 		.await
 		.map_err(|err| DaSequencerError::ChannelError(err.to_string()))?;
-	let block = get_height_rx
+	get_height_rx
+		.await
+		.map_err(|err| DaSequencerError::ChannelError(err.to_string()))?
+}
+
+/// Status returned when a requested block has been pruned from the storage.
+fn pruned_block_status(height: u64) -> tonic::Status {
+	tonic::Status::failed_precondition(format!(
+		"Block at height {height} is pruned, fetch it from the archive"
+	))
+}
+
+async fn get_current_height(
+	request_tx: &mpsc::Sender<GrpcRequests>,
+) -> Result<BlockHeight, DaSequencerError> {
+	let (current_height_tx, current_height_rx) = oneshot::channel();
+	request_tx
+		.send(GrpcRequests::GetCurrentHeight(current_height_tx))
 		.await
 		.map_err(|err| DaSequencerError::ChannelError(err.to_string()))?;
-	block.map(|block| block.try_into()).transpose()
+	current_height_rx
+		.await
+		.map_err(|err| DaSequencerError::ChannelError(err.to_string()))
 }
@@ -1,24 +1,34 @@
 use crate::{
+	assembly::{self, PendingCandidate, UNKNOWN_SUBMITTER},
 	batch::{DaBatch, UniqueFullNodeTxs},
 	block::{BlockHeight, SequencerBlock, MAX_SEQUENCER_BLOCK_SIZE},
 	celestia::CelestiaHeight,
 	error::DaSequencerError,
 };
 use bcs;
+use movement_da_sequencer_config::{BlockAssemblyConfig, BlockAssemblyPolicy};
 use movement_types::{
 	block::{self, Block, BlockMetadata},
 	transaction::Transaction,
 };
 use rocksdb::{ColumnFamilyDescriptor, Options, WriteBatch, DB};
 use std::path::Path;
-use std::{collections::BTreeSet, result::Result, sync::Arc};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::{result::Result, sync::Arc};
 
 pub mod cf {
 	pub const PENDING_TRANSACTIONS: &str = "pending_transactions";
+	pub const PENDING_SUBMITTERS: &str = "pending_submitters";
 	pub const BLOCKS: &str = "blocks";
 	pub const BLOCKS_BY_DIGEST: &str = "blocks_by_digest";
+	pub const BLOCKS_CELESTIA_HEIGHT: &str = "blocks_celestia_height";
+	pub const METADATA: &str = "metadata";
+	pub const BLOCK_SIGNATURES: &str = "block_signatures";
 }
 
+/// Key of the highest pruned block height in the metadata column family.
+const PRUNED_HEIGHT_KEY: &[u8] = b"pruned_height";
+
 /// Used to construct the composite key: [timestamp: u64][index: u32][tx_id: [32]u8].
 /// Using the composite key will naturally sort keys in lexical order in Rocksdb
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -63,6 +73,9 @@ impl TxCompositeKey {
 #[derive(Debug, Clone)]
 pub struct Storage {
 	db: Arc<DB>,
+	block_assembly: BlockAssemblyConfig,
+	/// Number of transactions in the pending column family.
+	pending_transactions: Arc<AtomicU64>,
 }
 
 pub trait DaSequencerStorage {
@@ -81,10 +94,19 @@ pub trait DaSequencerStorage {
 	/// Produces the next sequencer block from pending transactions.
 	///
 	/// - Computes the next block height.
-	/// - Aggregates pending transactions in batch-timestamp order until the block is full or size limit is reached.
+	/// - Selects pending transactions with the configured block assembly policy
+	///   until the block is full or size limit is reached.
+	/// - Signs the block header with `sign_header`, `None` leaves the block unsigned.
 	/// - Removes included transactions from the pending pool.
-	/// - Persists the new block and returns it.
-	fn produce_next_block(&self) -> Result<Option<SequencerBlock>, DaSequencerError>;
+	/// - Persists the new block with its signature and returns it.
+	///
+	/// If signing fails nothing is saved and the transactions stay pending.
+	fn produce_next_block<F>(
+		&self,
+		sign_header: F,
+	) -> Result<Option<SequencerBlock>, DaSequencerError>
+	where
+		F: FnOnce(&SequencerBlock) -> Result<Option<Vec<u8>>, DaSequencerError>;
 
 	/// Return, if exists, the Celestia height for given block height.
 	fn get_celestia_height_for_block(
@@ -99,7 +121,39 @@ pub trait DaSequencerStorage {
 		celestia_heigh: CelestiaHeight,
 	) -> Result<(), DaSequencerError>;
 
+	/// Return, if any, the highest block height that has been committed on Celestia
+	/// along with the Celestia height of the blob that contains it.
+	fn get_last_celestia_finalized_block(
+		&self,
+	) -> Result<Option<(BlockHeight, CelestiaHeight)>, DaSequencerError>;
+
 	fn get_current_block_height(&self) -> Result<BlockHeight, DaSequencerError>;
+
+	/// Save the sequencer signature of the block at the given height.
+	/// It's returned with the block by `get_block_at_height`.
+	fn set_block_signature(
+		&self,
+		height: BlockHeight,
+		signature: Vec<u8>,
+	) -> Result<(), DaSequencerError>;
+
+	/// Return the highest pruned block height, 0 if no block has been pruned.
+	fn get_pruned_height(&self) -> Result<BlockHeight, DaSequencerError>;
+
+	/// Return, in height order, up to `max_blocks` blocks that can be pruned.
+	///
+	/// A block can be pruned if it is confirmed on the external DA, it is not one of the
+	/// `keep_blocks` most recent blocks and it is below the last block finalized on the external DA,
+	/// which the external DA bootstrap starts from.
+	fn get_prunable_blocks(
+		&self,
+		keep_blocks: u64,
+		max_blocks: u64,
+	) -> Result<Vec<SequencerBlock>, DaSequencerError>;
+
+	/// Delete all blocks up to `height` included and record it as the pruned height.
+	/// The Celestia height of the pruned blocks is kept.
+	fn prune_blocks_up_to(&self, height: BlockHeight) -> Result<(), DaSequencerError>;
 }
 
 impl Storage {
@@ -111,18 +165,50 @@ impl Storage {
 
 		let pending_transactions_cf =
 			ColumnFamilyDescriptor::new(cf::PENDING_TRANSACTIONS, Options::default());
+		let pending_submitters_cf =
+			ColumnFamilyDescriptor::new(cf::PENDING_SUBMITTERS, Options::default());
 		let blocks_cf = ColumnFamilyDescriptor::new(cf::BLOCKS, Options::default());
 		let blocks_by_digest_cf =
 			ColumnFamilyDescriptor::new(cf::BLOCKS_BY_DIGEST, Options::default());
+		let blocks_celestia_height_cf =
+			ColumnFamilyDescriptor::new(cf::BLOCKS_CELESTIA_HEIGHT, Options::default());
+		let metadata_cf = ColumnFamilyDescriptor::new(cf::METADATA, Options::default());
+		let block_signatures_cf =
+			ColumnFamilyDescriptor::new(cf::BLOCK_SIGNATURES, Options::default());
 
 		let db = DB::open_cf_descriptors(
 			&options,
 			path,
-			[pending_transactions_cf, blocks_cf, blocks_by_digest_cf],
+			[
+				pending_transactions_cf,
+				pending_submitters_cf,
+				blocks_cf,
+				blocks_by_digest_cf,
+				blocks_celestia_height_cf,
+				metadata_cf,
+				block_signatures_cf,
+			],
 		)
 		.map_err(|e| DaSequencerError::StorageAccess(e.to_string()))?;
 
-		Ok(Storage { db: Arc::new(db) })
+		let pending_transactions = count_pending_transactions(&db)?;
+
+		Ok(Storage {
+			db: Arc::new(db),
+			block_assembly: BlockAssemblyConfig::default(),
+			pending_transactions: Arc::new(AtomicU64::new(pending_transactions)),
+		})
+	}
+
+	/// Shared count of the transactions waiting in the pending pool, used for backpressure.
+	pub fn pending_transactions(&self) -> Arc<AtomicU64> {
+		self.pending_transactions.clone()
+	}
+
+	/// Set the policy used to select pending transactions when a block is produced.
+	pub fn with_block_assembly(mut self, block_assembly: BlockAssemblyConfig) -> Self {
+		self.block_assembly = block_assembly;
+		self
 	}
 
 	fn determine_next_block_height(&self) -> Result<BlockHeight, DaSequencerError> {
@@ -135,21 +221,27 @@ impl DaSequencerStorage for Storage {
 		let cf = self.db.cf_handle(cf::PENDING_TRANSACTIONS).ok_or_else(|| {
 			DaSequencerError::StorageAccess("Missing column family: pending_transactions".into())
 		})?;
+		let cf_submitters = self.db.cf_handle(cf::PENDING_SUBMITTERS).ok_or_else(|| {
+			DaSequencerError::StorageAccess("Missing column family: pending_submitters".into())
+		})?;
 		let data = batch.data();
 		let txs = &data.txs;
 		let timestamp = data.timestamp;
+		let submitter = batch.signer.to_bytes();
 		let mut write_batch = WriteBatch::default();
 
 		for (i, tx) in txs.iter().enumerate() {
 			let key = TxCompositeKey::from_batch(i, tx, timestamp).encode();
 			let value =
 				bcs::to_bytes(tx).map_err(|e| DaSequencerError::Deserialization(e.to_string()))?;
+			write_batch.put_cf(&cf_submitters, &key, submitter);
 			write_batch.put_cf(&cf, key, value);
 		}
 
 		self.db
 			.write(write_batch)
 			.map_err(|e| DaSequencerError::RocksDbError(e.to_string()))?;
+		self.pending_transactions.fetch_add(txs.len() as u64, Ordering::Relaxed);
 
 		Ok(())
 	}
@@ -170,9 +262,21 @@ impl DaSequencerStorage for Storage {
 			.map_err(|e| DaSequencerError::RocksDbError(e.to_string()))?
 		{
 			Some(bytes) => {
-				let block = SequencerBlock::try_from(&bytes[..])?;
+				let cf_signatures = self.db.cf_handle(cf::BLOCK_SIGNATURES).ok_or_else(|| {
+					DaSequencerError::StorageAccess(
+						"Missing column family: block_signatures".into(),
+					)
+				})?;
+				let signature = self
+					.db
+					.get_cf(&cf_signatures, key)
+					.map_err(|e| DaSequencerError::RocksDbError(e.to_string()))?;
+				let block = SequencerBlock::try_from(&bytes[..])?.with_signature(signature);
 				Ok(Some(block))
 			}
+			None if height.0 != 0 && height <= self.get_pruned_height()? => {
+				Err(DaSequencerError::BlockPruned(height.0))
+			}
 			None => Ok(None),
 		}
 	}
@@ -203,37 +307,72 @@ impl DaSequencerStorage for Storage {
 		self.get_block_at_height(height)
 	}
 
-	fn produce_next_block(&self) -> Result<Option<SequencerBlock>, DaSequencerError> {
+	fn produce_next_block<F>(
+		&self,
+		sign_header: F,
+	) -> Result<Option<SequencerBlock>, DaSequencerError>
+	where
+		F: FnOnce(&SequencerBlock) -> Result<Option<Vec<u8>>, DaSequencerError>,
+	{
 		let cf_pending = self.db.cf_handle(cf::PENDING_TRANSACTIONS).ok_or_else(|| {
 			DaSequencerError::StorageAccess("Missing column family: pending_transactions".into())
 		})?;
 
+		let cf_submitters = self.db.cf_handle(cf::PENDING_SUBMITTERS).ok_or_else(|| {
+			DaSequencerError::StorageAccess("Missing column family: pending_submitters".into())
+		})?;
+
 		let iter = self.db.iterator_cf(&cf_pending, rocksdb::IteratorMode::Start);
 
-		let mut selected_txs = Vec::new();
-		let mut keys_to_delete = Vec::new();
+		// Fifo only needs the head of the pending pool, other policies choose among the
+		// `scan_factor` next blocks of it.
+		let scan_factor = match self.block_assembly.policy {
+			BlockAssemblyPolicy::Fifo => 1,
+			_ => self.block_assembly.scan_factor.max(1),
+		};
+		let max_scan_size = MAX_SEQUENCER_BLOCK_SIZE.saturating_mul(scan_factor);
+		let max_scan_count =
+			self.block_assembly.max_transactions_per_block.saturating_mul(scan_factor);
+		let mut candidates = Vec::new();
 		let mut total_size: u64 = 0;
 
 		for item in iter {
 			let (key, value) = item.map_err(|e| DaSequencerError::RocksDbError(e.to_string()))?;
 			let tx_size = value.len() as u64;
 
-			if total_size + tx_size > MAX_SEQUENCER_BLOCK_SIZE {
+			if total_size + tx_size > max_scan_size || candidates.len() as u64 >= max_scan_count {
 				break;
 			}
 
 			let tx: Transaction = bcs::from_bytes(&value)
 				.map_err(|e| DaSequencerError::Deserialization(e.to_string()))?;
+			let submitter = self
+				.db
+				.get_cf(&cf_submitters, &key)
+				.map_err(|e| DaSequencerError::RocksDbError(e.to_string()))?
+				.and_then(|bytes| <[u8; 32]>::try_from(bytes.as_slice()).ok())
+				.unwrap_or(UNKNOWN_SUBMITTER);
 
 			total_size += tx_size;
-			selected_txs.push(tx);
-			keys_to_delete.push(key.into_vec());
+			candidates.push(PendingCandidate { key: key.into_vec(), submitter, tx, size: tx_size });
 		}
 
-		if selected_txs.is_empty() {
+		let selected = assembly::select_transactions(
+			candidates,
+			&self.block_assembly,
+			MAX_SEQUENCER_BLOCK_SIZE,
+		);
+		if selected.is_empty() {
 			return Ok(None);
 		}
 
+		let mut selected_txs = Vec::with_capacity(selected.len());
+		let mut keys_to_delete = Vec::with_capacity(selected.len());
+		for candidate in selected {
+			selected_txs.push(candidate.tx);
+			keys_to_delete.push(candidate.key);
+		}
+
 		let height = self.determine_next_block_height()?;
 
 		let parent_id = match height.0 {
@@ -246,8 +385,8 @@ impl DaSequencerStorage for Storage {
 			}
 		};
 
-		let tx_set: BTreeSet<_> = selected_txs.into_iter().collect();
-		let block = Block::new(BlockMetadata::default(), parent_id, tx_set);
+		// The block keeps the selection order, it is the execution order.
+		let block = Block::new(BlockMetadata::default(), parent_id, selected_txs);
 		let sequencer_block = SequencerBlock::try_new(height, block)?;
 		tracing::info!(
 			"Producing new block: id:{} height:{} nb Tx:{}",
@@ -256,6 +395,10 @@ impl DaSequencerStorage for Storage {
 			sequencer_block.len()
 		);
 
+		// Sign before saving so a stored block is always signed.
+		let signature = sign_header(&sequencer_block)?;
+		let sequencer_block = sequencer_block.with_signature(signature);
+
 		// Save the block and clean up pending txs
 		self.save_block(&sequencer_block, Some(keys_to_delete))?;
 
@@ -264,17 +407,66 @@ impl DaSequencerStorage for Storage {
 
 	fn get_celestia_height_for_block(
 		&self,
-		_height: BlockHeight,
+		height: BlockHeight,
 	) -> Result<Option<CelestiaHeight>, DaSequencerError> {
-		todo!();
+		let cf = self.db.cf_handle(cf::BLOCKS_CELESTIA_HEIGHT).ok_or_else(|| {
+			DaSequencerError::StorageAccess("Missing column family: blocks_celestia_height".into())
+		})?;
+
+		let key: [u8; 8] = height.0.to_be_bytes();
+
+		match self
+			.db
+			.get_cf(&cf, key)
+			.map_err(|e| DaSequencerError::RocksDbError(e.to_string()))?
+		{
+			Some(bytes) => Ok(Some(decode_celestia_height(&bytes)?)),
+			None => Ok(None),
+		}
 	}
 
 	fn set_block_celestia_height(
 		&self,
-		_block_height: BlockHeight,
-		_celestia_height: CelestiaHeight,
+		block_height: BlockHeight,
+		celestia_height: CelestiaHeight,
 	) -> Result<(), DaSequencerError> {
-		todo!()
+		let cf = self.db.cf_handle(cf::BLOCKS_CELESTIA_HEIGHT).ok_or_else(|| {
+			DaSequencerError::StorageAccess("Missing column family: blocks_celestia_height".into())
+		})?;
+
+		let key: [u8; 8] = block_height.0.to_be_bytes();
+		let value: [u8; 8] = u64::from(celestia_height).to_be_bytes();
+
+		self.db
+			.put_cf(&cf, key, value)
+			.map_err(|e| DaSequencerError::RocksDbError(e.to_string()))?;
+
+		Ok(())
+	}
+
+	fn get_last_celestia_finalized_block(
+		&self,
+	) -> Result<Option<(BlockHeight, CelestiaHeight)>, DaSequencerError> {
+		let cf = self.db.cf_handle(cf::BLOCKS_CELESTIA_HEIGHT).ok_or_else(|| {
+			DaSequencerError::StorageAccess("Missing column family: blocks_celestia_height".into())
+		})?;
+
+		// Keys are big-endian block heights, so the last entry is the highest committed block.
+		let mut iter = self.db.iterator_cf(&cf, rocksdb::IteratorMode::End);
+		match iter.next() {
+			Some(item) => {
+				let (key, value) =
+					item.map_err(|e| DaSequencerError::RocksDbError(e.to_string()))?;
+				let block_height =
+					BlockHeight(key.as_ref().try_into().map(u64::from_be_bytes).map_err(|_| {
+						DaSequencerError::StorageFormat(
+							"Invalid block height key length in celestia height mapping".into(),
+						)
+					})?);
+				Ok(Some((block_height, decode_celestia_height(&value)?)))
+			}
+			None => Ok(None),
+		}
 	}
 
 	fn get_current_block_height(&self) -> Result<BlockHeight, DaSequencerError> {
@@ -298,10 +490,120 @@ impl DaSequencerStorage for Storage {
 			Ok(BlockHeight(0))
 		}
 	}
+
+	fn set_block_signature(
+		&self,
+		height: BlockHeight,
+		signature: Vec<u8>,
+	) -> Result<(), DaSequencerError> {
+		let cf = self.db.cf_handle(cf::BLOCK_SIGNATURES).ok_or_else(|| {
+			DaSequencerError::StorageAccess("Missing column family: block_signatures".into())
+		})?;
+
+		self.db
+			.put_cf(&cf, height.0.to_be_bytes(), signature)
+			.map_err(|e| DaSequencerError::RocksDbError(e.to_string()))?;
+
+		Ok(())
+	}
+
+	fn get_pruned_height(&self) -> Result<BlockHeight, DaSequencerError> {
+		let cf = self.db.cf_handle(cf::METADATA).ok_or_else(|| {
+			DaSequencerError::StorageAccess("Missing column family: metadata".into())
+		})?;
+
+		match self
+			.db
+			.get_cf(&cf, PRUNED_HEIGHT_KEY)
+			.map_err(|e| DaSequencerError::RocksDbError(e.to_string()))?
+		{
+			Some(bytes) => {
+				Ok(BlockHeight(bytes.as_slice().try_into().map(u64::from_be_bytes).map_err(
+					|_| DaSequencerError::StorageFormat("Invalid pruned height byte length".into()),
+				)?))
+			}
+			None => Ok(BlockHeight(0)),
+		}
+	}
+
+	fn get_prunable_blocks(
+		&self,
+		keep_blocks: u64,
+		max_blocks: u64,
+	) -> Result<Vec<SequencerBlock>, DaSequencerError> {
+		let Some((last_finalized_height, _)) = self.get_last_celestia_finalized_block()? else {
+			return Ok(vec![]);
+		};
+		let current_height = self.get_current_block_height()?;
+		let max_height = current_height
+			.0
+			.saturating_sub(keep_blocks)
+			.min(last_finalized_height.0.saturating_sub(1));
+
+		let mut blocks = vec![];
+		let mut height = self.get_pruned_height()?.0 + 1;
+		while height <= max_height && (blocks.len() as u64) < max_blocks {
+			// Stop at the first block not confirmed yet, pruned heights stay contiguous.
+			if self.get_celestia_height_for_block(BlockHeight(height))?.is_none() {
+				break;
+			}
+			match self.get_block_at_height(BlockHeight(height))? {
+				Some(block) => blocks.push(block),
+				None => break,
+			}
+			height += 1;
+		}
+		Ok(blocks)
+	}
+
+	fn prune_blocks_up_to(&self, height: BlockHeight) -> Result<(), DaSequencerError> {
+		let cf_blocks = self.db.cf_handle(cf::BLOCKS).ok_or_else(|| {
+			DaSequencerError::StorageAccess("Missing column family: blocks".into())
+		})?;
+		let cf_digests = self.db.cf_handle(cf::BLOCKS_BY_DIGEST).ok_or_else(|| {
+			DaSequencerError::StorageAccess("Missing column family: blocks_by_digest".into())
+		})?;
+		let cf_metadata = self.db.cf_handle(cf::METADATA).ok_or_else(|| {
+			DaSequencerError::StorageAccess("Missing column family: metadata".into())
+		})?;
+		let cf_signatures = self.db.cf_handle(cf::BLOCK_SIGNATURES).ok_or_else(|| {
+			DaSequencerError::StorageAccess("Missing column family: block_signatures".into())
+		})?;
+
+		// The current block is never pruned, the next block height is computed from it.
+		let height = height.0.min(self.get_current_block_height()?.0.saturating_sub(1));
+		let pruned_height = self.get_pruned_height()?.0;
+		if height <= pruned_height {
+			return Ok(());
+		}
+
+		let mut write_batch = WriteBatch::default();
+		for block_height in pruned_height + 1..=height {
+			let height_key = block_height.to_be_bytes();
+			if let Some(bytes) = self
+				.db
+				.get_cf(&cf_blocks, height_key)
+				.map_err(|e| DaSequencerError::RocksDbError(e.to_string()))?
+			{
+				let block = SequencerBlock::try_from(&bytes[..])?;
+				write_batch.delete_cf(&cf_digests, block.id());
+				write_batch.delete_cf(&cf_blocks, height_key);
+			}
+			write_batch.delete_cf(&cf_signatures, height_key);
+		}
+		write_batch.put_cf(&cf_metadata, PRUNED_HEIGHT_KEY, height.to_be_bytes());
+
+		self.db
+			.write(write_batch)
+			.map_err(|e| DaSequencerError::RocksDbError(e.to_string()))?;
+		tracing::info!("Pruned blocks from height:{} to height:{height}", pruned_height + 1);
+
+		Ok(())
+	}
 }
 
 impl Storage {
-	/// Saves the given block to storage by height and digest.
+	/// Saves the given block to storage by height and digest, with its signature if any.
 	/// Optionally deletes pending transaction keys from the DB.
 	pub fn save_block(
 		&self,
@@ -323,7 +625,14 @@ impl Storage {
 
 		write_batch.put_cf(&cf_blocks, height_key, &block_bytes);
 		write_batch.put_cf(&cf_digests, block.id(), &height_key);
+		if let Some(signature) = block.signature() {
+			let cf_signatures = self.db.cf_handle(cf::BLOCK_SIGNATURES).ok_or_else(|| {
+				DaSequencerError::StorageAccess("Missing column family: block_signatures".into())
+			})?;
+			write_batch.put_cf(&cf_signatures, height_key, signature);
+		}
 
+		let deleted_count = delete_keys.as_ref().map_or(0, |keys| keys.len() as u64);
 		if let Some(keys) = delete_keys {
 			let cf_pending = self.db.cf_handle(cf::PENDING_TRANSACTIONS).ok_or_else(|| {
 				DaSequencerError::StorageAccess(
@@ -331,7 +640,12 @@ impl Storage {
 				)
 			})?;
 
+			let cf_submitters = self.db.cf_handle(cf::PENDING_SUBMITTERS).ok_or_else(|| {
+				DaSequencerError::StorageAccess("Missing column family: pending_submitters".into())
+			})?;
+
 			for key in keys {
+				write_batch.delete_cf(&cf_submitters, &key);
 				write_batch.delete_cf(&cf_pending, key);
 			}
 		}
@@ -339,17 +653,44 @@ impl Storage {
 		self.db
 			.write(write_batch)
 			.map_err(|e| DaSequencerError::RocksDbError(e.to_string()))?;
+		// Saturate in case keys that were not pending are deleted.
+		let _ = self
+			.pending_transactions
+			.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+				Some(n.saturating_sub(deleted_count))
+			});
 
 		Ok(())
 	}
 }
 
+fn count_pending_transactions(db: &DB) -> Result<u64, DaSequencerError> {
+	let cf = db.cf_handle(cf::PENDING_TRANSACTIONS).ok_or_else(|| {
+		DaSequencerError::StorageAccess("Missing column family: pending_transactions".into())
+	})?;
+	let mut count = 0;
+	for item in db.iterator_cf(&cf, rocksdb::IteratorMode::Start) {
+		item.map_err(|e| DaSequencerError::RocksDbError(e.to_string()))?;
+		count += 1;
+	}
+	Ok(count)
+}
+
+fn decode_celestia_height(bytes: &[u8]) -> Result<CelestiaHeight, DaSequencerError> {
+	bytes
+		.try_into()
+		.map(u64::from_be_bytes)
+		.map(CelestiaHeight::from)
+		.map_err(|_| DaSequencerError::StorageFormat("Invalid celestia height byte length".into()))
+}
+
 #[cfg(test)]
 mod tests {
 	use crate::batch::FullNodeTxs;
 
 	use super::*;
 	use bcs;
+	use std::collections::BTreeSet;
 	use tempfile::TempDir;
 
 	#[test]
@@ -501,7 +842,7 @@ mod tests {
 
 		// Construct a dummy block to save
 		let height = BlockHeight(1);
-		let block = Block::new(BlockMetadata::default(), block::Id::default(), [tx.clone()].into());
+		let block = Block::new(BlockMetadata::default(), block::Id::default(), [tx.clone()]);
 		let sequencer_block = SequencerBlock::try_new(height, block).expect("valid block");
 
 		// Save the block and remove the pending tx
@@ -632,7 +973,8 @@ mod tests {
 		let txs = FullNodeTxs::new(vec![tx]);
 		let batch = DaBatch::test_only_new(txs).unique(0);
 		storage.write_batch(batch).expect("failed to write batch");
-		let maybe_block = storage.produce_next_block().expect("produce_next_block failed");
+		let maybe_block =
+			storage.produce_next_block(|_| Ok(None)).expect("produce_next_block failed");
 
 		assert!(maybe_block.is_some(), "Expected Some(block), got None");
 		let block = maybe_block.unwrap();
@@ -660,6 +1002,42 @@ mod tests {
 		assert_eq!(stored_block, block, "Stored block does not match produced block");
 	}
 
+	#[test]
+	fn test_failed_block_signing_saves_nothing() {
+		use crate::batch::DaBatch;
+		use movement_types::transaction::Transaction;
+		use tempfile::tempdir;
+
+		let temp_dir = tempdir().expect("failed to create temp dir");
+		let path = temp_dir.path().to_str().unwrap();
+		let storage = Storage::try_new(path).expect("failed to create storage");
+
+		let tx = Transaction::test_only_new(b"test data".to_vec(), 0, 1);
+		let batch = DaBatch::test_only_new(FullNodeTxs::new(vec![tx.clone()])).unique(0);
+		storage.write_batch(batch).expect("failed to write batch");
+
+		// The signer fails: no block is saved and the transaction stays pending.
+		let res = storage
+			.produce_next_block(|_| Err(DaSequencerError::BlockSigning("signer down".into())));
+		assert!(matches!(res, Err(DaSequencerError::BlockSigning(_))));
+		assert_eq!(storage.get_current_block_height().unwrap(), BlockHeight(0));
+		assert!(storage.get_block_at_height(BlockHeight(1)).unwrap().is_none());
+		assert_eq!(storage.pending_transactions().load(Ordering::Relaxed), 1);
+
+		// Once the signer is back the block is saved with its signature.
+		let block = storage
+			.produce_next_block(|block| Ok(Some(block.header_bytes())))
+			.expect("produce_next_block failed")
+			.expect("expected Some(block)");
+		assert_eq!(block.transactions().cloned().collect::<Vec<_>>(), vec![tx]);
+		let stored = storage
+			.get_block_at_height(BlockHeight(1))
+			.expect("failed to read block")
+			.expect("block not found");
+		assert_eq!(stored.signature(), Some(block.header_bytes().as_slice()));
+		assert_eq!(storage.pending_transactions().load(Ordering::Relaxed), 0);
+	}
+
 	#[test]
 	fn test_produce_block_fits_all_tx_and_clears_pending() {
 		use crate::batch::DaBatch;
@@ -679,7 +1057,7 @@ mod tests {
 
 		// Produce the block
 		let block = storage
-			.produce_next_block()
+			.produce_next_block(|_| Ok(None))
 			.expect("produce_next_block failed")
 			.expect("expected Some(block)");
 
@@ -704,4 +1082,293 @@ mod tests {
 		assert!(storage.get_block_at_height(BlockHeight(0)).unwrap().is_none());
 		assert!(storage.get_block_at_height(BlockHeight(2)).unwrap().is_none());
 	}
+
+	#[test]
+	fn test_set_and_get_block_celestia_height() {
+		use tempfile::tempdir;
+
+		let temp_dir = tempdir().expect("failed to create temp dir");
+		let path = temp_dir.path().to_str().unwrap();
+		let storage = Storage::try_new(path).expect("failed to create storage");
+
+		assert!(storage
+			.get_celestia_height_for_block(BlockHeight(1))
+			.expect("get_celestia_height_for_block failed")
+			.is_none());
+		assert!(storage
+			.get_last_celestia_finalized_block()
+			.expect("get_last_celestia_finalized_block failed")
+			.is_none());
+
+		storage
+			.set_block_celestia_height(BlockHeight(1), CelestiaHeight::from(10))
+			.expect("set_block_celestia_height failed");
+		storage
+			.set_block_celestia_height(BlockHeight(2), CelestiaHeight::from(10))
+			.expect("set_block_celestia_height failed");
+		storage
+			.set_block_celestia_height(BlockHeight(3), CelestiaHeight::from(12))
+			.expect("set_block_celestia_height failed");
+
+		assert_eq!(
+			storage.get_celestia_height_for_block(BlockHeight(2)).unwrap(),
+			Some(CelestiaHeight::from(10))
+		);
+		assert!(storage.get_celestia_height_for_block(BlockHeight(4)).unwrap().is_none());
+		assert_eq!(
+			storage.get_last_celestia_finalized_block().unwrap(),
+			Some((BlockHeight(3), CelestiaHeight::from(12)))
+		);
+	}
+
+	#[test]
+	fn test_celestia_height_survives_reopen() {
+		use tempfile::tempdir;
+
+		let temp_dir = tempdir().expect("failed to create temp dir");
+		let path = temp_dir.path().to_str().unwrap();
+		{
+			let storage = Storage::try_new(path).expect("failed to create storage");
+			storage
+				.set_block_celestia_height(BlockHeight(256), CelestiaHeight::from(42))
+				.expect("set_block_celestia_height failed");
+			storage
+				.set_block_celestia_height(BlockHeight(7), CelestiaHeight::from(3))
+				.expect("set_block_celestia_height failed");
+		}
+
+		let storage = Storage::try_new(path).expect("failed to reopen storage");
+		assert_eq!(
+			storage.get_last_celestia_finalized_block().unwrap(),
+			Some((BlockHeight(256), CelestiaHeight::from(42)))
+		);
+	}
+
+	#[test]
+	fn test_produce_next_block_round_robin_between_submitters() {
+		use crate::batch::DaBatch;
+		use movement_types::transaction::Transaction;
+		use tempfile::tempdir;
+
+		let temp_dir = tempdir().expect("failed to create temp dir");
+		let path = temp_dir.path().to_str().unwrap();
+		let storage = Storage::try_new(path)
+			.expect("failed to create storage")
+			.with_block_assembly(BlockAssemblyConfig {
+				policy: BlockAssemblyPolicy::RoundRobin,
+				max_transactions_per_block: 2,
+				round_robin_quota: 1,
+				scan_factor: 4,
+			});
+
+		// Each test batch is signed by a different key.
+		let chatty_txs: Vec<_> = (0..3)
+			.map(|i| Transaction::test_only_new(format!("chatty-{i}").into_bytes(), 1, i))
+			.collect();
+		let quiet_tx = Transaction::test_only_new(b"quiet".to_vec(), 1, 0);
+		let chatty_batch = DaBatch::test_only_new(FullNodeTxs::new(chatty_txs.clone())).unique(0);
+		let quiet_batch = DaBatch::test_only_new(FullNodeTxs::new(vec![quiet_tx.clone()]))
+			.unique(chatty_batch.data().timestamp);
+		storage.write_batch(chatty_batch).expect("write_batch (chatty) failed");
+		storage.write_batch(quiet_batch).expect("write_batch (quiet) failed");
+
+		let block = storage
+			.produce_next_block(|_| Ok(None))
+			.expect("produce_next_block failed")
+			.expect("expected Some(block)");
+		let block_tx_ids: BTreeSet<_> = block.transactions().map(|tx| tx.id()).collect();
+		assert_eq!(block_tx_ids, BTreeSet::from([chatty_txs[0].id(), quiet_tx.id()]));
+
+		// The remaining chatty transactions and their submitters stay pending.
+		let cf = storage.db.cf_handle(cf::PENDING_SUBMITTERS).expect("missing submitters CF");
+		let remaining = storage.db.iterator_cf(&cf, rocksdb::IteratorMode::Start).count();
+		assert_eq!(remaining, 2);
+	}
+
+	fn gas_price_storage(path: &str, max_transactions_per_block: u64, scan_factor: u64) -> Storage {
+		Storage::try_new(path).expect("failed to create storage").with_block_assembly(
+			BlockAssemblyConfig {
+				policy: BlockAssemblyPolicy::GasPrice,
+				max_transactions_per_block,
+				round_robin_quota: 1,
+				scan_factor,
+			},
+		)
+	}
+
+	fn write_priced_transactions(storage: &Storage, prices: &[u64]) -> Vec<Transaction> {
+		use crate::batch::DaBatch;
+		use crate::tests::create_aptos_transaction_with_gas_price;
+
+		let txs: Vec<_> = prices
+			.iter()
+			.enumerate()
+			.map(|(i, price)| {
+				let data = bcs::to_bytes(&create_aptos_transaction_with_gas_price(*price)).unwrap();
+				Transaction::test_only_new(data, 1, i as u64)
+			})
+			.collect();
+		let batch = DaBatch::test_only_new(FullNodeTxs::new(txs.clone())).unique(0);
+		storage.write_batch(batch).expect("failed to write batch");
+		txs
+	}
+
+	#[test]
+	fn test_produce_next_block_keeps_the_selection_order() {
+		use tempfile::tempdir;
+
+		let temp_dir = tempdir().expect("failed to create temp dir");
+		let storage = gas_price_storage(temp_dir.path().to_str().unwrap(), 10, 4);
+		let txs = write_priced_transactions(&storage, &[5, 20, 10]);
+
+		let block = storage
+			.produce_next_block(|_| Ok(None))
+			.expect("produce_next_block failed")
+			.expect("expected Some(block)");
+		let block_tx_ids: Vec<_> = block.transactions().map(|tx| tx.id()).collect();
+		assert_eq!(block_tx_ids, vec![txs[1].id(), txs[2].id(), txs[0].id()]);
+	}
+
+	#[test]
+	fn test_produce_next_block_scans_a_bounded_part_of_the_pending_pool() {
+		use tempfile::tempdir;
+
+		let temp_dir = tempdir().expect("failed to create temp dir");
+		let storage = gas_price_storage(temp_dir.path().to_str().unwrap(), 1, 2);
+		// The best price is out of the two blocks scanned.
+		let txs = write_priced_transactions(&storage, &[5, 10, 50]);
+
+		let block = storage
+			.produce_next_block(|_| Ok(None))
+			.expect("produce_next_block failed")
+			.expect("expected Some(block)");
+		let block_tx_ids: Vec<_> = block.transactions().map(|tx| tx.id()).collect();
+		assert_eq!(block_tx_ids, vec![txs[1].id()]);
+	}
+
+	#[test]
+	fn test_pending_transactions_count_follows_writes_and_blocks() {
+		use crate::batch::DaBatch;
+		use movement_types::transaction::Transaction;
+		use tempfile::tempdir;
+
+		let temp_dir = tempdir().expect("failed to create temp dir");
+		let path = temp_dir.path().to_str().unwrap();
+		{
+			let storage = Storage::try_new(path).expect("failed to create storage");
+			let pending = storage.pending_transactions();
+			let txs: Vec<_> = (0..3)
+				.map(|i| Transaction::test_only_new(format!("data-{i}").into_bytes(), 1, i))
+				.collect();
+			let batch = DaBatch::test_only_new(FullNodeTxs::new(txs)).unique(0);
+			storage.write_batch(batch).expect("failed to write batch");
+			assert_eq!(pending.load(Ordering::Relaxed), 3);
+		}
+
+		// The count is rebuilt from the pending column family on restart.
+		let storage = Storage::try_new(path).expect("failed to reopen storage");
+		assert_eq!(storage.pending_transactions().load(Ordering::Relaxed), 3);
+		storage.produce_next_block(|_| Ok(None)).expect("produce_next_block failed");
+		assert_eq!(storage.pending_transactions().load(Ordering::Relaxed), 0);
+	}
+
+	fn save_test_blocks(storage: &Storage, count: u64) -> Vec<SequencerBlock> {
+		let mut parent_id = block::Id::genesis_block();
+		(1..=count)
+			.map(|height| {
+				let tx = Transaction::test_only_new(height.to_be_bytes().to_vec(), 1, height);
+				let block = Block::new(BlockMetadata::default(), parent_id, BTreeSet::from([tx]));
+				parent_id = block.id();
+				let block = SequencerBlock::try_new(BlockHeight(height), block).unwrap();
+				storage.save_block(&block, None).expect("save_block failed");
+				block
+			})
+			.collect()
+	}
+
+	#[test]
+	fn test_prunable_blocks_are_confirmed_and_not_recent() {
+		let temp_dir = TempDir::new().expect("failed to create temp dir");
+		let storage = Storage::try_new(temp_dir.path()).expect("failed to create storage");
+		save_test_blocks(&storage, 10);
+
+		// Nothing is confirmed on the external DA.
+		assert!(storage.get_prunable_blocks(2, 100).unwrap().is_empty());
+
+		for height in [1, 2, 3, 5, 6, 7, 8, 9] {
+			storage
+				.set_block_celestia_height(BlockHeight(height), CelestiaHeight::from(height))
+				.unwrap();
+		}
+		// Block 4 is not confirmed yet, the pruning stops before it.
+		let heights = |blocks: Vec<SequencerBlock>| -> Vec<u64> {
+			blocks.iter().map(|block| block.height().0).collect()
+		};
+		assert_eq!(heights(storage.get_prunable_blocks(2, 100).unwrap()), vec![1, 2, 3]);
+		assert_eq!(heights(storage.get_prunable_blocks(2, 2).unwrap()), vec![1, 2]);
+
+		storage
+			.set_block_celestia_height(BlockHeight(4), CelestiaHeight::from(4))
+			.unwrap();
+		// The 2 most recent blocks are kept, and so is the last finalized block 9.
+		assert_eq!(
+			heights(storage.get_prunable_blocks(2, 100).unwrap()),
+			(1..=8).collect::<Vec<_>>()
+		);
+		assert_eq!(
+			heights(storage.get_prunable_blocks(0, 100).unwrap()),
+			(1..=8).collect::<Vec<_>>()
+		);
+	}
+
+	#[test]
+	fn test_block_signature_is_returned_with_the_block() {
+		let temp_dir = TempDir::new().expect("failed to create temp dir");
+		let storage = Storage::try_new(temp_dir.path()).expect("failed to create storage");
+		let blocks = save_test_blocks(&storage, 2);
+
+		storage
+			.set_block_signature(BlockHeight(2), vec![7; 64])
+			.expect("set signature failed");
+
+		let block = storage.get_block_at_height(BlockHeight(2)).unwrap().unwrap();
+		assert_eq!(block.signature(), Some(&[7; 64][..]));
+		assert_eq!(block.id(), blocks[1].id());
+		let block = storage.get_block_with_id(blocks[1].id()).unwrap().unwrap();
+		assert_eq!(block.signature(), Some(&[7; 64][..]));
+		assert!(storage
+			.get_block_at_height(BlockHeight(1))
+			.unwrap()
+			.unwrap()
+			.signature()
+			.is_none());
+	}
+
+	#[test]
+	fn test_pruned_blocks_are_reported_as_pruned() {
+		let temp_dir = TempDir::new().expect("failed to create temp dir");
+		let path = temp_dir.path().to_str().unwrap();
+		let blocks = {
+			let storage = Storage::try_new(path).expect("failed to create storage");
+			let blocks = save_test_blocks(&storage, 5);
+			storage.prune_blocks_up_to(BlockHeight(3)).expect("prune_blocks_up_to failed");
+			blocks
+		};
+
+		// The pruned height survives a restart.
+		let storage = Storage::try_new(path).expect("failed to reopen storage");
+		assert_eq!(storage.get_pruned_height().unwrap(), BlockHeight(3));
+		assert!(matches!(
+			storage.get_block_at_height(BlockHeight(2)),
+			Err(DaSequencerError::BlockPruned(2))
+		));
+		assert!(storage.get_block_with_id(blocks[1].id()).unwrap().is_none());
+		assert_eq!(storage.get_block_at_height(BlockHeight(4)).unwrap(), Some(blocks[3].clone()));
+		assert!(storage.get_block_at_height(BlockHeight(6)).unwrap().is_none());
+
+		// The current block is never pruned.
+		storage.prune_blocks_up_to(BlockHeight(5)).expect("prune_blocks_up_to failed");
+		assert_eq!(storage.get_pruned_height().unwrap(), BlockHeight(4));
+		assert_eq!(storage.get_current_block_height().unwrap(), BlockHeight(5));
+	}
 }
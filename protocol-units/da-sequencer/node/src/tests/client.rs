@@ -1,5 +1,6 @@
 use crate::{
 	batch::FullNodeTxs,
+	limiter::BatchWriteLimiter,
 	run,
 	server::run_server,
 	tests::{
@@ -10,11 +11,15 @@ use crate::{
 use ed25519_dalek::{Signature, Signer};
 use futures::StreamExt;
 use movement_da_sequencer_client::{
-	serialize_full_node_batch, DaSequencerClient, GrpcDaSequencerClient,
+	serialize_full_node_batch, BatchWriteError, BlockVerifier, ClientDaSequencerError,
+	DaSequencerClient, GrpcDaSequencerClient,
 };
 use movement_da_sequencer_config::DaSequencerConfig;
-use movement_da_sequencer_proto::{BatchWriteRequest, StreamReadFromHeightRequest};
+use movement_da_sequencer_proto::{
+	BatchWriteRequest, BlockFilter, StreamReadFromHeightRequest, StreamReadRangeRequest,
+};
 use movement_signer::cryptography::ed25519::Signature as SigningSignature;
+use movement_signer_loader::identifiers::{local::Local, SignerIdentifier};
 use movement_types::transaction::Transaction;
 use std::net::SocketAddr;
 use tokio::sync::mpsc;
@@ -34,15 +39,23 @@ async fn test_should_write_batch() {
 	let grpc_address = "0.0.0.0:30700"
 		.parse::<SocketAddr>()
 		.expect("Bad da sequencer listener address.");
-	let grpc_jh =
-		tokio::spawn(async move { run_server(grpc_address, request_tx, whitelist, None).await });
+	let grpc_jh = tokio::spawn(async move {
+		run_server(grpc_address, request_tx, whitelist, BatchWriteLimiter::unlimited(), None).await
+	});
 
 	//start main loop
 	let (_rest_health_tx, rest_health_rx) = tokio::sync::mpsc::channel(10);
+	let (_external_da_tx, external_da_rx) = tokio::sync::mpsc::channel(10);
 	let storage_mock = StorageMock::new();
 	let celestia_mock = CelestiaMock::new();
-	let loop_jh =
-		tokio::spawn(run(config, request_rx, rest_health_rx, storage_mock, celestia_mock));
+	let loop_jh = tokio::spawn(run(
+		config,
+		request_rx,
+		rest_health_rx,
+		external_da_rx,
+		storage_mock,
+		celestia_mock,
+	));
 
 	//need to wait the server is started before connecting
 	let _ = tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
@@ -87,15 +100,23 @@ async fn test_write_batch_gprc_main_loop_failed_validate_batch() {
 		.parse::<SocketAddr>()
 		.expect("Bad da sequencer listener address.");
 
-	let grpc_jh =
-		tokio::spawn(async move { run_server(grpc_address, request_tx, whitelist, None).await });
+	let grpc_jh = tokio::spawn(async move {
+		run_server(grpc_address, request_tx, whitelist, BatchWriteLimiter::unlimited(), None).await
+	});
 
 	//start main loop
 	let (_rest_health_tx, rest_health_rx) = tokio::sync::mpsc::channel(10);
+	let (_external_da_tx, external_da_rx) = tokio::sync::mpsc::channel(10);
 	let storage_mock = StorageMock::new();
 	let celestia_mock = CelestiaMock::new();
-	let loop_jh =
-		tokio::spawn(run(config, request_rx, rest_health_rx, storage_mock, celestia_mock));
+	let loop_jh = tokio::spawn(run(
+		config,
+		request_rx,
+		rest_health_rx,
+		external_da_rx,
+		storage_mock,
+		celestia_mock,
+	));
 
 	//need to wait the server is started before connecting
 	let _ = tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
@@ -120,10 +141,10 @@ async fn test_write_batch_gprc_main_loop_failed_validate_batch() {
 		.expect("gRPC client connection failed.");
 
 	let request = BatchWriteRequest { data: serialized };
-	let res = client.batch_write(request).await.expect("Failed to send the batch.");
+	let res = client.batch_write(request).await;
 	tracing::info!("{res:?}",);
-	//return false because of the signature.
-	assert!(!res.answer);
+	//rejected because of the signature.
+	assert!(matches!(res, Err(BatchWriteError::InvalidSignature(_))));
 
 	//TODO verify no block has been produced.
 	//Register to block stream
@@ -165,15 +186,23 @@ async fn test_produce_block_and_stream() {
 	let grpc_address = "0.0.0.0:30702"
 		.parse::<SocketAddr>()
 		.expect("Bad da sequencer listener address.");
-	let grpc_jh =
-		tokio::spawn(async move { run_server(grpc_address, request_tx, whitelist, None).await });
+	let grpc_jh = tokio::spawn(async move {
+		run_server(grpc_address, request_tx, whitelist, BatchWriteLimiter::unlimited(), None).await
+	});
 
 	//start main loop
 	let (_rest_health_tx, rest_health_rx) = tokio::sync::mpsc::channel(10);
+	let (_external_da_tx, external_da_rx) = tokio::sync::mpsc::channel(10);
 	let storage_mock = StorageMock::new();
 	let celestia_mock = CelestiaMock::new();
-	let loop_jh =
-		tokio::spawn(run(config, request_rx, rest_health_rx, storage_mock, celestia_mock));
+	let loop_jh = tokio::spawn(run(
+		config,
+		request_rx,
+		rest_health_rx,
+		external_da_rx,
+		storage_mock,
+		celestia_mock,
+	));
 
 	//need to wait the server is started before connecting
 	let _ = tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
@@ -260,12 +289,21 @@ async fn test_grpc_client_should_write_one_batch_with_a_correct_whitelist() {
 	let grpc_address = "0.0.0.0:30703"
 		.parse::<SocketAddr>()
 		.expect("Bad da sequencer listener address.");
-	let grpc_task = tokio::spawn(run_server(grpc_address, request_tx, whitelist, None));
+	let grpc_task = tokio::spawn(run_server(
+		grpc_address,
+		request_tx,
+		whitelist,
+		BatchWriteLimiter::unlimited(),
+		None,
+	));
 	let main_loop = tokio::spawn(async move {
 		let (_rest_health_tx, rest_health_rx) = tokio::sync::mpsc::channel(10);
+		let (_external_da_tx, external_da_rx) = tokio::sync::mpsc::channel(10);
 		let storage = StorageMock::new();
 		let da = CelestiaMock::new();
-		run(config, request_rx, rest_health_rx, storage, da).await.unwrap();
+		run(config, request_rx, rest_health_rx, external_da_rx, storage, da)
+			.await
+			.unwrap();
 	});
 
 	let _ = tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
@@ -310,14 +348,22 @@ async fn test_grpc_client_should_write_one_batch_with_an_empty_whitelist() {
 	let grpc_address = "0.0.0.0:30704"
 		.parse::<SocketAddr>()
 		.expect("Bad da sequencer listener address.");
-	let _grpc_jh =
-		tokio::spawn(async move { run_server(grpc_address, request_tx, whitelist, None).await });
+	let _grpc_jh = tokio::spawn(async move {
+		run_server(grpc_address, request_tx, whitelist, BatchWriteLimiter::unlimited(), None).await
+	});
 
 	let (_rest_health_tx, rest_health_rx) = tokio::sync::mpsc::channel(10);
+	let (_external_da_tx, external_da_rx) = tokio::sync::mpsc::channel(10);
 	let storage_mock = StorageMock::new();
 	let celestia_mock = CelestiaMock::new();
-	let _loop_jh =
-		tokio::spawn(run(config, request_rx, rest_health_rx, storage_mock, celestia_mock));
+	let _loop_jh = tokio::spawn(run(
+		config,
+		request_rx,
+		rest_health_rx,
+		external_da_rx,
+		storage_mock,
+		celestia_mock,
+	));
 
 	tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
 
@@ -335,9 +381,9 @@ async fn test_grpc_client_should_write_one_batch_with_an_empty_whitelist() {
 		.expect("gRPC client connection failed");
 
 	let request = BatchWriteRequest { data: serialized };
-	let res = client.batch_write(request).await.expect("Failed to send the batch.");
+	let res = client.batch_write(request).await;
 	tracing::info!("{res:?}");
-	assert!(!res.answer);
+	assert!(matches!(res, Err(BatchWriteError::NotWhitelisted(_))));
 
 	tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 }
@@ -361,14 +407,22 @@ async fn test_grpc_client_should_write_one_batch_with_a_wrong_verifying_key_in_w
 	let grpc_address = "0.0.0.0:30705"
 		.parse::<SocketAddr>()
 		.expect("Bad da sequencer listener address.");
-	let _grpc_jh =
-		tokio::spawn(async move { run_server(grpc_address, request_tx, whitelist, None).await });
+	let _grpc_jh = tokio::spawn(async move {
+		run_server(grpc_address, request_tx, whitelist, BatchWriteLimiter::unlimited(), None).await
+	});
 
 	let (_rest_health_tx, rest_health_rx) = tokio::sync::mpsc::channel(10);
+	let (_external_da_tx, external_da_rx) = tokio::sync::mpsc::channel(10);
 	let storage_mock = StorageMock::new();
 	let celestia_mock = CelestiaMock::new();
-	let _loop_jh =
-		tokio::spawn(run(config, request_rx, rest_health_rx, storage_mock, celestia_mock));
+	let _loop_jh = tokio::spawn(run(
+		config,
+		request_rx,
+		rest_health_rx,
+		external_da_rx,
+		storage_mock,
+		celestia_mock,
+	));
 
 	tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
 
@@ -386,9 +440,9 @@ async fn test_grpc_client_should_write_one_batch_with_a_wrong_verifying_key_in_w
 		.expect("gRPC client connection failed");
 
 	let request = BatchWriteRequest { data: serialized };
-	let res = client.batch_write(request).await.expect("Failed to send the batch.");
+	let res = client.batch_write(request).await;
 	tracing::info!("{res:?}");
-	assert!(!res.answer);
+	assert!(matches!(res, Err(BatchWriteError::NotWhitelisted(_))));
 
 	tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 }
@@ -406,14 +460,22 @@ async fn test_write_batch_grpc_main_loop_bad_signature() {
 	let grpc_address = "0.0.0.0:30706"
 		.parse::<SocketAddr>()
 		.expect("Bad da sequencer listener address.");
-	let _grpc_jh =
-		tokio::spawn(async move { run_server(grpc_address, request_tx, whitelist, None).await });
+	let _grpc_jh = tokio::spawn(async move {
+		run_server(grpc_address, request_tx, whitelist, BatchWriteLimiter::unlimited(), None).await
+	});
 
 	let (_rest_health_tx, rest_health_rx) = tokio::sync::mpsc::channel(10);
+	let (_external_da_tx, external_da_rx) = tokio::sync::mpsc::channel(10);
 	let storage_mock = StorageMock::new();
 	let celestia_mock = CelestiaMock::new();
-	let _loop_jh =
-		tokio::spawn(run(config, request_rx, rest_health_rx, storage_mock, celestia_mock));
+	let _loop_jh = tokio::spawn(run(
+		config,
+		request_rx,
+		rest_health_rx,
+		external_da_rx,
+		storage_mock,
+		celestia_mock,
+	));
 
 	tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
 
@@ -433,13 +495,279 @@ async fn test_write_batch_grpc_main_loop_bad_signature() {
 		.await
 		.expect("gRPC client connection failed");
 	let request = BatchWriteRequest { data: serialized };
-	let res = client.batch_write(request).await.expect("Failed to send the batch.");
+	let res = client.batch_write(request).await;
 	tracing::info!("{res:?}");
-	assert!(!res.answer);
+	assert!(matches!(res, Err(BatchWriteError::InvalidSignature(_))));
 
 	tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 }
 
+/// Submit a signed batch where some transactions are not valid Aptos transactions.
+/// The batch is rejected with the indices of the invalid transactions.
+#[tokio::test]
+async fn test_write_batch_grpc_invalid_transactions_indices() {
+	let (request_tx, request_rx) = mpsc::channel(100);
+
+	let config = DaSequencerConfig::default();
+	let signing_key = generate_signing_key();
+	let verifying_key = signing_key.verifying_key();
+	let whitelist = make_test_whitelist(vec![verifying_key]);
+
+	// Start gprc server. Define a different address for each test.
+	let grpc_address = "0.0.0.0:30707"
+		.parse::<SocketAddr>()
+		.expect("Bad da sequencer listener address.");
+	let _grpc_jh = tokio::spawn(async move {
+		run_server(grpc_address, request_tx, whitelist, BatchWriteLimiter::unlimited(), None).await
+	});
+
+	let (_rest_health_tx, rest_health_rx) = tokio::sync::mpsc::channel(10);
+	let (_external_da_tx, external_da_rx) = tokio::sync::mpsc::channel(10);
+	let storage_mock = StorageMock::new();
+	let celestia_mock = CelestiaMock::new();
+	let _loop_jh = tokio::spawn(run(
+		config,
+		request_rx,
+		rest_health_rx,
+		external_da_rx,
+		storage_mock,
+		celestia_mock,
+	));
+
+	tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
+
+	let valid_tx_data = bcs::to_bytes(&create_aptos_transaction()).unwrap();
+	let txs = FullNodeTxs::new(vec![
+		Transaction::test_only_new(valid_tx_data.clone(), 1, 1),
+		Transaction::test_only_new(b"not an aptos tx".to_vec(), 1, 2),
+		Transaction::test_only_new(valid_tx_data, 1, 3),
+		Transaction::test_only_new(b"not an aptos tx either".to_vec(), 1, 4),
+	]);
+	let batch_bytes = bcs::to_bytes(&txs).expect("Serialization failed");
+	let signature = signing_key.sign(&batch_bytes);
+	let signature = SigningSignature::try_from(&signature.to_bytes()[..]).unwrap();
+
+	let serialized = serialize_full_node_batch(verifying_key, signature, batch_bytes);
+
+	let connection_url = Url::parse(&format!("http://{}", grpc_address)).unwrap();
+	let mut client = GrpcDaSequencerClient::try_connect(&connection_url.clone(), 10)
+		.await
+		.expect("gRPC client connection failed");
+	let request = BatchWriteRequest { data: serialized };
+	let res = client.batch_write(request).await;
+	tracing::info!("{res:?}");
+	match res {
+		Err(BatchWriteError::InvalidTransactions { indices, .. }) => {
+			assert_eq!(indices, vec![1, 3]);
+		}
+		other => panic!("Expected invalid transactions rejection, got {other:?}"),
+	}
+}
+
+/// Read a bounded range of blocks, with a sender filter and in headers only mode.
+#[tokio::test]
+async fn test_stream_read_range_filter_and_headers_only() {
+	let (request_tx, request_rx) = mpsc::channel(100);
+
+	let config = DaSequencerConfig::default();
+	let signing_key = generate_signing_key();
+	let verifying_key = signing_key.verifying_key();
+	let whitelist = make_test_whitelist(vec![verifying_key]);
+
+	// Start gprc server. Define a different address for each test.
+	let grpc_address = "0.0.0.0:30708"
+		.parse::<SocketAddr>()
+		.expect("Bad da sequencer listener address.");
+	let grpc_jh = tokio::spawn(async move {
+		run_server(grpc_address, request_tx, whitelist, BatchWriteLimiter::unlimited(), None).await
+	});
+
+	let (_rest_health_tx, rest_health_rx) = tokio::sync::mpsc::channel(10);
+	let (_external_da_tx, external_da_rx) = tokio::sync::mpsc::channel(10);
+	let storage_mock = StorageMock::new();
+	let celestia_mock = CelestiaMock::new();
+	let loop_jh = tokio::spawn(run(
+		config,
+		request_rx,
+		rest_health_rx,
+		external_da_rx,
+		storage_mock,
+		celestia_mock,
+	));
+
+	tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
+
+	let connection_url = Url::parse(&format!("http://{}", grpc_address)).unwrap();
+	let mut client = GrpcDaSequencerClient::try_connect(&connection_url.clone(), 10)
+		.await
+		.expect("gRPC client connection failed");
+	let (mut block_stream, _alert_channel) = client
+		.stream_read_from_height(StreamReadFromHeightRequest { height: 0 })
+		.await
+		.expect("Failed to register to block stream");
+
+	// Produce 3 blocks, the second one contains a transaction from a known sender.
+	let filtered_tx = create_aptos_transaction();
+	for height in 1..=3 {
+		let aptos_tx = if height == 2 { filtered_tx.clone() } else { create_aptos_transaction() };
+		let tx = Transaction::test_only_new(bcs::to_bytes(&aptos_tx).unwrap(), 1, height);
+		let batch_bytes = bcs::to_bytes(&FullNodeTxs::new(vec![tx])).unwrap();
+		let signature = signing_key.sign(&batch_bytes);
+		let signature = SigningSignature::try_from(&signature.to_bytes()[..]).unwrap();
+		let data = serialize_full_node_batch(verifying_key, signature, batch_bytes);
+		client
+			.batch_write(BatchWriteRequest { data })
+			.await
+			.expect("Failed to send the batch.");
+		mock_wait_and_get_next_block(&mut block_stream, height).await;
+	}
+
+	// The range is bounded by the current height.
+	let blocks: Vec<_> = client
+		.stream_read_range(StreamReadRangeRequest {
+			from_height: 2,
+			to_height: 10,
+			filter: None,
+			headers_only: false,
+		})
+		.await
+		.expect("Failed to open range stream")
+		.collect()
+		.await;
+	let blocks = blocks.into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+	assert_eq!(blocks.iter().map(|block| block.height).collect::<Vec<_>>(), vec![2, 3]);
+	assert!(blocks.iter().all(|block| !block.data.is_empty()));
+
+	let blocks: Vec<_> = client
+		.stream_read_range(StreamReadRangeRequest {
+			from_height: 0,
+			to_height: 3,
+			filter: Some(BlockFilter {
+				sender_addresses: vec![filtered_tx.sender().to_vec()],
+				transaction_hashes: vec![],
+			}),
+			headers_only: true,
+		})
+		.await
+		.expect("Failed to open range stream")
+		.collect()
+		.await;
+	let blocks = blocks.into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+	assert_eq!(blocks.len(), 1);
+	assert_eq!(blocks[0].height, 2);
+	assert!(blocks[0].data.is_empty());
+	assert_eq!(blocks[0].matched_tx_indices, vec![0]);
+
+	// An inverted range is refused.
+	assert!(client
+		.stream_read_range(StreamReadRangeRequest {
+			from_height: 3,
+			to_height: 2,
+			filter: None,
+			headers_only: false,
+		})
+		.await
+		.is_err());
+
+	grpc_jh.abort();
+	loop_jh.abort();
+}
+
+#[tokio::test]
+async fn test_signed_blocks_are_verified_by_the_client() {
+	let (request_tx, request_rx) = mpsc::channel(100);
+
+	let block_signing_key = generate_signing_key();
+	let block_verifying_key = block_signing_key.verifying_key();
+	let mut config = DaSequencerConfig::default();
+	config.block_signer_identifier = Some(SignerIdentifier::Local(Local {
+		private_key_hex_bytes: hex::encode(block_signing_key.to_bytes()),
+	}));
+	let signing_key = generate_signing_key();
+	let verifying_key = signing_key.verifying_key();
+	let whitelist = make_test_whitelist(vec![verifying_key.clone()]);
+
+	// Start gprc server. Define a different address for each test.
+	let grpc_address = "0.0.0.0:30709"
+		.parse::<SocketAddr>()
+		.expect("Bad da sequencer listener address.");
+	let grpc_jh = tokio::spawn(async move {
+		run_server(grpc_address, request_tx, whitelist, BatchWriteLimiter::unlimited(), None).await
+	});
+
+	//start main loop
+	let (_rest_health_tx, rest_health_rx) = tokio::sync::mpsc::channel(10);
+	let (_external_da_tx, external_da_rx) = tokio::sync::mpsc::channel(10);
+	let loop_jh = tokio::spawn(run(
+		config,
+		request_rx,
+		rest_health_rx,
+		external_da_rx,
+		StorageMock::new(),
+		CelestiaMock::new(),
+	));
+
+	//need to wait the server is started before connecting
+	let _ = tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
+
+	let connection_url = Url::parse(&format!("http://127.0.0.1:{}", grpc_address.port())).unwrap();
+	let mut client = GrpcDaSequencerClient::try_connect(&connection_url, 10)
+		.await
+		.expect("gRPC client connection failed.")
+		.with_block_verifying_key(Some(block_verifying_key));
+
+	let (mut block_stream, _alert_channel) = client
+		.stream_read_from_height(StreamReadFromHeightRequest { height: 0 })
+		.await
+		.expect("Failed to register to block stream");
+
+	// The verified stream yields the signed and chained blocks.
+	mock_write_new_batch(&mut client, &signing_key, verifying_key).await;
+	mock_wait_and_get_next_block(&mut block_stream, 1).await;
+	mock_write_new_batch(&mut client, &signing_key, verifying_key).await;
+	mock_wait_and_get_next_block(&mut block_stream, 2).await;
+
+	// Blocks signed with another key are rejected.
+	let mut wrong_key_client = GrpcDaSequencerClient::try_connect(&connection_url, 10)
+		.await
+		.expect("gRPC client connection failed.")
+		.with_block_verifying_key(Some(generate_signing_key().verifying_key()));
+	let (mut wrong_key_stream, _alert_channel) = wrong_key_client
+		.stream_read_from_height(StreamReadFromHeightRequest { height: 1 })
+		.await
+		.expect("Failed to register to block stream");
+	let res = tokio::time::timeout(std::time::Duration::from_secs(2), wrong_key_stream.next())
+		.await
+		.expect("No block streamed");
+	assert!(matches!(res, Some(Err(ClientDaSequencerError::InvalidBlock { height: 1, .. }))));
+
+	// Tampered block data is detected.
+	let blocks: Vec<_> = client
+		.stream_read_range(StreamReadRangeRequest {
+			from_height: 1,
+			to_height: 2,
+			filter: None,
+			headers_only: false,
+		})
+		.await
+		.expect("Failed to open the range stream")
+		.collect::<Vec<_>>()
+		.await
+		.into_iter()
+		.collect::<Result<_, _>>()
+		.expect("Range stream blocks should be valid");
+	assert_eq!(blocks.len(), 2);
+	let mut verifier = BlockVerifier::new(block_verifying_key);
+	verifier.verify(&blocks[0]).expect("Block 1 should be valid");
+	let mut tampered = blocks[1].clone();
+	tampered.data = blocks[0].data.clone();
+	assert!(verifier.verify(&tampered).is_err());
+	verifier.verify(&blocks[1]).expect("Block 2 should be valid");
+
+	grpc_jh.abort();
+	loop_jh.abort();
+}
+
 #[tokio::test]
 async fn test_missed_grpc_heartbeat_twice_triggers_alert() {
 	let (request_tx, request_rx) = mpsc::channel(100);
@@ -451,14 +779,27 @@ async fn test_missed_grpc_heartbeat_twice_triggers_alert() {
 	let whitelist = make_test_whitelist(vec![verifying_key.clone()]);
 
 	let grpc_address = "0.0.0.0:30799".parse::<SocketAddr>().expect("Bad address");
-	let grpc_task = tokio::spawn(run_server(grpc_address, request_tx, whitelist, None));
+	let grpc_task = tokio::spawn(run_server(
+		grpc_address,
+		request_tx,
+		whitelist,
+		BatchWriteLimiter::unlimited(),
+		None,
+	));
 
 	let (_rest_health_tx, rest_health_rx) = tokio::sync::mpsc::channel(10);
+	let (_external_da_tx, external_da_rx) = tokio::sync::mpsc::channel(10);
 	let storage_mock = StorageMock::new();
 	let celestia_mock = CelestiaMock::new();
 	config.stream_heartbeat_interval_sec = 1; // short interval for test
-	let loop_task =
-		tokio::spawn(run(config, request_rx, rest_health_rx, storage_mock, celestia_mock));
+	let loop_task = tokio::spawn(run(
+		config,
+		request_rx,
+		rest_health_rx,
+		external_da_rx,
+		storage_mock,
+		celestia_mock,
+	));
 
 	tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
 	let connection_url = Url::parse(&format!("http://{}", grpc_address)).unwrap();
@@ -494,14 +835,27 @@ async fn test_missed_grpc_heartbeat_once_does_not_trigger_alert() {
 	let whitelist = make_test_whitelist(vec![verifying_key.clone()]);
 
 	let grpc_address = "0.0.0.0:30800".parse::<SocketAddr>().expect("Bad address");
-	let grpc_task = tokio::spawn(run_server(grpc_address, request_tx, whitelist, None));
+	let grpc_task = tokio::spawn(run_server(
+		grpc_address,
+		request_tx,
+		whitelist,
+		BatchWriteLimiter::unlimited(),
+		None,
+	));
 
 	let (_rest_health_tx, rest_health_rx) = tokio::sync::mpsc::channel(10);
+	let (_external_da_tx, external_da_rx) = tokio::sync::mpsc::channel(10);
 	let storage_mock = StorageMock::new();
 	let celestia_mock = CelestiaMock::new();
 	config.stream_heartbeat_interval_sec = 1; // short interval for test
-	let loop_task =
-		tokio::spawn(run(config, request_rx, rest_health_rx, storage_mock, celestia_mock));
+	let loop_task = tokio::spawn(run(
+		config,
+		request_rx,
+		rest_health_rx,
+		external_da_rx,
+		storage_mock,
+		celestia_mock,
+	));
 
 	tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
 
@@ -1,4 +1,4 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::future::Future;
 use std::sync::{Arc, Mutex};
 
@@ -70,6 +70,9 @@ pub struct StorageMockInternal {
 	pub produced_blocks: Vec<SequencerBlock>,
 	pub current_height: u64,
 	pub parent_block_id: block::Id,
+	pub celestia_heights: BTreeMap<BlockHeight, CelestiaHeight>,
+	pub pruned_height: u64,
+	pub signatures: BTreeMap<BlockHeight, Vec<u8>>,
 }
 
 #[derive(Debug, Clone)]
@@ -84,6 +87,9 @@ impl StorageMock {
 			current_height: 0,
 			produced_blocks: vec![],
 			parent_block_id: block::Id::genesis_block(),
+			celestia_heights: BTreeMap::new(),
+			pruned_height: 0,
+			signatures: BTreeMap::new(),
 		};
 		StorageMock { inner: Arc::new(Mutex::new(inner)) }
 	}
@@ -101,25 +107,50 @@ impl DaSequencerStorage for StorageMock {
 		height: BlockHeight,
 	) -> Result<Option<SequencerBlock>, DaSequencerError> {
 		let inner = self.inner.lock().unwrap();
-		Ok(inner.produced_blocks.iter().find(|b| b.height() == height).cloned())
+		if height.0 != 0 && height.0 <= inner.pruned_height {
+			return Err(DaSequencerError::BlockPruned(height.0));
+		}
+		Ok(inner
+			.produced_blocks
+			.iter()
+			.find(|b| b.height() == height)
+			.map(|b| b.clone().with_signature(inner.signatures.get(&height).cloned())))
 	}
 
 	fn get_block_with_id(&self, id: block::Id) -> Result<Option<SequencerBlock>, DaSequencerError> {
 		let inner = self.inner.lock().unwrap();
-		Ok(inner.produced_blocks.iter().find(|b| b.id() == id).cloned())
+		Ok(inner
+			.produced_blocks
+			.iter()
+			.find(|b| b.id() == id)
+			.map(|b| b.clone().with_signature(inner.signatures.get(&b.height()).cloned())))
 	}
 
-	fn produce_next_block(&self) -> Result<Option<SequencerBlock>, DaSequencerError> {
+	fn produce_next_block<F>(
+		&self,
+		sign_header: F,
+	) -> Result<Option<SequencerBlock>, DaSequencerError>
+	where
+		F: FnOnce(&SequencerBlock) -> Result<Option<Vec<u8>>, DaSequencerError>,
+	{
 		let mut inner = self.inner.lock().unwrap();
 		if inner.batches.len() == 0 {
 			return Ok(None);
 		}
 		let tx_list: BTreeSet<Transaction> =
-			inner.batches.drain(..).flat_map(|b| b.data.txs).collect();
+			inner.batches.iter().flat_map(|b| b.data.txs.clone()).collect();
 		let block = Block::new(BlockMetadata::default(), inner.parent_block_id, tx_list);
-		inner.parent_block_id = block.id();
-		inner.current_height += 1;
-		let sequencer_block = SequencerBlock::try_new(BlockHeight(inner.current_height), block)?;
+		let height = BlockHeight(inner.current_height + 1);
+		let sequencer_block = SequencerBlock::try_new(height, block)?;
+		// Nothing is saved if signing fails.
+		let signature = sign_header(&sequencer_block)?;
+		let sequencer_block = sequencer_block.with_signature(signature.clone());
+		inner.batches.clear();
+		inner.parent_block_id = sequencer_block.id();
+		inner.current_height = height.0;
+		if let Some(signature) = signature {
+			inner.signatures.insert(height, signature);
+		}
 		inner.produced_blocks.push(sequencer_block.clone());
 		tracing::info!("Mock Storage produce block at height:{}", inner.current_height);
 		Ok(Some(sequencer_block))
@@ -127,22 +158,78 @@ impl DaSequencerStorage for StorageMock {
 
 	fn get_celestia_height_for_block(
 		&self,
-		_height: BlockHeight,
+		height: BlockHeight,
 	) -> Result<Option<CelestiaHeight>, DaSequencerError> {
-		todo!();
+		Ok(self.inner.lock().unwrap().celestia_heights.get(&height).copied())
 	}
 
 	fn set_block_celestia_height(
 		&self,
-		_block_height: BlockHeight,
-		_celestia_height: CelestiaHeight,
+		block_height: BlockHeight,
+		celestia_height: CelestiaHeight,
 	) -> Result<(), DaSequencerError> {
-		todo!();
+		self.inner
+			.lock()
+			.unwrap()
+			.celestia_heights
+			.insert(block_height, celestia_height);
+		Ok(())
+	}
+
+	fn get_last_celestia_finalized_block(
+		&self,
+	) -> Result<Option<(BlockHeight, CelestiaHeight)>, DaSequencerError> {
+		let inner = self.inner.lock().unwrap();
+		Ok(inner.celestia_heights.iter().next_back().map(|(b, c)| (*b, *c)))
 	}
 
 	fn get_current_block_height(&self) -> Result<BlockHeight, DaSequencerError> {
 		Ok(self.inner.lock().unwrap().current_height.into())
 	}
+
+	fn set_block_signature(
+		&self,
+		height: BlockHeight,
+		signature: Vec<u8>,
+	) -> Result<(), DaSequencerError> {
+		self.inner.lock().unwrap().signatures.insert(height, signature);
+		Ok(())
+	}
+
+	fn get_pruned_height(&self) -> Result<BlockHeight, DaSequencerError> {
+		Ok(self.inner.lock().unwrap().pruned_height.into())
+	}
+
+	fn get_prunable_blocks(
+		&self,
+		keep_blocks: u64,
+		max_blocks: u64,
+	) -> Result<Vec<SequencerBlock>, DaSequencerError> {
+		let inner = self.inner.lock().unwrap();
+		let Some((last_finalized_height, _)) = inner.celestia_heights.iter().next_back() else {
+			return Ok(vec![]);
+		};
+		let max_height = inner
+			.current_height
+			.saturating_sub(keep_blocks)
+			.min(last_finalized_height.0.saturating_sub(1));
+		Ok(inner
+			.produced_blocks
+			.iter()
+			.filter(|b| b.height().0 > inner.pruned_height && b.height().0 <= max_height)
+			.take_while(|b| inner.celestia_heights.contains_key(&b.height()))
+			.take(max_blocks as usize)
+			.cloned()
+			.collect())
+	}
+
+	fn prune_blocks_up_to(&self, height: BlockHeight) -> Result<(), DaSequencerError> {
+		let mut inner = self.inner.lock().unwrap();
+		let height = height.0.min(inner.current_height.saturating_sub(1));
+		inner.produced_blocks.retain(|b| b.height().0 > height);
+		inner.pruned_height = inner.pruned_height.max(height);
+		Ok(())
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -49,13 +49,25 @@ pub fn generate_signing_key() -> DalekSigningKey {
 }
 
 pub fn create_aptos_transaction() -> SignedTransaction {
+	create_aptos_transaction_with_gas_price(0)
+}
+
+pub fn create_aptos_transaction_with_gas_price(gas_unit_price: u64) -> SignedTransaction {
+	create_aptos_transaction_with_sender(AccountAddress::random(), 0, gas_unit_price)
+}
+
+pub fn create_aptos_transaction_with_sender(
+	sender: AccountAddress,
+	sequence_number: u64,
+	gas_unit_price: u64,
+) -> SignedTransaction {
 	let transaction_payload = TransactionPayload::Script(Script::new(vec![0], vec![], vec![]));
 	let raw_transaction = RawTransaction::new(
-		AccountAddress::random(),
-		0,
+		sender,
+		sequence_number,
 		transaction_payload,
 		0,
-		0,
+		gas_unit_price,
 		0,
 		ChainId::test(), // This is the value used in aptos testing code.
 	);
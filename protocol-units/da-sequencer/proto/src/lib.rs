@@ -6,3 +6,33 @@ pub mod v1 {
 
 // Re-export the latest version at the crate root
 pub use v1::*;
+
+impl BatchWriteResponse {
+	/// Response for an accepted batch.
+	pub fn accepted() -> Self {
+		BatchWriteResponse {
+			answer: true,
+			reason: String::new(),
+			code: RejectionCode::Unspecified.into(),
+			invalid_tx_indices: vec![],
+		}
+	}
+
+	/// Response for a rejected batch with the code and the reason of the rejection.
+	pub fn rejected(code: RejectionCode, reason: impl Into<String>) -> Self {
+		BatchWriteResponse {
+			answer: false,
+			reason: reason.into(),
+			code: code.into(),
+			invalid_tx_indices: vec![],
+		}
+	}
+
+	/// Response for a batch rejected because of the transactions at `invalid_tx_indices`.
+	pub fn rejected_transactions(invalid_tx_indices: Vec<u32>, reason: impl Into<String>) -> Self {
+		BatchWriteResponse {
+			invalid_tx_indices,
+			..Self::rejected(RejectionCode::InvalidTransactions, reason)
+		}
+	}
+}
@@ -97,11 +97,20 @@ impl LightNodeRuntime
 		info!("Memseq path: {:?}", memseq_path);
 		let (max_block_size, build_time) = pass_through.config.block_building_parameters();
 
-		let memseq = Arc::new(memseq::Memseq::try_move_rocks(
-			PathBuf::from(memseq_path),
-			max_block_size,
-			build_time,
-		)?);
+		let priority_lane_senders = config
+			.memseq
+			.memseq_priority_lane_senders
+			.iter()
+			.map(|sender| {
+				sender
+					.parse::<memseq::AccountAddress>()
+					.map_err(|e| anyhow::anyhow!("Invalid priority lane sender {sender}: {e}"))
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+		let memseq = Arc::new(
+			memseq::Memseq::try_move_rocks(PathBuf::from(memseq_path), max_block_size, build_time)?
+				.with_priority_lane_senders(priority_lane_senders),
+		);
 		info!("Initialized Memseq with Move Rocks for LightNode in sequencer mode.");
 
 		// prevalidator
@@ -0,0 +1,496 @@
+//! Ingress admission of the transactions submitted to the mempool.
+//!
+//! The admission runs before the VM validation. The default policy is read from the
+//! access control config: account allow and deny lists, a cap on the transactions of a sender
+//! in flight, and an entry function allow-list. The list files are reloaded periodically.
+
+use super::load_shedding::{LoadShedding, ShedReason};
+use crate::mempool_admin::{Rejection, RejectionLog, RejectionStage};
+use aptos_account_whitelist::config::Config as WhitelistConfig;
+use aptos_account_whitelist::AllowedEntryFunction;
+use aptos_crypto::HashValue;
+use aptos_types::{
+	account_address::AccountAddress,
+	transaction::{SignedTransaction, TransactionPayload},
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+use tracing::{error, info};
+
+/// Why a transaction is refused by the admission policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdmissionRejection {
+	/// The sender is not in the account allow-list.
+	SenderNotAllowed(AccountAddress),
+	/// The sender is in the account deny-list.
+	SenderDenied(AccountAddress),
+	/// The sender already has the maximum number of transactions in flight.
+	SenderInFlightCapExceeded { sender: AccountAddress, cap: u64 },
+	/// The entry function is not in the entry function allow-list.
+	EntryFunctionNotAllowed(String),
+	/// Only entry function payloads are accepted with an entry function allow-list.
+	PayloadNotAllowed,
+}
+
+impl AdmissionRejection {
+	/// Label of the rejection in the metrics.
+	pub fn label(&self) -> &'static str {
+		match self {
+			AdmissionRejection::SenderNotAllowed(_) => "sender_not_allowed",
+			AdmissionRejection::SenderDenied(_) => "sender_denied",
+			AdmissionRejection::SenderInFlightCapExceeded { .. } => "sender_in_flight_cap",
+			AdmissionRejection::EntryFunctionNotAllowed(_) => "entry_function_not_allowed",
+			AdmissionRejection::PayloadNotAllowed => "payload_not_allowed",
+		}
+	}
+}
+
+impl fmt::Display for AdmissionRejection {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			AdmissionRejection::SenderNotAllowed(sender) => {
+				write!(f, "sender {sender} is not in the ingress allow-list")
+			}
+			AdmissionRejection::SenderDenied(sender) => {
+				write!(f, "sender {sender} is in the ingress deny-list")
+			}
+			AdmissionRejection::SenderInFlightCapExceeded { sender, cap } => {
+				write!(f, "sender {sender} already has {cap} transactions in flight")
+			}
+			AdmissionRejection::EntryFunctionNotAllowed(function) => {
+				write!(f, "entry function {function} is not in the ingress allow-list")
+			}
+			AdmissionRejection::PayloadNotAllowed => {
+				write!(f, "only entry function payloads are accepted")
+			}
+		}
+	}
+}
+
+/// Decides if a submitted transaction can enter the mempool.
+pub trait AdmissionPolicy: Send + Sync {
+	/// Check the transaction. `sender_in_flight` is the number of transactions of the sender
+	/// already in flight.
+	fn admit(
+		&self,
+		transaction: &SignedTransaction,
+		sender_in_flight: u64,
+	) -> Result<(), AdmissionRejection>;
+}
+
+/// The rules read from the access control config.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct IngressRules {
+	allowed_accounts: Option<HashSet<AccountAddress>>,
+	denied_accounts: Option<HashSet<AccountAddress>>,
+	allowed_entry_functions: Option<HashSet<AllowedEntryFunction>>,
+	max_in_flight_per_sender: Option<u64>,
+}
+
+impl IngressRules {
+	fn try_from_config(config: &WhitelistConfig) -> Result<Self, anyhow::Error> {
+		Ok(IngressRules {
+			allowed_accounts: config.whitelisted_accounts()?,
+			denied_accounts: config.denied_accounts()?,
+			allowed_entry_functions: config.allowed_entry_functions()?,
+			max_in_flight_per_sender: config.max_in_flight_per_sender,
+		})
+	}
+}
+
+/// The admission policy of the access control config.
+#[derive(Debug, Clone)]
+pub struct IngressPolicy {
+	rules: Arc<RwLock<IngressRules>>,
+}
+
+impl IngressPolicy {
+	pub fn try_from_config(config: &WhitelistConfig) -> Result<Self, anyhow::Error> {
+		let rules = IngressRules::try_from_config(config)?;
+		info!("Ingress admission rules: {:?}", rules);
+		Ok(IngressPolicy { rules: Arc::new(RwLock::new(rules)) })
+	}
+
+	/// Create the policy and spawn a thread that reloads the list files
+	/// every `ingress_reload_interval_sec`.
+	pub fn from_config_and_spawn_reload_thread(
+		config: &WhitelistConfig,
+	) -> Result<Self, anyhow::Error> {
+		let policy = Self::try_from_config(config)?;
+		if config.ingress_reload_interval_sec == 0 {
+			return Ok(policy);
+		}
+		let shared_rules = policy.rules.clone();
+		let config = config.clone();
+		thread::spawn(move || loop {
+			thread::sleep(Duration::from_secs(config.ingress_reload_interval_sec));
+			match IngressRules::try_from_config(&config) {
+				Ok(updated) => {
+					if let Ok(mut guard) = shared_rules.write() {
+						if *guard != updated {
+							info!("[ingress] Ingress admission rules updated: {:?}", updated);
+							*guard = updated;
+						}
+					} else {
+						error!("[ingress] Failed to acquire write lock");
+					}
+				}
+				// The previous rules are kept until the files are fixed.
+				Err(err) => error!("[ingress] Reload failed: {}", err),
+			}
+		});
+		Ok(policy)
+	}
+}
+
+impl AdmissionPolicy for IngressPolicy {
+	fn admit(
+		&self,
+		transaction: &SignedTransaction,
+		sender_in_flight: u64,
+	) -> Result<(), AdmissionRejection> {
+		let rules = self.rules.read().expect("Ingress rules lock poisoned");
+		let sender = transaction.sender();
+
+		if rules.denied_accounts.as_ref().is_some_and(|denied| denied.contains(&sender)) {
+			return Err(AdmissionRejection::SenderDenied(sender));
+		}
+		if rules
+			.allowed_accounts
+			.as_ref()
+			.is_some_and(|allowed| !allowed.contains(&sender))
+		{
+			return Err(AdmissionRejection::SenderNotAllowed(sender));
+		}
+		if let Some(cap) = rules.max_in_flight_per_sender {
+			if sender_in_flight >= cap {
+				return Err(AdmissionRejection::SenderInFlightCapExceeded { sender, cap });
+			}
+		}
+		if let Some(allowed_functions) = &rules.allowed_entry_functions {
+			let TransactionPayload::EntryFunction(entry_function) = transaction.payload() else {
+				return Err(AdmissionRejection::PayloadNotAllowed);
+			};
+			let module = entry_function.module();
+			let function = entry_function.function().as_str();
+			if !allowed_functions
+				.iter()
+				.any(|allowed| allowed.allows(module.address(), module.name().as_str(), function))
+			{
+				return Err(AdmissionRejection::EntryFunctionNotAllowed(format!(
+					"{}::{}::{}",
+					module.address().to_hex_literal(),
+					module.name(),
+					function
+				)));
+			}
+		}
+		Ok(())
+	}
+}
+
+/// The transactions of each sender accepted in the mempool and not executed yet, by sequence
+/// number, with their admission time in milliseconds. They cap the transactions of a sender in
+/// flight, and the simulations execute them before a transaction of their sender.
+#[derive(Debug, Default)]
+pub struct SenderInFlight {
+	senders: HashMap<AccountAddress, BTreeMap<u64, (u64, SignedTransaction)>>,
+}
+
+impl SenderInFlight {
+	pub fn count(&self, sender: &AccountAddress) -> u64 {
+		self.senders.get(sender).map(|in_flight| in_flight.len() as u64).unwrap_or(0)
+	}
+
+	/// Number of senders with transactions in flight.
+	pub fn sender_count(&self) -> usize {
+		self.senders.len()
+	}
+
+	pub fn add(&mut self, transaction: &SignedTransaction, now_ms: u64) {
+		self.senders
+			.entry(transaction.sender())
+			.or_default()
+			.insert(transaction.sequence_number(), (now_ms, transaction.clone()));
+	}
+
+	/// Remove the transactions of the sender up to the executed sequence number.
+	pub fn remove_up_to(&mut self, sender: &AccountAddress, sequence_number: u64) {
+		if let Some(in_flight) = self.senders.get_mut(sender) {
+			in_flight.retain(|seq, _| *seq > sequence_number);
+			if in_flight.is_empty() {
+				self.senders.remove(sender);
+			}
+		}
+	}
+
+	/// Remove a single transaction, the next ones of the sender stay in flight.
+	pub fn remove(&mut self, sender: &AccountAddress, sequence_number: u64) {
+		if let Some(in_flight) = self.senders.get_mut(sender) {
+			in_flight.remove(&sequence_number);
+			if in_flight.is_empty() {
+				self.senders.remove(sender);
+			}
+		}
+	}
+
+	/// Remove the transactions admitted more than `ttl_ms` ago, they expired or were dropped.
+	pub fn gc(&mut self, now_ms: u64, ttl_ms: u64) {
+		self.senders.retain(|_, in_flight| {
+			in_flight.retain(|_, (admitted_ms, _)| now_ms.saturating_sub(*admitted_ms) < ttl_ms);
+			!in_flight.is_empty()
+		});
+	}
+
+	/// The transactions of the sender in flight, ordered by sequence number.
+	pub fn of_sender(&self, sender: &AccountAddress) -> Vec<SignedTransaction> {
+		self.senders
+			.get(sender)
+			.map(|in_flight| {
+				in_flight.values().map(|(_, transaction)| transaction.clone()).collect()
+			})
+			.unwrap_or_default()
+	}
+
+	/// The transactions of the sender in flight with their admission time.
+	pub fn entries_of_sender(&self, sender: &AccountAddress) -> Vec<(u64, SignedTransaction)> {
+		self.senders
+			.get(sender)
+			.map(|in_flight| in_flight.values().cloned().collect())
+			.unwrap_or_default()
+	}
+
+	/// The senders with transactions in flight and their number of transactions in flight.
+	pub fn senders(&self) -> Vec<(AccountAddress, usize)> {
+		self.senders
+			.iter()
+			.map(|(sender, in_flight)| (*sender, in_flight.len()))
+			.collect()
+	}
+
+	pub fn get_by_hash(&self, hash: &HashValue) -> Option<SignedTransaction> {
+		self.senders.values().find_map(|in_flight| {
+			in_flight
+				.values()
+				.find(|(_, transaction)| transaction.committed_hash() == *hash)
+				.map(|(_, transaction)| transaction.clone())
+		})
+	}
+}
+
+/// The admission layer of the transaction pipe: the policy, the load shedding and the in-flight
+/// transactions of each sender they need. The transactions in flight are shared with the
+/// simulator, and the last rejections are kept for the operators.
+#[derive(Clone)]
+pub struct Admission {
+	policy: Arc<dyn AdmissionPolicy>,
+	load_shedding: LoadShedding,
+	sender_in_flight: Arc<RwLock<SenderInFlight>>,
+	rejections: RejectionLog,
+}
+
+impl Admission {
+	pub fn new(
+		policy: Arc<dyn AdmissionPolicy>,
+		load_shedding: LoadShedding,
+		sender_in_flight: Arc<RwLock<SenderInFlight>>,
+		rejections: RejectionLog,
+	) -> Self {
+		Admission { policy, load_shedding, sender_in_flight, rejections }
+	}
+
+	pub fn sender_in_flight(&self) -> &Arc<RwLock<SenderInFlight>> {
+		&self.sender_in_flight
+	}
+
+	pub fn rejections(&self) -> &RejectionLog {
+		&self.rejections
+	}
+
+	/// Replace the policy, keeping the load shedding and the transactions in flight.
+	pub fn with_policy(self, policy: Arc<dyn AdmissionPolicy>) -> Self {
+		Admission { policy, ..self }
+	}
+
+	/// Check the transaction with the policy.
+	pub fn check(&self, transaction: &SignedTransaction) -> Result<(), AdmissionRejection> {
+		let sender_in_flight = self.sender_in_flight.read().unwrap().count(&transaction.sender());
+		self.policy.admit(transaction, sender_in_flight)
+	}
+
+	/// Check if the transaction must be shed given the `in_flight` transactions of all senders.
+	pub fn shed(
+		&self,
+		sender: &AccountAddress,
+		in_flight: u64,
+		in_flight_limit: Option<u64>,
+	) -> Result<(), ShedReason> {
+		let sender_in_flight = self.sender_in_flight.read().unwrap();
+		self.load_shedding.check(sender, &sender_in_flight, in_flight, in_flight_limit)
+	}
+
+	/// Record a transaction accepted in the mempool.
+	pub fn admitted(&self, transaction: &SignedTransaction, now_ms: u64) {
+		self.sender_in_flight.write().unwrap().add(transaction, now_ms);
+	}
+
+	/// Record a transaction executed or discarded.
+	pub fn executed(&self, sender: &AccountAddress, sequence_number: u64) {
+		self.sender_in_flight.write().unwrap().remove_up_to(sender, sequence_number);
+	}
+
+	/// Record a transaction refused or dropped by the transaction pipe.
+	pub fn rejected(&self, rejection: Rejection) {
+		self.rejections.record(rejection);
+	}
+
+	/// Record a transaction evicted from the mempool by an operator.
+	pub fn evicted(&self, transaction: &SignedTransaction) {
+		let sender = transaction.sender();
+		let sequence_number = transaction.sequence_number();
+		self.sender_in_flight.write().unwrap().remove(&sender, sequence_number);
+		self.rejected(Rejection::of_transaction(
+			transaction,
+			RejectionStage::Eviction,
+			"evicted by an operator",
+		));
+	}
+
+	pub fn gc(&self, now_ms: u64, ttl_ms: u64) {
+		self.sender_in_flight.write().unwrap().gc(now_ms, ttl_ms);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use aptos_sdk::move_types::{identifier::Identifier, language_storage::ModuleId};
+	use aptos_types::test_helpers::transaction_test_helpers::get_test_signed_txn;
+	use aptos_types::transaction::EntryFunction;
+	use aptos_vm_genesis::GENESIS_KEYPAIR;
+
+	fn policy(rules: IngressRules) -> IngressPolicy {
+		IngressPolicy { rules: Arc::new(RwLock::new(rules)) }
+	}
+
+	fn transaction(sender: AccountAddress) -> SignedTransaction {
+		transaction_with_sequence_number(sender, 0)
+	}
+
+	fn transaction_with_sequence_number(
+		sender: AccountAddress,
+		sequence_number: u64,
+	) -> SignedTransaction {
+		get_test_signed_txn(
+			sender,
+			sequence_number,
+			&GENESIS_KEYPAIR.0,
+			GENESIS_KEYPAIR.1.clone(),
+			None,
+		)
+	}
+
+	fn entry_function_transaction(module: &str, function: &str) -> SignedTransaction {
+		let payload = TransactionPayload::EntryFunction(EntryFunction::new(
+			ModuleId::new(AccountAddress::ONE, Identifier::new(module).unwrap()),
+			Identifier::new(function).unwrap(),
+			vec![],
+			vec![],
+		));
+		get_test_signed_txn(
+			AccountAddress::random(),
+			0,
+			&GENESIS_KEYPAIR.0,
+			GENESIS_KEYPAIR.1.clone(),
+			Some(payload),
+		)
+	}
+
+	#[test]
+	fn test_account_lists_and_sender_cap() {
+		let allowed = AccountAddress::random();
+		let denied = AccountAddress::random();
+		let policy = policy(IngressRules {
+			allowed_accounts: Some(HashSet::from([allowed, denied])),
+			denied_accounts: Some(HashSet::from([denied])),
+			allowed_entry_functions: None,
+			max_in_flight_per_sender: Some(2),
+		});
+
+		assert_eq!(policy.admit(&transaction(allowed), 1), Ok(()));
+		assert_eq!(
+			policy.admit(&transaction(allowed), 2),
+			Err(AdmissionRejection::SenderInFlightCapExceeded { sender: allowed, cap: 2 })
+		);
+		assert_eq!(
+			policy.admit(&transaction(denied), 0),
+			Err(AdmissionRejection::SenderDenied(denied))
+		);
+		let other = AccountAddress::random();
+		assert_eq!(
+			policy.admit(&transaction(other), 0),
+			Err(AdmissionRejection::SenderNotAllowed(other))
+		);
+	}
+
+	#[test]
+	fn test_entry_function_allow_list() {
+		let allowed_entry_functions = Some(HashSet::from([
+			AllowedEntryFunction {
+				address: AccountAddress::ONE,
+				module: "aptos_account".to_string(),
+				function: None,
+			},
+			AllowedEntryFunction {
+				address: AccountAddress::ONE,
+				module: "coin".to_string(),
+				function: Some("transfer".to_string()),
+			},
+		]));
+		let policy = policy(IngressRules { allowed_entry_functions, ..Default::default() });
+
+		assert_eq!(
+			policy.admit(&entry_function_transaction("aptos_account", "transfer"), 0),
+			Ok(())
+		);
+		assert_eq!(policy.admit(&entry_function_transaction("coin", "transfer"), 0), Ok(()));
+		assert_eq!(
+			policy.admit(&entry_function_transaction("coin", "register"), 0),
+			Err(AdmissionRejection::EntryFunctionNotAllowed("0x1::coin::register".to_string()))
+		);
+		// The default test transactions have a script payload.
+		assert_eq!(
+			policy.admit(&transaction(AccountAddress::random()), 0),
+			Err(AdmissionRejection::PayloadNotAllowed)
+		);
+	}
+
+	#[test]
+	fn test_sender_in_flight_tracking() {
+		let sender = AccountAddress::random();
+		let other = AccountAddress::random();
+		let mut in_flight = SenderInFlight::default();
+		in_flight.add(&transaction_with_sequence_number(sender, 2), 10);
+		in_flight.add(&transaction_with_sequence_number(sender, 1), 0);
+		in_flight.add(&transaction_with_sequence_number(sender, 3), 20);
+		in_flight.add(&transaction_with_sequence_number(other, 0), 0);
+		assert_eq!(in_flight.count(&sender), 3);
+		assert_eq!(in_flight.sender_count(), 2);
+		let sequence_numbers: Vec<u64> =
+			in_flight.of_sender(&sender).iter().map(|tx| tx.sequence_number()).collect();
+		assert_eq!(sequence_numbers, vec![1, 2, 3]);
+
+		in_flight.remove_up_to(&sender, 1);
+		assert_eq!(in_flight.count(&sender), 2);
+		let hash = transaction_with_sequence_number(sender, 2).committed_hash();
+		assert_eq!(in_flight.get_by_hash(&hash).map(|tx| tx.sequence_number()), Some(2));
+
+		in_flight.gc(25, 10);
+		assert_eq!(in_flight.count(&sender), 1);
+		in_flight.gc(100, 10);
+		assert_eq!(in_flight.count(&sender), 0);
+	}
+}
@@ -12,6 +12,8 @@ pub enum Error {
 	LoadSign(#[from] LoaderError),
 	#[error("Error serialization of transaction batch: {0}")]
 	SerialisationFailed(#[from] bcs::Error),
+	#[error("Batch outbox storage error: {0}")]
+	Outbox(String),
 }
 
 impl From<anyhow::Error> for Error {
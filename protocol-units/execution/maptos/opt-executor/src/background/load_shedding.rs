@@ -0,0 +1,187 @@
+//! Load shedding of the transactions submitted to the mempool.
+//!
+//! On top of the global limit of transactions in flight, once the transactions in flight reach
+//! the fair admission threshold, each sender is limited to its weighted share of the global limit,
+//! so a single sender can't starve the others. The cap on the transactions in flight of a single
+//! sender is the `max_in_flight_per_sender` of the ingress admission policy.
+
+use super::admission::SenderInFlight;
+use aptos_types::account_address::AccountAddress;
+use maptos_execution_util::config::load_shedding::Config as LoadSheddingConfig;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Why a transaction is shed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShedReason {
+	/// The global limit of transactions in flight is reached.
+	MempoolFull { limit: u64 },
+	/// The mempool is loaded and the sender has its fair share of transactions in flight.
+	FairShare { sender: AccountAddress, share: u64 },
+}
+
+impl ShedReason {
+	/// Label of the reason in the metrics.
+	pub fn label(&self) -> &'static str {
+		match self {
+			ShedReason::MempoolFull { .. } => "mempool_full",
+			ShedReason::FairShare { .. } => "fair_share",
+		}
+	}
+}
+
+impl fmt::Display for ShedReason {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ShedReason::MempoolFull { limit } => {
+				write!(f, "mempool full, {limit} transactions in flight")
+			}
+			ShedReason::FairShare { sender, share } => {
+				write!(f, "sender {sender} already has its share of {share} transactions in flight")
+			}
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LoadShedding {
+	fair_admission_threshold_percent: u64,
+	account_weights: HashMap<AccountAddress, u64>,
+}
+
+impl LoadShedding {
+	pub fn from_config(config: &LoadSheddingConfig) -> Self {
+		LoadShedding {
+			fair_admission_threshold_percent: config.fair_admission_threshold_percent,
+			account_weights: config
+				.account_weights
+				.iter()
+				.map(|(account, weight)| (*account, *weight))
+				.collect(),
+		}
+	}
+
+	fn weight(&self, account: &AccountAddress) -> u64 {
+		self.account_weights.get(account).copied().unwrap_or(1)
+	}
+
+	/// Total weight of the senders with transactions in flight. Only the accounts with a
+	/// configured weight are looked up, the others weigh 1.
+	fn active_weight(&self, sender_in_flight: &SenderInFlight) -> u64 {
+		let (weighted_count, weighted_total) = self
+			.account_weights
+			.iter()
+			.filter(|(account, _)| sender_in_flight.count(account) > 0)
+			.fold((0u64, 0u64), |(count, total), (_, weight)| {
+				(count + 1, total.saturating_add(*weight))
+			});
+		(sender_in_flight.sender_count() as u64 - weighted_count).saturating_add(weighted_total)
+	}
+
+	/// Check if a new transaction of the sender can be admitted.
+	pub fn check(
+		&self,
+		sender: &AccountAddress,
+		sender_in_flight: &SenderInFlight,
+		in_flight: u64,
+		in_flight_limit: Option<u64>,
+	) -> Result<(), ShedReason> {
+		let Some(limit) = in_flight_limit else {
+			return Ok(());
+		};
+		if in_flight >= limit {
+			return Err(ShedReason::MempoolFull { limit });
+		}
+
+		// Below the threshold, the senders are not limited.
+		let threshold = limit.saturating_mul(self.fair_admission_threshold_percent) / 100;
+		if in_flight < threshold {
+			return Ok(());
+		}
+
+		// Share the global limit between the senders with transactions in flight, and this one.
+		let sender_weight = self.weight(sender);
+		let sender_count = sender_in_flight.count(sender);
+		let mut total_weight = self.active_weight(sender_in_flight);
+		if sender_count == 0 {
+			total_weight = total_weight.saturating_add(sender_weight);
+		}
+		let share = if total_weight == 0 {
+			limit
+		} else {
+			(limit.saturating_mul(sender_weight) / total_weight).max(1)
+		};
+		if sender_count >= share {
+			return Err(ShedReason::FairShare { sender: *sender, share });
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use aptos_types::test_helpers::transaction_test_helpers::get_test_signed_txn;
+	use aptos_types::transaction::SignedTransaction;
+	use aptos_vm_genesis::GENESIS_KEYPAIR;
+	use std::collections::BTreeMap;
+
+	fn transaction(sender: AccountAddress, sequence_number: u64) -> SignedTransaction {
+		get_test_signed_txn(
+			sender,
+			sequence_number,
+			&GENESIS_KEYPAIR.0,
+			GENESIS_KEYPAIR.1.clone(),
+			None,
+		)
+	}
+
+	fn load_shedding(account_weights: BTreeMap<AccountAddress, u64>) -> LoadShedding {
+		LoadShedding::from_config(&LoadSheddingConfig {
+			fair_admission_threshold_percent: 50,
+			account_weights,
+			..LoadSheddingConfig::default()
+		})
+	}
+
+	#[test]
+	fn test_global_limit() {
+		let shedding = load_shedding(BTreeMap::new());
+		let sender = AccountAddress::random();
+		let mut in_flight = SenderInFlight::default();
+
+		assert_eq!(shedding.check(&sender, &in_flight, 0, None), Ok(()));
+		in_flight.add(&transaction(sender, 0), 0);
+		in_flight.add(&transaction(sender, 1), 0);
+		assert_eq!(shedding.check(&sender, &in_flight, 2, None), Ok(()));
+
+		let other = AccountAddress::random();
+		assert_eq!(
+			shedding.check(&other, &in_flight, 10, Some(10)),
+			Err(ShedReason::MempoolFull { limit: 10 })
+		);
+	}
+
+	#[test]
+	fn test_fair_admission_stops_the_flooding_sender() {
+		let spammer = AccountAddress::random();
+		let partner = AccountAddress::random();
+		let shedding = load_shedding(BTreeMap::from([(partner, 3)]));
+		let mut in_flight = SenderInFlight::default();
+		for seq in 0..60 {
+			in_flight.add(&transaction(spammer, seq), 0);
+		}
+
+		// Below the threshold, a single sender can fill the mempool.
+		assert_eq!(shedding.check(&spammer, &in_flight, 40, Some(100)), Ok(()));
+		// Above it, a sender alone keeps the whole limit.
+		assert_eq!(shedding.check(&spammer, &in_flight, 60, Some(100)), Ok(()));
+		// Once the partner is active, the spammer gets 1/4 of the limit and the partner 3/4.
+		in_flight.add(&transaction(partner, 0), 0);
+		assert_eq!(
+			shedding.check(&spammer, &in_flight, 61, Some(100)),
+			Err(ShedReason::FairShare { sender: spammer, share: 25 })
+		);
+		assert_eq!(shedding.check(&partner, &in_flight, 61, Some(100)), Ok(()));
+	}
+}
@@ -1,11 +1,20 @@
 mod task;
 
+mod admission;
+mod load_shedding;
+
+mod outbox;
 mod read_only;
 mod transaction_pipe;
 
 mod error;
 
+pub use admission::{
+	Admission, AdmissionPolicy, AdmissionRejection, IngressPolicy, SenderInFlight,
+};
 pub use error::Error;
+pub use load_shedding::{LoadShedding, ShedReason};
+pub use outbox::{BatchOutbox, OutboxBatch};
 use read_only::NullMempool;
 pub use task::BackgroundTask;
 pub use transaction_pipe::TransactionPipe;
@@ -0,0 +1,207 @@
+//! Write-ahead outbox of the transaction batches sent to the DA Sequencer.
+//!
+//! A batch is persisted before it is sent and removed once the DA Sequencer accepts it,
+//! so the transactions of a failed `batch_write` are not lost when the node stops.
+//! The batches left in the outbox at startup are re-admitted to the mempool.
+//! An entry that can't be read is moved aside to the corrupt batches instead of blocking
+//! the others.
+
+use super::Error;
+use movement_types::transaction::Transaction;
+use rocksdb::{ColumnFamily, ColumnFamilyDescriptor, IteratorMode, Options, WriteBatch, DB};
+use std::path::Path;
+use std::sync::{
+	atomic::{AtomicU64, Ordering},
+	Arc,
+};
+use tracing::warn;
+
+mod cf {
+	pub const PENDING_BATCHES: &str = "pending_batches";
+	pub const CORRUPT_BATCHES: &str = "corrupt_batches";
+}
+
+/// A batch waiting for the DA Sequencer acknowledgement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboxBatch {
+	pub id: u64,
+	pub transactions: Vec<Transaction>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchOutbox {
+	db: Arc<DB>,
+	next_id: Arc<AtomicU64>,
+}
+
+impl BatchOutbox {
+	pub fn try_new(path: impl AsRef<Path>) -> Result<Self, Error> {
+		let mut options = Options::default();
+		options.create_if_missing(true);
+		options.create_missing_column_families(true);
+
+		let pending_batches_cf =
+			ColumnFamilyDescriptor::new(cf::PENDING_BATCHES, Options::default());
+		let corrupt_batches_cf =
+			ColumnFamilyDescriptor::new(cf::CORRUPT_BATCHES, Options::default());
+		let db = DB::open_cf_descriptors(&options, path, [pending_batches_cf, corrupt_batches_cf])
+			.map_err(|e| Error::Outbox(e.to_string()))?;
+		let outbox = BatchOutbox { db: Arc::new(db), next_id: Arc::new(AtomicU64::new(0)) };
+
+		// Batch ids keep increasing across restarts so the batches are kept in sending order.
+		// The keys that aren't ids are skipped, `pending_batches` moves them aside.
+		let last_id = outbox
+			.db
+			.iterator_cf(outbox.cf_handle(cf::PENDING_BATCHES)?, IteratorMode::End)
+			.map(|entry| entry.map(|(key, _)| decode_id(&key).ok()))
+			.find_map(Result::transpose)
+			.transpose()
+			.map_err(|e| Error::Outbox(e.to_string()))?;
+		outbox.next_id.store(last_id.map_or(0, |id| id + 1), Ordering::Relaxed);
+
+		Ok(outbox)
+	}
+
+	fn cf_handle(&self, name: &str) -> Result<&ColumnFamily, Error> {
+		self.db
+			.cf_handle(name)
+			.ok_or_else(|| Error::Outbox(format!("CF handle {name} not found")))
+	}
+
+	/// The id of the next pushed batch.
+	pub fn next_id(&self) -> u64 {
+		self.next_id.load(Ordering::Relaxed)
+	}
+
+	/// The id of the oldest pending batch, if any.
+	pub fn oldest_pending_id(&self) -> Result<Option<u64>, Error> {
+		self.db
+			.iterator_cf(self.cf_handle(cf::PENDING_BATCHES)?, IteratorMode::Start)
+			.map(|entry| entry.map(|(key, _)| decode_id(&key).ok()))
+			.find_map(Result::transpose)
+			.transpose()
+			.map_err(|e| Error::Outbox(e.to_string()))
+	}
+
+	/// Persist a new batch and return it with its id.
+	pub fn push(&self, transactions: Vec<Transaction>) -> Result<OutboxBatch, Error> {
+		let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+		let batch = OutboxBatch { id, transactions };
+		self.put(&batch)?;
+		Ok(batch)
+	}
+
+	/// Write a batch, replacing the pending batch with the same id.
+	pub fn put(&self, batch: &OutboxBatch) -> Result<(), Error> {
+		let value = bcs::to_bytes(&batch.transactions)?;
+		self.db
+			.put_cf(self.cf_handle(cf::PENDING_BATCHES)?, batch.id.to_be_bytes(), value)
+			.map_err(|e| Error::Outbox(e.to_string()))
+	}
+
+	/// Remove an acknowledged or dropped batch.
+	pub fn remove(&self, id: u64) -> Result<(), Error> {
+		self.db
+			.delete_cf(self.cf_handle(cf::PENDING_BATCHES)?, id.to_be_bytes())
+			.map_err(|e| Error::Outbox(e.to_string()))
+	}
+
+	/// The pending batches, in sending order.
+	/// The entries that can't be decoded are logged and moved aside to the corrupt batches.
+	pub fn pending_batches(&self) -> Result<Vec<OutboxBatch>, Error> {
+		let mut batches = Vec::new();
+		let mut corrupt = Vec::new();
+		for entry in self.db.iterator_cf(self.cf_handle(cf::PENDING_BATCHES)?, IteratorMode::Start)
+		{
+			let (key, value) = entry.map_err(|e| Error::Outbox(e.to_string()))?;
+			match decode_batch(&key, &value) {
+				Ok(batch) => batches.push(batch),
+				Err(e) => {
+					warn!("Moving aside the unreadable outbox batch 0x{}: {e}", hex::encode(&key));
+					corrupt.push((key, value));
+				}
+			}
+		}
+		if !corrupt.is_empty() {
+			let pending_cf = self.cf_handle(cf::PENDING_BATCHES)?;
+			let corrupt_cf = self.cf_handle(cf::CORRUPT_BATCHES)?;
+			let mut write_batch = WriteBatch::default();
+			for (key, value) in corrupt {
+				write_batch.put_cf(corrupt_cf, &key, value);
+				write_batch.delete_cf(pending_cf, key);
+			}
+			self.db.write(write_batch).map_err(|e| Error::Outbox(e.to_string()))?;
+		}
+		Ok(batches)
+	}
+}
+
+fn decode_batch(key: &[u8], value: &[u8]) -> Result<OutboxBatch, Error> {
+	Ok(OutboxBatch { id: decode_id(key)?, transactions: bcs::from_bytes(value)? })
+}
+
+fn decode_id(key: &[u8]) -> Result<u64, Error> {
+	let bytes: [u8; 8] = key
+		.try_into()
+		.map_err(|_| Error::Outbox("Invalid pending batch key".to_string()))?;
+	Ok(u64::from_be_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_outbox_keeps_pending_batches_across_restart() -> Result<(), anyhow::Error> {
+		let tempdir = tempfile::tempdir()?;
+		let first = Transaction::new(vec![1], 0, 1);
+		let second = Transaction::new(vec![2], 0, 2);
+		{
+			let outbox = BatchOutbox::try_new(tempdir.path())?;
+			let acknowledged = outbox.push(vec![Transaction::test()])?;
+			let mut pending = outbox.push(vec![first.clone(), second.clone()])?;
+			outbox.remove(acknowledged.id)?;
+			// Drop a rejected transaction from the pending batch.
+			pending.transactions.remove(0);
+			outbox.put(&pending)?;
+		}
+
+		let outbox = BatchOutbox::try_new(tempdir.path())?;
+		assert_eq!(
+			outbox.pending_batches()?,
+			vec![OutboxBatch { id: 1, transactions: vec![second] }]
+		);
+		// New batches are sent after the pending ones.
+		assert_eq!(outbox.push(vec![first])?.id, 2);
+		Ok(())
+	}
+
+	#[test]
+	fn test_outbox_moves_aside_corrupt_batches() -> Result<(), anyhow::Error> {
+		let tempdir = tempfile::tempdir()?;
+		let transaction = Transaction::new(vec![1], 0, 1);
+		{
+			let outbox = BatchOutbox::try_new(tempdir.path())?;
+			outbox.push(vec![Transaction::test()])?;
+			outbox.push(vec![transaction.clone()])?;
+			let pending_cf = outbox.cf_handle(cf::PENDING_BATCHES)?;
+			outbox.db.put_cf(pending_cf, 0u64.to_be_bytes(), [0xff; 3])?;
+			outbox.db.put_cf(pending_cf, [7], bcs::to_bytes(&vec![Transaction::test()])?)?;
+		}
+
+		let outbox = BatchOutbox::try_new(tempdir.path())?;
+		assert_eq!(outbox.next_id(), 2);
+		assert_eq!(
+			outbox.pending_batches()?,
+			vec![OutboxBatch { id: 1, transactions: vec![transaction] }]
+		);
+		assert_eq!(outbox.oldest_pending_id()?, Some(1));
+		let corrupt_keys = outbox
+			.db
+			.iterator_cf(outbox.cf_handle(cf::CORRUPT_BATCHES)?, IteratorMode::Start)
+			.map(|entry| entry.map(|(key, _)| key.to_vec()))
+			.collect::<Result<Vec<_>, _>>()?;
+		assert_eq!(corrupt_keys, vec![0u64.to_be_bytes().to_vec(), vec![7]]);
+		Ok(())
+	}
+}
@@ -1,10 +1,12 @@
-use super::{Error, NullMempool, TransactionPipe};
+use super::{BatchOutbox, Error, NullMempool, SenderInFlight, TransactionPipe};
 use crate::executor::TxExecutionResult;
+use crate::mempool_admin::MempoolAdmin;
 use aptos_account_whitelist::config::Config as WhitelistConfig;
 use aptos_config::config::NodeConfig;
 use aptos_mempool::MempoolClientRequest;
 use aptos_storage_interface::DbReader;
 use futures::channel::mpsc as futures_mpsc;
+use maptos_execution_util::config::load_shedding::Config as LoadSheddingConfig;
 use maptos_execution_util::config::mempool::Config as MempoolConfig;
 use movement_collections::garbage::counted::GcCounter;
 use movement_da_sequencer_client::GrpcDaSequencerClient;
@@ -33,9 +35,11 @@ impl BackgroundTask {
 		node_config: &NodeConfig,
 		mempool_config: &MempoolConfig,
 		whitelist_config: &WhitelistConfig,
+		load_shedding_config: &LoadSheddingConfig,
 		transactions_in_flight: Arc<RwLock<GcCounter>>,
-		transactions_in_flight_limit: Option<u64>,
 		da_batch_signer: SignerIdentifier,
+		outbox: BatchOutbox,
+		sender_in_flight: Arc<RwLock<SenderInFlight>>,
 	) -> Result<Self, anyhow::Error> {
 		Ok(Self {
 			inner: BackgroundInner::Full(TransactionPipe::new(
@@ -44,9 +48,11 @@ impl BackgroundTask {
 				node_config,
 				mempool_config,
 				whitelist_config,
+				load_shedding_config,
 				transactions_in_flight,
-				transactions_in_flight_limit,
 				da_batch_signer,
+				outbox,
+				sender_in_flight,
 			)?),
 		})
 	}
@@ -79,6 +85,14 @@ impl BackgroundTask {
 		}
 	}
 
+	/// The admin access to the mempool, `None` for a read-only node.
+	pub fn mempool_admin(&self) -> Option<MempoolAdmin> {
+		match &self.inner {
+			BackgroundInner::Full(transaction_pipe) => Some(transaction_pipe.mempool_admin()),
+			BackgroundInner::ReadOnly(_) => None,
+		}
+	}
+
 	/// A test helper to extract the transaction pipe task.
 	///
 	/// # Panics
@@ -1,7 +1,12 @@
 //! Task processing incoming transactions for the opt API.
-use super::Error;
+use super::{
+	Admission, AdmissionPolicy, BatchOutbox, Error, IngressPolicy, LoadShedding, OutboxBatch,
+	SenderInFlight, ShedReason,
+};
 use crate::executor::TxExecutionResult;
 use crate::gc_account_sequence_number::UsedSequenceNumberPool;
+use crate::mempool_admin::{MempoolAdmin, Rejection, RejectionLog, RejectionStage};
+use crate::metrics;
 use aptos_account_whitelist::config::Config as WhitelistConfig;
 use aptos_config::config::NodeConfig;
 use aptos_mempool::{
@@ -10,7 +15,6 @@ use aptos_mempool::{
 };
 use aptos_storage_interface::{state_view::LatestDbStateCheckpointView, DbReader};
 use aptos_types::{
-	account_address::AccountAddress,
 	mempool_status::{MempoolStatus, MempoolStatusCode},
 	transaction::{SignedTransaction, TransactionStatus},
 };
@@ -21,10 +25,11 @@ use bcs;
 use futures::channel::mpsc as futures_mpsc;
 use futures::stream::FuturesUnordered;
 use futures::StreamExt;
+use maptos_execution_util::config::load_shedding::Config as LoadSheddingConfig;
 use maptos_execution_util::config::mempool::Config as MempoolConfig;
 use movement_collections::garbage::counted::GcCounter;
-use movement_da_sequencer_client::DaSequencerClient;
-use movement_da_sequencer_proto::BatchWriteRequest;
+use movement_da_sequencer_client::{BatchWriteError, DaSequencerClient};
+use movement_da_sequencer_proto::{BatchWriteRequest, BatchWriteResponse};
 use movement_signer::cryptography::ed25519::Ed25519;
 use movement_signer_loader::identifiers::SignerIdentifier;
 use movement_signer_loader::{Load, LoadedSigner};
@@ -37,6 +42,8 @@ use tokio::task::JoinHandle;
 use tracing::{debug, info, info_span, warn, Instrument};
 
 const GC_INTERVAL: Duration = Duration::from_secs(30);
+// The delay doubles at each failure of a batch, up to the configured maximum delay.
+const MAX_RETRY_DELAY_DOUBLING: u32 = 16;
 const MEMPOOL_INTERVAL: Duration = Duration::from_millis(240); // this is based on slot times and global TCP RTT, essentially we expect to collect all transactions sent in the same slot in around 240ms
 
 pub struct TransactionPipe {
@@ -54,13 +61,23 @@ pub struct TransactionPipe {
 	// Timestamp of the last garbage collection
 	last_gc: Instant,
 	// The pool of used sequence numbers
-	used_sequence_number_pool: UsedSequenceNumberPool,
-	/// The accounts whitelisted for ingress
-	whitelisted_accounts: Option<HashSet<AccountAddress>>,
+	used_sequence_number_pool: Arc<RwLock<UsedSequenceNumberPool>>,
+	/// Ingress admission of the submitted transactions.
+	admission: Admission,
 	/// Batch signer
 	da_batch_signer: SignerIdentifier,
 	/// Mempool configuration from maptos_execution.
 	mempool_config: MempoolConfig,
+	/// Batches sent to the DA and not acknowledged yet.
+	outbox: BatchOutbox,
+}
+
+/// The outcome of sending a batch to the DA Sequencer.
+pub(crate) struct SentBatch {
+	batch: OutboxBatch,
+	// Number of failed sendings of the batch.
+	failures: u32,
+	result: Result<BatchWriteResponse, BatchWriteError>,
 }
 
 impl TransactionPipe {
@@ -75,33 +92,64 @@ impl TransactionPipe {
 		(self.transactions_in_flight.clone(), self.in_flight_limit.clone())
 	}
 
+	pub fn outbox(&self) -> BatchOutbox {
+		self.outbox.clone()
+	}
+
+	pub fn admission(&self) -> Admission {
+		self.admission.clone()
+	}
+
+	/// Returns the admin access to the state of the pipe.
+	pub fn mempool_admin(&self) -> MempoolAdmin {
+		MempoolAdmin::new(
+			self.db_reader(),
+			self.core_mempool(),
+			self.used_sequence_number_pool.clone(),
+			self.admission(),
+		)
+	}
+
+	/// Replace the ingress policy built from the whitelist configuration.
+	pub fn with_admission_policy(mut self, policy: Arc<dyn AdmissionPolicy>) -> Self {
+		self.admission = self.admission.with_policy(policy);
+		self
+	}
+
 	pub(crate) fn new(
 		mempool_commit_tx_receiver: UnboundedReceiver<Vec<TxExecutionResult>>, // Sender, seq number)
 		db_reader: Arc<dyn DbReader>,
 		node_config: &NodeConfig,
 		mempool_config: &MempoolConfig,
 		whitelist_config: &WhitelistConfig,
+		load_shedding_config: &LoadSheddingConfig,
 		transactions_in_flight: Arc<RwLock<GcCounter>>,
-		transactions_in_flight_limit: Option<u64>,
 		da_batch_signer: SignerIdentifier,
+		outbox: BatchOutbox,
+		sender_in_flight: Arc<RwLock<SenderInFlight>>,
 	) -> Result<Self, anyhow::Error> {
-		let whitelisted_accounts = whitelist_config.whitelisted_accounts()?;
-		info!("Whitelisted accounts: {:?}", whitelisted_accounts);
+		let ingress_policy = IngressPolicy::from_config_and_spawn_reload_thread(whitelist_config)?;
 
 		Ok(TransactionPipe {
 			mempool_commit_tx_receiver,
 			db_reader,
 			core_mempool: Arc::new(RwLock::new(CoreMempool::new(node_config))),
 			transactions_in_flight,
-			in_flight_limit: transactions_in_flight_limit,
+			in_flight_limit: load_shedding_config.max_transactions_in_flight,
 			last_gc: Instant::now(),
-			used_sequence_number_pool: UsedSequenceNumberPool::new(
+			used_sequence_number_pool: Arc::new(RwLock::new(UsedSequenceNumberPool::new(
 				mempool_config.sequence_number_ttl_ms,
 				mempool_config.gc_slot_duration_ms,
+			))),
+			admission: Admission::new(
+				Arc::new(ingress_policy),
+				LoadShedding::from_config(load_shedding_config),
+				sender_in_flight,
+				RejectionLog::new(mempool_config.rejection_log_size),
 			),
-			whitelisted_accounts,
 			da_batch_signer,
 			mempool_config: mempool_config.clone(),
+			outbox,
 		})
 	}
 
@@ -112,7 +160,11 @@ impl TransactionPipe {
 	) -> Result<(), Error> {
 		let mut build_batch_deadline = tokio::time::Instant::now() + MEMPOOL_INTERVAL;
 		let mut mempool_gc_interval = tokio::time::interval(GC_INTERVAL);
-		let mut sent_batch_futures = FuturesUnordered::new();
+		// The batches sent and not acknowledged yet, each one retried on its own.
+		let mut sent_batches: FuturesUnordered<JoinHandle<SentBatch>> = FuturesUnordered::new();
+
+		// Batches not acknowledged before the last stop go back to the mempool.
+		self.readmit_outbox_batches().await?;
 
 		// Start 2 loops because we manage 2 differentes process.
 		// The Tx request stream that can be very fast with a lot of request.
@@ -123,6 +175,7 @@ impl TransactionPipe {
 			let core_mempool = self.core_mempool();
 			let db_reader = self.db_reader();
 			let (transactions_in_flight, in_flight_limit) = self.transactions_in_flight();
+			let admission = self.admission();
 			let mut counter = 0;
 			async move {
 				// Process messages received on the channel.
@@ -135,6 +188,7 @@ impl TransactionPipe {
 								&db_reader,
 								&transactions_in_flight,
 								in_flight_limit,
+								&admission,
 								&mut counter,
 							)
 							.await?;
@@ -156,37 +210,53 @@ impl TransactionPipe {
 			let core_mempool = self.core_mempool.clone();
 			let da_batch_signer = self.da_batch_signer.clone();
 			let mempool_config = self.mempool_config.clone();
+			let outbox = self.outbox.clone();
+			let admission = self.admission();
 			async move {
 				loop {
+					let batch_window_open =
+						TransactionPipe::batch_window_open(&mempool_config, &outbox)?;
 					tokio::select! {
 						Some(batches) = self.mempool_commit_tx_receiver.recv() => {
-							TransactionPipe::tick_commit_tx(&core_mempool, batches).await?;
+							TransactionPipe::tick_commit_tx(&core_mempool, &admission, batches).await?;
 						}
-						_ = tokio::time::sleep_until(build_batch_deadline) => {
+						// The next batch is built once it fits in the window of batches in flight.
+						_ = tokio::time::sleep_until(build_batch_deadline), if batch_window_open => {
 							build_batch_deadline = tokio::time::Instant::now() + MEMPOOL_INTERVAL;
-							if let Some(jh) = TransactionPipe::tick_mempool_sender(&core_mempool, &da_client, &da_batch_signer, &mempool_config).await? {
-								sent_batch_futures.push(jh);
+							if let Some(sent_batch) = TransactionPipe::tick_mempool_sender(
+								&core_mempool,
+								&da_client,
+								&da_batch_signer,
+								&mempool_config,
+								&outbox,
+							)
+							.await?
+							{
+								sent_batches.push(sent_batch);
 							}
 						}
 						_ = mempool_gc_interval.tick() => {
 							self.tick_gc();
 						}
 
-						Some(result) = sent_batch_futures.next() => {
+						Some(result) = sent_batches.next(), if !sent_batches.is_empty() => {
 							match result {
-								Ok(Ok(response)) => {
-									debug!("After sent batch.");
-									if !response.answer {
-										tracing::warn!("DA Sequencer reject batch, can't send the batch.");
+								Ok(result) => {
+									if let Some(sent_batch) = TransactionPipe::tick_sent_batch(
+										result,
+										&da_client,
+										&da_batch_signer,
+										&mempool_config,
+										&outbox,
+									)
+									.await?
+									{
+										sent_batches.push(sent_batch);
 									}
 								}
-								Ok(Err(err)) => {
-									tracing::error!("Send batch to Da failed because of a connection issue: {err}, can't send batch, exit process");
-										return Err(Error::InternalError(format!("Send batch to Da failed because of a connection issue: {err}, can't send batch, exit process")));
-								}
 								Err(err) => {
 									tracing::error!("Tokio send batch task execution failed: {err}, can't send batch, exit process");
-										return Err(Error::InternalError(format!("Tokio send batch task execution failed: {err}, can't send batch, exit process")));
+									return Err(Error::InternalError(format!("Tokio send batch task execution failed: {err}, can't send batch, exit process")));
 								}
 							}
 						}
@@ -214,6 +284,7 @@ impl TransactionPipe {
 		db_reader: &Arc<dyn DbReader>,
 		transactions_in_flight: &Arc<RwLock<GcCounter>>,
 		in_flight_limit: Option<u64>,
+		admission: &Admission,
 		counter: &mut u64,
 	) -> Result<(), Error> {
 		match request {
@@ -232,6 +303,7 @@ impl TransactionPipe {
 					db_reader,
 					transactions_in_flight,
 					in_flight_limit,
+					admission,
 				)
 				.instrument(span)
 				.await?;
@@ -258,21 +330,46 @@ impl TransactionPipe {
 	/// Pipes a batch of transactions from the executor to commit then in the Aptos mempool.
 	pub(crate) async fn tick_commit_tx(
 		core_mempool: &RwLock<CoreMempool>,
+		admission: &Admission,
 		batches: Vec<TxExecutionResult>,
 	) -> Result<(), Error> {
 		for tx_result in batches {
+			admission.executed(&tx_result.sender, tx_result.seq_number);
 			if let TransactionStatus::Discard(discard_status) = tx_result.status {
 				tracing::info!(
 					"Transaction pipe, mempool rejecting Tx:{} with status:{:?}",
 					tx_result.hash,
 					discard_status
 				);
+				admission.rejected(Rejection::new(
+					tx_result.hash,
+					tx_result.sender,
+					tx_result.seq_number,
+					RejectionStage::Execution,
+					format!("{discard_status:?}"),
+				));
 				core_mempool.write().unwrap().reject_transaction(
 					&tx_result.sender,
 					tx_result.seq_number,
 					&tx_result.hash,
 					&discard_status,
 				)
+			} else if let TransactionStatus::Retry = tx_result.status {
+				// Not executed because the block reached the gas limit of the on-chain
+				// execution config. The transaction already left the mempool.
+				tracing::info!(
+					tx_hash = %tx_result.hash,
+					sender = %tx_result.sender,
+					sequence_number = %tx_result.seq_number,
+					"TX not executed, the block reached its gas limit.",
+				);
+				admission.rejected(Rejection::new(
+					tx_result.hash,
+					tx_result.sender,
+					tx_result.seq_number,
+					RejectionStage::Execution,
+					"Retry: block gas limit reached".to_string(),
+				));
 			} else {
 				tracing::info!(
 					tx_hash = %tx_result.hash,
@@ -291,10 +388,8 @@ impl TransactionPipe {
 		da_client: &(impl DaSequencerClient + 'static),
 		da_batch_signer: &SignerIdentifier,
 		mempool_config: &MempoolConfig,
-	) -> Result<
-		Option<JoinHandle<Result<movement_da_sequencer_proto::BatchWriteResponse, tonic::Status>>>,
-		Error,
-	> {
+		outbox: &BatchOutbox,
+	) -> Result<Option<JoinHandle<SentBatch>>, Error> {
 		let batch: Vec<Transaction> = {
 			let mut core_mempool = core_mempool.write().unwrap();
 			let transactions = core_mempool.get_batch_with_ranking_score(
@@ -312,13 +407,7 @@ impl TransactionPipe {
 					let seq = transaction.sequence_number();
 					// `commit_transaction` indicate that the Tx has been stored
 					// so it doesn't return in each `get_batch_with_ranking_score` call.
-					// The Tx storage is the DA.
-					// We commit here (before the DA call) because
-					// if the DA call fails, the node is stopped so
-					// in both case the Tx will be lost if the Da is not available.
-					// To be able to manage this case, we need first to be able to recover
-					// from a DA connection break.
-					// When done the `commit_transaction` can be called after the DA call.
+					// From here the Tx is kept in the outbox until the DA acknowledges it.
 					core_mempool.commit_transaction(&sender, seq);
 					debug!(
 						target: "movement_timing",
@@ -339,26 +428,175 @@ impl TransactionPipe {
 		if !batch.is_empty() {
 			// Build batch and submit request.
 			tracing::info!("Build new batch with {} tx.", batch.len());
-			let loader: LoadedSigner<Ed25519> = da_batch_signer.load().await?;
-
-			//send the batch in a separate task to avoid to slow the loop.
-			let handle = tokio::spawn({
-				let mut client = da_client.clone();
-				async move {
-					let batch_bytes = bcs::to_bytes(&batch).expect("Serialization failed");
-					let encoded =
-						movement_da_sequencer_client::sign_and_encode_batch(batch_bytes, &loader)
-							.await
-							.unwrap();
-					client.batch_write(BatchWriteRequest { data: encoded }).await
-				}
-			});
-			Ok(Some(handle))
+			// The batch is persisted before the DA call, so it's not lost if the call fails.
+			let batch = outbox.push(batch)?;
+			Ok(Some(Self::send_batch(da_client, da_batch_signer, batch, 0, Duration::ZERO).await?))
 		} else {
 			Ok(None)
 		}
 	}
 
+	/// Whether a new batch can be sent. The batches in flight are at most
+	/// `outbox_max_batches_in_flight` consecutive outbox ids from the oldest unacknowledged one,
+	/// so a batch being retried is overtaken by a bounded number of batches.
+	fn batch_window_open(
+		mempool_config: &MempoolConfig,
+		outbox: &BatchOutbox,
+	) -> Result<bool, Error> {
+		let window = mempool_config.outbox_max_batches_in_flight.max(1);
+		Ok(match outbox.oldest_pending_id()? {
+			Some(oldest_id) => outbox.next_id() < oldest_id.saturating_add(window),
+			None => true,
+		})
+	}
+
+	/// Processes the DA answer to a sent batch.
+	/// Returns the handle of the new sending if the batch has to be sent again.
+	pub(crate) async fn tick_sent_batch(
+		sent_batch: SentBatch,
+		da_client: &(impl DaSequencerClient + 'static),
+		da_batch_signer: &SignerIdentifier,
+		mempool_config: &MempoolConfig,
+		outbox: &BatchOutbox,
+	) -> Result<Option<JoinHandle<SentBatch>>, Error> {
+		let SentBatch { mut batch, failures, result } = sent_batch;
+		let delay = match result {
+			Ok(_) => {
+				debug!("After sent batch.");
+				outbox.remove(batch.id)?;
+				return Ok(None);
+			}
+			Err(BatchWriteError::InvalidTransactions { indices, reason }) => {
+				// The whole batch is rejected, send it again without the invalid transactions.
+				let invalid: HashSet<usize> = indices.iter().map(|index| *index as usize).collect();
+				let mut index = 0;
+				batch.transactions.retain(|tx| {
+					let is_valid = !invalid.contains(&index);
+					if !is_valid {
+						warn!(
+							tx_id = %tx.id(),
+							sequence_number = tx.sequence_number(),
+							"DA Sequencer rejected an invalid transaction: {reason}, dropping it."
+						);
+					}
+					index += 1;
+					is_valid
+				});
+				if batch.transactions.is_empty() {
+					outbox.remove(batch.id)?;
+					return Ok(None);
+				}
+				outbox.put(&batch)?;
+				Duration::ZERO
+			}
+			Err(err) if err.is_retryable() => {
+				if failures + 1 >= mempool_config.outbox_max_send_attempts {
+					tracing::error!(
+						batch_id = batch.id,
+						failures = failures + 1,
+						"Send batch to DA Sequencer failed: {err}, no more retry, dropping its {} transactions",
+						batch.transactions.len()
+					);
+					metrics::drop_batch("send_attempts_exhausted");
+					outbox.remove(batch.id)?;
+					return Ok(None);
+				}
+				let delay = Self::retry_delay(mempool_config, failures);
+				warn!(
+					batch_id = batch.id,
+					failures = failures + 1,
+					"Send batch to DA Sequencer failed: {err}, retry in {delay:?}"
+				);
+				delay
+			}
+			Err(err) => {
+				tracing::error!(
+					batch_id = batch.id,
+					"DA Sequencer reject batch, dropping its {} transactions: {err}",
+					batch.transactions.len()
+				);
+				metrics::drop_batch("rejected");
+				outbox.remove(batch.id)?;
+				return Ok(None);
+			}
+		};
+		Ok(Some(Self::send_batch(da_client, da_batch_signer, batch, failures + 1, delay).await?))
+	}
+
+	/// Delay before sending again a batch that failed `failures` times.
+	fn retry_delay(mempool_config: &MempoolConfig, failures: u32) -> Duration {
+		let initial_delay = Duration::from_millis(mempool_config.outbox_retry_initial_delay_ms);
+		let max_delay = Duration::from_millis(mempool_config.outbox_retry_max_delay_ms);
+		initial_delay
+			.saturating_mul(1 << failures.min(MAX_RETRY_DELAY_DOUBLING))
+			.min(max_delay)
+	}
+
+	/// Signs the batch and sends it after the delay, in a separate task to avoid to slow the loop.
+	async fn send_batch(
+		da_client: &(impl DaSequencerClient + 'static),
+		da_batch_signer: &SignerIdentifier,
+		batch: OutboxBatch,
+		failures: u32,
+		delay: Duration,
+	) -> Result<JoinHandle<SentBatch>, Error> {
+		let loader: LoadedSigner<Ed25519> = da_batch_signer.load().await?;
+		let batch_bytes = bcs::to_bytes(&batch.transactions)?;
+		let encoded =
+			movement_da_sequencer_client::sign_and_encode_batch(batch_bytes, &loader).await?;
+		Ok(tokio::spawn({
+			let mut client = da_client.clone();
+			async move {
+				if !delay.is_zero() {
+					tokio::time::sleep(delay).await;
+				}
+				let result = client.batch_write(BatchWriteRequest { data: encoded }).await;
+				SentBatch { batch, failures, result }
+			}
+		}))
+	}
+
+	/// Adds the transactions of the batches left in the outbox back to the mempool.
+	/// They are validated again, so the transactions executed in the meantime are dropped.
+	/// The transactions that can't be decoded are logged and skipped.
+	async fn readmit_outbox_batches(&self) -> Result<(), Error> {
+		for batch in self.outbox.pending_batches()? {
+			info!(
+				batch_id = batch.id,
+				"Re-admit {} transactions of an unacknowledged batch to the mempool.",
+				batch.transactions.len()
+			);
+			for tx in &batch.transactions {
+				let transaction: SignedTransaction = match bcs::from_bytes(tx.data()) {
+					Ok(transaction) => transaction,
+					Err(err) => {
+						warn!(
+							batch_id = batch.id,
+							tx_id = %tx.id(),
+							"Unacknowledged transaction can't be decoded, not re-admitted: {err}"
+						);
+						continue;
+					}
+				};
+				let tx_hash = transaction.committed_hash();
+				let (status, _) = TransactionPipe::add_transaction_to_aptos_mempool(
+					transaction,
+					&self.core_mempool,
+					&self.db_reader,
+					&self.transactions_in_flight,
+					self.in_flight_limit,
+					&self.admission,
+				)
+				.await?;
+				if status.code != MempoolStatusCode::Accepted {
+					warn!(%tx_hash, ?status, "Unacknowledged transaction not re-admitted.");
+				}
+			}
+			self.outbox.remove(batch.id)?;
+		}
+		Ok(())
+	}
+
 	pub(crate) fn tick_gc(&mut self) {
 		if self.last_gc.elapsed() >= GC_INTERVAL {
 			debug!("Start execute mempool gc");
@@ -367,7 +605,7 @@ impl TransactionPipe {
 			let epoch_ms_now = chrono::Utc::now().timestamp_millis() as u64;
 
 			// garbage collect the used sequence number pool
-			self.used_sequence_number_pool.gc(epoch_ms_now);
+			self.used_sequence_number_pool.write().unwrap().gc(epoch_ms_now);
 
 			// garbage collect the transactions in flight
 			{
@@ -376,6 +614,9 @@ impl TransactionPipe {
 				transactions_in_flight.gc(epoch_ms_now);
 			}
 
+			// garbage collect the transactions in flight of each sender
+			self.admission.gc(epoch_ms_now, self.mempool_config.sequence_number_ttl_ms);
+
 			// garbage collect the core mempool
 			{
 				self.core_mempool.write().unwrap().gc();
@@ -393,11 +634,25 @@ impl TransactionPipe {
 		db_reader: &Arc<dyn DbReader>,
 		transactions_in_flight: &Arc<RwLock<GcCounter>>,
 		in_flight_limit: Option<u64>,
+		admission: &Admission,
 	) -> Result<SubmissionStatus, Error> {
-		// Check whether the account is whitelisted
-		// if !self.is_whitelisted(&transaction.sender())? {
-		// 	return Ok((MempoolStatus::new(MempoolStatusCode::TooManyTransactions), None));
-		// }
+		// Check the transaction against the ingress policy.
+		if let Err(rejection) = admission.check(&transaction) {
+			warn!(
+				tx_hash = %transaction.committed_hash(),
+				sender = %transaction.sender(),
+				"Transaction rejected by ingress policy: {rejection}"
+			);
+			metrics::reject_submission(rejection.label());
+			admission.rejected(Rejection::of_transaction(
+				&transaction,
+				RejectionStage::Admission,
+				&rejection,
+			));
+			let status = MempoolStatus::new(MempoolStatusCode::RejectedByFilter)
+				.with_message(rejection.to_string());
+			return Ok((status, None));
+		}
 
 		// For now, we are going to consider a transaction in flight until it exits the mempool and is sent to the DA as is indicated by WriteBatch.
 		let in_flight = {
@@ -409,15 +664,23 @@ impl TransactionPipe {
 			in_flight = %in_flight,
 			"transactions_in_flight"
 		);
-		if let Some(inflight_limit) = in_flight_limit {
-			if in_flight >= inflight_limit {
-				warn!(
-					target: "movement_timing",
-					"mempool full, shedding_load"
-				);
-				let status = MempoolStatus::new(MempoolStatusCode::MempoolIsFull);
-				return Ok((status, None));
-			}
+		if let Err(reason) = admission.shed(&transaction.sender(), in_flight, in_flight_limit) {
+			warn!(
+				target: "movement_timing",
+				"shedding_load: {reason}"
+			);
+			metrics::reject_submission(reason.label());
+			admission.rejected(Rejection::of_transaction(
+				&transaction,
+				RejectionStage::LoadShedding,
+				&reason,
+			));
+			let code = match reason {
+				ShedReason::MempoolFull { .. } => MempoolStatusCode::MempoolIsFull,
+				ShedReason::FairShare { .. } => MempoolStatusCode::TooManyTransactions,
+			};
+			let status = MempoolStatus::new(code).with_message(reason.to_string());
+			return Ok((status, None));
 		}
 
 		// Pre-execute Tx to validate its content.
@@ -427,9 +690,14 @@ impl TransactionPipe {
 		// invert the application priority with the u64 max minus the score from aptos (which is high to low)
 		let ranking_score = tx_result.score();
 		match tx_result.status() {
-			Some(_) => {
+			Some(vm_status) => {
 				let ms = MempoolStatus::new(MempoolStatusCode::VmError);
 				warn!(status = ?tx_result.status(), "Transaction not accepted by VM");
+				admission.rejected(Rejection::of_transaction(
+					&transaction,
+					RejectionStage::Validation,
+					format!("{vm_status:?}"),
+				));
 				return Ok((ms, tx_result.status()));
 			}
 			None => {
@@ -448,6 +716,7 @@ impl TransactionPipe {
 			tx_seq_num = %transaction.sequence_number(),
 		);
 		let tx_hash = transaction.committed_hash();
+		let admitted_transaction = transaction.clone();
 		let status = {
 			core_mempool.write().unwrap().add_txn(
 				transaction,
@@ -467,9 +736,15 @@ impl TransactionPipe {
 					let mut transactions_in_flight = transactions_in_flight.write().unwrap();
 					transactions_in_flight.increment(now, 1);
 				}
+				admission.admitted(&admitted_transaction, now);
 			}
 			_ => {
 				warn!(status = ?tx_result.status(), "Transaction not accepted by mempool");
+				admission.rejected(Rejection::of_transaction(
+					&admitted_transaction,
+					RejectionStage::Mempool,
+					format!("{:?}: {}", status.code, status.message),
+				));
 			}
 		}
 
@@ -481,6 +756,8 @@ impl TransactionPipe {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::background::AdmissionRejection;
+	use crate::mempool_admin::{Eviction, PendingLocation, SequenceNumberGap};
 	use crate::{Context, Executor, Service};
 	use aptos_api::{accept_type::AcceptType, transactions::SubmitTransactionPost};
 	use aptos_crypto::HashValue;
@@ -502,12 +779,15 @@ mod tests {
 	use futures::stream;
 	use futures::SinkExt;
 	use maptos_execution_util::config::chain::Config;
-	use movement_da_sequencer_client::{ClientDaSequencerError, StreamReadBlockFromHeight};
+	use movement_da_sequencer_client::{
+		ClientDaSequencerError, StreamReadBlockFromHeight, StreamReadBlockRange,
+	};
 	use movement_da_sequencer_proto::{
 		BatchWriteResponse, BlockResponse, BlockV1, ReadAtHeightResponse,
 	};
 	use movement_types::transaction::Transaction as MvTransaction;
 	use std::collections::BTreeSet;
+	use std::sync::atomic::{AtomicU32, Ordering};
 	use std::sync::Mutex;
 	use tempfile::TempDir;
 	use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
@@ -531,11 +811,16 @@ mod tests {
 	#[derive(Clone)]
 	pub struct TxPipeTestDaSequencerClient {
 		pub received_tx: Arc<Mutex<Vec<MvTransaction>>>,
+		// Number of next batch writes that fail as if the DA was not reachable.
+		pub unavailable_writes: Arc<AtomicU32>,
 	}
 
 	impl TxPipeTestDaSequencerClient {
 		fn new() -> Self {
-			TxPipeTestDaSequencerClient { received_tx: Arc::new(Mutex::new(vec![])) }
+			TxPipeTestDaSequencerClient {
+				received_tx: Arc::new(Mutex::new(vec![])),
+				unavailable_writes: Arc::new(AtomicU32::new(0)),
+			}
 		}
 	}
 
@@ -550,12 +835,28 @@ mod tests {
 			Ok((Box::pin(never_ending_stream), alert_rx))
 		}
 
+		async fn stream_read_range(
+			&mut self,
+			_request: movement_da_sequencer_proto::StreamReadRangeRequest,
+		) -> Result<StreamReadBlockRange, ClientDaSequencerError> {
+			Ok(Box::pin(stream::empty()))
+		}
+
 		/// Writes a batch of transactions to the Da Sequencer node
 		async fn batch_write(
 			&mut self,
 			request: movement_da_sequencer_proto::BatchWriteRequest,
-		) -> Result<movement_da_sequencer_proto::BatchWriteResponse, tonic::Status> {
+		) -> Result<movement_da_sequencer_proto::BatchWriteResponse, BatchWriteError> {
 			tracing::info!("TxPipeTestDaSequencerClient receive a batch");
+			if self
+				.unavailable_writes
+				.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| count.checked_sub(1))
+				.is_ok()
+			{
+				return Err(BatchWriteError::Transport(tonic::Status::unavailable(
+					"DA unavailable",
+				)));
+			}
 			let batch_data = request.data;
 			let batch: Vec<MvTransaction> =
 				match movement_da_sequencer_client::deserialize_full_node_batch(batch_data)
@@ -567,19 +868,19 @@ mod tests {
 					Ok(batch) => batch,
 					Err(err) => {
 						tracing::warn!(error = %err, "Invalid batch send, verification / validation failed.");
-						return Ok(BatchWriteResponse { answer: false });
+						return Err(BatchWriteError::MalformedBatch(err));
 					}
 				};
 			tracing::info!("TxPipeTestDaSequencerClient that contains {} Tx", batch.len());
 			batch.into_iter().for_each(|tx| self.received_tx.lock().unwrap().push(tx));
-			Ok(BatchWriteResponse { answer: true })
+			Ok(BatchWriteResponse::accepted())
 		}
 		async fn send_state(
 			&mut self,
 			_signer: &LoadedSigner<Ed25519>,
 			_state: movement_da_sequencer_proto::MainNodeState,
 		) -> Result<movement_da_sequencer_proto::BatchWriteResponse, tonic::Status> {
-			Ok(BatchWriteResponse { answer: true })
+			Ok(BatchWriteResponse::accepted())
 		}
 		async fn read_at_height(
 			&mut self,
@@ -636,6 +937,189 @@ mod tests {
 		Ok(())
 	}
 
+	#[tokio::test]
+	async fn test_pipe_mempool_retries_unacknowledged_batch() -> Result<(), anyhow::Error> {
+		// set up
+		let maptos_config = Config::default();
+		let (context, transaction_pipe, _tempdir, tx_receiver) = setup().await;
+		let outbox = transaction_pipe.outbox();
+
+		// The 2 first batch writes fail.
+		let da_client = TxPipeTestDaSequencerClient::new();
+		da_client.unavailable_writes.store(2, Ordering::Relaxed);
+		let mempool_handle = tokio::spawn(transaction_pipe.run(da_client.clone(), tx_receiver));
+
+		let user_transaction = create_signed_transaction(0, &maptos_config);
+		let (req_sender, callback) = oneshot::channel();
+		context
+			.mempool_client_sender()
+			.send(MempoolClientRequest::SubmitTransaction(user_transaction.clone(), req_sender))
+			.await?;
+		let (status, _vm_status_code) = callback.await??;
+		assert_eq!(status.code, MempoolStatusCode::Accepted);
+
+		// The batch stays in the outbox until the DA accepts it.
+		let _ = tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+		assert!(da_client.received_tx.lock().unwrap().is_empty());
+		assert_eq!(outbox.pending_batches()?.len(), 1);
+
+		// Retried after 500ms then 1s.
+		let _ = tokio::time::sleep(tokio::time::Duration::from_millis(2000)).await;
+		{
+			let txs = da_client.received_tx.lock().unwrap();
+			assert_eq!(txs.len(), 1);
+			assert_eq!(txs.get(0).unwrap().sequence_number(), user_transaction.sequence_number());
+		}
+		assert!(outbox.pending_batches()?.is_empty());
+
+		mempool_handle.abort();
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_pipe_mempool_retried_batch_is_not_overtaken() -> Result<(), anyhow::Error> {
+		// set up
+		let maptos_config = Config::default();
+		let (context, mut transaction_pipe, _tempdir, tx_receiver) = setup().await;
+		// One batch in flight at a time.
+		transaction_pipe.mempool_config.outbox_max_batches_in_flight = 1;
+
+		// The first batch write fails.
+		let da_client = TxPipeTestDaSequencerClient::new();
+		da_client.unavailable_writes.store(1, Ordering::Relaxed);
+		let mempool_handle = tokio::spawn(transaction_pipe.run(da_client.clone(), tx_receiver));
+
+		for sequence_number in 0..2 {
+			let user_transaction = create_signed_transaction(sequence_number, &maptos_config);
+			let (req_sender, callback) = oneshot::channel();
+			context
+				.mempool_client_sender()
+				.send(MempoolClientRequest::SubmitTransaction(user_transaction, req_sender))
+				.await?;
+			let (status, _vm_status_code) = callback.await??;
+			assert_eq!(status.code, MempoolStatusCode::Accepted);
+			// Let the first batch fail before the second transaction is submitted.
+			let _ = tokio::time::sleep(tokio::time::Duration::from_millis(400)).await;
+		}
+
+		// The second transaction is sent after the retried first batch.
+		let _ = tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+		{
+			let txs = da_client.received_tx.lock().unwrap();
+			let sequence_numbers: Vec<_> = txs.iter().map(|tx| tx.sequence_number()).collect();
+			assert_eq!(sequence_numbers, vec![0, 1]);
+		}
+
+		mempool_handle.abort();
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_pipe_mempool_sends_batches_while_one_is_retried() -> Result<(), anyhow::Error> {
+		// set up
+		let maptos_config = Config::default();
+		let (context, mut transaction_pipe, _tempdir, tx_receiver) = setup().await;
+		transaction_pipe.mempool_config.outbox_max_batches_in_flight = 2;
+		let outbox = transaction_pipe.outbox();
+
+		// The first batch write fails.
+		let da_client = TxPipeTestDaSequencerClient::new();
+		da_client.unavailable_writes.store(1, Ordering::Relaxed);
+		let mempool_handle = tokio::spawn(transaction_pipe.run(da_client.clone(), tx_receiver));
+
+		for sequence_number in 0..3 {
+			let user_transaction = create_signed_transaction(sequence_number, &maptos_config);
+			let (req_sender, callback) = oneshot::channel();
+			context
+				.mempool_client_sender()
+				.send(MempoolClientRequest::SubmitTransaction(user_transaction, req_sender))
+				.await?;
+			let (status, _vm_status_code) = callback.await??;
+			assert_eq!(status.code, MempoolStatusCode::Accepted);
+			// Let each batch be sent before the next transaction is submitted.
+			let _ = tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
+		}
+
+		// The second batch doesn't wait for the retry of the first one.
+		// The third one waits for the first one to leave the window.
+		let _ = tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+		{
+			let txs = da_client.received_tx.lock().unwrap();
+			let sequence_numbers: Vec<_> = txs.iter().map(|tx| tx.sequence_number()).collect();
+			assert_eq!(sequence_numbers, vec![1, 0, 2]);
+		}
+		assert!(outbox.pending_batches()?.is_empty());
+
+		mempool_handle.abort();
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_pipe_mempool_drops_batch_after_max_send_attempts() -> Result<(), anyhow::Error> {
+		// set up
+		let maptos_config = Config::default();
+		let (context, mut transaction_pipe, _tempdir, tx_receiver) = setup().await;
+		transaction_pipe.mempool_config.outbox_max_send_attempts = 2;
+		let outbox = transaction_pipe.outbox();
+
+		// The DA is never reachable.
+		let da_client = TxPipeTestDaSequencerClient::new();
+		da_client.unavailable_writes.store(u32::MAX, Ordering::Relaxed);
+		let mempool_handle = tokio::spawn(transaction_pipe.run(da_client.clone(), tx_receiver));
+
+		let user_transaction = create_signed_transaction(0, &maptos_config);
+		let (req_sender, callback) = oneshot::channel();
+		context
+			.mempool_client_sender()
+			.send(MempoolClientRequest::SubmitTransaction(user_transaction, req_sender))
+			.await?;
+		let (status, _vm_status_code) = callback.await??;
+		assert_eq!(status.code, MempoolStatusCode::Accepted);
+
+		// Sent once, retried after 500ms, then dropped.
+		let _ = tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+		assert_eq!(da_client.unavailable_writes.load(Ordering::Relaxed), u32::MAX - 2);
+		assert!(outbox.pending_batches()?.is_empty());
+		assert!(da_client.received_tx.lock().unwrap().is_empty());
+
+		mempool_handle.abort();
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_pipe_mempool_readmits_outbox_batches() -> Result<(), anyhow::Error> {
+		// set up
+		let maptos_config = Config::default();
+		let (_context, transaction_pipe, _tempdir, tx_receiver) = setup().await;
+
+		// A batch left in the outbox by a previous run.
+		let user_transaction = create_signed_transaction(0, &maptos_config);
+		let outbox = transaction_pipe.outbox();
+		outbox.push(vec![
+			MvTransaction::new(vec![0xff; 3], 0, 0),
+			MvTransaction::new(
+				bcs::to_bytes(&user_transaction)?,
+				0,
+				user_transaction.sequence_number(),
+			),
+		])?;
+
+		let da_client = TxPipeTestDaSequencerClient::new();
+		let mempool_handle = tokio::spawn(transaction_pipe.run(da_client.clone(), tx_receiver));
+
+		//wait Tx propagation
+		let _ = tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+		{
+			let txs = da_client.received_tx.lock().unwrap();
+			assert_eq!(txs.len(), 1);
+			assert_eq!(txs.get(0).unwrap().sequence_number(), user_transaction.sequence_number());
+		}
+		assert!(outbox.pending_batches()?.is_empty());
+
+		mempool_handle.abort();
+		Ok(())
+	}
+
 	#[tokio::test]
 	async fn test_pipe_mempool_cancellation() -> Result<(), anyhow::Error> {
 		// set up
@@ -681,6 +1165,7 @@ mod tests {
 		let core_mempool = transaction_pipe.core_mempool();
 		let db_reader = transaction_pipe.db_reader();
 		let (transactions_in_flight, in_flight_limit) = transaction_pipe.transactions_in_flight();
+		let admission = transaction_pipe.admission();
 
 		// Add 2 time the transaction to mempool
 		let (mempool_status, _) = TransactionPipe::add_transaction_to_aptos_mempool(
@@ -689,6 +1174,7 @@ mod tests {
 			&db_reader,
 			&transactions_in_flight,
 			in_flight_limit,
+			&admission,
 		)
 		.await?;
 
@@ -700,6 +1186,7 @@ mod tests {
 			&db_reader,
 			&transactions_in_flight,
 			in_flight_limit,
+			&admission,
 		)
 		.await?;
 		assert_eq!(mempool_status.code, MempoolStatusCode::Accepted);
@@ -800,6 +1287,7 @@ mod tests {
 		let core_mempool = transaction_pipe.core_mempool();
 		let db_reader = transaction_pipe.db_reader();
 		let (transactions_in_flight, in_flight_limit) = transaction_pipe.transactions_in_flight();
+		let admission = transaction_pipe.admission();
 
 		// submit a transaction with a valid sequence number
 		let user_transaction = create_signed_transaction(0, &maptos_config);
@@ -811,6 +1299,7 @@ mod tests {
 			&db_reader,
 			&transactions_in_flight,
 			in_flight_limit,
+			&admission,
 		)
 		.await?;
 
@@ -824,6 +1313,7 @@ mod tests {
 			&db_reader,
 			&transactions_in_flight,
 			in_flight_limit,
+			&admission,
 		)
 		.await?;
 
@@ -837,6 +1327,7 @@ mod tests {
 			&db_reader,
 			&transactions_in_flight,
 			in_flight_limit,
+			&admission,
 		)
 		.await?;
 
@@ -850,6 +1341,7 @@ mod tests {
 			&db_reader,
 			&transactions_in_flight,
 			in_flight_limit,
+			&admission,
 		)
 		.await?;
 
@@ -858,6 +1350,100 @@ mod tests {
 		Ok(())
 	}
 
+	struct DenyAllPolicy;
+
+	impl AdmissionPolicy for DenyAllPolicy {
+		fn admit(
+			&self,
+			transaction: &SignedTransaction,
+			_sender_in_flight: u64,
+		) -> Result<(), AdmissionRejection> {
+			Err(AdmissionRejection::SenderDenied(transaction.sender()))
+		}
+	}
+
+	#[tokio::test]
+	async fn test_rejected_by_admission_policy() -> Result<(), anyhow::Error> {
+		let maptos_config = Config::default();
+		let (_context, transaction_pipe, _tempdir, _tx_receiver) = setup().await;
+		let transaction_pipe = transaction_pipe.with_admission_policy(Arc::new(DenyAllPolicy));
+
+		let core_mempool = transaction_pipe.core_mempool();
+		let db_reader = transaction_pipe.db_reader();
+		let (transactions_in_flight, in_flight_limit) = transaction_pipe.transactions_in_flight();
+		let admission = transaction_pipe.admission();
+
+		let user_transaction = create_signed_transaction(0, &maptos_config);
+		let tx_hash = user_transaction.committed_hash();
+		let (mempool_status, _) = TransactionPipe::add_transaction_to_aptos_mempool(
+			user_transaction,
+			&core_mempool,
+			&db_reader,
+			&transactions_in_flight,
+			in_flight_limit,
+			&admission,
+		)
+		.await?;
+
+		assert_eq!(mempool_status.code, MempoolStatusCode::RejectedByFilter);
+		assert!(!mempool_status.message.is_empty());
+		assert!(core_mempool.read().unwrap().get_by_hash(tx_hash).is_none());
+		let rejections = admission.rejections().recent(10);
+		assert_eq!(rejections.len(), 1);
+		assert_eq!(rejections[0].hash, tx_hash);
+		assert_eq!(rejections[0].stage, RejectionStage::Admission);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_mempool_admin_lists_and_evicts() -> Result<(), anyhow::Error> {
+		let maptos_config = Config::default();
+		let (_context, transaction_pipe, _tempdir, _tx_receiver) = setup().await;
+		let core_mempool = transaction_pipe.core_mempool();
+		let db_reader = transaction_pipe.db_reader();
+		let (transactions_in_flight, in_flight_limit) = transaction_pipe.transactions_in_flight();
+		let admission = transaction_pipe.admission();
+		let mempool_admin = transaction_pipe.mempool_admin();
+
+		// Leave a gap at sequence number 1.
+		let mut hashes = vec![];
+		for sequence_number in [0, 2] {
+			let user_transaction = create_signed_transaction(sequence_number, &maptos_config);
+			hashes.push(user_transaction.committed_hash());
+			let (mempool_status, _) = TransactionPipe::add_transaction_to_aptos_mempool(
+				user_transaction,
+				&core_mempool,
+				&db_reader,
+				&transactions_in_flight,
+				in_flight_limit,
+				&admission,
+			)
+			.await?;
+			assert_eq!(mempool_status.code, MempoolStatusCode::Accepted);
+		}
+
+		let sender = account_config::aptos_test_root_address();
+		let pending = mempool_admin.sender_pending(sender)?;
+		assert_eq!(pending.ledger_sequence_number, 0);
+		assert_eq!(pending.transactions.len(), 2);
+		assert!(pending
+			.transactions
+			.iter()
+			.all(|transaction| transaction.location == PendingLocation::Mempool));
+		assert_eq!(pending.gaps, vec![SequenceNumberGap { from: 1, to: 1 }]);
+
+		assert!(matches!(mempool_admin.evict(hashes[1]), Eviction::Evicted(_)));
+		assert!(core_mempool.read().unwrap().get_by_hash(hashes[1]).is_none());
+		assert!(matches!(mempool_admin.evict(hashes[1]), Eviction::NotFound));
+		assert_eq!(mempool_admin.sender_pending(sender)?.transactions.len(), 1);
+		let rejections = mempool_admin.recent_rejections(10);
+		assert_eq!(rejections[0].hash, hashes[1]);
+		assert_eq!(rejections[0].stage, RejectionStage::Eviction);
+
+		Ok(())
+	}
+
 	#[tokio::test]
 	async fn test_sequence_number_too_old() -> Result<(), anyhow::Error> {
 		let (tx_sender, tx_receiver) = futures::channel::mpsc::channel::<MempoolClientRequest>(1);
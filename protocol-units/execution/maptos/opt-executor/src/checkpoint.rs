@@ -0,0 +1,258 @@
+//! Named checkpoints of the AptosDB.
+//!
+//! A checkpoint is a RocksDB checkpoint of the AptosDB taken at the block head, so its SST files
+//! are hard links and it is fast to create. Restoring a checkpoint replaces the AptosDB
+//! directories with a new checkpoint of the saved one, so a checkpoint can be restored several
+//! times. Other directories in the DB path, like the mempool outbox, are left untouched.
+//!
+//! The AptosDB must not be opened by a running node while a checkpoint is created or restored.
+
+use aptos_config::config::{NodeConfig, StorageDirPaths};
+use aptos_db::AptosDB;
+use aptos_storage_interface::DbReader;
+use maptos_execution_util::config::Config;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+const METADATA_FILE: &str = "checkpoint.json";
+const DB_DIR: &str = "db";
+const STAGING_DIR: &str = ".staging";
+const REPLACED_DIR: &str = ".replaced";
+
+/// A checkpoint of the AptosDB at a block height.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerCheckpoint {
+	pub name: String,
+	pub block_height: u64,
+	pub ledger_version: u64,
+	pub created_at_secs: u64,
+}
+
+/// The checkpoints of an AptosDB, each in a directory named after the checkpoint.
+#[derive(Debug, Clone)]
+pub struct CheckpointStore {
+	db_path: PathBuf,
+	root: PathBuf,
+	sharding: bool,
+}
+
+impl CheckpointStore {
+	pub fn new(db_path: impl Into<PathBuf>, root: impl Into<PathBuf>) -> Self {
+		CheckpointStore {
+			db_path: db_path.into(),
+			root: root.into(),
+			sharding: NodeConfig::default().storage.rocksdb_configs.enable_storage_sharding,
+		}
+	}
+
+	/// The store of the configured DB, in the `-checkpoints` directory next to it.
+	pub fn try_from_config(config: &Config) -> Result<Self, anyhow::Error> {
+		let db_path = config
+			.chain
+			.maptos_db_path
+			.clone()
+			.ok_or(anyhow::anyhow!("No db path provided."))?;
+		let mut root = OsString::from(db_path.as_os_str());
+		root.push("-checkpoints");
+		Ok(Self::new(db_path, root))
+	}
+
+	pub fn root(&self) -> &Path {
+		&self.root
+	}
+
+	fn checkpoint_path(&self, name: &str) -> Result<PathBuf, anyhow::Error> {
+		if name.is_empty()
+			|| name.starts_with('.')
+			|| !name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+		{
+			anyhow::bail!(
+				"Invalid checkpoint name {name:?}, use alphanumeric characters, '-', '_' or '.'"
+			);
+		}
+		Ok(self.root.join(name))
+	}
+
+	/// Snapshot the AptosDB under `name`.
+	pub fn create(&self, name: &str) -> Result<LedgerCheckpoint, anyhow::Error> {
+		let path = self.checkpoint_path(name)?;
+		if path.exists() {
+			anyhow::bail!("Checkpoint {name} already exists");
+		}
+		fs::create_dir_all(&path)?;
+		let result = (|| {
+			let db_path = path.join(DB_DIR);
+			AptosDB::create_checkpoint(&self.db_path, &db_path, self.sharding)?;
+			let (block_height, ledger_version) = read_block_head(&db_path)?;
+			let checkpoint = LedgerCheckpoint {
+				name: name.to_string(),
+				block_height,
+				ledger_version,
+				created_at_secs: chrono::Utc::now().timestamp() as u64,
+			};
+			fs::write(path.join(METADATA_FILE), serde_json::to_vec_pretty(&checkpoint)?)?;
+			Ok(checkpoint)
+		})();
+		if result.is_err() {
+			let _ = fs::remove_dir_all(&path);
+		}
+		let checkpoint = result?;
+		info!(
+			name,
+			block_height = checkpoint.block_height,
+			ledger_version = checkpoint.ledger_version,
+			"Checkpoint created"
+		);
+		Ok(checkpoint)
+	}
+
+	pub fn get(&self, name: &str) -> Result<LedgerCheckpoint, anyhow::Error> {
+		let metadata = self.checkpoint_path(name)?.join(METADATA_FILE);
+		let bytes =
+			fs::read(&metadata).map_err(|e| anyhow::anyhow!("No checkpoint {name}: {e}"))?;
+		Ok(serde_json::from_slice(&bytes)?)
+	}
+
+	/// The checkpoints, ordered by block height.
+	pub fn list(&self) -> Result<Vec<LedgerCheckpoint>, anyhow::Error> {
+		if !self.root.exists() {
+			return Ok(vec![]);
+		}
+		let mut checkpoints = vec![];
+		for entry in fs::read_dir(&self.root)? {
+			let entry = entry?;
+			let name = entry.file_name().to_string_lossy().to_string();
+			// Incomplete checkpoints have no metadata.
+			if name.starts_with('.') || !entry.path().join(METADATA_FILE).exists() {
+				continue;
+			}
+			checkpoints.push(self.get(&name)?);
+		}
+		checkpoints.sort_by_key(|checkpoint| (checkpoint.block_height, checkpoint.created_at_secs));
+		Ok(checkpoints)
+	}
+
+	pub fn delete(&self, name: &str) -> Result<(), anyhow::Error> {
+		self.get(name)?;
+		fs::remove_dir_all(self.checkpoint_path(name)?)?;
+		Ok(())
+	}
+
+	/// Replace the AptosDB with the checkpoint. An interrupted restore can be run again.
+	pub fn restore(&self, name: &str) -> Result<LedgerCheckpoint, anyhow::Error> {
+		let checkpoint = self.get(name)?;
+		let staging = self.root.join(STAGING_DIR);
+		if staging.exists() {
+			fs::remove_dir_all(&staging)?;
+		}
+		AptosDB::create_checkpoint(
+			self.checkpoint_path(name)?.join(DB_DIR),
+			&staging,
+			self.sharding,
+		)?;
+
+		let replaced = self.root.join(REPLACED_DIR);
+		replace_entries(&staging, &self.db_path, &replaced)?;
+		fs::remove_dir_all(&staging)?;
+		fs::remove_dir_all(&replaced)?;
+		info!(name, block_height = checkpoint.block_height, "Checkpoint restored");
+		Ok(checkpoint)
+	}
+}
+
+/// Read the block height and the ledger version of the head of a closed AptosDB.
+fn read_block_head(db_path: &Path) -> Result<(u64, u64), anyhow::Error> {
+	let config = NodeConfig::default();
+	let aptos_db = AptosDB::open(
+		StorageDirPaths::from_path(db_path),
+		true,
+		config.storage.storage_pruner_config,
+		config.storage.rocksdb_configs,
+		false,
+		config.storage.buffered_state_target_items,
+		config.storage.max_num_nodes_per_lru_cache_shard,
+	)?;
+	let version = aptos_db.get_latest_ledger_info()?.ledger_info().version();
+	let (_, _, block_event) = aptos_db.get_block_info_by_version(version)?;
+	Ok((block_event.height, version))
+}
+
+/// Move each entry of `source` into `target`. The entries of `target` they replace are moved
+/// to `replaced` first. If `replaced` already holds an entry, the entry of `target` comes from
+/// an interrupted replacement and is removed instead.
+fn replace_entries(source: &Path, target: &Path, replaced: &Path) -> Result<(), anyhow::Error> {
+	fs::create_dir_all(target)?;
+	fs::create_dir_all(replaced)?;
+	for entry in fs::read_dir(source)? {
+		let entry = entry?;
+		let target_entry = target.join(entry.file_name());
+		let replaced_entry = replaced.join(entry.file_name());
+		if target_entry.exists() {
+			if replaced_entry.exists() {
+				remove_entry(&target_entry)?;
+			} else {
+				fs::rename(&target_entry, &replaced_entry)?;
+			}
+		}
+		fs::rename(entry.path(), &target_entry)?;
+	}
+	Ok(())
+}
+
+fn remove_entry(path: &Path) -> Result<(), anyhow::Error> {
+	if path.is_dir() {
+		fs::remove_dir_all(path)?;
+	} else {
+		fs::remove_file(path)?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn write(path: &Path, content: &str) {
+		fs::create_dir_all(path.parent().unwrap()).unwrap();
+		fs::write(path, content).unwrap();
+	}
+
+	#[test]
+	fn test_replace_entries_can_be_resumed() -> Result<(), anyhow::Error> {
+		let dir = tempfile::tempdir()?;
+		let source = dir.path().join("source");
+		let target = dir.path().join("target");
+		let replaced = dir.path().join("replaced");
+		write(&source.join("ledger_db/CURRENT"), "checkpoint");
+		write(&source.join("state_merkle_db/CURRENT"), "checkpoint");
+		write(&target.join("ledger_db/CURRENT"), "head");
+		write(&target.join("state_merkle_db/CURRENT"), "head");
+		write(&target.join("mempool-outbox/CURRENT"), "outbox");
+
+		// An interrupted replacement moved the ledger DB only.
+		fs::create_dir_all(&replaced)?;
+		fs::rename(target.join("ledger_db"), replaced.join("ledger_db"))?;
+		fs::rename(source.join("ledger_db"), target.join("ledger_db"))?;
+		write(&source.join("ledger_db/CURRENT"), "checkpoint");
+
+		replace_entries(&source, &target, &replaced)?;
+		assert_eq!(fs::read_to_string(target.join("ledger_db/CURRENT"))?, "checkpoint");
+		assert_eq!(fs::read_to_string(target.join("state_merkle_db/CURRENT"))?, "checkpoint");
+		assert_eq!(fs::read_to_string(target.join("mempool-outbox/CURRENT"))?, "outbox");
+		assert_eq!(fs::read_to_string(replaced.join("ledger_db/CURRENT"))?, "head");
+		assert_eq!(fs::read_to_string(replaced.join("state_merkle_db/CURRENT"))?, "head");
+		Ok(())
+	}
+
+	#[test]
+	fn test_checkpoint_names() {
+		let store = CheckpointStore::new("/tmp/db", "/tmp/db-checkpoints");
+		assert!(store.checkpoint_path("before-upgrade_1.2").is_ok());
+		assert!(store.checkpoint_path("").is_err());
+		assert!(store.checkpoint_path(".staging").is_err());
+		assert!(store.checkpoint_path("../db").is_err());
+	}
+}
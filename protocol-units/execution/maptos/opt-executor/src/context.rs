@@ -1,9 +1,12 @@
+use crate::background::SenderInFlight;
+use crate::mempool_admin::MempoolAdmin;
+use crate::simulation::Simulator;
 use aptos_config::config::NodeConfig;
 use aptos_mempool::MempoolClientSender;
 use aptos_storage_interface::{DbReader, DbReaderWriter};
 use maptos_execution_util::config::Config;
 
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 /// Infrastructure shared by services using the storage and the mempool.
 pub struct Context {
@@ -11,6 +14,8 @@ pub struct Context {
 	pub(crate) mempool_client_sender: MempoolClientSender,
 	pub(crate) maptos_config: Config,
 	pub(crate) node_config: NodeConfig,
+	pub(crate) sender_in_flight: Arc<RwLock<SenderInFlight>>,
+	pub(crate) mempool_admin: Option<MempoolAdmin>,
 }
 
 impl Context {
@@ -19,8 +24,17 @@ impl Context {
 		mempool_client_sender: MempoolClientSender,
 		maptos_config: Config,
 		node_config: NodeConfig,
+		sender_in_flight: Arc<RwLock<SenderInFlight>>,
+		mempool_admin: Option<MempoolAdmin>,
 	) -> Self {
-		Context { db, mempool_client_sender, maptos_config, node_config }
+		Context {
+			db,
+			mempool_client_sender,
+			maptos_config,
+			node_config,
+			sender_in_flight,
+			mempool_admin,
+		}
 	}
 
 	/// Returns a reference on the data store reader.
@@ -33,6 +47,16 @@ impl Context {
 		self.mempool_client_sender.clone()
 	}
 
+	/// Returns a simulator of transactions against the pending state of their sender.
+	pub fn simulator(&self) -> Simulator {
+		Simulator::new(self.db_reader(), self.sender_in_flight.clone())
+	}
+
+	/// Returns the admin access to the mempool, `None` for a read-only node.
+	pub fn mempool_admin(&self) -> Option<MempoolAdmin> {
+		self.mempool_admin.clone()
+	}
+
 	pub fn config(&self) -> &Config {
 		&self.maptos_config
 	}
@@ -15,6 +15,7 @@ use aptos_types::{
 	block_info::BlockInfo,
 	epoch_state::EpochState,
 	ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+	on_chain_config::{OnChainConfig, OnChainExecutionConfig},
 	transaction::{Transaction, Version},
 	validator_verifier::{ValidatorConsensusInfo, ValidatorVerifier},
 };
@@ -23,6 +24,21 @@ use tracing::debug;
 use tracing::info;
 
 impl Executor {
+	/// The executor config of the next block, read from the on-chain execution config, so the
+	/// block gas limit is the same on every node and only changes through governance.
+	/// The execution config written at genesis isn't applied: the chains that never changed it
+	/// keep executing their blocks without limit.
+	fn block_executor_config(&self) -> Result<BlockExecutorConfigFromOnchain, anyhow::Error> {
+		let version = self.db().reader.get_latest_ledger_info_version()?;
+		let state_view = self.state_view_at_version(Some(version))?;
+		Ok(match OnChainExecutionConfig::fetch_config(&state_view) {
+			Some(config) if config != OnChainExecutionConfig::default_for_genesis() => {
+				config.block_executor_onchain_config()
+			}
+			_ => BlockExecutorConfigFromOnchain::new_no_block_limit(),
+		})
+	}
+
 	pub fn execute_block(
 		&mut self,
 		block: ExecutableBlock,
@@ -68,12 +84,10 @@ impl Executor {
 		let block_id = block.block_id.clone();
 		let parent_block_id = self.block_executor.committed_block_id();
 
+		let block_executor_config = self.block_executor_config()?;
 		let block_executor_clone = self.block_executor.clone();
-		let state_compute: StateComputeResult = block_executor_clone.execute_block(
-			block,
-			parent_block_id,
-			BlockExecutorConfigFromOnchain::new_no_block_limit(),
-		)?;
+		let state_compute: StateComputeResult =
+			block_executor_clone.execute_block(block, parent_block_id, block_executor_config)?;
 
 		let tx_execution_results =
 			TxExecutionResult::merge_result(senders_and_sequence_numbers, &state_compute);
@@ -315,6 +329,28 @@ mod tests {
 		Ok(())
 	}
 
+	#[tokio::test]
+	async fn test_genesis_execution_config_has_no_block_limit() -> Result<(), anyhow::Error> {
+		let private_key = Ed25519PrivateKey::generate_for_testing();
+		let (mempool_tx_exec_result_sender, _mempool_commit_tx_receiver) =
+			unbounded_channel::<Vec<TxExecutionResult>>();
+		let (executor, _tempdir) =
+			Executor::try_test_default(private_key, mempool_tx_exec_result_sender).await?;
+
+		// The genesis writes an execution config, it's not applied.
+		let version = executor.db().reader.get_latest_ledger_info_version()?;
+		let state_view = executor.state_view_at_version(Some(version))?;
+		assert_eq!(
+			OnChainExecutionConfig::fetch_config(&state_view),
+			Some(OnChainExecutionConfig::default_for_genesis())
+		);
+		assert_eq!(
+			executor.block_executor_config()?,
+			BlockExecutorConfigFromOnchain::new_no_block_limit()
+		);
+		Ok(())
+	}
+
 	// https://github.com/movementlabsxyz/aptos-core/blob/ea91067b81f9673547417bff9c70d5a2fe1b0e7b/execution/executor-test-helpers/src/integration_test_impl.rs#L535
 	//	#[tracing_test::traced_test]
 	#[tokio::test]
@@ -1,5 +1,5 @@
 use super::Executor;
-use crate::background::BackgroundTask;
+use crate::background::{BackgroundTask, BatchOutbox, SenderInFlight};
 use crate::executor::TxExecutionResult;
 use crate::executor::EXECUTOR_CHANNEL_SIZE;
 use crate::{bootstrap, Context};
@@ -11,6 +11,7 @@ use aptos_crypto::Uniform;
 use aptos_crypto::ValidCryptoMaterialStringExt;
 use aptos_executor::block_executor::BlockExecutor;
 use aptos_mempool::MempoolClientRequest;
+use aptos_vm::AptosVM;
 use dot_movement::DotMovement;
 use futures::channel::mpsc as futures_mpsc;
 use maptos_execution_util::config::Config;
@@ -113,6 +114,9 @@ impl Executor {
 			maptos_config.chain.enable_indexer_grpc,
 		)?;
 
+		// The VM keeps the execution settings of the first executor of the process.
+		AptosVM::set_concurrency_level_once(maptos_config.execution.concurrency_level());
+
 		Ok(Self {
 			mempool_tx_exec_result_sender,
 			block_executor: Arc::new(BlockExecutor::new(db.clone())),
@@ -121,6 +125,7 @@ impl Executor {
 				Duration::try_new(maptos_config.mempool.sequence_number_ttl_ms)?,
 				Duration::try_new(maptos_config.mempool.gc_slot_duration_ms)?,
 			))),
+			sender_in_flight: Arc::new(RwLock::new(SenderInFlight::default())),
 			config: maptos_config.clone(),
 			node_config: node_config.clone(),
 		})
@@ -244,20 +249,39 @@ impl Executor {
 				futures_mpsc::channel::<MempoolClientRequest>(EXECUTOR_CHANNEL_SIZE);
 			BackgroundTask::read_only(mempool_client_receiver)
 		} else {
+			let outbox_path = match &maptos_config.mempool.outbox_db_path {
+				Some(path) => path.clone(),
+				None => maptos_config
+					.chain
+					.maptos_db_path
+					.as_ref()
+					.context("No db path provided.")?
+					.join("mempool-outbox"),
+			};
+			let outbox = BatchOutbox::try_new(&outbox_path)
+				.with_context(|| format!("Failed to open the batch outbox at {outbox_path:?}"))?;
 			BackgroundTask::transaction_pipe(
 				mempool_commit_tx_receiver,
 				self.db().reader.clone(),
 				&node_config,
 				&self.config.mempool,
 				&self.config.access_control,
+				&self.config.load_shedding,
 				self.transactions_in_flight.clone(),
-				maptos_config.load_shedding.max_transactions_in_flight,
 				da_batch_signer,
+				outbox,
+				self.sender_in_flight.clone(),
 			)?
 		};
 
-		let cx =
-			Context::new(self.db().clone(), mempool_request_sender, maptos_config, node_config);
+		let cx = Context::new(
+			self.db().clone(),
+			mempool_request_sender,
+			maptos_config,
+			node_config,
+			self.sender_in_flight.clone(),
+			background_task.mempool_admin(),
+		);
 
 		Ok((cx, background_task))
 	}
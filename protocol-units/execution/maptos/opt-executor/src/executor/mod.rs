@@ -3,6 +3,7 @@
 pub mod execution;
 pub mod initialization;
 
+use crate::background::SenderInFlight;
 use aptos_config::config::NodeConfig;
 use aptos_crypto::HashValue;
 use aptos_executor::block_executor::BlockExecutor;
@@ -142,6 +143,9 @@ pub struct Executor {
 	pub signer: ValidatorSigner,
 	// Shared reference on the counter of transactions in flight.
 	transactions_in_flight: Arc<RwLock<GcCounter>>,
+	// Transactions of each sender accepted in the mempool and not executed yet, shared by the
+	// admission and the simulations.
+	sender_in_flight: Arc<RwLock<SenderInFlight>>,
 	// The config for the executor.
 	pub config: Config,
 	/// The node config derived from the maptos config.
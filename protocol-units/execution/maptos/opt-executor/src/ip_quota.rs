@@ -0,0 +1,425 @@
+//! Quota of the transactions submitted from each IP address.
+//!
+//! The API doesn't know when a submitted transaction is executed, so a submission accepted
+//! from an IP address is counted in flight for this address during a fixed time to live.
+//! A batch submission counts once per transaction of the batch, and a simulation counts once.
+//!
+//! Behind a reverse proxy, the client address is read from the `Forwarded` or
+//! `X-Forwarded-For` header, but only for requests coming from one of the trusted proxies
+//! of the config, as anyone else could set these headers to evade the quota.
+
+use crate::metrics;
+use maptos_execution_util::config::load_shedding::Config as LoadSheddingConfig;
+use poem::http::{header, HeaderMap, Method, StatusCode};
+use poem::{Endpoint, IntoResponse, Middleware, Request, Response};
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncReadExt;
+use tracing::warn;
+
+/// The admission times in milliseconds of the submissions in flight of each IP address.
+#[derive(Debug)]
+pub struct IpInFlight {
+	ttl_ms: u64,
+	submissions: HashMap<IpAddr, VecDeque<u64>>,
+	last_gc_ms: u64,
+}
+
+impl IpInFlight {
+	pub fn new(ttl_ms: u64) -> Self {
+		IpInFlight { ttl_ms, submissions: HashMap::new(), last_gc_ms: 0 }
+	}
+
+	/// Number of submissions of the address still in flight.
+	pub fn count(&mut self, ip: &IpAddr, now_ms: u64) -> u64 {
+		let ttl_ms = self.ttl_ms;
+		match self.submissions.get_mut(ip) {
+			Some(admitted) => {
+				while admitted.front().is_some_and(|at| now_ms.saturating_sub(*at) >= ttl_ms) {
+					admitted.pop_front();
+				}
+				if admitted.is_empty() {
+					self.submissions.remove(ip);
+					0
+				} else {
+					admitted.len() as u64
+				}
+			}
+			None => 0,
+		}
+	}
+
+	/// Record `count` submissions. The addresses without submissions in flight are garbage
+	/// collected at most once per time to live.
+	pub fn add(&mut self, ip: IpAddr, count: u64, now_ms: u64) {
+		if now_ms.saturating_sub(self.last_gc_ms) >= self.ttl_ms {
+			self.gc(now_ms);
+		}
+		let admitted = self.submissions.entry(ip).or_default();
+		admitted.extend(std::iter::repeat(now_ms).take(count as usize));
+	}
+
+	/// Record `count` submissions if the address stays within the limit, in one step so
+	/// concurrent requests can't go over it. Returns false if the submissions are refused.
+	pub fn try_add(&mut self, ip: IpAddr, count: u64, limit: u64, now_ms: u64) -> bool {
+		if self.count(&ip, now_ms).saturating_add(count) > limit {
+			return false;
+		}
+		self.add(ip, count, now_ms);
+		true
+	}
+
+	/// Remove `count` submissions recorded at `at_ms`, for a request that failed.
+	pub fn remove(&mut self, ip: &IpAddr, count: u64, at_ms: u64) {
+		let Some(admitted) = self.submissions.get_mut(ip) else {
+			return;
+		};
+		let mut to_remove = count;
+		let mut index = admitted.len();
+		while to_remove > 0 && index > 0 {
+			index -= 1;
+			if admitted[index] == at_ms {
+				admitted.remove(index);
+				to_remove -= 1;
+			}
+		}
+		if admitted.is_empty() {
+			self.submissions.remove(ip);
+		}
+	}
+
+	/// Remove the addresses without submissions in flight.
+	pub fn gc(&mut self, now_ms: u64) {
+		self.last_gc_ms = now_ms;
+		let ttl_ms = self.ttl_ms;
+		self.submissions.retain(|_, admitted| {
+			admitted.back().is_some_and(|at| now_ms.saturating_sub(*at) < ttl_ms)
+		});
+	}
+}
+
+/// Middleware rejecting the transaction submissions of the IP addresses over their quota.
+#[derive(Debug, Clone)]
+pub struct IpQuota {
+	limit: Option<u64>,
+	trusted_proxies: Arc<Vec<IpAddr>>,
+	max_body_bytes: usize,
+	in_flight: Arc<Mutex<IpInFlight>>,
+}
+
+impl IpQuota {
+	/// `max_body_bytes` bounds the batch bodies buffered to count their transactions.
+	pub fn from_config(config: &LoadSheddingConfig, max_body_bytes: usize) -> Self {
+		IpQuota {
+			limit: config.max_transactions_in_flight_per_ip,
+			trusted_proxies: Arc::new(config.trusted_proxies.clone()),
+			max_body_bytes,
+			in_flight: Arc::new(Mutex::new(IpInFlight::new(config.ip_in_flight_ttl_ms))),
+		}
+	}
+}
+
+impl<E: Endpoint> Middleware<E> for IpQuota {
+	type Output = IpQuotaEndpoint<E>;
+
+	fn transform(&self, ep: E) -> Self::Output {
+		IpQuotaEndpoint {
+			inner: ep,
+			limit: self.limit,
+			trusted_proxies: Arc::clone(&self.trusted_proxies),
+			max_body_bytes: self.max_body_bytes,
+			in_flight: Arc::clone(&self.in_flight),
+		}
+	}
+}
+
+pub struct IpQuotaEndpoint<E> {
+	inner: E,
+	limit: Option<u64>,
+	trusted_proxies: Arc<Vec<IpAddr>>,
+	max_body_bytes: usize,
+	in_flight: Arc<Mutex<IpInFlight>>,
+}
+
+/// The transaction submission routes, and the simulation route as it executes transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Submission {
+	Single,
+	Batch,
+}
+
+fn submission(req: &Request) -> Option<Submission> {
+	let path = req.uri().path().trim_end_matches('/');
+	if req.method() != Method::POST {
+		None
+	} else if path.ends_with("/transactions") || path.ends_with("/movement/v1/simulate") {
+		Some(Submission::Single)
+	} else if path.ends_with("/transactions/batch") {
+		Some(Submission::Batch)
+	} else {
+		None
+	}
+}
+
+/// Address of a hop of a forwarding header: an IP address, possibly quoted,
+/// with a port, or in brackets for IPv6. Obfuscated and unknown hops have none.
+fn parse_hop(hop: &str) -> Option<IpAddr> {
+	let hop = hop.trim().trim_matches('"');
+	if let Some(bracketed) = hop.strip_prefix('[') {
+		return bracketed.split(']').next()?.parse().ok();
+	}
+	hop.parse::<IpAddr>()
+		.ok()
+		.or_else(|| hop.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
+
+/// The hops of the forwarding headers, from the client to the last proxy.
+/// The standard `Forwarded` header is used if present, `X-Forwarded-For` otherwise.
+fn forwarded_hops(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+	let forwarded: Vec<&str> = headers
+		.get_all(header::FORWARDED)
+		.iter()
+		.filter_map(|value| value.to_str().ok())
+		.collect();
+	if !forwarded.is_empty() {
+		return forwarded
+			.iter()
+			.flat_map(|value| value.split(','))
+			.filter_map(|element| {
+				element.split(';').find_map(|pair| {
+					let (name, value) = pair.split_once('=')?;
+					name.trim().eq_ignore_ascii_case("for").then(|| parse_hop(value))
+				})
+			})
+			.collect();
+	}
+	headers
+		.get_all("x-forwarded-for")
+		.iter()
+		.filter_map(|value| value.to_str().ok())
+		.flat_map(|value| value.split(','))
+		.map(parse_hop)
+		.collect()
+}
+
+/// Address of the client of a request from `peer`. For a request from a trusted proxy, this
+/// is the last address of the forwarding headers not of a trusted proxy, as the hops before
+/// it could be set by the client. Without a readable one, the quota falls on the last proxy.
+fn client_ip(peer: IpAddr, headers: &HeaderMap, trusted_proxies: &[IpAddr]) -> IpAddr {
+	if !trusted_proxies.contains(&peer) {
+		return peer;
+	}
+	let mut client = peer;
+	for hop in forwarded_hops(headers).into_iter().rev() {
+		match hop {
+			Some(ip) => {
+				client = ip;
+				if !trusted_proxies.contains(&ip) {
+					break;
+				}
+			}
+			None => break,
+		}
+	}
+	client
+}
+
+/// Length of a BCS sequence, read from its ULEB128 prefix.
+fn bcs_sequence_len(bytes: &[u8]) -> Option<u64> {
+	let mut len: u64 = 0;
+	for (index, byte) in bytes.iter().take(10).enumerate() {
+		len |= u64::from(byte & 0x7f) << (7 * index);
+		if byte & 0x80 == 0 {
+			return Some(len);
+		}
+	}
+	None
+}
+
+/// Number of transactions of a batch submission, a JSON array or a BCS sequence.
+/// A body that can't be read counts as one transaction, the API rejects it anyway.
+fn batch_len(content_type: Option<&str>, body: &[u8]) -> u64 {
+	let len = if content_type.is_some_and(|content_type| content_type.ends_with("+bcs")) {
+		bcs_sequence_len(body)
+	} else {
+		serde_json::from_slice::<Vec<serde::de::IgnoredAny>>(body)
+			.ok()
+			.map(|transactions| transactions.len() as u64)
+	};
+	len.unwrap_or(1).max(1)
+}
+
+#[poem::async_trait]
+impl<E: Endpoint> Endpoint for IpQuotaEndpoint<E> {
+	type Output = Response;
+
+	async fn call(&self, mut req: Request) -> poem::Result<Self::Output> {
+		let ip = req
+			.remote_addr()
+			.as_socket_addr()
+			.map(|addr| client_ip(addr.ip(), req.headers(), &self.trusted_proxies));
+		let (Some(limit), Some(ip), Some(submission)) = (self.limit, ip, submission(&req)) else {
+			return self.inner.call(req).await.map(IntoResponse::into_response);
+		};
+
+		let count = match submission {
+			Submission::Single => 1,
+			Submission::Batch => {
+				let too_large = || {
+					poem::Error::from_string(
+						format!("Batch submissions are limited to {} bytes", self.max_body_bytes),
+						StatusCode::PAYLOAD_TOO_LARGE,
+					)
+				};
+				let content_length = req
+					.header(header::CONTENT_LENGTH)
+					.and_then(|length| length.parse::<u64>().ok());
+				if content_length.is_some_and(|length| length > self.max_body_bytes as u64) {
+					return Err(too_large());
+				}
+				// Read at most one byte over the limit, for a body without a length.
+				let mut body = Vec::new();
+				req.take_body()
+					.into_async_read()
+					.take(self.max_body_bytes as u64 + 1)
+					.read_to_end(&mut body)
+					.await
+					.map_err(|e| {
+						poem::Error::from_string(e.to_string(), StatusCode::BAD_REQUEST)
+					})?;
+				if body.len() > self.max_body_bytes {
+					return Err(too_large());
+				}
+				let count = batch_len(req.content_type(), &body);
+				req.set_body(body);
+				count
+			}
+		};
+
+		// Reserve the submissions before the call, they are released if it fails.
+		let now_ms = chrono::Utc::now().timestamp_millis() as u64;
+		if !self.in_flight.lock().unwrap().try_add(ip, count, limit, now_ms) {
+			warn!(
+				"{count} transaction submissions from {ip} rejected, limit of {limit} in flight."
+			);
+			metrics::reject_submission("ip_quota");
+			return Ok(Response::builder().status(StatusCode::TOO_MANY_REQUESTS).body(format!(
+				"{ip} can't have more than {limit} transaction submissions in flight"
+			)));
+		}
+
+		let response = self.inner.call(req).await.map(IntoResponse::into_response);
+		if !response.as_ref().is_ok_and(|response| response.status().is_success()) {
+			self.in_flight.lock().unwrap().remove(&ip, count, now_ms);
+		}
+		response
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_ip_in_flight_expires_submissions() {
+		let mut in_flight = IpInFlight::new(1000);
+		let ip: IpAddr = "10.0.0.1".parse().unwrap();
+		let other: IpAddr = "10.0.0.2".parse().unwrap();
+
+		in_flight.add(ip, 1, 0);
+		in_flight.add(ip, 1, 500);
+		in_flight.add(other, 1, 500);
+		assert_eq!(in_flight.count(&ip, 900), 2);
+		assert_eq!(in_flight.count(&ip, 1000), 1);
+		assert_eq!(in_flight.count(&other, 1000), 1);
+
+		in_flight.gc(1500);
+		assert_eq!(in_flight.count(&ip, 1500), 0);
+		assert!(in_flight.submissions.is_empty());
+	}
+
+	#[test]
+	fn test_ip_in_flight_reserves_all_the_submissions_or_none() {
+		let mut in_flight = IpInFlight::new(1000);
+		let ip: IpAddr = "10.0.0.1".parse().unwrap();
+
+		assert!(in_flight.try_add(ip, 3, 4, 0));
+		assert!(!in_flight.try_add(ip, 2, 4, 100));
+		assert_eq!(in_flight.count(&ip, 100), 3);
+		assert!(in_flight.try_add(ip, 1, 4, 100));
+
+		// A failed request gives its submissions back.
+		in_flight.remove(&ip, 3, 0);
+		assert_eq!(in_flight.count(&ip, 100), 1);
+		assert!(in_flight.try_add(ip, 3, 4, 200));
+	}
+
+	#[test]
+	fn test_submission_routes() {
+		let request = |method: Method, path: &'static str| {
+			Request::builder()
+				.method(method)
+				.uri(poem::http::Uri::from_static(path))
+				.finish()
+		};
+		assert_eq!(
+			submission(&request(Method::POST, "/v1/transactions")),
+			Some(Submission::Single)
+		);
+		assert_eq!(
+			submission(&request(Method::POST, "/v1/transactions/batch")),
+			Some(Submission::Batch)
+		);
+		assert_eq!(
+			submission(&request(Method::POST, "/movement/v1/simulate")),
+			Some(Submission::Single)
+		);
+		assert_eq!(submission(&request(Method::GET, "/v1/transactions")), None);
+	}
+
+	#[test]
+	fn test_client_ip_trusts_the_forwarding_headers_of_the_proxies_only() {
+		let headers = |name: &'static str, value: &'static str| {
+			let mut headers = HeaderMap::new();
+			headers.insert(name, value.parse().unwrap());
+			headers
+		};
+		let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+		let client: IpAddr = "203.0.113.7".parse().unwrap();
+
+		// Anyone else can't choose the address of the quota.
+		let spoofed = headers("x-forwarded-for", "198.51.100.1");
+		assert_eq!(client_ip(client, &spoofed, &[proxy]), client);
+		assert_eq!(client_ip(client, &spoofed, &[]), client);
+
+		// The hops added before the one of the proxy can't be trusted.
+		let proxied = headers("x-forwarded-for", "198.51.100.1, 203.0.113.7");
+		assert_eq!(client_ip(proxy, &proxied, &[proxy]), client);
+
+		let forwarded =
+			headers("forwarded", "for=198.51.100.1, for=\"[2001:db8::1]:4711\";proto=https");
+		assert_eq!(
+			client_ip(proxy, &forwarded, &[proxy]),
+			"2001:db8::1".parse::<IpAddr>().unwrap()
+		);
+		let forwarded = headers("forwarded", "for=203.0.113.7:4711");
+		assert_eq!(client_ip(proxy, &forwarded, &[proxy]), client);
+
+		// Without a readable client address, the quota falls on the proxy.
+		let unknown = headers("forwarded", "for=unknown");
+		assert_eq!(client_ip(proxy, &unknown, &[proxy]), proxy);
+		assert_eq!(client_ip(proxy, &HeaderMap::new(), &[proxy]), proxy);
+	}
+
+	#[test]
+	fn test_batch_len_counts_the_transactions() {
+		assert_eq!(batch_len(Some("application/json"), b"[{}, {}, {}]"), 3);
+		assert_eq!(batch_len(None, b"not json"), 1);
+		assert_eq!(
+			batch_len(Some("application/x.aptos.signed_transaction+bcs"), &[0x80, 0x01]),
+			128
+		);
+		assert_eq!(batch_len(Some("application/x.aptos.signed_transaction+bcs"), &[]), 1);
+	}
+}
@@ -1,11 +1,16 @@
 pub mod background;
 pub mod bootstrap;
+pub mod checkpoint;
 pub mod context;
 #[warn(unused_imports)]
 pub mod executor;
 pub mod gc_account_sequence_number;
 pub mod indexer;
+pub mod ip_quota;
+pub mod mempool_admin;
+pub mod metrics;
 pub mod service;
+pub mod simulation;
 
 pub use context::Context;
 pub use executor::Executor;
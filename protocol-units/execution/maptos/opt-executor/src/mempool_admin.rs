@@ -0,0 +1,336 @@
+//! Inspection of the mempool of a full node for the operators.
+//!
+//! The admin API lists the transactions of a sender not executed yet with the gaps in their
+//! sequence numbers, shows the last rejected transactions and evicts a transaction from the
+//! `CoreMempool`. A transaction already batched to the DA can't be evicted anymore.
+
+use crate::background::Admission;
+use crate::gc_account_sequence_number::UsedSequenceNumberPool;
+use aptos_crypto::HashValue;
+use aptos_mempool::core_mempool::CoreMempool;
+use aptos_storage_interface::{state_view::LatestDbStateCheckpointView, DbReader};
+use aptos_types::account_address::AccountAddress;
+use aptos_types::transaction::SignedTransaction;
+use aptos_types::vm_status::DiscardedVMStatus;
+use aptos_vm_validator::vm_validator::get_account_sequence_number;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::info;
+
+/// Where a transaction was refused or dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectionStage {
+	/// Refused by the ingress admission policy.
+	Admission,
+	/// Refused by the load shedding.
+	LoadShedding,
+	/// Refused by the VM validation.
+	Validation,
+	/// Refused by the `CoreMempool`.
+	Mempool,
+	/// Discarded at execution.
+	Execution,
+	/// Evicted by an operator.
+	Eviction,
+}
+
+/// A transaction refused or dropped by the transaction pipe.
+#[derive(Debug, Clone, Serialize)]
+pub struct Rejection {
+	pub timestamp_ms: u64,
+	pub hash: HashValue,
+	pub sender: AccountAddress,
+	pub sequence_number: u64,
+	pub stage: RejectionStage,
+	pub reason: String,
+}
+
+impl Rejection {
+	pub fn new(
+		hash: HashValue,
+		sender: AccountAddress,
+		sequence_number: u64,
+		stage: RejectionStage,
+		reason: impl fmt::Display,
+	) -> Self {
+		Rejection {
+			timestamp_ms: chrono::Utc::now().timestamp_millis() as u64,
+			hash,
+			sender,
+			sequence_number,
+			stage,
+			reason: reason.to_string(),
+		}
+	}
+
+	pub fn of_transaction(
+		transaction: &SignedTransaction,
+		stage: RejectionStage,
+		reason: impl fmt::Display,
+	) -> Self {
+		Self::new(
+			transaction.committed_hash(),
+			transaction.sender(),
+			transaction.sequence_number(),
+			stage,
+			reason,
+		)
+	}
+}
+
+/// The last rejections, bounded to `capacity` entries.
+#[derive(Debug, Clone)]
+pub struct RejectionLog {
+	capacity: usize,
+	rejections: Arc<Mutex<VecDeque<Rejection>>>,
+}
+
+impl RejectionLog {
+	pub fn new(capacity: usize) -> Self {
+		RejectionLog { capacity, rejections: Arc::new(Mutex::new(VecDeque::new())) }
+	}
+
+	pub fn record(&self, rejection: Rejection) {
+		if self.capacity == 0 {
+			return;
+		}
+		let mut rejections = self.rejections.lock().unwrap();
+		if rejections.len() == self.capacity {
+			rejections.pop_front();
+		}
+		rejections.push_back(rejection);
+	}
+
+	/// The last `count` rejections, the most recent first.
+	pub fn recent(&self, count: usize) -> Vec<Rejection> {
+		self.rejections.lock().unwrap().iter().rev().take(count).cloned().collect()
+	}
+}
+
+/// A range of missing sequence numbers, bounds included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SequenceNumberGap {
+	pub from: u64,
+	pub to: u64,
+}
+
+/// The missing sequence numbers between the ledger sequence number of a sender
+/// and its pending transactions. `sequence_numbers` must be sorted.
+pub fn sequence_number_gaps(
+	ledger_sequence_number: u64,
+	sequence_numbers: impl IntoIterator<Item = u64>,
+) -> Vec<SequenceNumberGap> {
+	let mut gaps = Vec::new();
+	let mut expected = ledger_sequence_number;
+	for sequence_number in sequence_numbers {
+		if sequence_number > expected {
+			gaps.push(SequenceNumberGap { from: expected, to: sequence_number - 1 });
+		}
+		expected = expected.max(sequence_number + 1);
+	}
+	gaps
+}
+
+/// Where a pending transaction is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PendingLocation {
+	/// In the `CoreMempool`, waiting to be batched.
+	Mempool,
+	/// Sent to the DA, waiting to be executed.
+	Batched,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingTransaction {
+	pub hash: HashValue,
+	pub sequence_number: u64,
+	pub admitted_ms: u64,
+	pub gas_unit_price: u64,
+	pub expiration_timestamp_secs: u64,
+	pub location: PendingLocation,
+}
+
+/// The transactions of a sender not executed yet.
+#[derive(Debug, Clone, Serialize)]
+pub struct SenderPending {
+	pub sender: AccountAddress,
+	pub ledger_sequence_number: u64,
+	/// The sequence number recorded in the used sequence number pool, if any.
+	pub used_sequence_number: Option<u64>,
+	pub transactions: Vec<PendingTransaction>,
+	pub gaps: Vec<SequenceNumberGap>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SenderSummary {
+	pub sender: AccountAddress,
+	pub pending: usize,
+}
+
+/// The outcome of an eviction.
+#[derive(Debug)]
+pub enum Eviction {
+	Evicted(SignedTransaction),
+	/// The transaction is already sent to the DA.
+	Batched(SignedTransaction),
+	NotFound,
+}
+
+/// Read access to the state of the transaction pipe, and eviction of mempool transactions.
+#[derive(Clone)]
+pub struct MempoolAdmin {
+	db_reader: Arc<dyn DbReader>,
+	core_mempool: Arc<RwLock<CoreMempool>>,
+	used_sequence_number_pool: Arc<RwLock<UsedSequenceNumberPool>>,
+	admission: Admission,
+}
+
+impl MempoolAdmin {
+	pub(crate) fn new(
+		db_reader: Arc<dyn DbReader>,
+		core_mempool: Arc<RwLock<CoreMempool>>,
+		used_sequence_number_pool: Arc<RwLock<UsedSequenceNumberPool>>,
+		admission: Admission,
+	) -> Self {
+		MempoolAdmin { db_reader, core_mempool, used_sequence_number_pool, admission }
+	}
+
+	/// The senders with pending transactions.
+	pub fn senders(&self) -> Vec<SenderSummary> {
+		self.admission
+			.sender_in_flight()
+			.read()
+			.unwrap()
+			.senders()
+			.into_iter()
+			.map(|(sender, pending)| SenderSummary { sender, pending })
+			.collect()
+	}
+
+	/// The pending transactions of the sender and the gaps in their sequence numbers.
+	pub fn sender_pending(&self, sender: AccountAddress) -> Result<SenderPending, anyhow::Error> {
+		let state_view = self.db_reader.latest_state_checkpoint_view()?;
+		let ledger_sequence_number = get_account_sequence_number(&state_view, sender)?;
+		let used_sequence_number =
+			self.used_sequence_number_pool.read().unwrap().get_sequence_number(&sender);
+
+		let transactions: Vec<PendingTransaction> = {
+			let core_mempool = self.core_mempool.read().unwrap();
+			self.admission
+				.sender_in_flight()
+				.read()
+				.unwrap()
+				.entries_of_sender(&sender)
+				.into_iter()
+				// Executed transactions are removed when the executor reports them.
+				.filter(|(_, transaction)| transaction.sequence_number() >= ledger_sequence_number)
+				.map(|(admitted_ms, transaction)| {
+					let hash = transaction.committed_hash();
+					let location = match core_mempool.get_by_hash(hash) {
+						Some(_) => PendingLocation::Mempool,
+						None => PendingLocation::Batched,
+					};
+					PendingTransaction {
+						hash,
+						sequence_number: transaction.sequence_number(),
+						admitted_ms,
+						gas_unit_price: transaction.gas_unit_price(),
+						expiration_timestamp_secs: transaction.expiration_timestamp_secs(),
+						location,
+					}
+				})
+				.collect()
+		};
+		let gaps = sequence_number_gaps(
+			ledger_sequence_number,
+			transactions.iter().map(|transaction| transaction.sequence_number),
+		);
+
+		Ok(SenderPending {
+			sender,
+			ledger_sequence_number,
+			used_sequence_number,
+			transactions,
+			gaps,
+		})
+	}
+
+	/// The last `count` rejections, the most recent first.
+	pub fn recent_rejections(&self, count: usize) -> Vec<Rejection> {
+		self.admission.rejections().recent(count)
+	}
+
+	/// Remove the transaction from the mempool. The next transactions of its sender
+	/// stay in the mempool behind the gap.
+	pub fn evict(&self, hash: HashValue) -> Eviction {
+		let transaction = {
+			let mut core_mempool = self.core_mempool.write().unwrap();
+			match core_mempool.get_by_hash(hash) {
+				Some(transaction) => {
+					// Any status other than `SEQUENCE_NUMBER_TOO_NEW` removes the transaction.
+					core_mempool.reject_transaction(
+						&transaction.sender(),
+						transaction.sequence_number(),
+						&hash,
+						&DiscardedVMStatus::UNKNOWN_STATUS,
+					);
+					transaction
+				}
+				None => {
+					let sender_in_flight = self.admission.sender_in_flight().read().unwrap();
+					return match sender_in_flight.get_by_hash(&hash) {
+						Some(transaction) => Eviction::Batched(transaction),
+						None => Eviction::NotFound,
+					};
+				}
+			}
+		};
+		info!(
+			tx_hash = %hash,
+			sender = %transaction.sender(),
+			sequence_number = transaction.sequence_number(),
+			"Transaction evicted from the mempool by an operator."
+		);
+		self.admission.evicted(&transaction);
+		Eviction::Evicted(transaction)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_sequence_number_gaps() {
+		assert_eq!(sequence_number_gaps(3, vec![3, 4, 5]), vec![]);
+		assert_eq!(
+			sequence_number_gaps(3, vec![5, 6, 9]),
+			vec![SequenceNumberGap { from: 3, to: 4 }, SequenceNumberGap { from: 7, to: 8 }]
+		);
+		assert_eq!(sequence_number_gaps(3, vec![]), vec![]);
+	}
+
+	#[test]
+	fn test_rejection_log_keeps_the_last_rejections() {
+		let log = RejectionLog::new(2);
+		let sender = AccountAddress::random();
+		for sequence_number in 0..3 {
+			log.record(Rejection::new(
+				HashValue::random(),
+				sender,
+				sequence_number,
+				RejectionStage::Validation,
+				"SEQUENCE_NUMBER_TOO_OLD",
+			));
+		}
+
+		let sequence_numbers: Vec<u64> =
+			log.recent(10).iter().map(|rejection| rejection.sequence_number).collect();
+		assert_eq!(sequence_numbers, vec![2, 1]);
+		assert_eq!(log.recent(1).len(), 1);
+	}
+}
@@ -0,0 +1,36 @@
+//! Prometheus metrics of the transaction submissions.
+//!
+//! The metrics are registered in the default registry, exported with the aptos-core ones.
+
+use aptos_metrics_core::{register_int_counter_vec, IntCounterVec};
+use once_cell::sync::Lazy;
+
+/// Number of transaction submissions rejected before entering the mempool, by reason.
+pub static REJECTED_SUBMISSIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+	register_int_counter_vec!(
+		"movement_mempool_rejected_submissions",
+		"Number of transaction submissions rejected before entering the mempool, by reason",
+		&["reason"]
+	)
+	.unwrap()
+});
+
+/// Count a rejected submission.
+pub fn reject_submission(reason: &str) {
+	REJECTED_SUBMISSIONS.with_label_values(&[reason]).inc();
+}
+
+/// Number of batches dropped without being acknowledged by the DA Sequencer, by reason.
+pub static DROPPED_BATCHES: Lazy<IntCounterVec> = Lazy::new(|| {
+	register_int_counter_vec!(
+		"movement_mempool_dropped_batches",
+		"Number of batches dropped without being acknowledged by the DA Sequencer, by reason",
+		&["reason"]
+	)
+	.unwrap()
+});
+
+/// Count a dropped batch.
+pub fn drop_batch(reason: &str) {
+	DROPPED_BATCHES.with_label_values(&[reason]).inc();
+}
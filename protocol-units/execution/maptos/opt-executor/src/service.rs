@@ -1,3 +1,6 @@
+use crate::ip_quota::IpQuota;
+use crate::mempool_admin::{Eviction, MempoolAdmin, Rejection, SenderPending, SenderSummary};
+use crate::simulation::{SimulationView, Simulator};
 use crate::Context;
 
 use aptos_api::{
@@ -6,10 +9,22 @@ use aptos_api::{
 	set_failpoints,
 };
 
+use aptos_crypto::HashValue;
 use aptos_storage_interface::DbReaderWriter;
+use aptos_types::account_address::AccountAddress;
+use aptos_types::transaction::SignedTransaction;
 
 use futures::prelude::*;
-use poem::{http::Method, listener::TcpListener, middleware::Cors, EndpointExt, Route, Server};
+use poem::{
+	http::{Method, StatusCode},
+	listener::TcpListener,
+	middleware::{Cors, SizeLimit},
+	web::{Data, Json, Path, Query},
+	EndpointExt, Route, Server,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::Semaphore;
 use tracing::info;
 
 use std::future::Future;
@@ -21,6 +36,16 @@ pub struct Service {
 	context: Arc<aptos_api::Context>,
 	// URL for the API endpoint
 	listen_url: String,
+	// Quota of the transaction submissions of each IP address
+	ip_quota: IpQuota,
+	// Simulations against the pending state of the senders
+	simulations: Simulations,
+	// Maximum size of a request body, the one of the Aptos API
+	content_length_limit: usize,
+	// Mempool admin API, served only if enabled in the config
+	mempool_admin: Option<MempoolAdmin>,
+	// URL for the mempool admin API endpoint, on localhost only
+	admin_listen_url: String,
 }
 
 impl Service {
@@ -30,6 +55,7 @@ impl Service {
 			mempool_client_sender,
 			maptos_config,
 			node_config,
+			..
 		} = cx;
 		let context = Arc::new(aptos_api::Context::new(
 			maptos_config.chain.maptos_chain_id.clone(),
@@ -43,7 +69,26 @@ impl Service {
 			maptos_config.chain.maptos_rest_listen_hostname,
 			maptos_config.chain.maptos_rest_listen_port
 		);
-		Service { context, listen_url }
+		let mempool_admin =
+			if maptos_config.mempool.admin_api_enabled { cx.mempool_admin() } else { None };
+		let simulations = Simulations {
+			simulator: cx.simulator(),
+			permits: Arc::new(Semaphore::new(
+				maptos_config.load_shedding.max_concurrent_simulations,
+			)),
+		};
+		let admin_listen_url = format!("127.0.0.1:{}", maptos_config.mempool.admin_api_listen_port);
+		let content_length_limit = node_config.api.content_length_limit() as usize;
+		let ip_quota = IpQuota::from_config(&maptos_config.load_shedding, content_length_limit);
+		Service {
+			context,
+			listen_url,
+			ip_quota,
+			simulations,
+			content_length_limit,
+			mempool_admin,
+			admin_listen_url,
+		}
 	}
 
 	pub fn api_context(&self) -> Arc<aptos_api::Context> {
@@ -79,11 +124,147 @@ impl Service {
 				"/set_failpoint",
 				poem::get(set_failpoints::set_failpoint_poem).data(self.api_context()),
 			)
+			.at(
+				"/movement/v1/simulate",
+				poem::post(simulate)
+					.data(self.simulations.clone())
+					.with(SizeLimit::new(self.content_length_limit)),
+			)
+			.with(self.ip_quota.clone())
 			.with(cors);
-
-		Server::new(listener)
+		let api_server = Server::new(listener)
 			.run(app)
-			.map_err(|e| anyhow::anyhow!("Server error: {:?}", e))
+			.map_err(|e| anyhow::anyhow!("Server error: {:?}", e));
+
+		// The admin API can evict the transactions of any sender, it is only served on localhost.
+		let admin_server = self.mempool_admin.clone().map(|mempool_admin| {
+			info!(
+				"Serving the mempool admin API at {:?}/movement/v1/admin/mempool",
+				self.admin_listen_url
+			);
+			let app = Route::new()
+				.nest("/movement/v1/admin/mempool", mempool_admin_routes(mempool_admin));
+			Server::new(TcpListener::bind(self.admin_listen_url.clone()))
+				.run(app)
+				.map_err(|e| anyhow::anyhow!("Mempool admin server error: {:?}", e))
+		});
+
+		async move {
+			match admin_server {
+				Some(admin_server) => futures::try_join!(api_server, admin_server).map(|_| ()),
+				None => api_server.await,
+			}
+		}
+	}
+}
+
+/// The simulator and the permits bounding the simulations run at the same time.
+#[derive(Clone)]
+struct Simulations {
+	simulator: Simulator,
+	permits: Arc<Semaphore>,
+}
+
+/// Simulate a BCS encoded signed transaction after the pending transactions of its sender.
+#[poem::handler]
+async fn simulate(
+	Data(simulations): Data<&Simulations>,
+	body: Vec<u8>,
+) -> poem::Result<Json<SimulationView>> {
+	let transaction: SignedTransaction = bcs::from_bytes(&body).map_err(|e| {
+		poem::Error::from_string(
+			format!("Invalid signed transaction: {e}"),
+			StatusCode::BAD_REQUEST,
+		)
+	})?;
+	// The simulations run on the blocking threads, they are refused rather than queued.
+	let permit = simulations.permits.clone().try_acquire_owned().map_err(|_| {
+		poem::Error::from_string(
+			"Too many simulations in progress",
+			StatusCode::SERVICE_UNAVAILABLE,
+		)
+	})?;
+	let simulator = simulations.simulator.clone();
+	let simulation = tokio::task::spawn_blocking(move || {
+		let _permit = permit;
+		simulator.simulate(&transaction)
+	})
+	.await
+	.map_err(|e| poem::Error::from_string(e.to_string(), StatusCode::INTERNAL_SERVER_ERROR))?
+	.map_err(|e| poem::Error::from_string(e.to_string(), StatusCode::INTERNAL_SERVER_ERROR))?;
+
+	Ok(Json(SimulationView::from(&simulation)))
+}
+
+fn mempool_admin_routes(mempool_admin: MempoolAdmin) -> impl poem::Endpoint {
+	Route::new()
+		.at("/senders", poem::get(mempool_senders))
+		.at("/senders/:sender", poem::get(mempool_sender_pending))
+		.at("/rejections", poem::get(mempool_rejections))
+		.at("/transactions/:hash", poem::delete(mempool_evict))
+		.data(mempool_admin)
+}
+
+/// List the senders with transactions not executed yet.
+#[poem::handler]
+async fn mempool_senders(Data(mempool_admin): Data<&MempoolAdmin>) -> Json<Vec<SenderSummary>> {
+	Json(mempool_admin.senders())
+}
+
+/// List the transactions of a sender not executed yet and the gaps in their sequence numbers.
+#[poem::handler]
+async fn mempool_sender_pending(
+	Data(mempool_admin): Data<&MempoolAdmin>,
+	Path(sender): Path<String>,
+) -> poem::Result<Json<SenderPending>> {
+	let sender = sender.parse::<AccountAddress>().map_err(|e| {
+		poem::Error::from_string(format!("Invalid sender: {e}"), StatusCode::BAD_REQUEST)
+	})?;
+	let mempool_admin = mempool_admin.clone();
+	let pending = tokio::task::spawn_blocking(move || mempool_admin.sender_pending(sender))
+		.await
+		.map_err(|e| poem::Error::from_string(e.to_string(), StatusCode::INTERNAL_SERVER_ERROR))?
+		.map_err(|e| poem::Error::from_string(e.to_string(), StatusCode::INTERNAL_SERVER_ERROR))?;
+	Ok(Json(pending))
+}
+
+#[derive(Debug, Deserialize)]
+struct RejectionsQuery {
+	limit: Option<usize>,
+}
+
+/// Show the last rejected transactions, the most recent first.
+#[poem::handler]
+async fn mempool_rejections(
+	Data(mempool_admin): Data<&MempoolAdmin>,
+	Query(query): Query<RejectionsQuery>,
+) -> Json<Vec<Rejection>> {
+	Json(mempool_admin.recent_rejections(query.limit.unwrap_or(100)))
+}
+
+/// Evict a transaction from the mempool.
+#[poem::handler]
+async fn mempool_evict(
+	Data(mempool_admin): Data<&MempoolAdmin>,
+	Path(hash): Path<String>,
+) -> poem::Result<Json<serde_json::Value>> {
+	let hash = HashValue::from_hex(hash.trim_start_matches("0x")).map_err(|e| {
+		poem::Error::from_string(format!("Invalid transaction hash: {e}"), StatusCode::BAD_REQUEST)
+	})?;
+	match mempool_admin.evict(hash) {
+		Eviction::Evicted(transaction) => Ok(Json(json!({
+			"hash": hash,
+			"sender": transaction.sender(),
+			"sequence_number": transaction.sequence_number(),
+		}))),
+		Eviction::Batched(_) => Err(poem::Error::from_string(
+			format!("Transaction {hash} is already sent to the DA"),
+			StatusCode::CONFLICT,
+		)),
+		Eviction::NotFound => Err(poem::Error::from_string(
+			format!("Transaction {hash} is not in the mempool"),
+			StatusCode::NOT_FOUND,
+		)),
 	}
 }
 
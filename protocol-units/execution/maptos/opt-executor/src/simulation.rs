@@ -0,0 +1,252 @@
+//! Simulation of a transaction against the pending state of its sender.
+//!
+//! The transactions of a sender accepted in the mempool stay pending until they are executed,
+//! including while they are batched to the DA. A transaction simulated against the ledger state
+//! alone doesn't see their effects, so its sequence number is rejected and its gas estimate is
+//! wrong. The simulator executes the pending transactions of the sender on top of the latest
+//! state view first, and simulates the transaction against the resulting state.
+
+use crate::background::SenderInFlight;
+use aptos_storage_interface::state_view::{DbStateView, DbStateViewAtVersion};
+use aptos_storage_interface::DbReader;
+use aptos_types::state_store::{
+	state_key::StateKey, state_storage_usage::StateStorageUsage, state_value::StateValue,
+	StateViewResult, TStateView,
+};
+use aptos_types::transaction::{SignedTransaction, TransactionOutput, TransactionStatus};
+use aptos_types::vm_status::VMStatus;
+use aptos_types::write_set::WriteSet;
+use aptos_vm::AptosSimulationVM;
+use aptos_vm_validator::vm_validator::get_account_sequence_number;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tracing::debug;
+
+/// A state view with the writes of simulated transactions on top of a base view.
+pub struct PendingStateView<'a, S> {
+	base: &'a S,
+	writes: HashMap<StateKey, Option<StateValue>>,
+}
+
+impl<'a, S> PendingStateView<'a, S> {
+	pub fn new(base: &'a S) -> Self {
+		PendingStateView { base, writes: HashMap::new() }
+	}
+
+	pub fn apply(&mut self, write_set: &WriteSet) {
+		for (key, op) in write_set.iter() {
+			self.writes.insert(key.clone(), op.clone().as_state_value());
+		}
+	}
+}
+
+impl<'a, S: TStateView<Key = StateKey>> TStateView for PendingStateView<'a, S> {
+	type Key = StateKey;
+
+	fn get_state_value(&self, state_key: &StateKey) -> StateViewResult<Option<StateValue>> {
+		match self.writes.get(state_key) {
+			Some(value) => Ok(value.clone()),
+			None => self.base.get_state_value(state_key),
+		}
+	}
+
+	fn get_usage(&self) -> StateViewResult<StateStorageUsage> {
+		self.base.get_usage()
+	}
+}
+
+/// Result of a simulation.
+#[derive(Debug)]
+pub struct Simulation {
+	pub vm_status: VMStatus,
+	/// The predicted output of the transaction.
+	pub output: TransactionOutput,
+	/// Number of pending transactions of the sender applied before the simulated one.
+	pub pending_transactions: usize,
+}
+
+/// An event emitted by a simulated transaction, its data is BCS encoded.
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulatedEvent {
+	pub type_tag: String,
+	pub data: String,
+}
+
+/// A write of a simulated transaction, its value is BCS encoded and `None` for a deletion.
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulatedWrite {
+	pub state_key: String,
+	pub value: Option<String>,
+}
+
+/// The predicted output of a simulated transaction, as returned by the simulate endpoint.
+/// The BCS encoded values are hex strings.
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulationView {
+	pub success: bool,
+	pub status: TransactionStatus,
+	pub vm_status: String,
+	pub gas_used: u64,
+	pub events: Vec<SimulatedEvent>,
+	pub write_set: Vec<SimulatedWrite>,
+	pub pending_transactions: usize,
+}
+
+impl From<&Simulation> for SimulationView {
+	fn from(simulation: &Simulation) -> Self {
+		let output = &simulation.output;
+		SimulationView {
+			success: matches!(output.status(), TransactionStatus::Keep(status) if status.is_success()),
+			status: output.status().clone(),
+			vm_status: format!("{:?}", simulation.vm_status),
+			gas_used: output.gas_used(),
+			events: output
+				.events()
+				.iter()
+				.map(|event| SimulatedEvent {
+					type_tag: event.type_tag().to_string(),
+					data: format!("0x{}", hex::encode(event.event_data())),
+				})
+				.collect(),
+			write_set: output
+				.write_set()
+				.iter()
+				.map(|(state_key, op)| SimulatedWrite {
+					state_key: format!("{state_key:?}"),
+					value: op
+						.clone()
+						.as_state_value()
+						.map(|value| format!("0x{}", hex::encode(value.bytes()))),
+				})
+				.collect(),
+			pending_transactions: simulation.pending_transactions,
+		}
+	}
+}
+
+/// Simulates transactions against the latest state and the pending transactions of the sender.
+#[derive(Clone)]
+pub struct Simulator {
+	db_reader: Arc<dyn DbReader>,
+	sender_in_flight: Arc<RwLock<SenderInFlight>>,
+}
+
+impl Simulator {
+	pub fn new(
+		db_reader: Arc<dyn DbReader>,
+		sender_in_flight: Arc<RwLock<SenderInFlight>>,
+	) -> Self {
+		Simulator { db_reader, sender_in_flight }
+	}
+
+	fn latest_state_view(&self) -> Result<DbStateView, anyhow::Error> {
+		let version = self.db_reader.get_latest_ledger_info_version()?;
+		Ok(self.db_reader.state_view_at_version(Some(version))?)
+	}
+
+	/// Simulate the transaction. The signature is not checked.
+	pub fn simulate(&self, transaction: &SignedTransaction) -> Result<Simulation, anyhow::Error> {
+		let base = self.latest_state_view()?;
+		let sender = transaction.sender();
+		let ledger_sequence_number = get_account_sequence_number(&base, sender)?;
+
+		let mut state_view = PendingStateView::new(&base);
+		let mut pending_transactions = 0;
+		let pending_of_sender = self.sender_in_flight.read().unwrap().of_sender(&sender);
+		for pending in pending_of_sender {
+			// Already executed transactions may not be removed yet, and the simulated
+			// transaction can replace a pending one.
+			if pending.sequence_number() < ledger_sequence_number
+				|| pending.sequence_number() >= transaction.sequence_number()
+			{
+				continue;
+			}
+			let (vm_status, output) =
+				AptosSimulationVM::create_vm_and_simulate_signed_transaction(&pending, &state_view);
+			match output.status() {
+				TransactionStatus::Keep(_) => {
+					state_view.apply(output.write_set());
+					pending_transactions += 1;
+				}
+				// The next transactions of the sender can't be executed after a gap.
+				_ => {
+					debug!(
+						sender = %sender,
+						sequence_number = pending.sequence_number(),
+						"Pending transaction not executable in simulation: {vm_status:?}"
+					);
+					break;
+				}
+			}
+		}
+
+		let (vm_status, output) =
+			AptosSimulationVM::create_vm_and_simulate_signed_transaction(transaction, &state_view);
+		Ok(Simulation { vm_status, output, pending_transactions })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::executor::TxExecutionResult;
+	use crate::Executor;
+	use aptos_sdk::transaction_builder::TransactionFactory;
+	use aptos_sdk::types::{account_config::aptos_test_root_address, AccountKey, LocalAccount};
+	use aptos_vm_genesis::GENESIS_KEYPAIR;
+	use rand::SeedableRng;
+	use tokio::sync::mpsc::unbounded_channel;
+
+	fn is_success(simulation: &Simulation) -> bool {
+		matches!(simulation.output.status(), TransactionStatus::Keep(status) if status.is_success())
+	}
+
+	#[tokio::test]
+	async fn test_simulate_after_pending_transaction_of_sender() -> Result<(), anyhow::Error> {
+		let (mempool_tx_exec_result_sender, _mempool_commit_tx_receiver) =
+			unbounded_channel::<Vec<TxExecutionResult>>();
+		let (executor, _tempdir) =
+			Executor::try_test_default(GENESIS_KEYPAIR.0.clone(), mempool_tx_exec_result_sender)
+				.await?;
+		let sender_in_flight = Arc::new(RwLock::new(SenderInFlight::default()));
+		let simulator = Simulator::new(executor.db_reader(), sender_in_flight.clone());
+
+		let root_account = LocalAccount::new(
+			aptos_test_root_address(),
+			AccountKey::from_private_key(GENESIS_KEYPAIR.0.clone()),
+			0,
+		);
+		let tx_factory = TransactionFactory::new(executor.config.chain.maptos_chain_id.clone());
+		let mut rng = ::rand::rngs::StdRng::from_seed([3u8; 32]);
+		let new_account = LocalAccount::generate(&mut rng);
+		// The mint needs the account created by the pending transaction.
+		let create_account = root_account.sign_with_transaction_builder(
+			tx_factory.create_user_account(new_account.public_key()),
+		);
+		let mint = root_account
+			.sign_with_transaction_builder(tx_factory.mint(new_account.address(), 2000));
+
+		// Against the ledger state alone, the sequence number of the mint is too new.
+		let simulation = simulator.simulate(&mint)?;
+		assert!(!is_success(&simulation));
+		assert_eq!(simulation.pending_transactions, 0);
+
+		sender_in_flight.write().unwrap().add(&create_account, 0);
+		let simulation = simulator.simulate(&mint)?;
+		assert!(is_success(&simulation), "{:?}", simulation.vm_status);
+		assert_eq!(simulation.pending_transactions, 1);
+		let view = SimulationView::from(&simulation);
+		assert!(view.success);
+		assert_eq!(view.gas_used, simulation.output.gas_used());
+		assert_eq!(view.events.len(), simulation.output.events().len());
+		assert!(!view.write_set.is_empty());
+
+		// The simulated transaction replaces a pending one with the same sequence number.
+		let simulation = simulator.simulate(&create_account)?;
+		assert!(is_success(&simulation), "{:?}", simulation.vm_status);
+		assert_eq!(simulation.pending_transactions, 0);
+
+		Ok(())
+	}
+}
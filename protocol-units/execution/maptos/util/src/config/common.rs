@@ -94,6 +94,10 @@ env_default!(
 	false
 );
 
+pub fn default_da_sequencer_block_verifying_key() -> Option<String> {
+	std::env::var("MAPTOS_DA_SEQUENCER_BLOCK_VERIFYING_KEY").ok()
+}
+
 pub fn default_batch_signer_identifier() -> SignerIdentifier {
 	match std::env::var("MAPTOS_DA_SEQUENCER_SIGNER_IDENTIFIER") {
 		Ok(val) => SignerIdentifier::try_from_canonical_string(&val).unwrap(),
@@ -274,8 +278,23 @@ env_default!(default_metrics_server_port, "METRICS_SERVER_PORT", u16, 18186);
 
 env_default!(default_batch_production_time, "MAPTOS_BATCH_PRODUCTION_TIME_MS", u64, 2000);
 
+env_default!(default_execution_concurrency_level, "MAPTOS_EXECUTION_CONCURRENCY_LEVEL", u16, 0);
+
 env_default!(default_max_transactions_in_flight, "MAPTOS_MAX_TRANSACTIONS_IN_FLIGHT", u64);
 
+env_default!(default_max_in_flight_per_ip, "MAPTOS_MAX_TRANSACTIONS_IN_FLIGHT_PER_IP", u64);
+
+env_default!(default_ip_in_flight_ttl_ms, "MAPTOS_IP_IN_FLIGHT_TTL_MS", u64, 1000 * 10);
+
+env_default!(default_max_concurrent_simulations, "MAPTOS_MAX_CONCURRENT_SIMULATIONS", usize, 8);
+
+env_default!(
+	default_fair_admission_threshold_percent,
+	"MAPTOS_FAIR_ADMISSION_THRESHOLD_PERCENT",
+	u64,
+	50
+);
+
 env_default!(default_sequence_number_ttl_ms, "MAPTOS_SEQUENCE_NUMBER_TTL_MS", u64, 1000 * 60 * 3);
 
 env_default!(default_gc_slot_duration_ms, "MAPTOS_GC_SLOT_DURATION_MS", u64, 1000 * 2);
@@ -290,3 +309,20 @@ env_default!(
 	u64,
 	1024 * 1024 //1Mo
 );
+
+env_default!(
+	default_outbox_retry_initial_delay_ms,
+	"MAPTOS_OUTBOX_RETRY_INITIAL_DELAY_MS",
+	u64,
+	500
+);
+
+env_default!(default_outbox_retry_max_delay_ms, "MAPTOS_OUTBOX_RETRY_MAX_DELAY_MS", u64, 30_000);
+
+env_default!(default_outbox_max_send_attempts, "MAPTOS_OUTBOX_MAX_SEND_ATTEMPTS", u32, 20);
+
+env_default!(default_outbox_max_batches_in_flight, "MAPTOS_OUTBOX_MAX_BATCHES_IN_FLIGHT", u64, 4);
+
+env_default!(default_rejection_log_size, "MAPTOS_MEMPOOL_REJECTION_LOG_SIZE", usize, 1000);
+
+env_default!(default_admin_api_listen_port, "MAPTOS_MEMPOOL_ADMIN_API_LISTEN_PORT", u16, 30737);
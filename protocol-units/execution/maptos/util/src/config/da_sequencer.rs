@@ -1,3 +1,4 @@
+use crate::config::common::default_da_sequencer_block_verifying_key;
 use crate::config::common::default_propagate_execution_state;
 use crate::config::common::default_stream_heartbeat_interval_sec;
 use crate::config::common::{default_batch_signer_identifier, default_da_sequencer_connection_url};
@@ -21,6 +22,11 @@ pub struct Config {
 
 	#[serde(default = "default_propagate_execution_state")]
 	pub propagate_execution_state: bool,
+
+	/// Hex encoded Ed25519 public key of the DA Sequencer block signer.
+	/// If set, streamed blocks must be signed with it and chained to their parent.
+	#[serde(default = "default_da_sequencer_block_verifying_key")]
+	pub block_verifying_key: Option<String>,
 }
 
 impl Default for Config {
@@ -30,6 +36,7 @@ impl Default for Config {
 			batch_signer_identifier: default_batch_signer_identifier(),
 			stream_heartbeat_interval_sec: default_stream_heartbeat_interval_sec(),
 			propagate_execution_state: default_propagate_execution_state(),
+			block_verifying_key: default_da_sequencer_block_verifying_key(),
 		}
 	}
 }
@@ -0,0 +1,33 @@
+//! Configuration of the block execution.
+//!
+//! Only the settings local to a node belong here: the output of the execution doesn't depend on
+//! them. Settings changing the executed transactions would make the nodes compute different
+//! state roots, so the block gas limit is read from the on-chain execution config instead.
+
+use super::common::default_execution_concurrency_level;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Config {
+	/// Number of threads of the parallel (BlockSTM) execution of a block.
+	/// 0 uses the number of available CPUs, 1 executes the transactions sequentially.
+	#[serde(default = "default_execution_concurrency_level")]
+	pub concurrency_level: u16,
+}
+
+impl Config {
+	/// The concurrency level, resolving 0 to the number of available CPUs.
+	pub fn concurrency_level(&self) -> usize {
+		match self.concurrency_level {
+			0 => std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+			level => level as usize,
+		}
+	}
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Self { concurrency_level: default_execution_concurrency_level() }
+	}
+}
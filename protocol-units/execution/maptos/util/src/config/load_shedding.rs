@@ -1,8 +1,15 @@
 //! Configuration for load-shedding limits.
 
-use super::common::{default_batch_production_time, default_max_transactions_in_flight};
+use super::common::{
+	default_batch_production_time, default_fair_admission_threshold_percent,
+	default_ip_in_flight_ttl_ms, default_max_concurrent_simulations, default_max_in_flight_per_ip,
+	default_max_transactions_in_flight,
+};
 
+use aptos_types::account_address::AccountAddress;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Config {
@@ -13,6 +20,26 @@ pub struct Config {
 	/// Time between 2 batch production.
 	#[serde(default = "default_batch_production_time")]
 	pub batch_production_time: u64,
+	/// The maximum number of transactions submitted from one IP address permitted to be in flight.
+	#[serde(default = "default_max_in_flight_per_ip")]
+	pub max_transactions_in_flight_per_ip: Option<u64>,
+	/// How long a transaction submitted from an IP address is counted in flight for this IP.
+	#[serde(default = "default_ip_in_flight_ttl_ms")]
+	pub ip_in_flight_ttl_ms: u64,
+	/// The maximum number of transaction simulations run at the same time.
+	#[serde(default = "default_max_concurrent_simulations")]
+	pub max_concurrent_simulations: usize,
+	/// Percentage of `max_transactions_in_flight` above which the senders are limited
+	/// to their weighted share of the in flight transactions.
+	#[serde(default = "default_fair_admission_threshold_percent")]
+	pub fair_admission_threshold_percent: u64,
+	/// Weight of the accounts in the fair admission. Accounts not listed have a weight of 1.
+	#[serde(default)]
+	pub account_weights: BTreeMap<AccountAddress, u64>,
+	/// Addresses of the reverse proxies in front of the API. The IP quota reads the client
+	/// address from the `Forwarded` or `X-Forwarded-For` header only for requests from them.
+	#[serde(default)]
+	pub trusted_proxies: Vec<IpAddr>,
 }
 
 impl Default for Config {
@@ -20,6 +47,12 @@ impl Default for Config {
 		Self {
 			max_transactions_in_flight: default_max_transactions_in_flight(),
 			batch_production_time: default_batch_production_time(),
+			max_transactions_in_flight_per_ip: default_max_in_flight_per_ip(),
+			ip_in_flight_ttl_ms: default_ip_in_flight_ttl_ms(),
+			max_concurrent_simulations: default_max_concurrent_simulations(),
+			fair_admission_threshold_percent: default_fair_admission_threshold_percent(),
+			account_weights: BTreeMap::new(),
+			trusted_proxies: Vec::new(),
 		}
 	}
 }
@@ -1,6 +1,12 @@
 use super::common::{default_gc_slot_duration_ms, default_sequence_number_ttl_ms};
-use crate::config::common::{default_max_batch_size, default_max_tx_per_batch};
+use crate::config::common::{
+	default_admin_api_listen_port, default_max_batch_size, default_max_tx_per_batch,
+	default_outbox_max_batches_in_flight, default_outbox_max_send_attempts,
+	default_outbox_retry_initial_delay_ms, default_outbox_retry_max_delay_ms,
+	default_rejection_log_size,
+};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Config {
@@ -19,6 +25,43 @@ pub struct Config {
 	/// Max batch size in bytes.
 	#[serde(default = "default_max_batch_size")]
 	pub max_batch_size: u64,
+
+	/// The path of the outbox holding the batches not acknowledged by the DA Sequencer.
+	/// Defaults to the `mempool-outbox` directory in the Aptos database path.
+	#[serde(default)]
+	pub outbox_db_path: Option<PathBuf>,
+
+	/// Delay before sending again a batch that failed the first time, in milliseconds.
+	/// The delay doubles at each new failure.
+	#[serde(default = "default_outbox_retry_initial_delay_ms")]
+	pub outbox_retry_initial_delay_ms: u64,
+
+	/// Maximum delay between two sendings of a failed batch, in milliseconds.
+	#[serde(default = "default_outbox_retry_max_delay_ms")]
+	pub outbox_retry_max_delay_ms: u64,
+
+	/// Number of sendings of a batch failing with a transient error before the batch is dropped.
+	#[serde(default = "default_outbox_max_send_attempts")]
+	pub outbox_max_send_attempts: u32,
+
+	/// Number of batches sent to the DA Sequencer and not acknowledged yet.
+	/// A batch is sent only if its outbox sequence number is within this window from the
+	/// oldest unacknowledged batch, so 1 keeps the batches in strict order.
+	#[serde(default = "default_outbox_max_batches_in_flight")]
+	pub outbox_max_batches_in_flight: u64,
+
+	/// Number of the last rejected transactions kept for the mempool admin API.
+	#[serde(default = "default_rejection_log_size")]
+	pub rejection_log_size: usize,
+
+	/// Serve the mempool admin API under `/movement/v1/admin/mempool`.
+	/// It allows to evict transactions, so it is disabled by default.
+	#[serde(default)]
+	pub admin_api_enabled: bool,
+
+	/// Port of the mempool admin API. It has its own listener, bound to localhost only.
+	#[serde(default = "default_admin_api_listen_port")]
+	pub admin_api_listen_port: u16,
 }
 
 impl Default for Config {
@@ -28,6 +71,14 @@ impl Default for Config {
 			gc_slot_duration_ms: default_gc_slot_duration_ms(),
 			max_tx_per_batch: default_max_tx_per_batch(),
 			max_batch_size: default_max_batch_size(),
+			outbox_db_path: None,
+			outbox_retry_initial_delay_ms: default_outbox_retry_initial_delay_ms(),
+			outbox_retry_max_delay_ms: default_outbox_retry_max_delay_ms(),
+			outbox_max_send_attempts: default_outbox_max_send_attempts(),
+			outbox_max_batches_in_flight: default_outbox_max_batches_in_flight(),
+			rejection_log_size: default_rejection_log_size(),
+			admin_api_enabled: false,
+			admin_api_listen_port: default_admin_api_listen_port(),
 		}
 	}
 }
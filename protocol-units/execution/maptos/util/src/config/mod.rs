@@ -2,6 +2,7 @@ pub mod chain;
 pub mod client;
 pub mod common;
 pub mod da_sequencer;
+pub mod execution;
 pub mod faucet;
 pub mod fin;
 pub mod health_server;
@@ -44,6 +45,10 @@ pub struct Config {
 	#[serde(default)]
 	pub fin: fin::Config,
 
+	/// The block execution parameters
+	#[serde(default)]
+	pub execution: execution::Config,
+
 	/// The load shedding parameters
 	#[serde(default)]
 	pub load_shedding: load_shedding::Config,
@@ -71,6 +76,7 @@ impl Default for Config {
 			client: client::Config::default(),
 			faucet: faucet::Config::default(),
 			fin: fin::Config::default(),
+			execution: execution::Config::default(),
 			load_shedding: load_shedding::Config::default(),
 			mempool: mempool::Config::default(),
 			access_control: aptos_account_whitelist::config::Config::default(),
@@ -1,31 +1,84 @@
+//! RocksDB mempool.
+//!
+//! The transactions are popped by lane, then by application priority, slot and sequence number.
+//! The transactions whose data is a signed Aptos transaction have per-sender semantics:
+//! - the senders of the priority lane, e.g. governance and admin accounts, are popped before
+//!   all the others;
+//! - a pending transaction is replaced by a transaction with the same sender and sequence number
+//!   of a strictly higher priority, i.e. a higher fee; a lower or equal one is dropped;
+//! - only the transaction of a sender with the lowest sequence number is ready to be popped,
+//!   the following ones are held and the next one becomes ready when it is popped or removed;
+//! - a transaction with a sequence number above the next one expected for its sender is held
+//!   until the gap fills.
+//!
+//! The transactions stored by the versions without lanes are moved to the default lane when
+//! the mempool is opened.
+
 use anyhow::Error;
 use bcs;
-use mempool_util::{MempoolBlockOperations, MempoolTransaction, MempoolTransactionOperations};
+use mempool_util::{
+	AccountAddress, MempoolBlockOperations, MempoolChainOperations, MempoolTransaction,
+	MempoolTransactionOperations,
+};
 use movement_types::{
 	block::{self, Block},
 	transaction,
 };
-use rocksdb::{ColumnFamilyDescriptor, IteratorMode, Options, ReadOptions, WriteBatch, DB};
-use std::fmt::Write;
-use std::sync::Arc;
+use rocksdb::{
+	BoundColumnFamily, ColumnFamilyDescriptor, DBIteratorWithThreadMode, Direction, IteratorMode,
+	Options, WriteBatch, DB,
+};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::{self, Write};
+use std::iter::Peekable;
+use std::sync::{Arc, Mutex};
 
 mod cf {
 	pub const MEMPOOL_TRANSACTIONS: &str = "mempool_transactions";
 	pub const BLOCKS: &str = "blocks";
 	pub const TRANSACTION_LOOKUPS: &str = "transaction_lookups";
+	/// Keys of the ready transactions by sender and sequence number, one per sender.
+	pub const SENDER_TRANSACTIONS: &str = "sender_transactions";
+	/// Transactions held by sender and sequence number, until the ones before are popped.
+	pub const HELD_TRANSACTIONS: &str = "held_transactions";
+	/// Next sequence number expected for each sender, and the slot of its last transaction.
+	pub const SENDER_NEXT_SEQUENCE_NUMBERS: &str = "sender_next_sequence_numbers";
+	/// Headers of the built blocks by height: their id and the id of their parent.
+	/// The blocks themselves are not kept.
+	pub const BLOCK_HEIGHTS: &str = "block_heights";
+	/// The chain tip.
+	pub const CHAIN: &str = "chain";
 }
 
+/// Key of the chain tip in the chain column family.
+const CHAIN_TIP_KEY: &[u8] = b"tip";
+
+/// Prefix of the lookup entries of the held transactions.
+const HELD_LOOKUP_PREFIX: &[u8] = b"held:";
+
+/// Lanes of the transactions, in pop order.
+const PRIORITY_LANE: u8 = 0;
+const DEFAULT_LANE: u8 = 1;
+
 #[derive(Debug, Clone)]
 pub struct RocksdbMempool {
 	db: Arc<DB>,
+	/// Senders of the priority lane.
+	priority_lane_senders: Arc<HashSet<AccountAddress>>,
+	/// Serializes the writes reading the per-sender state.
+	write_lock: Arc<Mutex<()>>,
 }
 
-fn construct_mempool_transaction_key(transaction: &MempoolTransaction) -> Result<String, Error> {
+fn construct_mempool_transaction_key(
+	transaction: &MempoolTransaction,
+	lane: u8,
+) -> Result<String, Error> {
 	// Pre-allocate a string with the required capacity
-	let mut key = String::with_capacity(32 + 1 + 32 + 1 + 32 + 1 + 32);
+	let mut key = String::with_capacity(1 + 1 + 32 + 1 + 32 + 1 + 32 + 1 + 32);
 	// Write key components. The numbers are zero-padded to 32 characters.
 	key.write_fmt(format_args!(
-		"{:032}:{:032}:{:032}:{}",
+		"{}:{:032}:{:032}:{:032}:{}",
+		lane,
 		transaction.transaction.application_priority(),
 		transaction.timestamp,
 		transaction.transaction.sequence_number(),
@@ -35,13 +88,133 @@ fn construct_mempool_transaction_key(transaction: &MempoolTransaction) -> Result
 	Ok(key)
 }
 
-fn construct_timestamp_threshold_key(timestamp_threshold: u64) -> Result<String, Error> {
-	// FIXME: this is wrong since the application priority buckets were introduced
-	// in construct_mempool_transaction_key above.
-	let mut key = String::with_capacity(32 + 1);
-	key.write_fmt(format_args!("{:032}:", timestamp_threshold))
-		.map_err(|_| Error::msg("Error writing timestamp threshold key"))?;
-	Ok(key)
+/// Whether the key of the mempool column family starts with a lane.
+fn has_lane(key: &[u8]) -> bool {
+	key.get(1) == Some(&b':')
+}
+
+fn construct_sender_key(sender: &AccountAddress, sequence_number: u64) -> Vec<u8> {
+	let mut key = sender.to_vec();
+	key.extend_from_slice(&sequence_number.to_be_bytes());
+	key
+}
+
+/// The first entry of the sender, with its sequence number, in a column family keyed by sender
+/// and sequence number.
+fn first_sender_entry(
+	db: &DB,
+	name: &str,
+	sender: &AccountAddress,
+) -> Result<Option<(u64, Box<[u8]>)>, Error> {
+	let prefix = sender.to_vec();
+	let cf_handle = cf_handle(db, name)?;
+	let Some(res) = db
+		.iterator_cf(&cf_handle, IteratorMode::From(&prefix, Direction::Forward))
+		.next()
+	else {
+		return Ok(None);
+	};
+	let (key, value) = res?;
+	let Some(sequence_number) = key.strip_prefix(prefix.as_slice()) else {
+		return Ok(None);
+	};
+	Ok(Some((u64::from_be_bytes(sequence_number.try_into()?), value)))
+}
+
+fn cf_handle<'a>(db: &'a DB, name: &str) -> Result<Arc<BoundColumnFamily<'a>>, Error> {
+	db.cf_handle(name).ok_or_else(|| Error::msg("CF handle not found"))
+}
+
+/// Result of the verification of the persisted block chain.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChainReport {
+	/// Id and height of the chain tip.
+	pub tip: Option<(block::Id, u64)>,
+	/// Number of blocks on the chain.
+	pub blocks: u64,
+	/// Heights without a block, below the tip.
+	pub gaps: Vec<u64>,
+	/// Heights of the blocks whose parent is not the block at the previous height.
+	pub forks: Vec<u64>,
+	/// The tip is not the block at the highest height.
+	pub tip_mismatch: bool,
+}
+
+impl ChainReport {
+	pub fn is_consistent(&self) -> bool {
+		self.gaps.is_empty() && self.forks.is_empty() && !self.tip_mismatch
+	}
+}
+
+impl fmt::Display for ChainReport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.tip {
+			Some((id, height)) => writeln!(f, "tip: {id} at height {height}")?,
+			None => writeln!(f, "tip: none")?,
+		}
+		writeln!(f, "blocks: {}", self.blocks)?;
+		writeln!(f, "gaps at heights: {:?}", self.gaps)?;
+		writeln!(f, "forks at heights: {:?}", self.forks)?;
+		write!(f, "tip mismatch: {}", self.tip_mismatch)
+	}
+}
+
+/// Where a transaction is stored.
+enum Location {
+	Ready(Vec<u8>),
+	Held(Vec<u8>),
+}
+
+/// Reads the transactions in pop order, each one taken followed by the next transaction of its
+/// sender, as if it were popped.
+struct PopOrder<'a> {
+	mempool: &'a RocksdbMempool,
+	ready: Peekable<DBIteratorWithThreadMode<'a, DB>>,
+	/// The held transactions following the ones taken, by their key in the ready index.
+	successors: BTreeMap<Vec<u8>, MempoolTransaction>,
+}
+
+impl<'a> PopOrder<'a> {
+	fn new(mempool: &'a RocksdbMempool) -> Result<Self, Error> {
+		let mempool_cf_handle = cf_handle(&mempool.db, cf::MEMPOOL_TRANSACTIONS)?;
+		let ready = mempool.db.iterator_cf(&mempool_cf_handle, IteratorMode::Start).peekable();
+		Ok(PopOrder { mempool, ready, successors: BTreeMap::new() })
+	}
+
+	/// The next transaction, with its key if it is in the ready index.
+	fn next(&mut self) -> Result<Option<(MempoolTransaction, Option<Vec<u8>>)>, Error> {
+		let from_ready = match (self.ready.peek(), self.successors.first_key_value()) {
+			(Some(Ok((key, _))), Some((successor_key, _))) => {
+				key.as_ref() <= successor_key.as_slice()
+			}
+			(Some(_), _) => true,
+			(None, Some(_)) => false,
+			(None, None) => return Ok(None),
+		};
+		if from_ready {
+			let (key, value) = self.ready.next().expect("ready transaction peeked")?;
+			Ok(Some((bcs::from_bytes(&value)?, Some(key.into_vec()))))
+		} else {
+			let (_, transaction) = self.successors.pop_first().expect("successor exists");
+			Ok(Some((transaction, None)))
+		}
+	}
+
+	/// Takes the transaction, the next transaction of its sender follows it.
+	fn take(&mut self, transaction: &MempoolTransaction) -> Result<(), Error> {
+		let Some(sender) = transaction.sender() else {
+			return Ok(());
+		};
+		let sequence_number = transaction.transaction.sequence_number();
+		if let Some(successor) =
+			RocksdbMempool::successor(&self.mempool.db, &sender, sequence_number, &HashSet::new())?
+		{
+			let key =
+				construct_mempool_transaction_key(&successor, self.mempool.lane(Some(&sender)))?;
+			self.successors.insert(key.into_bytes(), successor);
+		}
+		Ok(())
+	}
 }
 
 impl RocksdbMempool {
@@ -50,59 +223,333 @@ impl RocksdbMempool {
 		options.create_if_missing(true);
 		options.create_missing_column_families(true);
 
-		let mempool_transactions_cf =
-			ColumnFamilyDescriptor::new(cf::MEMPOOL_TRANSACTIONS, Options::default());
-		let blocks_cf = ColumnFamilyDescriptor::new(cf::BLOCKS, Options::default());
-		let transaction_lookups_cf =
-			ColumnFamilyDescriptor::new(cf::TRANSACTION_LOOKUPS, Options::default());
+		let column_families = [
+			cf::MEMPOOL_TRANSACTIONS,
+			cf::BLOCKS,
+			cf::TRANSACTION_LOOKUPS,
+			cf::SENDER_TRANSACTIONS,
+			cf::HELD_TRANSACTIONS,
+			cf::SENDER_NEXT_SEQUENCE_NUMBERS,
+			cf::BLOCK_HEIGHTS,
+			cf::CHAIN,
+		]
+		.map(|name| ColumnFamilyDescriptor::new(name, Options::default()));
+
+		let db =
+			DB::open_cf_descriptors(&options, path, column_families).map_err(|e| Error::new(e))?;
+
+		let mempool = RocksdbMempool {
+			db: Arc::new(db),
+			priority_lane_senders: Arc::new(HashSet::new()),
+			write_lock: Arc::new(Mutex::new(())),
+		};
+		mempool.migrate_keys_without_lane()?;
+		Ok(mempool)
+	}
+
+	/// Adds again the transactions stored with a key without lane, which would sort before the
+	/// priority lane, so they get a lane and the per-sender rules. Their keys start with the
+	/// zero-padded priority, so they come first.
+	fn migrate_keys_without_lane(&self) -> Result<(), Error> {
+		let db = &self.db;
+		let mempool_cf_handle = cf_handle(db, cf::MEMPOOL_TRANSACTIONS)?;
+		let mut transactions = vec![];
+		for res in db.iterator_cf(&mempool_cf_handle, IteratorMode::Start) {
+			let (key, value) = res?;
+			if has_lane(&key) {
+				break;
+			}
+			transactions.push((key, bcs::from_bytes::<MempoolTransaction>(&value)?));
+		}
+		let lookups_cf_handle = cf_handle(db, cf::TRANSACTION_LOOKUPS)?;
+		for (key, transaction) in transactions {
+			let mut batch = WriteBatch::default();
+			batch.delete_cf(&mempool_cf_handle, &key);
+			batch.delete_cf(&lookups_cf_handle, transaction.id().to_vec());
+			self.internal_add_mempool_transaction(&mut batch, transaction)?;
+			db.write(batch)?;
+		}
+		Ok(())
+	}
 
-		let db = DB::open_cf_descriptors(
-			&options,
-			path,
-			[mempool_transactions_cf, blocks_cf, transaction_lookups_cf],
-		)
-		.map_err(|e| Error::new(e))?;
+	/// Sets the senders of the priority lane.
+	pub fn with_priority_lane_senders(
+		mut self,
+		senders: impl IntoIterator<Item = AccountAddress>,
+	) -> Self {
+		self.priority_lane_senders = Arc::new(senders.into_iter().collect());
+		self
+	}
 
-		Ok(RocksdbMempool { db: Arc::new(db) })
+	fn lane(&self, sender: Option<&AccountAddress>) -> u8 {
+		match sender {
+			Some(sender) if self.priority_lane_senders.contains(sender) => PRIORITY_LANE,
+			_ => DEFAULT_LANE,
+		}
 	}
 
-	fn internal_get_mempool_transaction_key(
+	fn internal_get_location(
 		db: &DB,
 		transaction_id: transaction::Id,
-	) -> Result<Option<Vec<u8>>, Error> {
-		let cf_handle = db
-			.cf_handle(cf::TRANSACTION_LOOKUPS)
-			.ok_or_else(|| Error::msg("CF handle not found"))?;
-		db.get_cf(&cf_handle, transaction_id.to_vec()).map_err(|e| Error::new(e))
+	) -> Result<Option<Location>, Error> {
+		let cf_handle = cf_handle(db, cf::TRANSACTION_LOOKUPS)?;
+		let key = db.get_cf(&cf_handle, transaction_id.to_vec()).map_err(|e| Error::new(e))?;
+		Ok(key.map(|key| match key.strip_prefix(HELD_LOOKUP_PREFIX) {
+			Some(held_key) => Location::Held(held_key.to_vec()),
+			None => Location::Ready(key),
+		}))
 	}
 
-	/// Helper function to retrieve the key for mempool transaction from the lookup table.
-	async fn get_mempool_transaction_key(
-		&self,
+	fn internal_get_mempool_transaction(
+		db: &DB,
 		transaction_id: transaction::Id,
-	) -> Result<Option<Vec<u8>>, Error> {
-		let db = self.db.clone();
-		let transaction_id = transaction_id.clone();
-		tokio::task::spawn_blocking(move || {
-			Self::internal_get_mempool_transaction_key(&db, transaction_id)
-		})
-		.await?
+	) -> Result<Option<MempoolTransaction>, Error> {
+		let value = match Self::internal_get_location(db, transaction_id)? {
+			Some(Location::Ready(key)) => {
+				db.get_cf(&cf_handle(db, cf::MEMPOOL_TRANSACTIONS)?, key)?
+			}
+			Some(Location::Held(key)) => db.get_cf(&cf_handle(db, cf::HELD_TRANSACTIONS)?, key)?,
+			None => None,
+		};
+		value.map(|value| bcs::from_bytes(&value).map_err(Error::from)).transpose()
 	}
 
 	fn internal_has_mempool_transaction(
 		db: &DB,
 		transaction_id: transaction::Id,
 	) -> Result<bool, Error> {
-		let key = Self::internal_get_mempool_transaction_key(&db, transaction_id)?;
-		match key {
-			Some(k) => {
-				let cf_handle = db
-					.cf_handle(cf::MEMPOOL_TRANSACTIONS)
-					.ok_or_else(|| Error::msg("CF handle not found"))?;
-				Ok(db.get_cf(&cf_handle, k)?.is_some())
+		Ok(Self::internal_get_mempool_transaction(db, transaction_id)?.is_some())
+	}
+
+	/// Writes a transaction ready to be popped.
+	fn put_ready(
+		&self,
+		batch: &mut WriteBatch,
+		transaction: &MempoolTransaction,
+		sender: Option<&AccountAddress>,
+	) -> Result<(), Error> {
+		let db = &self.db;
+		let key = construct_mempool_transaction_key(transaction, self.lane(sender))?;
+		batch.put_cf(&cf_handle(db, cf::MEMPOOL_TRANSACTIONS)?, &key, bcs::to_bytes(transaction)?);
+		batch.put_cf(
+			&cf_handle(db, cf::TRANSACTION_LOOKUPS)?,
+			transaction.transaction.id().to_vec(),
+			&key,
+		);
+		if let Some(sender) = sender {
+			batch.put_cf(
+				&cf_handle(db, cf::SENDER_TRANSACTIONS)?,
+				construct_sender_key(sender, transaction.transaction.sequence_number()),
+				&key,
+			);
+		}
+		Ok(())
+	}
+
+	/// Writes a transaction held until the sequence number gap of its sender fills.
+	fn put_held(
+		&self,
+		batch: &mut WriteBatch,
+		transaction: &MempoolTransaction,
+		sender: &AccountAddress,
+	) -> Result<(), Error> {
+		let db = &self.db;
+		let key = construct_sender_key(sender, transaction.transaction.sequence_number());
+		batch.put_cf(&cf_handle(db, cf::HELD_TRANSACTIONS)?, &key, bcs::to_bytes(transaction)?);
+		let mut lookup = HELD_LOOKUP_PREFIX.to_vec();
+		lookup.extend_from_slice(&key);
+		batch.put_cf(
+			&cf_handle(db, cf::TRANSACTION_LOOKUPS)?,
+			transaction.transaction.id().to_vec(),
+			lookup,
+		);
+		Ok(())
+	}
+
+	fn next_sequence_number(db: &DB, sender: &AccountAddress) -> Result<Option<u64>, Error> {
+		let value =
+			db.get_cf(&cf_handle(db, cf::SENDER_NEXT_SEQUENCE_NUMBERS)?, sender.to_vec())?;
+		value
+			.map(|value| {
+				let bytes = value.get(..8).ok_or_else(|| Error::msg("Invalid sequence number"))?;
+				Ok(u64::from_be_bytes(bytes.try_into()?))
+			})
+			.transpose()
+	}
+
+	/// The next held transaction of the sender after the sequence number, if there is no gap
+	/// before it. The transactions in `removed` are skipped, they are removed with the write.
+	fn successor(
+		db: &DB,
+		sender: &AccountAddress,
+		sequence_number: u64,
+		removed: &HashSet<transaction::Id>,
+	) -> Result<Option<MempoolTransaction>, Error> {
+		let Some(next_sequence_number) = Self::next_sequence_number(db, sender)? else {
+			return Ok(None);
+		};
+		let held_cf_handle = cf_handle(db, cf::HELD_TRANSACTIONS)?;
+		for sequence_number in sequence_number + 1..next_sequence_number {
+			let held_key = construct_sender_key(sender, sequence_number);
+			let Some(value) = db.get_cf(&held_cf_handle, &held_key)? else {
+				return Ok(None);
+			};
+			let held: MempoolTransaction = bcs::from_bytes(&value)?;
+			if !removed.contains(&held.id()) {
+				return Ok(Some(held));
+			}
+		}
+		Ok(None)
+	}
+
+	/// Moves a held transaction to the ready index.
+	fn promote(
+		&self,
+		batch: &mut WriteBatch,
+		transaction: &MempoolTransaction,
+	) -> Result<(), Error> {
+		let sender = transaction
+			.sender()
+			.ok_or_else(|| Error::msg("Held transaction without sender"))?;
+		batch.delete_cf(
+			&cf_handle(&self.db, cf::HELD_TRANSACTIONS)?,
+			construct_sender_key(&sender, transaction.transaction.sequence_number()),
+		);
+		self.put_ready(batch, transaction, Some(&sender))
+	}
+
+	/// Adds a transaction to the batch, applying the replacement, the nonce order and the lane
+	/// rules. Must be called with the write lock.
+	fn internal_add_mempool_transaction(
+		&self,
+		batch: &mut WriteBatch,
+		transaction: MempoolTransaction,
+	) -> Result<(), Error> {
+		let db = &self.db;
+		let Some(sender) = transaction.sender() else {
+			return self.put_ready(batch, &transaction, None);
+		};
+		let sequence_number = transaction.transaction.sequence_number();
+		let sender_key = construct_sender_key(&sender, sequence_number);
+		let lookups_cf_handle = cf_handle(db, cf::TRANSACTION_LOOKUPS)?;
+
+		// Replacement of a ready transaction.
+		if let Some(key) = db.get_cf(&cf_handle(db, cf::SENDER_TRANSACTIONS)?, &sender_key)? {
+			let mempool_cf_handle = cf_handle(db, cf::MEMPOOL_TRANSACTIONS)?;
+			if let Some(value) = db.get_cf(&mempool_cf_handle, &key)? {
+				let pending: MempoolTransaction = bcs::from_bytes(&value)?;
+				if !transaction.replaces(&pending) {
+					return Ok(());
+				}
+				batch.delete_cf(&mempool_cf_handle, &key);
+				batch.delete_cf(&lookups_cf_handle, pending.id().to_vec());
+			}
+			return self.put_ready(batch, &transaction, Some(&sender));
+		}
+
+		// Replacement of a held transaction.
+		let held_cf_handle = cf_handle(db, cf::HELD_TRANSACTIONS)?;
+		if let Some(value) = db.get_cf(&held_cf_handle, &sender_key)? {
+			let pending: MempoolTransaction = bcs::from_bytes(&value)?;
+			if !transaction.replaces(&pending) {
+				return Ok(());
+			}
+			batch.delete_cf(&lookups_cf_handle, pending.id().to_vec());
+			return self.put_held(batch, &transaction, &sender);
+		}
+
+		let next_sequence_number = match Self::next_sequence_number(db, &sender)? {
+			Some(next) if sequence_number > next => {
+				self.put_held(batch, &transaction, &sender)?;
+				next
 			}
-			None => Ok(false),
+			next => {
+				// Only the lowest sequence number of the sender is ready.
+				match first_sender_entry(db, cf::SENDER_TRANSACTIONS, &sender)? {
+					Some((ready_sequence_number, _)) if ready_sequence_number < sequence_number => {
+						self.put_held(batch, &transaction, &sender)?
+					}
+					Some((_, ready_key)) => {
+						let mempool_cf_handle = cf_handle(db, cf::MEMPOOL_TRANSACTIONS)?;
+						let value = db
+							.get_cf(&mempool_cf_handle, &ready_key)?
+							.ok_or_else(|| Error::msg("Ready transaction not found"))?;
+						let ready: MempoolTransaction = bcs::from_bytes(&value)?;
+						Self::delete_ready(db, batch, &ready_key, &ready)?;
+						self.put_held(batch, &ready, &sender)?;
+						self.put_ready(batch, &transaction, Some(&sender))?;
+					}
+					None => match first_sender_entry(db, cf::HELD_TRANSACTIONS, &sender)? {
+						Some((held_sequence_number, _))
+							if held_sequence_number < sequence_number =>
+						{
+							self.put_held(batch, &transaction, &sender)?
+						}
+						_ => self.put_ready(batch, &transaction, Some(&sender))?,
+					},
+				}
+				// The held transactions following this one have no gap before them anymore.
+				let mut next = next.unwrap_or_default().max(sequence_number + 1);
+				while db.get_cf(&held_cf_handle, construct_sender_key(&sender, next))?.is_some() {
+					next += 1;
+				}
+				next
+			}
+		};
+		let mut value = next_sequence_number.to_be_bytes().to_vec();
+		value.extend_from_slice(&transaction.timestamp.to_be_bytes());
+		batch.put_cf(&cf_handle(db, cf::SENDER_NEXT_SEQUENCE_NUMBERS)?, sender.to_vec(), value);
+		Ok(())
+	}
+
+	/// Deletes a ready transaction read from the mempool column family.
+	fn delete_ready(
+		db: &DB,
+		batch: &mut WriteBatch,
+		key: &[u8],
+		transaction: &MempoolTransaction,
+	) -> Result<(), Error> {
+		batch.delete_cf(&cf_handle(db, cf::MEMPOOL_TRANSACTIONS)?, key);
+		batch.delete_cf(&cf_handle(db, cf::TRANSACTION_LOOKUPS)?, transaction.id().to_vec());
+		if let Some(sender) = transaction.sender() {
+			batch.delete_cf(
+				&cf_handle(db, cf::SENDER_TRANSACTIONS)?,
+				construct_sender_key(&sender, transaction.transaction.sequence_number()),
+			);
 		}
+		Ok(())
+	}
+
+	/// Deletes a ready transaction and moves the next transaction of its sender to the ready
+	/// index. The transactions in `removed` are skipped, they are removed with the write.
+	fn delete_ready_and_promote(
+		&self,
+		batch: &mut WriteBatch,
+		key: &[u8],
+		transaction: &MempoolTransaction,
+		removed: &HashSet<transaction::Id>,
+	) -> Result<(), Error> {
+		Self::delete_ready(&self.db, batch, key, transaction)?;
+		let Some(sender) = transaction.sender() else {
+			return Ok(());
+		};
+		let sequence_number = transaction.transaction.sequence_number();
+		match Self::successor(&self.db, &sender, sequence_number, removed)? {
+			Some(successor) => self.promote(batch, &successor),
+			None => Ok(()),
+		}
+	}
+
+	/// Deletes a held transaction stored at the key.
+	fn delete_held(
+		db: &DB,
+		batch: &mut WriteBatch,
+		key: &[u8],
+		transaction_id: transaction::Id,
+	) -> Result<(), Error> {
+		batch.delete_cf(&cf_handle(db, cf::HELD_TRANSACTIONS)?, key);
+		batch.delete_cf(&cf_handle(db, cf::TRANSACTION_LOOKUPS)?, transaction_id.to_vec());
+		Ok(())
 	}
 }
 
@@ -118,42 +565,23 @@ impl MempoolTransactionOperations for RocksdbMempool {
 		.await?
 	}
 
+	/// Adds the transactions, each one atomically in a single write batch.
+	/// https://github.com/movementlabsxyz/movement/issues/322
 	async fn add_mempool_transactions(
 		&self,
 		transactions: Vec<MempoolTransaction>,
 	) -> Result<(), anyhow::Error> {
-		let db = self.db.clone();
+		let mempool = self.clone();
 		tokio::task::spawn_blocking(move || {
-			let mempool_transactions_cf_handle = db
-				.cf_handle(cf::MEMPOOL_TRANSACTIONS)
-				.ok_or_else(|| Error::msg("CF handle not found"))?;
-			let transaction_lookups_cf_handle = db
-				.cf_handle(cf::TRANSACTION_LOOKUPS)
-				.ok_or_else(|| Error::msg("CF handle not found"))?;
-
-			// Add the transactions and update the lookup table atomically
-			// in a single write batch.
-			// https://github.com/movementlabsxyz/movement/issues/322
-
-			let mut batch = WriteBatch::default();
-
+			let _lock = mempool.write_lock.lock().unwrap();
 			for transaction in transactions {
-				if Self::internal_has_mempool_transaction(&db, transaction.transaction.id())? {
+				if Self::internal_has_mempool_transaction(&mempool.db, transaction.id())? {
 					continue;
 				}
-
-				let serialized_transaction = bcs::to_bytes(&transaction)?;
-				let key = construct_mempool_transaction_key(&transaction)?;
-				batch.put_cf(&mempool_transactions_cf_handle, &key, &serialized_transaction);
-				batch.put_cf(
-					&transaction_lookups_cf_handle,
-					transaction.transaction.id().to_vec(),
-					&key,
-				);
+				let mut batch = WriteBatch::default();
+				mempool.internal_add_mempool_transaction(&mut batch, transaction)?;
+				mempool.db.write(batch)?;
 			}
-
-			db.write(batch)?;
-
 			Ok::<(), Error>(())
 		})
 		.await??;
@@ -161,166 +589,101 @@ impl MempoolTransactionOperations for RocksdbMempool {
 	}
 
 	async fn add_mempool_transaction(&self, transaction: MempoolTransaction) -> Result<(), Error> {
-		let serialized_transaction = bcs::to_bytes(&transaction)?;
-		let db = self.db.clone();
-
-		tokio::task::spawn_blocking(move || {
-			let mempool_transactions_cf_handle = db
-				.cf_handle(cf::MEMPOOL_TRANSACTIONS)
-				.ok_or_else(|| Error::msg("CF handle not found"))?;
-			let transaction_lookups_cf_handle = db
-				.cf_handle(cf::TRANSACTION_LOOKUPS)
-				.ok_or_else(|| Error::msg("CF handle not found"))?;
-
-			// Add the transaction and update the lookup table atomically
-			// in a single write batch.
-			// https://github.com/movementlabsxyz/movement/issues/322
-
-			let mut batch = WriteBatch::default();
-
-			let key = construct_mempool_transaction_key(&transaction)?;
-			batch.put_cf(&mempool_transactions_cf_handle, &key, &serialized_transaction);
-			batch.put_cf(
-				&transaction_lookups_cf_handle,
-				transaction.transaction.id().to_vec(),
-				&key,
-			);
-
-			db.write(batch)?;
-
-			Ok::<(), Error>(())
-		})
-		.await??;
-
-		Ok(())
+		self.add_mempool_transactions(vec![transaction]).await
 	}
 
 	async fn remove_mempool_transaction(
 		&self,
 		transaction_id: transaction::Id,
 	) -> Result<(), Error> {
-		let key = self.get_mempool_transaction_key(transaction_id).await?;
-		let db = self.db.clone();
+		let mempool = self.clone();
 		tokio::task::spawn_blocking(move || {
-			match key {
-				Some(k) => {
-					let cf_handle = db
-						.cf_handle(cf::MEMPOOL_TRANSACTIONS)
-						.ok_or_else(|| Error::msg("CF handle not found"))?;
-					let lookups_cf_handle = db
-						.cf_handle(cf::TRANSACTION_LOOKUPS)
-						.ok_or_else(|| Error::msg("CF handle not found"))?;
-
-					// Remove the transaction and its entry in the lookup table
-					// atomically in a single write batch.
-					// https://github.com/movementlabsxyz/movement/issues/322
-
-					let mut batch = WriteBatch::default();
-					batch.delete_cf(&cf_handle, k);
-					batch.delete_cf(&lookups_cf_handle, transaction_id.to_vec());
-					db.write(batch)?;
+			let db = &mempool.db;
+			let _lock = mempool.write_lock.lock().unwrap();
+			let Some(transaction) = Self::internal_get_mempool_transaction(db, transaction_id)?
+			else {
+				return Ok(());
+			};
+
+			// Remove the transaction and its entries in the lookup tables
+			// atomically in a single write batch.
+			// https://github.com/movementlabsxyz/movement/issues/322
+
+			let mut batch = WriteBatch::default();
+			match Self::internal_get_location(db, transaction_id)? {
+				Some(Location::Ready(key)) => mempool.delete_ready_and_promote(
+					&mut batch,
+					&key,
+					&transaction,
+					&HashSet::new(),
+				)?,
+				Some(Location::Held(key)) => {
+					Self::delete_held(db, &mut batch, &key, transaction_id)?
 				}
 				None => (),
 			}
+			db.write(batch)?;
 			Ok::<(), Error>(())
 		})
 		.await??;
 		Ok(())
 	}
 
-	// Updated method signatures and implementations go here
 	async fn get_mempool_transaction(
 		&self,
 		transaction_id: transaction::Id,
 	) -> Result<Option<MempoolTransaction>, Error> {
-		let key = match self.get_mempool_transaction_key(transaction_id).await? {
-			Some(k) => k,
-			None => return Ok(None), // If no key found in lookup, return None
-		};
 		let db = self.db.clone();
 		tokio::task::spawn_blocking(move || {
-			let cf_handle = db
-				.cf_handle(cf::MEMPOOL_TRANSACTIONS)
-				.ok_or_else(|| Error::msg("CF handle not found"))?;
-			match db.get_cf(&cf_handle, &key)? {
-				Some(serialized_transaction) => {
-					let transaction: MempoolTransaction = bcs::from_bytes(&serialized_transaction)?;
-					Ok(Some(transaction))
-				}
-				None => Ok(None),
-			}
+			Self::internal_get_mempool_transaction(&db, transaction_id)
 		})
 		.await?
 	}
 
 	async fn pop_mempool_transaction(&self) -> Result<Option<MempoolTransaction>, Error> {
-		let db = self.db.clone();
-		tokio::task::spawn_blocking(move || {
-			let cf_handle = db
-				.cf_handle(cf::MEMPOOL_TRANSACTIONS)
-				.ok_or_else(|| Error::msg("CF handle not found"))?;
-			let lookups_cf_handle = db
-				.cf_handle(cf::TRANSACTION_LOOKUPS)
-				.ok_or_else(|| Error::msg("CF handle not found"))?;
-			let mut iter = db.iterator_cf(&cf_handle, IteratorMode::Start);
-
-			match iter.next() {
-				None => return Ok(None), // No transactions to pop
-				Some(res) => {
-					// Drop the database reader early
-					drop(iter);
-
-					let (key, value) = res?;
-					let transaction: MempoolTransaction = bcs::from_bytes(&value)?;
-
-					// Remove the transaction and its entry in the lookup table
-					// atomically in a single write batch.
-					// https://github.com/movementlabsxyz/movement/issues/322
-
-					let mut batch = WriteBatch::default();
-					batch.delete_cf(&cf_handle, &key);
-					batch.delete_cf(&lookups_cf_handle, transaction.transaction.id().to_vec());
-					db.write(batch)?;
-
-					Ok(Some(transaction))
-				}
-			}
-		})
-		.await?
+		Ok(self.pop_mempool_transactions(1).await?.pop())
 	}
 
 	async fn pop_mempool_transactions(
 		&self,
 		n: usize,
 	) -> Result<Vec<MempoolTransaction>, anyhow::Error> {
-		let db = self.db.clone();
+		let mempool = self.clone();
 		tokio::task::spawn_blocking(move || {
-			let cf_handle = db
-				.cf_handle(cf::MEMPOOL_TRANSACTIONS)
-				.ok_or_else(|| Error::msg("CF handle not found"))?;
-			let lookups_cf_handle = db
-				.cf_handle(cf::TRANSACTION_LOOKUPS)
-				.ok_or_else(|| Error::msg("CF handle not found"))?;
-
-			// Remove the transactions and their lookup table entries
-			// atomically in a single write batch.
+			let db = &mempool.db;
+			let _lock = mempool.write_lock.lock().unwrap();
+
+			// Remove the transactions and their lookup table entries, and make the next
+			// transactions of their senders ready, atomically in a single write batch.
 			// https://github.com/movementlabsxyz/movement/issues/322
 
-			let mut iter = db.iterator_cf(&cf_handle, IteratorMode::Start);
+			let mut order = PopOrder::new(&mempool)?;
 			let mut batch = WriteBatch::default();
-			let mut mempool_transactions = Vec::with_capacity(n as usize);
-			while let Some(res) = iter.next() {
-				let (key, value) = res?;
-				let transaction: MempoolTransaction = bcs::from_bytes(&value)?;
-
-				batch.delete_cf(&cf_handle, &key);
-				batch.delete_cf(&lookups_cf_handle, transaction.transaction.id().to_vec());
-
-				mempool_transactions.push(transaction);
-				if mempool_transactions.len() >= n {
+			let mut mempool_transactions = Vec::with_capacity(n);
+			while mempool_transactions.len() < n {
+				let Some((transaction, ready_key)) = order.next()? else {
 					break;
+				};
+				match ready_key {
+					Some(key) => Self::delete_ready(db, &mut batch, &key, &transaction)?,
+					None => {
+						let sender = transaction
+							.sender()
+							.ok_or_else(|| Error::msg("Held transaction without sender"))?;
+						let key = construct_sender_key(
+							&sender,
+							transaction.transaction.sequence_number(),
+						);
+						Self::delete_held(db, &mut batch, &key, transaction.id())?;
+					}
 				}
+				order.take(&transaction)?;
+				mempool_transactions.push(transaction);
+			}
+			for successor in std::mem::take(&mut order.successors).into_values() {
+				mempool.promote(&mut batch, &successor)?;
 			}
+			drop(order);
 			db.write(batch)?;
 
 			Ok(mempool_transactions)
@@ -328,33 +691,54 @@ impl MempoolTransactionOperations for RocksdbMempool {
 		.await?
 	}
 
+	/// Removes the ready and held transactions of slots before the threshold, and forgets the
+	/// next sequence numbers of the senders without transactions since.
 	async fn gc_mempool_transactions(
 		&self,
 		timestamp_threshold: u64,
 	) -> Result<u64, anyhow::Error> {
-		let db = self.db.clone();
+		let mempool = self.clone();
 		tokio::task::spawn_blocking(move || {
-			let cf_handle = db
-				.cf_handle(cf::MEMPOOL_TRANSACTIONS)
-				.ok_or_else(|| Error::msg("CF handle not found"))?;
-			let lookups_cf_handle = db
-				.cf_handle(cf::TRANSACTION_LOOKUPS)
-				.ok_or_else(|| Error::msg("CF handle not found"))?;
-			let mut read_options = ReadOptions::default();
-			read_options
-				.set_iterate_upper_bound(construct_timestamp_threshold_key(timestamp_threshold)?);
-			let mut iter = db.iterator_cf_opt(&cf_handle, read_options, IteratorMode::Start);
+			let db = &mempool.db;
+			let _lock = mempool.write_lock.lock().unwrap();
+			let mempool_cf_handle = cf_handle(db, cf::MEMPOOL_TRANSACTIONS)?;
+			let held_cf_handle = cf_handle(db, cf::HELD_TRANSACTIONS)?;
+			let next_cf_handle = cf_handle(db, cf::SENDER_NEXT_SEQUENCE_NUMBERS)?;
 			let mut transaction_count = 0;
 			let mut batch = WriteBatch::default();
 
-			while let Some(res) = iter.next() {
+			// The held transactions are removed first, so the ready transactions removed
+			// are followed by the next transactions of their senders which stay.
+			let mut removed = HashSet::new();
+			for res in db.iterator_cf(&held_cf_handle, IteratorMode::Start) {
 				let (key, value) = res?;
 				let transaction: MempoolTransaction = bcs::from_bytes(&value)?;
+				if transaction.timestamp < timestamp_threshold {
+					Self::delete_held(db, &mut batch, &key, transaction.id())?;
+					removed.insert(transaction.id());
+					transaction_count += 1;
+				}
+			}
 
-				batch.delete_cf(&cf_handle, &key);
-				batch.delete_cf(&lookups_cf_handle, transaction.transaction.id().to_vec());
+			// The keys are ordered by lane and priority first, so all the keys are visited.
+			for res in db.iterator_cf(&mempool_cf_handle, IteratorMode::Start) {
+				let (key, value) = res?;
+				let transaction: MempoolTransaction = bcs::from_bytes(&value)?;
+				if transaction.timestamp < timestamp_threshold {
+					mempool.delete_ready_and_promote(&mut batch, &key, &transaction, &removed)?;
+					transaction_count += 1;
+				}
+			}
 
-				transaction_count += 1;
+			for res in db.iterator_cf(&next_cf_handle, IteratorMode::Start) {
+				let (sender, value) = res?;
+				let timestamp = value
+					.get(8..16)
+					.map(|bytes| u64::from_be_bytes(bytes.try_into().unwrap()))
+					.unwrap_or_default();
+				if timestamp < timestamp_threshold {
+					batch.delete_cf(&next_cf_handle, &sender);
+				}
 			}
 
 			db.write(batch)?;
@@ -365,6 +749,138 @@ impl MempoolTransactionOperations for RocksdbMempool {
 	}
 }
 
+impl RocksdbMempool {
+	fn internal_get_chain_tip(db: &DB) -> Result<Option<(block::Id, u64)>, Error> {
+		let value = db.get_cf(&cf_handle(db, cf::CHAIN)?, CHAIN_TIP_KEY)?;
+		value.map(|value| bcs::from_bytes(&value).map_err(Error::from)).transpose()
+	}
+
+	/// Reads the chain tip, blocking. To recover the chain on initialization.
+	pub fn read_chain_tip(&self) -> Result<Option<(block::Id, u64)>, Error> {
+		Self::internal_get_chain_tip(&self.db)
+	}
+
+	/// Walks the persisted block chain from the first height and reports its inconsistencies.
+	pub async fn verify_chain(&self) -> Result<ChainReport, Error> {
+		let db = self.db.clone();
+		tokio::task::spawn_blocking(move || {
+			let heights_cf_handle = cf_handle(&db, cf::BLOCK_HEIGHTS)?;
+			let mut report =
+				ChainReport { tip: Self::internal_get_chain_tip(&db)?, ..Default::default() };
+
+			let mut previous: Option<(u64, block::Id)> = None;
+			// The keys are the big endian heights, so the headers are visited in height order.
+			for res in db.iterator_cf(&heights_cf_handle, IteratorMode::Start) {
+				let (key, value) = res?;
+				let height = u64::from_be_bytes(key.as_ref().try_into()?);
+				let (id, parent): (block::Id, block::Id) = bcs::from_bytes(&value)?;
+				report.blocks += 1;
+
+				let expected_height = previous.map(|(height, _)| height + 1).unwrap_or(1);
+				report.gaps.extend(expected_height..height);
+				let expected_parent = match previous {
+					Some((previous_height, previous_id)) if previous_height + 1 == height => {
+						Some(previous_id)
+					}
+					None if height == 1 => Some(block::Id::genesis_block()),
+					_ => None,
+				};
+				if expected_parent.is_some_and(|expected| expected != parent) {
+					report.forks.push(height);
+				}
+				previous = Some((height, id));
+			}
+			report.tip_mismatch = report.tip != previous.map(|(height, id)| (id, height));
+
+			Ok(report)
+		})
+		.await?
+	}
+}
+
+impl MempoolChainOperations for RocksdbMempool {
+	async fn next_mempool_transactions(
+		&self,
+		n: usize,
+		skip: &BTreeSet<transaction::Id>,
+	) -> Result<Vec<MempoolTransaction>, Error> {
+		let mempool = self.clone();
+		let skip = skip.clone();
+		tokio::task::spawn_blocking(move || {
+			// The transactions read are followed by the next ones of their senders, as when
+			// they are popped.
+			let mut order = PopOrder::new(&mempool)?;
+			let mut mempool_transactions = Vec::with_capacity(n);
+			while mempool_transactions.len() < n {
+				let Some((transaction, _)) = order.next()? else {
+					break;
+				};
+				order.take(&transaction)?;
+				if !skip.contains(&transaction.id()) {
+					mempool_transactions.push(transaction);
+				}
+			}
+			Ok(mempool_transactions)
+		})
+		.await?
+	}
+
+	async fn append_block(&self, block: Block, height: u64) -> Result<(), Error> {
+		let mempool = self.clone();
+		tokio::task::spawn_blocking(move || {
+			let db = &mempool.db;
+			let _lock = mempool.write_lock.lock().unwrap();
+			// Remove the block transactions from the mempool and store the block header and the
+			// tip atomically in a single write batch, so the transactions leave the mempool only
+			// with their block and the recovered tip is always a stored block.
+			let mut batch = WriteBatch::default();
+			let mempool_cf_handle = cf_handle(db, cf::MEMPOOL_TRANSACTIONS)?;
+			let removed: HashSet<_> =
+				block.transactions().map(|transaction| transaction.id()).collect();
+			for transaction in block.transactions() {
+				// A transaction replaced or garbage collected since it was read is already gone.
+				match Self::internal_get_location(db, transaction.id())? {
+					Some(Location::Ready(key)) => {
+						let Some(value) = db.get_cf(&mempool_cf_handle, &key)? else {
+							continue;
+						};
+						let mempool_transaction: MempoolTransaction = bcs::from_bytes(&value)?;
+						mempool.delete_ready_and_promote(
+							&mut batch,
+							&key,
+							&mempool_transaction,
+							&removed,
+						)?;
+					}
+					// The next transactions of a sender read after the ready one.
+					Some(Location::Held(key)) => {
+						Self::delete_held(db, &mut batch, &key, transaction.id())?
+					}
+					None => (),
+				}
+			}
+			batch.put_cf(
+				&cf_handle(db, cf::BLOCK_HEIGHTS)?,
+				height.to_be_bytes(),
+				bcs::to_bytes(&(block.id(), block.parent()))?,
+			);
+			batch.put_cf(
+				&cf_handle(db, cf::CHAIN)?,
+				CHAIN_TIP_KEY,
+				bcs::to_bytes(&(block.id(), height))?,
+			);
+			db.write(batch)?;
+			Ok(())
+		})
+		.await?
+	}
+
+	async fn get_chain_tip(&self) -> Result<Option<(block::Id, u64)>, Error> {
+		let db = self.db.clone();
+		tokio::task::spawn_blocking(move || Self::internal_get_chain_tip(&db)).await?
+	}
+}
+
 impl MempoolBlockOperations for RocksdbMempool {
 	async fn has_block(&self, block_id: block::Id) -> Result<bool, Error> {
 		let db = self.db.clone();
@@ -421,10 +937,40 @@ impl MempoolBlockOperations for RocksdbMempool {
 pub mod tests {
 
 	use super::*;
+	use aptos_crypto::{ed25519::Ed25519PrivateKey, PrivateKey};
+	use aptos_types::chain_id::ChainId;
+	use aptos_types::transaction::{RawTransaction, Script, TransactionPayload};
 	use movement_types::transaction::Transaction;
 	use tempfile::tempdir;
 	use tokio::time::{sleep, Duration};
 
+	/// A mempool transaction of a signed Aptos transaction, with a priority from its gas price.
+	fn signed_transaction(
+		sender: AccountAddress,
+		sequence_number: u64,
+		gas_unit_price: u64,
+	) -> MempoolTransaction {
+		let private_key = Ed25519PrivateKey::try_from(&[1u8; 32][..]).unwrap();
+		let signed_transaction = RawTransaction::new(
+			sender,
+			sequence_number,
+			TransactionPayload::Script(Script::new(vec![], vec![], vec![])),
+			0,
+			gas_unit_price,
+			0,
+			ChainId::test(),
+		)
+		.sign(&private_key, private_key.public_key())
+		.unwrap()
+		.into_inner();
+		let transaction = Transaction::new(
+			bcs::to_bytes(&signed_transaction).unwrap(),
+			u64::MAX - gas_unit_price,
+			sequence_number,
+		);
+		MempoolTransaction::at_time(transaction, 0)
+	}
+
 	#[tokio::test]
 	async fn test_rocksdb_mempool_basic_operations() -> Result<(), Error> {
 		let temp_dir = tempdir().unwrap();
@@ -615,4 +1161,247 @@ pub mod tests {
 
 		Ok(())
 	}
+
+	#[tokio::test]
+	async fn test_replace_by_fee() -> Result<(), Error> {
+		let temp_dir = tempdir().unwrap();
+		let path = temp_dir.path().to_str().unwrap();
+		let mempool = RocksdbMempool::try_new(path)?;
+		let sender = AccountAddress::random();
+
+		let pending = signed_transaction(sender, 0, 100);
+		let same_fee = signed_transaction(sender, 0, 100);
+		let lower_fee = signed_transaction(sender, 0, 50);
+		let higher_fee = signed_transaction(sender, 0, 200);
+		assert_eq!(pending.sender(), Some(sender));
+
+		mempool.add_mempool_transaction(pending.clone()).await?;
+		mempool.add_mempool_transaction(same_fee.clone()).await?;
+		mempool.add_mempool_transaction(lower_fee.clone()).await?;
+		assert!(mempool.has_mempool_transaction(pending.id()).await?);
+		assert!(!mempool.has_mempool_transaction(lower_fee.id()).await?);
+
+		mempool.add_mempool_transaction(higher_fee.clone()).await?;
+		assert!(!mempool.has_mempool_transaction(pending.id()).await?);
+
+		let transactions = mempool.pop_mempool_transactions(10).await?;
+		assert_eq!(transactions, vec![higher_fee]);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_nonce_gap_holds_transactions() -> Result<(), Error> {
+		let temp_dir = tempdir().unwrap();
+		let path = temp_dir.path().to_str().unwrap();
+		let mempool = RocksdbMempool::try_new(path)?;
+		let sender = AccountAddress::random();
+
+		let transaction0 = signed_transaction(sender, 0, 100);
+		let transaction1 = signed_transaction(sender, 1, 100);
+		let transaction2 = signed_transaction(sender, 2, 100);
+		let transaction3 = signed_transaction(sender, 3, 100);
+
+		mempool.add_mempool_transaction(transaction0.clone()).await?;
+		mempool.add_mempool_transaction(transaction2.clone()).await?;
+		mempool.add_mempool_transaction(transaction3.clone()).await?;
+		assert!(mempool.has_mempool_transaction(transaction2.id()).await?);
+
+		// The transactions after the gap are held.
+		let transactions = mempool.pop_mempool_transactions(10).await?;
+		assert_eq!(transactions, vec![transaction0]);
+
+		// A held transaction can be replaced too.
+		let transaction3_replacement = signed_transaction(sender, 3, 200);
+		mempool.add_mempool_transaction(transaction3_replacement.clone()).await?;
+		assert!(!mempool.has_mempool_transaction(transaction3.id()).await?);
+
+		// Filling the gap releases them, in sequence number order.
+		mempool.add_mempool_transaction(transaction1.clone()).await?;
+		let transactions = mempool.pop_mempool_transactions(10).await?;
+		assert_eq!(transactions, vec![transaction1, transaction2, transaction3_replacement]);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_only_the_next_sequence_number_of_a_sender_is_ready() -> Result<(), Error> {
+		let temp_dir = tempdir().unwrap();
+		let path = temp_dir.path().to_str().unwrap();
+		let mempool = RocksdbMempool::try_new(path)?;
+		let sender = AccountAddress::random();
+		let other = AccountAddress::random();
+
+		// The second transaction of the sender pays more than the one of the other sender.
+		let transaction0 = signed_transaction(sender, 0, 10);
+		let transaction1 = signed_transaction(sender, 1, 1000);
+		let other_transaction = signed_transaction(other, 0, 100);
+		mempool.add_mempool_transaction(transaction1.clone()).await?;
+		mempool.add_mempool_transaction(transaction0.clone()).await?;
+		mempool.add_mempool_transaction(other_transaction.clone()).await?;
+
+		// Reading the transactions for a block follows the next sequence number once read.
+		let next = mempool.next_mempool_transactions(1, &BTreeSet::new()).await?;
+		assert_eq!(next, vec![other_transaction.clone()]);
+		let skip = BTreeSet::from([other_transaction.id(), transaction0.id()]);
+		let next = mempool.next_mempool_transactions(3, &skip).await?;
+		assert_eq!(next, vec![transaction1.clone()]);
+
+		let transactions = mempool.pop_mempool_transactions(1).await?;
+		assert_eq!(transactions, vec![other_transaction]);
+		let transactions = mempool.pop_mempool_transactions(1).await?;
+		assert_eq!(transactions, vec![transaction0]);
+		// The next transaction of the sender became ready with the pop.
+		let ready_cf_handle = cf_handle(&mempool.db, cf::SENDER_TRANSACTIONS)?;
+		let ready = mempool.db.get_cf(&ready_cf_handle, construct_sender_key(&sender, 1))?;
+		assert!(ready.is_some());
+		assert_eq!(mempool.pop_mempool_transactions(10).await?, vec![transaction1]);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_keys_without_lane_are_migrated() -> Result<(), Error> {
+		let temp_dir = tempdir().unwrap();
+		let path = temp_dir.path().to_str().unwrap();
+		let legacy_transaction = MempoolTransaction::at_time(Transaction::new(vec![1], 0, 0), 0);
+		{
+			// Stored as by the versions without lanes.
+			let mempool = RocksdbMempool::try_new(path)?;
+			let key = format!(
+				"{:032}:{:032}:{:032}:{}",
+				0,
+				legacy_transaction.timestamp,
+				0,
+				legacy_transaction.transaction.id()
+			);
+			let db = &mempool.db;
+			db.put_cf(
+				&cf_handle(db, cf::MEMPOOL_TRANSACTIONS)?,
+				&key,
+				bcs::to_bytes(&legacy_transaction)?,
+			)?;
+			db.put_cf(
+				&cf_handle(db, cf::TRANSACTION_LOOKUPS)?,
+				legacy_transaction.id().to_vec(),
+				&key,
+			)?;
+		}
+
+		let admin = AccountAddress::random();
+		let mempool = RocksdbMempool::try_new(path)?.with_priority_lane_senders([admin]);
+		assert!(mempool.has_mempool_transaction(legacy_transaction.id()).await?);
+		let admin_transaction = signed_transaction(admin, 0, 1);
+		mempool.add_mempool_transaction(admin_transaction.clone()).await?;
+
+		let transactions = mempool.pop_mempool_transactions(3).await?;
+		assert_eq!(transactions, vec![admin_transaction, legacy_transaction]);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_priority_lane() -> Result<(), Error> {
+		let temp_dir = tempdir().unwrap();
+		let path = temp_dir.path().to_str().unwrap();
+		let admin = AccountAddress::random();
+		let mempool = RocksdbMempool::try_new(path)?.with_priority_lane_senders([admin]);
+
+		let user_transaction = signed_transaction(AccountAddress::random(), 0, 1000);
+		let plain_transaction = MempoolTransaction::at_time(Transaction::new(vec![1], 0, 0), 0);
+		let admin_transaction = signed_transaction(admin, 0, 1);
+
+		mempool.add_mempool_transaction(user_transaction.clone()).await?;
+		mempool.add_mempool_transaction(plain_transaction.clone()).await?;
+		mempool.add_mempool_transaction(admin_transaction.clone()).await?;
+
+		let transactions = mempool.pop_mempool_transactions(3).await?;
+		assert_eq!(transactions, vec![admin_transaction, plain_transaction, user_transaction]);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_block_transactions_leave_the_mempool_with_the_block() -> Result<(), Error> {
+		let temp_dir = tempdir().unwrap();
+		let path = temp_dir.path().to_str().unwrap();
+		let mempool = RocksdbMempool::try_new(path)?;
+
+		let transaction1 = MempoolTransaction::at_time(Transaction::new(vec![1], 0, 0), 0);
+		let transaction2 = MempoolTransaction::at_time(Transaction::new(vec![2], 0, 0), 1);
+		mempool.add_mempool_transaction(transaction1.clone()).await?;
+		mempool.add_mempool_transaction(transaction2.clone()).await?;
+
+		// Reading the transactions for a block doesn't remove them.
+		let next = mempool.next_mempool_transactions(1, &BTreeSet::new()).await?;
+		assert_eq!(next, vec![transaction1.clone()]);
+		let skip = BTreeSet::from([transaction1.id()]);
+		let next = mempool.next_mempool_transactions(2, &skip).await?;
+		assert_eq!(next, vec![transaction2.clone()]);
+		assert!(mempool.has_mempool_transaction(transaction1.id()).await?);
+
+		let block = Block::new(
+			block::BlockMetadata::default(),
+			block::Id::genesis_block(),
+			[transaction1.transaction.clone()],
+		);
+		mempool.append_block(block.clone(), 1).await?;
+		assert!(!mempool.has_mempool_transaction(transaction1.id()).await?);
+		assert_eq!(mempool.pop_mempool_transactions(2).await?, vec![transaction2]);
+		assert_eq!(mempool.get_chain_tip().await?, Some((block.id(), 1)));
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_chain_tip_and_verification() -> Result<(), Error> {
+		let temp_dir = tempdir().unwrap();
+		let path = temp_dir.path().to_str().unwrap();
+		let mempool = RocksdbMempool::try_new(path)?;
+		assert_eq!(mempool.get_chain_tip().await?, None);
+		assert!(mempool.verify_chain().await?.is_consistent());
+
+		let block1 = Block::new(
+			block::BlockMetadata::default(),
+			block::Id::genesis_block(),
+			[Transaction::new(vec![1], 0, 0)],
+		);
+		let block2 = Block::new(
+			block::BlockMetadata::default(),
+			block1.id(),
+			[Transaction::new(vec![2], 0, 0)],
+		);
+		mempool.append_block(block1.clone(), 1).await?;
+		mempool.append_block(block2.clone(), 2).await?;
+		assert_eq!(mempool.get_chain_tip().await?, Some((block2.id(), 2)));
+		let report = mempool.verify_chain().await?;
+		assert!(report.is_consistent(), "{report}");
+		assert_eq!(report.blocks, 2);
+
+		// A block built on the genesis again, as after a restart losing the tip, and a gap.
+		let block4 = Block::new(
+			block::BlockMetadata::default(),
+			block::Id::genesis_block(),
+			[Transaction::new(vec![4], 0, 0)],
+		);
+		mempool.append_block(block4.clone(), 4).await?;
+		let report = mempool.verify_chain().await?;
+		assert_eq!(report.gaps, vec![3]);
+		assert!(report.forks.is_empty());
+		assert!(!report.tip_mismatch);
+
+		let block3 = Block::new(
+			block::BlockMetadata::default(),
+			block::Id::genesis_block(),
+			[Transaction::new(vec![3], 0, 0)],
+		);
+		mempool.append_block(block3.clone(), 3).await?;
+		let report = mempool.verify_chain().await?;
+		assert!(report.gaps.is_empty());
+		assert_eq!(report.forks, vec![3, 4]);
+		assert_eq!(report.tip, Some((block3.id(), 3)));
+		assert!(report.tip_mismatch);
+
+		Ok(())
+	}
 }
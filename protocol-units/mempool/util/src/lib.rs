@@ -1,3 +1,4 @@
+use aptos_types::transaction::SignedTransaction;
 use serde::{Deserialize, Serialize};
 
 use movement_types::{
@@ -6,8 +7,11 @@ use movement_types::{
 };
 
 use std::cmp::Ordering;
+use std::collections::BTreeSet;
 use std::future::Future;
 
+pub use aptos_types::account_address::AccountAddress;
+
 pub trait MempoolTransactionOperations {
 	// todo: move mempool_transaction methods into separate trait
 
@@ -163,6 +167,24 @@ pub trait MempoolBlockOperations {
 	async fn get_block(&self, block_id: block::Id) -> Result<Option<Block>, anyhow::Error>;
 }
 
+/// Persistence of the chain of the blocks built from the mempool.
+pub trait MempoolChainOperations {
+	/// Reads the next n mempool transactions in pop order, without removing them,
+	/// skipping the ones in `skip`.
+	async fn next_mempool_transactions(
+		&self,
+		n: usize,
+		skip: &BTreeSet<transaction::Id>,
+	) -> Result<Vec<MempoolTransaction>, anyhow::Error>;
+
+	/// Stores the header of a built block at the given height, makes it the chain tip and
+	/// removes the block transactions from the mempool, atomically.
+	async fn append_block(&self, block: Block, height: u64) -> Result<(), anyhow::Error>;
+
+	/// Gets the id and the height of the chain tip, if a block was appended.
+	async fn get_chain_tip(&self) -> Result<Option<(block::Id, u64)>, anyhow::Error>;
+}
+
 /// Wraps a transaction with a timestamp for help ordering.
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MempoolTransaction {
@@ -235,6 +257,19 @@ impl MempoolTransaction {
 	pub fn id(&self) -> transaction::Id {
 		self.transaction.id()
 	}
+
+	/// The sender of the transaction, if its data is a signed Aptos transaction.
+	pub fn sender(&self) -> Option<AccountAddress> {
+		bcs::from_bytes::<SignedTransaction>(self.transaction.data())
+			.ok()
+			.map(|signed_transaction| signed_transaction.sender())
+	}
+
+	/// Whether the transaction can replace a pending transaction with the same sender and
+	/// sequence number: it must pay for a strictly higher priority.
+	pub fn replaces(&self, pending: &MempoolTransaction) -> bool {
+		self.transaction.application_priority() < pending.transaction.application_priority()
+	}
 }
 
 #[cfg(test)]
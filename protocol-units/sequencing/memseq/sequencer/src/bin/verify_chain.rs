@@ -0,0 +1,26 @@
+use clap::Parser;
+use move_rocks::RocksdbMempool;
+use std::path::PathBuf;
+
+/// Walks the block chain persisted by memseq and reports its gaps and forks.
+/// The sequencer must be stopped, the database is opened exclusively.
+#[derive(Parser, Debug)]
+#[clap(rename_all = "kebab-case")]
+struct VerifyChain {
+	/// Path of the memseq database.
+	#[clap(long)]
+	path: PathBuf,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+	let VerifyChain { path } = VerifyChain::parse();
+	let mempool =
+		RocksdbMempool::try_new(path.to_str().ok_or(anyhow::anyhow!("PathBuf to str failed"))?)?;
+	let report = mempool.verify_chain().await?;
+	println!("{report}");
+	if !report.is_consistent() {
+		std::process::exit(1);
+	}
+	Ok(())
+}
@@ -1,17 +1,20 @@
-use mempool_util::MempoolTransactionOperations;
-pub use move_rocks::RocksdbMempool;
+pub use mempool_util::AccountAddress;
+use mempool_util::{MempoolChainOperations, MempoolTransactionOperations};
+pub use move_rocks::{ChainReport, RocksdbMempool};
 pub use movement_types::{
+	atomic_transaction_bundle::{self, AtomicTransactionBundle},
 	block::{self, Block},
 	transaction::{self, Transaction},
 };
 pub use sequencing_util::Sequencer;
 
-use tokio::sync::{Notify, RwLock};
+use tokio::sync::{Mutex, Notify, RwLock};
 use tokio::time::Instant;
 use tracing::{debug, info};
 
 use std::collections::BTreeSet;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
@@ -23,20 +26,33 @@ pub struct Memseq<T: MempoolTransactionOperations> {
 	block_size: u32,
 	/// The id of the parent block.
 	pub parent_block: Arc<RwLock<block::Id>>,
+	/// The height of the parent block, updated with the parent block lock held.
+	height: AtomicU64,
 	// this value should not be changed after initialization
 	building_time_ms: u64,
 	// The notifier used to wake up the block building routine
 	changed: Notify,
+	// Serializes the block building, the transactions stay in the mempool until their block
+	// is appended
+	building: Mutex<()>,
 }
 
-impl<T: MempoolTransactionOperations> Memseq<T> {
+impl<T: MempoolTransactionOperations + MempoolChainOperations> Memseq<T> {
 	pub(crate) fn new(
 		mempool: T,
 		block_size: u32,
 		parent_block: Arc<RwLock<block::Id>>,
 		building_time_ms: u64,
 	) -> Self {
-		Self { mempool, block_size, parent_block, building_time_ms, changed: Notify::new() }
+		Self {
+			mempool,
+			block_size,
+			parent_block,
+			height: AtomicU64::new(0),
+			building_time_ms,
+			changed: Notify::new(),
+			building: Mutex::new(()),
+		}
 	}
 
 	pub fn with_block_size(mut self, block_size: u32) -> Self {
@@ -57,20 +73,98 @@ impl<T: MempoolTransactionOperations> Memseq<T> {
 		*self.parent_block.read().await
 	}
 
+	/// The id and the height of the last built block.
+	pub async fn chain_tip(&self) -> (block::Id, u64) {
+		let parent_block = self.parent_block.read().await;
+		(*parent_block, self.height.load(Ordering::Acquire))
+	}
+
+	/// Builds the next block and persists it as the chain tip before returning it.
+	/// The block transactions are removed from the mempool with the same write.
 	async fn build_next_block(
 		&self,
 		metadata: block::BlockMetadata,
 		transactions: Vec<Transaction>,
 	) -> Result<Block, anyhow::Error> {
 		let mut parent_block = self.parent_block.write().await;
-		let new_block = Block::new(metadata, *parent_block, BTreeSet::from_iter(transactions));
+		let new_block = Block::new(metadata, *parent_block, transactions);
+		let height = self.height.load(Ordering::Acquire) + 1;
+		self.mempool.append_block(new_block.clone(), height).await?;
 		*parent_block = new_block.id();
+		self.height.store(height, Ordering::Release);
 		Ok(new_block)
 	}
+
+	/// Builds the next block, either when the block size is reached or the building time expires.
+	///
+	/// The transactions are read from the mempool and only removed when the block is appended,
+	/// so they stay in the mempool if the block can't be built.
+	///
+	/// A bundle counts as its number of transactions. A bundle that doesn't fit in the space left
+	/// in the block stays in the mempool for a next block.
+	async fn next_block(&self) -> Result<Option<Block>, anyhow::Error> {
+		info!(target: "movement_timing",  "CALLED wait_for_next_block");
+		let _building = self.building.lock().await;
+		let mut transactions = Vec::with_capacity(self.block_size as usize);
+		let mut bundles = BTreeSet::new();
+		// The transactions already read, placed in the block or deferred.
+		let mut read = BTreeSet::new();
+		let mut current_block_size = 0u32;
+
+		let now = Instant::now();
+		let build_deadline = now + Duration::from_millis(self.building_time_ms);
+
+		loop {
+			if current_block_size >= self.block_size {
+				info!("block is above the size limit");
+				info!(target: "movement_timing",  "BREAK out of wait_for_next_block");
+				break;
+			}
+
+			let remaining = self.block_size - current_block_size;
+			let mut deferred = 0;
+			for mempool_transaction in
+				self.mempool.next_mempool_transactions(remaining as usize, &read).await?
+			{
+				read.insert(mempool_transaction.transaction.id());
+				let size = AtomicTransactionBundle::block_transaction_size(
+					&mempool_transaction.transaction,
+				) as u32;
+				if current_block_size + size > self.block_size {
+					deferred += 1;
+				} else {
+					current_block_size += size;
+					// Plain transactions with the bundle prefix are refused on publication.
+					if AtomicTransactionBundle::is_tagged(&mempool_transaction.transaction) {
+						bundles.insert(mempool_transaction.transaction.id());
+					}
+					transactions.push(mempool_transaction.transaction);
+				}
+			}
+			if deferred > 0 {
+				debug!("{deferred} bundles deferred to a next block");
+			}
+
+			if let Err(_) = tokio::time::timeout_at(build_deadline, self.changed.notified()).await {
+				info!(target: "movement_timing",  "block building deadline elapsed");
+				info!(target: "movement_timing",  "BREAK out of wait_for_next_block");
+				break;
+			}
+		}
+
+		if transactions.is_empty() {
+			Ok(None)
+		} else {
+			let metadata = block::BlockMetadata::with_atomic_bundles(bundles);
+			let new_block = self.build_next_block(metadata, transactions).await?;
+			Ok(Some(new_block))
+		}
+	}
 }
 
 impl Memseq<RocksdbMempool> {
 	/// Attempts to create a new Memseq instance with a RocksDB mempool, given a path, block size, and building time.
+	/// The chain continues from the tip persisted in the mempool.
 	pub fn try_move_rocks(
 		path: PathBuf,
 		block_size: u32,
@@ -79,8 +173,28 @@ impl Memseq<RocksdbMempool> {
 		let mempool = RocksdbMempool::try_new(
 			path.to_str().ok_or(anyhow::anyhow!("PathBuf to str failed"))?,
 		)?;
-		let parent_block = Arc::new(RwLock::new(block::Id::default()));
-		Ok(Self::new(mempool, block_size, parent_block, building_time_ms))
+		let (tip, height) = mempool.read_chain_tip()?.unwrap_or_default();
+		if height > 0 {
+			info!("Recovered memseq chain tip {tip} at height {height}");
+		}
+		let parent_block = Arc::new(RwLock::new(tip));
+		let memseq = Self::new(mempool, block_size, parent_block, building_time_ms);
+		memseq.height.store(height, Ordering::Release);
+		Ok(memseq)
+	}
+
+	/// Walks the persisted block chain and reports its gaps and forks.
+	pub async fn verify_chain(&self) -> Result<ChainReport, anyhow::Error> {
+		self.mempool.verify_chain().await
+	}
+
+	/// Sets the senders whose transactions are popped before all the others.
+	pub fn with_priority_lane_senders(
+		mut self,
+		senders: impl IntoIterator<Item = AccountAddress>,
+	) -> Self {
+		self.mempool = self.mempool.with_priority_lane_senders(senders);
+		self
 	}
 
 	pub fn try_from_env_toml_file() -> Result<Self, anyhow::Error> {
@@ -88,14 +202,29 @@ impl Memseq<RocksdbMempool> {
 	}
 }
 
-impl<T: MempoolTransactionOperations> Sequencer for Memseq<T> {
+/// Refuses a plain transaction that would be taken for a bundle.
+fn check_not_tagged(transaction: &Transaction) -> Result<(), anyhow::Error> {
+	if AtomicTransactionBundle::is_tagged(transaction) {
+		anyhow::bail!(
+			"Transaction {} has the data prefix reserved to AtomicTransactionBundle",
+			transaction.id()
+		);
+	}
+	Ok(())
+}
+
+impl<T: MempoolTransactionOperations + MempoolChainOperations> Sequencer for Memseq<T> {
 	async fn publish_many(&self, transactions: Vec<Transaction>) -> Result<(), anyhow::Error> {
+		for transaction in &transactions {
+			check_not_tagged(transaction)?;
+		}
 		self.mempool.add_transactions(transactions).await?;
 		self.changed.notify_waiters();
 		Ok(())
 	}
 
 	async fn publish(&self, transaction: Transaction) -> Result<(), anyhow::Error> {
+		check_not_tagged(&transaction)?;
 		self.mempool.add_transaction(transaction).await?;
 		self.changed.notify_waiters();
 		Ok(())
@@ -103,38 +232,7 @@ impl<T: MempoolTransactionOperations> Sequencer for Memseq<T> {
 
 	/// Waits for the next block to be built, either when the block size is reached or the building time expires.
 	async fn wait_for_next_block(&self) -> Result<Option<Block>, anyhow::Error> {
-		info!(target: "movement_timing",  "CALLED wait_for_next_block");
-		let mut transactions = Vec::with_capacity(self.block_size as usize);
-
-		let now = Instant::now();
-		let build_deadline = now + Duration::from_millis(self.building_time_ms);
-
-		loop {
-			let current_block_size = transactions.len() as u32;
-			if current_block_size >= self.block_size {
-				info!("block is above the size limit");
-				info!(target: "movement_timing",  "BREAK out of wait_for_next_block");
-				break;
-			}
-
-			let remaining = self.block_size - current_block_size;
-			let mut transactions_to_add = self.mempool.pop_transactions(remaining as usize).await?;
-			transactions.append(&mut transactions_to_add);
-
-			if let Err(_) = tokio::time::timeout_at(build_deadline, self.changed.notified()).await {
-				info!(target: "movement_timing",  "block building deadline elapsed");
-				info!(target: "movement_timing",  "BREAK out of wait_for_next_block");
-				break;
-			}
-		}
-
-		if transactions.is_empty() {
-			Ok(None)
-		} else {
-			let new_block =
-				self.build_next_block(block::BlockMetadata::default(), transactions).await?;
-			Ok(Some(new_block))
-		}
+		self.next_block().await
 	}
 
 	async fn gc(&self) -> Result<(), anyhow::Error> {
@@ -155,6 +253,28 @@ impl<T: MempoolTransactionOperations> Sequencer for Memseq<T> {
 	}
 }
 
+impl<T: MempoolTransactionOperations + MempoolChainOperations> sequencing_util::SharedSequencer
+	for Memseq<T>
+{
+	/// Publishes a bundle, to be placed in a block with all its transactions or not at all.
+	async fn publish(&self, bundle: AtomicTransactionBundle) -> Result<(), anyhow::Error> {
+		if bundle.len() > self.block_size as usize {
+			anyhow::bail!(
+				"AtomicTransactionBundle of {} transactions exceeds the block size {}",
+				bundle.len(),
+				self.block_size
+			);
+		}
+		self.mempool.add_transaction(Transaction::try_from(bundle)?).await?;
+		self.changed.notify_waiters();
+		Ok(())
+	}
+
+	async fn wait_for_next_block(&self) -> Result<Option<Block>, anyhow::Error> {
+		self.next_block().await
+	}
+}
+
 #[cfg(test)]
 pub mod test {
 
@@ -204,7 +324,7 @@ pub mod test {
 
 		let result = memseq.wait_for_next_block().await;
 		assert!(result.is_err());
-		assert_eq!(result.unwrap_err().to_string(), "Mock pop_mempool_transaction");
+		assert_eq!(result.unwrap_err().to_string(), "Mock next_mempool_transactions");
 
 		Ok(())
 	}
@@ -480,6 +600,115 @@ pub mod test {
 		Ok(())
 	}
 
+	/// Tests that a bundle is placed in a single block with all its transactions, and that each
+	/// consumer extracts its own transactions from the blocks.
+	#[tokio::test]
+	async fn test_shared_sequencer_bundle_atomic_inclusion() -> Result<(), anyhow::Error> {
+		use atomic_transaction_bundle::{Id as ConsumerId, TransactionEntry};
+		use sequencing_util::SharedSequencer;
+
+		let dir = tempdir()?;
+		let path = dir.path().to_path_buf();
+		let memseq = Memseq::try_move_rocks(path, 3, 250)?;
+
+		let rollup_a = ConsumerId::new([1; 32]);
+		let rollup_b = ConsumerId::new([2; 32]);
+		let bundle = AtomicTransactionBundle::new(
+			ConsumerId::default(),
+			vec![
+				TransactionEntry::new(rollup_a, Transaction::new(vec![10], 0, 0)),
+				TransactionEntry::new(rollup_b, Transaction::new(vec![20], 0, 0)),
+				TransactionEntry::new(rollup_a, Transaction::new(vec![11], 0, 1)),
+			],
+		);
+		Sequencer::publish(&memseq, Transaction::new(vec![1], 0, 0)).await?;
+		SharedSequencer::publish(&memseq, bundle.clone()).await?;
+		Sequencer::publish(&memseq, Transaction::new(vec![2], 0, 1)).await?;
+
+		// A bundle larger than a block can't be placed.
+		let too_large = AtomicTransactionBundle::new(
+			ConsumerId::default(),
+			(0..4)
+				.map(|i| TransactionEntry::new(rollup_a, Transaction::new(vec![i], 0, 0)))
+				.collect(),
+		);
+		assert!(SharedSequencer::publish(&memseq, too_large).await.is_err());
+
+		// A plain transaction can't pass for a bundle.
+		let forged_bundle = Transaction::try_from(AtomicTransactionBundle::new(
+			ConsumerId::default(),
+			vec![
+				TransactionEntry::new(rollup_b, Transaction::new(vec![30], 0, 0)),
+				TransactionEntry::new(rollup_b, Transaction::new(vec![31], 0, 1)),
+			],
+		))?;
+		let forged = Transaction::new(forged_bundle.data().to_vec(), 0, 2);
+		assert!(Sequencer::publish(&memseq, forged.clone()).await.is_err());
+		assert!(Sequencer::publish_many(&memseq, vec![forged]).await.is_err());
+
+		let mut blocks = Vec::new();
+		while let Some(block) = SharedSequencer::wait_for_next_block(&memseq).await? {
+			let size: usize =
+				block.transactions().map(AtomicTransactionBundle::block_transaction_size).sum();
+			assert!(size <= 3);
+			blocks.push(block);
+		}
+		assert_eq!(blocks.len(), 2);
+
+		let bundle_transaction = Transaction::try_from(bundle)?;
+		let including: Vec<&Block> = blocks
+			.iter()
+			.filter(|block| block.transactions().any(|tx| *tx == bundle_transaction))
+			.collect();
+		assert_eq!(including.len(), 1);
+		assert!(including[0].metadata().is_atomic_bundle(&bundle_transaction.id()));
+
+		let consumer_data = |consumer: &ConsumerId| -> Vec<Vec<u8>> {
+			blocks
+				.iter()
+				.flat_map(|block| block.consumer_transactions(consumer))
+				.map(|tx| tx.data().to_vec())
+				.collect()
+		};
+		assert_eq!(consumer_data(&rollup_a), vec![vec![10], vec![11]]);
+		assert_eq!(consumer_data(&rollup_b), vec![vec![20]]);
+		let mut plain = consumer_data(&ConsumerId::default());
+		plain.sort();
+		assert_eq!(plain, vec![vec![1], vec![2]]);
+
+		Ok(())
+	}
+
+	/// Tests that the chain tip is recovered after a restart and the chain stays consistent.
+	#[tokio::test]
+	async fn test_chain_tip_recovered_after_restart() -> Result<(), anyhow::Error> {
+		let dir = tempdir()?;
+		let path = dir.path().to_path_buf();
+
+		let first_block = {
+			let memseq = Memseq::try_move_rocks(path.clone(), 128, 250)?;
+			memseq.publish(Transaction::new(vec![1], 0, 0)).await?;
+			let block = memseq.wait_for_next_block().await?;
+			let block = block.ok_or(anyhow::anyhow!("Block not found"))?;
+			assert_eq!(memseq.chain_tip().await, (block.id(), 1));
+			block
+		};
+
+		let memseq = Memseq::try_move_rocks(path, 128, 250)?;
+		assert_eq!(memseq.chain_tip().await, (first_block.id(), 1));
+		memseq.publish(Transaction::new(vec![2], 0, 0)).await?;
+		let block = memseq.wait_for_next_block().await?;
+		let block = block.ok_or(anyhow::anyhow!("Block not found"))?;
+		assert_eq!(block.parent(), first_block.id());
+		assert_eq!(memseq.chain_tip().await, (block.id(), 2));
+
+		let report = memseq.verify_chain().await?;
+		assert!(report.is_consistent(), "{report}");
+		assert_eq!(report.blocks, 2);
+
+		Ok(())
+	}
+
 	/// Mock Mempool
 	struct MockMempool;
 	impl MempoolTransactionOperations for MockMempool {
@@ -539,6 +768,24 @@ pub mod test {
 			Err(anyhow::anyhow!("Mock pop_transaction"))
 		}
 	}
+
+	impl MempoolChainOperations for MockMempool {
+		async fn next_mempool_transactions(
+			&self,
+			_n: usize,
+			_skip: &BTreeSet<transaction::Id>,
+		) -> Result<Vec<MempoolTransaction>, anyhow::Error> {
+			Err(anyhow::anyhow!("Mock next_mempool_transactions"))
+		}
+
+		async fn append_block(&self, _block: Block, _height: u64) -> Result<(), anyhow::Error> {
+			Err(anyhow::anyhow!("Mock append_block"))
+		}
+
+		async fn get_chain_tip(&self) -> Result<Option<(block::Id, u64)>, anyhow::Error> {
+			Err(anyhow::anyhow!("Mock get_chain_tip"))
+		}
+	}
 }
 
 pub mod degradation_tests {}
@@ -21,6 +21,10 @@ pub struct Config {
 	/// The memseq max block size
 	#[serde(default = "default_memseq_max_block_size")]
 	pub memseq_max_block_size: u32,
+
+	/// The senders of the priority lane, e.g. the governance and admin accounts, as hex addresses
+	#[serde(default)]
+	pub memseq_priority_lane_senders: Vec<String>,
 }
 
 env_default!(default_memseq_build_time, "MEMSEQ_BUILD_TIME", u64, 500);
@@ -34,6 +38,7 @@ impl Default for Config {
 			sequencer_database_path: Config::default_sequencer_database_path(),
 			memseq_build_time: default_memseq_build_time(),
 			memseq_max_block_size: default_memseq_max_block_size(),
+			memseq_priority_lane_senders: Vec::new(),
 		}
 	}
 }
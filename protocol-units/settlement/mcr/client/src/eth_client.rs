@@ -1,8 +1,9 @@
 use crate::send_eth_transaction::InsufficentFunds;
 use crate::send_eth_transaction::SendTransactionErrorRule;
+use crate::send_eth_transaction::SendTransactionParams;
 use crate::send_eth_transaction::UnderPriced;
 use crate::send_eth_transaction::VerifyRule;
-use crate::{CommitmentStream, McrSettlementClientOperations};
+use crate::{AttesterCommitment, AttesterStake, CommitmentStream, McrSettlementClientOperations};
 use alloy::providers::fillers::ChainIdFiller;
 use alloy::providers::fillers::FillProvider;
 use alloy::providers::fillers::GasFiller;
@@ -20,6 +21,7 @@ use alloy_sol_types::sol;
 use alloy_transport::BoxTransport;
 use alloy_transport_ws::WsConnect;
 use anyhow::Context;
+use mcr_settlement_config::common::transactions;
 use mcr_settlement_config::Config;
 use movement_signer::cryptography::secp256k1::Secp256k1;
 use movement_signer_loader::Load;
@@ -29,6 +31,7 @@ use serde_json::Value as JsonValue;
 use std::array::TryFromSliceError;
 use std::fs;
 use std::path::Path;
+use std::time::Duration;
 use thiserror::Error;
 use tokio_stream::StreamExt;
 use tracing::info;
@@ -82,8 +85,7 @@ pub struct McrSettlementClient<P> {
 	pub signer_address: Address,
 	contract_address: Address,
 	send_transaction_error_rules: Vec<Box<dyn VerifyRule>>,
-	gas_limit: u64,
-	send_transaction_retries: u32,
+	send_transaction_params: SendTransactionParams,
 }
 
 impl
@@ -131,8 +133,7 @@ impl
 			ws_url,
 			signer_address,
 			contract_address,
-			config.transactions.gas_limit,
-			config.transactions.transaction_send_retries,
+			&config.transactions,
 		)
 		.await
 		.context(
@@ -149,8 +150,7 @@ impl<P> McrSettlementClient<P> {
 		ws_url: S,
 		signer_address: Address,
 		contract_address: Address,
-		gas_limit: u64,
-		send_transaction_retries: u32,
+		transactions: &transactions::Config,
 	) -> Result<Self, anyhow::Error>
 	where
 		P: Provider + Clone,
@@ -175,8 +175,13 @@ impl<P> McrSettlementClient<P> {
 			signer_address,
 			contract_address,
 			send_transaction_error_rules,
-			gas_limit,
-			send_transaction_retries,
+			send_transaction_params: SendTransactionParams {
+				signer_address,
+				number_retry: transactions.transaction_send_retries,
+				gas_limit: transactions.gas_limit as u128,
+				receipt_timeout: Duration::from_secs(transactions.transaction_receipt_timeout),
+				gas_price_bump_percent: transactions.gas_price_bump_percent as u128,
+			},
 		})
 	}
 }
@@ -206,8 +211,7 @@ where
 			crate::send_eth_transaction::send_transaction(
 				call_builder,
 				&self.send_transaction_error_rules,
-				self.send_transaction_retries,
-				self.gas_limit as u128,
+				&self.send_transaction_params,
 			)
 			.await
 		} else {
@@ -215,8 +219,7 @@ where
 			crate::send_eth_transaction::send_transaction(
 				call_builder,
 				&self.send_transaction_error_rules,
-				self.send_transaction_retries,
-				self.gas_limit as u128,
+				&self.send_transaction_params,
 			)
 			.await
 		}
@@ -249,8 +252,7 @@ where
 		crate::send_eth_transaction::send_transaction(
 			call_builder,
 			&self.send_transaction_error_rules,
-			self.send_transaction_retries,
-			self.gas_limit as u128,
+			&self.send_transaction_params,
 		)
 		.await
 	}
@@ -274,8 +276,7 @@ where
 		crate::send_eth_transaction::send_transaction(
 			call_builder,
 			&self.send_transaction_error_rules,
-			self.send_transaction_retries,
-			self.gas_limit as u128,
+			&self.send_transaction_params,
 		)
 		.await
 	}
@@ -361,6 +362,73 @@ where
 			.try_into()
 			.context("Failed to convert the max tolerable block height from U256 to u64")?)
 	}
+
+	async fn get_current_epoch(&self) -> Result<u64, anyhow::Error> {
+		let contract = MCR::new(self.contract_address, &self.ws_provider);
+		let MCR::getCurrentEpochReturn { _0: epoch } = contract.getCurrentEpoch().call().await?;
+		Ok(epoch
+			.try_into()
+			.context("Failed to convert the current epoch from U256 to u64")?)
+	}
+
+	async fn get_last_accepted_block_height(&self) -> Result<u64, anyhow::Error> {
+		let contract = MCR::new(self.contract_address, &self.ws_provider);
+		let MCR::lastAcceptedBlockHeightReturn { _0: block_height } =
+			contract.lastAcceptedBlockHeight().call().await?;
+		Ok(block_height
+			.try_into()
+			.context("Failed to convert the last accepted block height from U256 to u64")?)
+	}
+
+	async fn get_attester_stakes(&self) -> Result<Vec<AttesterStake>, anyhow::Error> {
+		let contract = MCR::new(self.contract_address, &self.ws_provider);
+		let MCR::getAttestersReturn { _0: attesters } = contract.getAttesters().call().await?;
+		let mut stakes = Vec::with_capacity(attesters.len());
+		for attester in attesters {
+			let MCR::computeAllCurrentEpochStakeReturn { _0: stake } =
+				contract.computeAllCurrentEpochStake(attester).call().await?;
+			stakes.push(AttesterStake {
+				attester: attester.to_string(),
+				stake: stake.try_into().context("Failed to convert the stake from U256 to u128")?,
+			});
+		}
+		Ok(stakes)
+	}
+
+	async fn get_attester_commitments_at_height(
+		&self,
+		height: u64,
+	) -> Result<Vec<AttesterCommitment>, anyhow::Error> {
+		let contract = MCR::new(self.contract_address, &self.ws_provider);
+		let MCR::getAttestersReturn { _0: attesters } = contract.getAttesters().call().await?;
+		let mut commitments = Vec::new();
+		for attester in attesters {
+			let MCR::getValidatorCommitmentAtBlockHeightReturn { _0: commitment } = contract
+				.getValidatorCommitmentAtBlockHeight(U256::from(height), attester)
+				.call()
+				.await?;
+			// Commitment with height 0 mean not found
+			if commitment.height.is_zero() {
+				continue;
+			}
+			let MCR::commitmentStakesReturn { stake } = contract
+				.commitmentStakes(U256::from(height), commitment.commitment)
+				.call()
+				.await?;
+			commitments.push(AttesterCommitment {
+				attester: attester.to_string(),
+				commitment: BlockCommitment::new(
+					height,
+					Id::new(commitment.blockId.into()),
+					Commitment::new(commitment.commitment.into()),
+				),
+				commitment_stake: stake
+					.try_into()
+					.context("Failed to convert the commitment stake from U256 to u128")?,
+			});
+		}
+		Ok(commitments)
+	}
 }
 
 pub struct AnvilAddressEntry {
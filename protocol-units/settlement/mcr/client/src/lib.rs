@@ -1,4 +1,5 @@
 use movement_types::block::BlockCommitment;
+use serde::{Deserialize, Serialize};
 use tokio_stream::Stream;
 pub mod mock;
 
@@ -13,9 +14,26 @@ pub use eth_client::McrSettlementClient;
 
 pub mod send_eth_transaction;
 
+pub mod simulator;
+
 type CommitmentStream =
 	std::pin::Pin<Box<dyn Stream<Item = Result<BlockCommitment, anyhow::Error>> + Send>>;
 
+/// The stake of an attester in the current epoch, over all the custodians.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttesterStake {
+	pub attester: String,
+	pub stake: u128,
+}
+
+/// A commitment posted by an attester, with the stake committed to it at its height.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttesterCommitment {
+	pub attester: String,
+	pub commitment: BlockCommitment,
+	pub commitment_stake: u128,
+}
+
 #[async_trait::async_trait]
 pub trait McrSettlementClientOperations {
 	/// Posts a block commitment to the settlement client.
@@ -54,4 +72,19 @@ pub trait McrSettlementClientOperations {
 
 	/// Gets the max tolerable block height.
 	async fn get_max_tolerable_block_height(&self) -> Result<u64, anyhow::Error>;
+
+	/// Gets the epoch up to which blocks have been accepted.
+	async fn get_current_epoch(&self) -> Result<u64, anyhow::Error>;
+
+	/// Gets the height of the last accepted block.
+	async fn get_last_accepted_block_height(&self) -> Result<u64, anyhow::Error>;
+
+	/// Gets the stake of each attester in the current epoch.
+	async fn get_attester_stakes(&self) -> Result<Vec<AttesterStake>, anyhow::Error>;
+
+	/// Gets the commitments the attesters have made at a given height.
+	async fn get_attester_commitments_at_height(
+		&self,
+		height: u64,
+	) -> Result<Vec<AttesterCommitment>, anyhow::Error>;
 }
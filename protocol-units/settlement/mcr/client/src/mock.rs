@@ -1,4 +1,4 @@
-use crate::{CommitmentStream, McrSettlementClientOperations};
+use crate::{AttesterCommitment, AttesterStake, CommitmentStream, McrSettlementClientOperations};
 use mcr_settlement_config::Config;
 use movement_types::block::BlockCommitment;
 use std::collections::BTreeMap;
@@ -7,14 +7,19 @@ use tokio::sync::{mpsc, RwLock};
 use tokio_stream::wrappers::ReceiverStream;
 use tracing::info;
 
+/// The attester of the commitments posted through the mock.
+pub const MOCK_ATTESTER: &str = "mock";
+
 #[derive(Clone)]
 pub struct McrSettlementClient {
 	commitments: Arc<RwLock<BTreeMap<u64, BlockCommitment>>>,
+	posted_commitments: Arc<RwLock<BTreeMap<u64, BlockCommitment>>>,
 	stream_sender: mpsc::Sender<Result<BlockCommitment, anyhow::Error>>,
 	stream_receiver: Arc<Mutex<Option<mpsc::Receiver<Result<BlockCommitment, anyhow::Error>>>>>,
 	pub current_height: Arc<RwLock<u64>>,
 	pub block_lead_tolerance: u64,
 	paused_at_height: Arc<RwLock<Option<u64>>>,
+	failing_posts: Arc<RwLock<u32>>,
 }
 
 impl McrSettlementClient {
@@ -22,11 +27,13 @@ impl McrSettlementClient {
 		let (stream_sender, receiver) = mpsc::channel(10);
 		McrSettlementClient {
 			commitments: Arc::new(RwLock::new(BTreeMap::new())),
+			posted_commitments: Arc::new(RwLock::new(BTreeMap::new())),
 			stream_sender,
 			stream_receiver: Arc::new(Mutex::new(Some(receiver))),
 			current_height: Arc::new(RwLock::new(0)),
 			block_lead_tolerance: 16,
 			paused_at_height: Arc::new(RwLock::new(None)),
+			failing_posts: Arc::new(RwLock::new(0)),
 		}
 	}
 
@@ -44,6 +51,25 @@ impl McrSettlementClient {
 		commitments.insert(commitment.height(), commitment);
 	}
 
+	/// Settles a commitment as if other validators had posted it, and streams it
+	/// even if streaming is paused.
+	pub async fn accept_block_commitment(&self, commitment: BlockCommitment) {
+		let height = commitment.height();
+		self.commitments.write().await.insert(height, commitment.clone());
+		{
+			let mut current_height = self.current_height.write().await;
+			if height > *current_height {
+				*current_height = height;
+			}
+		}
+		self.stream_sender.send(Ok(commitment)).await.unwrap();
+	}
+
+	/// Make the next `count` batch postings fail, as a dropped L1 transaction would.
+	pub async fn fail_next_posts(&self, count: u32) {
+		*self.failing_posts.write().await = count;
+	}
+
 	/// Stop streaming commitments after the given height.
 	///
 	/// Any posted commitments will be accumulated.
@@ -77,6 +103,7 @@ impl McrSettlementClientOperations for McrSettlementClient {
 		block_commitment: BlockCommitment,
 	) -> Result<(), anyhow::Error> {
 		let height = block_commitment.height();
+		self.posted_commitments.write().await.insert(height, block_commitment.clone());
 
 		let settled = {
 			let mut commitments = self.commitments.write().await;
@@ -106,6 +133,13 @@ impl McrSettlementClientOperations for McrSettlementClient {
 		&self,
 		block_commitment: Vec<BlockCommitment>,
 	) -> Result<(), anyhow::Error> {
+		{
+			let mut failing_posts = self.failing_posts.write().await;
+			if *failing_posts > 0 {
+				*failing_posts -= 1;
+				anyhow::bail!("Posting of the commitment batch failed");
+			}
+		}
 		for commitment in block_commitment {
 			self.post_block_commitment(commitment).await?;
 		}
@@ -121,9 +155,9 @@ impl McrSettlementClientOperations for McrSettlementClient {
 
 	async fn get_posted_commitment_at_height(
 		&self,
-		_height: u64,
+		height: u64,
 	) -> Result<Option<BlockCommitment>, anyhow::Error> {
-		unimplemented!();
+		Ok(self.posted_commitments.read().await.get(&height).cloned())
 	}
 
 	async fn stream_block_commitments(&self) -> Result<CommitmentStream, anyhow::Error> {
@@ -147,6 +181,34 @@ impl McrSettlementClientOperations for McrSettlementClient {
 	async fn get_max_tolerable_block_height(&self) -> Result<u64, anyhow::Error> {
 		Ok(*self.current_height.read().await + self.block_lead_tolerance)
 	}
+
+	async fn get_current_epoch(&self) -> Result<u64, anyhow::Error> {
+		Ok(0)
+	}
+
+	async fn get_last_accepted_block_height(&self) -> Result<u64, anyhow::Error> {
+		Ok(*self.current_height.read().await)
+	}
+
+	/// The mock has no stakes, each posted commitment is accepted.
+	async fn get_attester_stakes(&self) -> Result<Vec<AttesterStake>, anyhow::Error> {
+		Ok(vec![])
+	}
+
+	async fn get_attester_commitments_at_height(
+		&self,
+		height: u64,
+	) -> Result<Vec<AttesterCommitment>, anyhow::Error> {
+		let posted = self.posted_commitments.read().await.get(&height).cloned();
+		Ok(posted
+			.into_iter()
+			.map(|commitment| AttesterCommitment {
+				attester: MOCK_ATTESTER.to_string(),
+				commitment,
+				commitment_stake: 0,
+			})
+			.collect())
+	}
 }
 
 #[cfg(test)]
@@ -3,9 +3,12 @@ use alloy::providers::Provider;
 use alloy_contract::CallBuilder;
 use alloy_contract::CallDecoder;
 use alloy_network::Ethereum;
+use alloy_primitives::{Address, TxHash};
 use alloy_transport::{Transport, TransportError};
 use std::marker::PhantomData;
-use tracing::info;
+use std::mem;
+use std::time::Duration;
+use tracing::{info, warn};
 
 // Define a rule to verify the error generated when a transaction is send to determine if:
 // * the Transaction must me resend with more gas: return Ok(true)
@@ -59,6 +62,24 @@ impl VerifyRule for SendTransactionErrorRule<InsufficentFunds> {
 	}
 }
 
+/// Gas and nonce settings of [send_transaction].
+#[derive(Debug, Clone)]
+pub struct SendTransactionParams {
+	/// Address of the signer, to read its nonce.
+	pub signer_address: Address,
+	pub number_retry: u32,
+	/// Maximum fee of the transaction, in wei.
+	pub gas_limit: u128,
+	/// Time to wait for the receipt before resubmitting the transaction.
+	pub receipt_timeout: Duration,
+	/// Increase of the gas price of a resubmitted transaction, in percent.
+	pub gas_price_bump_percent: u128,
+}
+
+fn bump(value: u128, percent: u128) -> u128 {
+	value + (value * percent).div_ceil(100)
+}
+
 pub async fn send_transaction<
 	P: Provider<T, Ethereum> + Clone,
 	T: Transport + Clone,
@@ -66,10 +87,10 @@ pub async fn send_transaction<
 >(
 	base_call_builder: CallBuilder<T, &&P, D, Ethereum>,
 	send_transaction_error_rules: &[Box<dyn VerifyRule>],
-	number_retry: u32,
-	gas_limit: u128,
+	params: &SendTransactionParams,
 ) -> Result<(), anyhow::Error> {
-	info!("Sending transaction with gas limit: {}", gas_limit);
+	info!("Sending transaction with gas limit: {}", params.gas_limit);
+	let provider = base_call_builder.provider;
 	//validate gas price.
 	let mut estimate_gas = base_call_builder.estimate_gas().await.expect("Failed to estimate gas");
 	// Add 20% because initial gas estimate are too low.
@@ -77,48 +98,95 @@ pub async fn send_transaction<
 
 	println!("estimated_gas: {}", estimate_gas);
 
+	// Use the nonce of the next mined transaction rather than the pending one, so a transaction
+	// of a previous run stuck in the mempool is replaced instead of blocking this one.
+	let mut nonce = provider.get_transaction_count(params.signer_address).latest().await?;
+	let mut gas_price = provider.get_gas_price().await?;
+	// The transactions sent with the current nonce, one of them can be mined after a replacement.
+	let mut sent_transactions: Vec<TxHash> = Vec::new();
+
 	// Sending Transaction automatically can lead to errors that depend on the state for Eth.
 	// It's convenient to manage some of them automatically to avoid to fail commitment Transaction.
 	// I define a first one but other should be added depending on the test with mainnet.
-	for _ in 0..number_retry {
-		let call_builder = base_call_builder.clone().gas(estimate_gas);
+	'send: for _ in 0..params.number_retry {
+		let call_builder =
+			base_call_builder.clone().nonce(nonce).gas(estimate_gas).gas_price(gas_price);
 
 		//detect if the gas price doesn't execeed the limit.
-		let gas_price = call_builder.provider.get_gas_price().await?;
 		let transaction_fee_wei = estimate_gas * gas_price;
-		if transaction_fee_wei > gas_limit {
-			return Err(McrEthConnectorError::GasLimitExceed(transaction_fee_wei, gas_limit).into());
+		if transaction_fee_wei > params.gas_limit {
+			return Err(McrEthConnectorError::GasLimitExceed(
+				transaction_fee_wei,
+				params.gas_limit,
+			)
+			.into());
 		}
 
-		info!("Sending transaction with gas: {}", estimate_gas);
+		info!(
+			"Sending transaction with nonce: {nonce}, gas: {estimate_gas}, gas price: {gas_price}"
+		);
 
 		//send the Transaction and detect send error.
 		let pending_transaction = match call_builder.send().await {
 			Ok(pending_transaction) => pending_transaction,
 			Err(err) => {
+				if is_nonce_too_low(&err) {
+					// A transaction with this nonce was mined meanwhile, maybe a previous attempt.
+					for hash in mem::take(&mut sent_transactions) {
+						if let Some(receipt) = provider.get_transaction_receipt(hash).await? {
+							if receipt.status() {
+								info!("Transaction {hash} was mined before its replacement");
+								return Ok(());
+							}
+						}
+					}
+					nonce = provider.get_transaction_count(params.signer_address).latest().await?;
+					continue 'send;
+				}
 				//apply defined rules.
 				for rule in send_transaction_error_rules {
 					// Verify all rules. If one rule return true or an error stop verification.
-					// If true retry with more gas else return the error.
+					// If true retry with a higher gas price else return the error.
 					if rule.verify(&err)? {
-						//increase gas of 10% and retry
-						estimate_gas += (estimate_gas * 10) / 100;
+						let network_gas_price = provider.get_gas_price().await?;
+						gas_price =
+							bump(gas_price.max(network_gas_price), params.gas_price_bump_percent);
 						tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-						continue;
+						continue 'send;
 					}
 				}
 
 				return Err(McrEthConnectorError::from(err).into());
 			}
 		};
+		let hash = *pending_transaction.tx_hash();
+		sent_transactions.push(hash);
+
+		let receipt =
+			match tokio::time::timeout(params.receipt_timeout, pending_transaction.get_receipt())
+				.await
+			{
+				Ok(receipt) => receipt,
+				Err(_) => {
+					// The transaction is stuck, replace it with a higher gas price.
+					let network_gas_price = provider.get_gas_price().await?;
+					gas_price =
+						bump(gas_price.max(network_gas_price), params.gas_price_bump_percent);
+					warn!("No receipt for transaction {hash}, resubmit with gas price {gas_price}");
+					continue 'send;
+				}
+			};
 
-		match pending_transaction.get_receipt().await {
+		match receipt {
 			// Transaction execution fail
 			Ok(transaction_receipt) if !transaction_receipt.status() => {
 				tracing::debug!(
 					"transaction_receipt.gas_used: {} / estimate_gas: {estimate_gas}",
 					transaction_receipt.gas_used
 				);
+				// The nonce is used by the failed transaction.
+				sent_transactions.clear();
+				nonce += 1;
 				// Some valid Tx can abort cause of insufficient gas without consuming all its gas.
 				// Define a threshold a little less than estimated gas to detect them.
 				let tx_gas_consumption_threshold = estimate_gas - (estimate_gas * 10) / 100;
@@ -146,3 +214,26 @@ pub async fn send_transaction<
 	)
 	.into())
 }
+
+fn is_nonce_too_low(error: &alloy_contract::Error) -> bool {
+	matches!(
+		error,
+		alloy_contract::Error::TransportError(TransportError::ErrorResp(payload))
+			if payload.message.contains("nonce too low")
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_bump() {
+		assert_eq!(bump(1_000, 10), 1_100);
+		assert_eq!(bump(1_000, 0), 1_000);
+		// The increase is rounded up, so a bumped small gas price still replaces the transaction.
+		assert_eq!(bump(5, 10), 6);
+		assert_eq!(bump(1, 12), 2);
+		assert_eq!(bump(0, 10), 0);
+	}
+}
@@ -0,0 +1,680 @@
+//! In-process simulator of the MCR contract.
+//!
+//! [McrSimulator] follows the semantics of `MCR.sol` and of the staking contract it relies on:
+//! the commitments of the attesters are weighted by their stake in the epoch of the block
+//! height, a height is accepted once a commitment gets more than two thirds of the stake,
+//! and the heights are accepted in order up to the max tolerable block height.
+//! A transaction that reverts on the contract leaves the simulated state unchanged.
+//!
+//! Each attester posts through its own [McrSimulatorClient], so multi-validator settlement
+//! scenarios run in plain `cargo test`, without anvil or the contract artifacts.
+
+use crate::{AttesterCommitment, AttesterStake, CommitmentStream, McrSettlementClientOperations};
+use alloy_primitives::Address;
+use async_stream::stream;
+use movement_types::block::{BlockCommitment, Commitment};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tokio::sync::broadcast;
+
+/// The reverts of the simulated contract.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum McrSimulatorError {
+	#[error("UNAUTHORIZED_BLOCK_COMMITMENT: {0} is not a trusted attester")]
+	UnauthorizedBlockCommitment(Address),
+	#[error("AttesterAlreadyCommitted: {attester} already committed at height {height}")]
+	AttesterAlreadyCommitted { attester: Address, height: u64 },
+	/// The contract reverts with `AttesterAlreadyCommitted` in this case too.
+	#[error("Height {height} is above the max tolerable block height {max_tolerable_height}")]
+	HeightNotTolerable { height: u64, max_tolerable_height: u64 },
+	#[error("UnacceptableBlockCommitment: height {height} is not assigned to the current epoch")]
+	UnacceptableBlockCommitment { height: u64 },
+	#[error("Only the commitment admins can set accepted commitments, {0} is not one")]
+	NotCommitmentAdmin(Address),
+}
+
+/// Settings of the simulated contract, as given to its `initialize` function.
+#[derive(Debug, Clone)]
+pub struct McrSimulatorParams {
+	pub last_accepted_block_height: u64,
+	/// Number of heights that can be committed ahead of the last accepted one.
+	pub leading_block_tolerance: u64,
+	pub epoch_duration_secs: u64,
+	/// Stake removed from an attester whose commitment lost the vote on a height.
+	/// The contract doesn't slash yet, so the default is 0.
+	pub slash_amount: u64,
+}
+
+impl Default for McrSimulatorParams {
+	fn default() -> Self {
+		McrSimulatorParams {
+			last_accepted_block_height: 0,
+			leading_block_tolerance: 10,
+			epoch_duration_secs: 7200,
+			slash_amount: 0,
+		}
+	}
+}
+
+/// An attester whose commitment differs from the accepted one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slash {
+	pub height: u64,
+	pub attester: Address,
+	pub commitment: BlockCommitment,
+	/// Stake removed from the next epoch.
+	pub amount: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct McrState {
+	params: McrSimulatorParams,
+	block_timestamp: u64,
+	current_epoch: u64,
+	/// The stakes of the current epoch.
+	stakes: HashMap<Address, u64>,
+	/// The stakes of the next epoch, they include the stakes and unstakes of this epoch.
+	next_stakes: HashMap<Address, u64>,
+	attesters: BTreeSet<Address>,
+	trusted_attesters: HashSet<Address>,
+	commitment_admins: HashSet<Address>,
+	open_attestation_enabled: bool,
+	last_accepted_block_height: u64,
+	block_height_epoch_assignments: HashMap<u64, u64>,
+	commitments: HashMap<u64, HashMap<Address, BlockCommitment>>,
+	commitment_stakes: HashMap<u64, HashMap<Commitment, u64>>,
+	accepted_blocks_version: u64,
+	versioned_accepted_blocks: BTreeMap<(u64, u64), BlockCommitment>,
+	slashes: Vec<Slash>,
+	/// The `BlockAccepted` events of the running transaction.
+	events: Vec<BlockCommitment>,
+}
+
+impl McrState {
+	fn epoch_by_block_time(&self) -> u64 {
+		self.block_timestamp / self.params.epoch_duration_secs
+	}
+
+	fn total_stake(&self) -> u64 {
+		self.stakes.values().sum()
+	}
+
+	fn roll_over_epoch(&mut self) {
+		self.current_epoch += 1;
+		self.stakes = self.next_stakes.clone();
+	}
+
+	fn max_tolerable_block_height(&self) -> u64 {
+		self.last_accepted_block_height + self.params.leading_block_tolerance
+	}
+
+	fn accepted_commitment_at(&self, height: u64) -> Option<BlockCommitment> {
+		self.versioned_accepted_blocks
+			.get(&(self.accepted_blocks_version, height))
+			.cloned()
+	}
+
+	fn submit(
+		&mut self,
+		attester: Address,
+		block_commitment: BlockCommitment,
+	) -> Result<(), McrSimulatorError> {
+		if !self.open_attestation_enabled && !self.trusted_attesters.contains(&attester) {
+			return Err(McrSimulatorError::UnauthorizedBlockCommitment(attester));
+		}
+		let height = block_commitment.height();
+		if self.commitments.get(&height).is_some_and(|c| c.contains_key(&attester)) {
+			return Err(McrSimulatorError::AttesterAlreadyCommitted { attester, height });
+		}
+		// Commitments to already accepted heights are allowed for lagging attesters.
+		let max_tolerable_height = self.max_tolerable_block_height();
+		if height > max_tolerable_height {
+			return Err(McrSimulatorError::HeightNotTolerable { height, max_tolerable_height });
+		}
+
+		let epoch = self.epoch_by_block_time();
+		self.block_height_epoch_assignments.entry(height).or_insert(epoch);
+		let stake = self.stakes.get(&attester).copied().unwrap_or(0);
+		*self
+			.commitment_stakes
+			.entry(height)
+			.or_default()
+			.entry(block_commitment.commitment())
+			.or_insert(0) += stake;
+		self.commitments.entry(height).or_default().insert(attester, block_commitment);
+
+		// Accept the following heights in order, this is what lets batches settle.
+		while self.tick_on_block_height(self.last_accepted_block_height + 1)? {}
+		Ok(())
+	}
+
+	fn tick_on_block_height(&mut self, height: u64) -> Result<bool, McrSimulatorError> {
+		let Some(block_epoch) = self.block_height_epoch_assignments.get(&height).copied() else {
+			return Ok(false);
+		};
+		// No commitments in the epochs between, roll over until the epoch of the height.
+		while self.current_epoch < block_epoch {
+			self.roll_over_epoch();
+		}
+
+		let supermajority = (2 * self.total_stake()) / 3;
+		let Some(commitments) = self.commitments.get(&height) else {
+			return Ok(false);
+		};
+		let accepted = self.attesters.iter().find_map(|attester| {
+			let block_commitment = commitments.get(attester)?;
+			let stake = self
+				.commitment_stakes
+				.get(&height)
+				.and_then(|stakes| stakes.get(&block_commitment.commitment()))
+				.copied()
+				.unwrap_or(0);
+			(stake > supermajority).then(|| block_commitment.clone())
+		});
+		match accepted {
+			Some(block_commitment) => {
+				self.accept_block_commitment(block_commitment)?;
+				Ok(true)
+			}
+			None => Ok(false),
+		}
+	}
+
+	fn accept_block_commitment(
+		&mut self,
+		block_commitment: BlockCommitment,
+	) -> Result<(), McrSimulatorError> {
+		let height = block_commitment.height();
+		if self.block_height_epoch_assignments.get(&height) != Some(&self.current_epoch) {
+			return Err(McrSimulatorError::UnacceptableBlockCommitment { height });
+		}
+		self.versioned_accepted_blocks
+			.insert((self.accepted_blocks_version, height), block_commitment.clone());
+		self.last_accepted_block_height = height;
+		self.slash_minority(&block_commitment);
+		self.events.push(block_commitment);
+
+		if self.epoch_by_block_time() > self.current_epoch {
+			self.roll_over_epoch();
+		}
+		Ok(())
+	}
+
+	fn slash_minority(&mut self, accepted: &BlockCommitment) {
+		let Some(commitments) = self.commitments.get(&accepted.height()) else {
+			return;
+		};
+		let mut slashes: Vec<Slash> = commitments
+			.iter()
+			.filter(|(_, c)| c.commitment() != accepted.commitment())
+			.map(|(attester, commitment)| Slash {
+				height: accepted.height(),
+				attester: *attester,
+				commitment: commitment.clone(),
+				amount: self.params.slash_amount,
+			})
+			.collect();
+		slashes.sort_by_key(|slash| slash.attester);
+		for slash in &slashes {
+			if let Some(stake) = self.next_stakes.get_mut(&slash.attester) {
+				*stake = stake.saturating_sub(slash.amount);
+			}
+		}
+		self.slashes.extend(slashes);
+	}
+
+	fn force_latest_commitment(&mut self, block_commitment: BlockCommitment) {
+		// A new version drops all the previously accepted commitments.
+		self.accepted_blocks_version += 1;
+		self.last_accepted_block_height = block_commitment.height();
+		self.versioned_accepted_blocks
+			.insert((self.accepted_blocks_version, block_commitment.height()), block_commitment);
+	}
+}
+
+/// The simulated MCR contract, shared by the clients of the attesters.
+#[derive(Debug, Clone)]
+pub struct McrSimulator {
+	state: Arc<Mutex<McrState>>,
+	events: broadcast::Sender<BlockCommitment>,
+}
+
+impl McrSimulator {
+	pub fn new(params: McrSimulatorParams) -> Self {
+		let (events, _) = broadcast::channel(1024);
+		let state = McrState {
+			last_accepted_block_height: params.last_accepted_block_height,
+			params,
+			..Default::default()
+		};
+		McrSimulator { state: Arc::new(Mutex::new(state)), events }
+	}
+
+	/// Run a transaction on the contract. If it fails, the state is left unchanged.
+	fn transact<T>(
+		&self,
+		f: impl FnOnce(&mut McrState) -> Result<T, McrSimulatorError>,
+	) -> Result<T, McrSimulatorError> {
+		let mut state = self.state.lock().unwrap();
+		let mut next = state.clone();
+		let result = f(&mut next)?;
+		for event in next.events.drain(..) {
+			// There may be no subscribers.
+			let _ = self.events.send(event);
+		}
+		*state = next;
+		Ok(result)
+	}
+
+	fn read<T>(&self, f: impl FnOnce(&McrState) -> T) -> T {
+		f(&self.state.lock().unwrap())
+	}
+
+	/// The client posting the commitments of the attester.
+	pub fn client(&self, attester: Address) -> McrSimulatorClient {
+		McrSimulatorClient { mcr: self.clone(), attester, admin_mode: false }
+	}
+
+	/// The client of a commitment admin, posting with `forceLatestCommitment`.
+	pub fn admin_client(&self, admin: Address) -> McrSimulatorClient {
+		self.state.lock().unwrap().commitment_admins.insert(admin);
+		McrSimulatorClient { mcr: self.clone(), attester: admin, admin_mode: true }
+	}
+
+	/// Set the timestamp of the next blocks of the L1, it selects the epoch of new heights.
+	pub fn set_block_timestamp(&self, timestamp_secs: u64) {
+		self.state.lock().unwrap().block_timestamp = timestamp_secs;
+	}
+
+	/// Advance the timestamp of the L1 by a number of epochs.
+	pub fn advance_epochs(&self, epochs: u64) {
+		let mut state = self.state.lock().unwrap();
+		state.block_timestamp += epochs * state.params.epoch_duration_secs;
+	}
+
+	/// Stake for the attester, it counts from the next epoch.
+	pub fn stake(&self, attester: Address, amount: u64) {
+		let mut state = self.state.lock().unwrap();
+		state.attesters.insert(attester);
+		*state.next_stakes.entry(attester).or_insert(0) += amount;
+	}
+
+	/// Unstake for the attester, it counts from the next epoch.
+	pub fn unstake(&self, attester: Address, amount: u64) {
+		let mut state = self.state.lock().unwrap();
+		if let Some(stake) = state.next_stakes.get_mut(&attester) {
+			*stake = stake.saturating_sub(amount);
+		}
+	}
+
+	/// Start the first epoch with the stakes staked so far.
+	pub fn accept_genesis_ceremony(&self) {
+		let mut state = self.state.lock().unwrap();
+		state.stakes = state.next_stakes.clone();
+	}
+
+	pub fn grant_trusted_attester(&self, attester: Address) {
+		self.state.lock().unwrap().trusted_attesters.insert(attester);
+	}
+
+	pub fn set_open_attestation_enabled(&self, enabled: bool) {
+		self.state.lock().unwrap().open_attestation_enabled = enabled;
+	}
+
+	pub fn submit_block_commitment(
+		&self,
+		attester: Address,
+		block_commitment: BlockCommitment,
+	) -> Result<(), McrSimulatorError> {
+		self.transact(|state| state.submit(attester, block_commitment))
+	}
+
+	/// Submit the commitments in one transaction, none is recorded if one of them fails.
+	pub fn submit_batch_block_commitment(
+		&self,
+		attester: Address,
+		block_commitments: Vec<BlockCommitment>,
+	) -> Result<(), McrSimulatorError> {
+		self.transact(|state| {
+			block_commitments
+				.into_iter()
+				.try_for_each(|block_commitment| state.submit(attester, block_commitment))
+		})
+	}
+
+	/// Accept the commitment and drop the commitments accepted before it.
+	/// This is only safe with a single validator, the following commitments are kept.
+	pub fn force_latest_commitment(&self, block_commitment: BlockCommitment) {
+		let _ = self.transact(|state| {
+			state.force_latest_commitment(block_commitment);
+			Ok(())
+		});
+	}
+
+	/// Set the accepted commitment at a height, without changing the last accepted height.
+	pub fn set_accepted_commitment_at_block_height(
+		&self,
+		admin: Address,
+		block_commitment: BlockCommitment,
+	) -> Result<(), McrSimulatorError> {
+		self.transact(|state| {
+			if !state.commitment_admins.contains(&admin) {
+				return Err(McrSimulatorError::NotCommitmentAdmin(admin));
+			}
+			state.versioned_accepted_blocks.insert(
+				(state.accepted_blocks_version, block_commitment.height()),
+				block_commitment,
+			);
+			Ok(())
+		})
+	}
+
+	pub fn accepted_commitment_at(&self, height: u64) -> Option<BlockCommitment> {
+		self.read(|state| state.accepted_commitment_at(height))
+	}
+
+	pub fn validator_commitment_at(
+		&self,
+		height: u64,
+		attester: Address,
+	) -> Option<BlockCommitment> {
+		self.read(|state| state.commitments.get(&height)?.get(&attester).cloned())
+	}
+
+	pub fn last_accepted_block_height(&self) -> u64 {
+		self.read(|state| state.last_accepted_block_height)
+	}
+
+	pub fn max_tolerable_block_height(&self) -> u64 {
+		self.read(|state| state.max_tolerable_block_height())
+	}
+
+	pub fn current_epoch(&self) -> u64 {
+		self.read(|state| state.current_epoch)
+	}
+
+	pub fn epoch_by_block_time(&self) -> u64 {
+		self.read(|state| state.epoch_by_block_time())
+	}
+
+	/// The stake of the attester in the current epoch.
+	pub fn current_epoch_stake(&self, attester: Address) -> u64 {
+		self.read(|state| state.stakes.get(&attester).copied().unwrap_or(0))
+	}
+
+	/// The attesters whose commitments lost the vote, in the order of the accepted heights.
+	pub fn slashes(&self) -> Vec<Slash> {
+		self.read(|state| state.slashes.clone())
+	}
+}
+
+/// The settlement client of an attester on the simulated contract.
+#[derive(Debug, Clone)]
+pub struct McrSimulatorClient {
+	mcr: McrSimulator,
+	attester: Address,
+	admin_mode: bool,
+}
+
+impl McrSimulatorClient {
+	pub fn attester(&self) -> Address {
+		self.attester
+	}
+
+	pub fn simulator(&self) -> &McrSimulator {
+		&self.mcr
+	}
+}
+
+#[async_trait::async_trait]
+impl McrSettlementClientOperations for McrSimulatorClient {
+	async fn post_block_commitment(
+		&self,
+		block_commitment: BlockCommitment,
+	) -> Result<(), anyhow::Error> {
+		if self.admin_mode {
+			self.mcr.force_latest_commitment(block_commitment);
+			return Ok(());
+		}
+		Ok(self.mcr.submit_block_commitment(self.attester, block_commitment)?)
+	}
+
+	async fn post_block_commitment_batch(
+		&self,
+		block_commitment: Vec<BlockCommitment>,
+	) -> Result<(), anyhow::Error> {
+		Ok(self.mcr.submit_batch_block_commitment(self.attester, block_commitment)?)
+	}
+
+	async fn force_block_commitment(
+		&self,
+		block_commitment: BlockCommitment,
+	) -> Result<(), anyhow::Error> {
+		self.mcr.force_latest_commitment(block_commitment);
+		Ok(())
+	}
+
+	async fn stream_block_commitments(&self) -> Result<CommitmentStream, anyhow::Error> {
+		let mut receiver = self.mcr.events.subscribe();
+		Ok(Box::pin(stream! {
+			loop {
+				match receiver.recv().await {
+					Ok(block_commitment) => yield Ok(block_commitment),
+					Err(broadcast::error::RecvError::Lagged(skipped)) => {
+						yield Err(anyhow::anyhow!("Missed {} BlockAccepted events", skipped));
+					}
+					Err(broadcast::error::RecvError::Closed) => break,
+				}
+			}
+		}))
+	}
+
+	async fn get_commitment_at_height(
+		&self,
+		height: u64,
+	) -> Result<Option<BlockCommitment>, anyhow::Error> {
+		Ok(self.mcr.accepted_commitment_at(height))
+	}
+
+	async fn get_posted_commitment_at_height(
+		&self,
+		height: u64,
+	) -> Result<Option<BlockCommitment>, anyhow::Error> {
+		Ok(self.mcr.validator_commitment_at(height, self.attester))
+	}
+
+	async fn get_max_tolerable_block_height(&self) -> Result<u64, anyhow::Error> {
+		Ok(self.mcr.max_tolerable_block_height())
+	}
+
+	async fn get_current_epoch(&self) -> Result<u64, anyhow::Error> {
+		Ok(self.mcr.current_epoch())
+	}
+
+	async fn get_last_accepted_block_height(&self) -> Result<u64, anyhow::Error> {
+		Ok(self.mcr.last_accepted_block_height())
+	}
+
+	async fn get_attester_stakes(&self) -> Result<Vec<AttesterStake>, anyhow::Error> {
+		Ok(self.mcr.read(|state| {
+			state
+				.attesters
+				.iter()
+				.map(|attester| AttesterStake {
+					attester: attester.to_string(),
+					stake: state.stakes.get(attester).copied().unwrap_or(0) as u128,
+				})
+				.collect()
+		}))
+	}
+
+	async fn get_attester_commitments_at_height(
+		&self,
+		height: u64,
+	) -> Result<Vec<AttesterCommitment>, anyhow::Error> {
+		Ok(self.mcr.read(|state| {
+			let Some(commitments) = state.commitments.get(&height) else {
+				return vec![];
+			};
+			state
+				.attesters
+				.iter()
+				.filter_map(|attester| {
+					let commitment = commitments.get(attester)?.clone();
+					let commitment_stake = state
+						.commitment_stakes
+						.get(&height)
+						.and_then(|stakes| stakes.get(&commitment.commitment()))
+						.copied()
+						.unwrap_or(0);
+					Some(AttesterCommitment {
+						attester: attester.to_string(),
+						commitment,
+						commitment_stake: commitment_stake as u128,
+					})
+				})
+				.collect()
+		}))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use movement_types::block::Id;
+	use tokio_stream::StreamExt;
+
+	fn commitment(height: u64, byte: u8) -> BlockCommitment {
+		BlockCommitment::new(height, Id::new([height as u8; 32]), Commitment::new([byte; 32]))
+	}
+
+	/// A simulator with attesters staking the amounts, trusted and staked from genesis.
+	fn simulator(stakes: &[u64]) -> (McrSimulator, Vec<McrSimulatorClient>) {
+		let mcr = McrSimulator::new(McrSimulatorParams::default());
+		let clients = stakes
+			.iter()
+			.enumerate()
+			.map(|(i, stake)| {
+				let attester = Address::with_last_byte(i as u8 + 1);
+				mcr.stake(attester, *stake);
+				mcr.grant_trusted_attester(attester);
+				mcr.client(attester)
+			})
+			.collect();
+		mcr.accept_genesis_ceremony();
+		(mcr, clients)
+	}
+
+	#[tokio::test]
+	async fn test_supermajority_of_stake_accepts() -> Result<(), anyhow::Error> {
+		let (mcr, clients) = simulator(&[40, 30, 30]);
+		let mut stream = clients[0].stream_block_commitments().await?;
+
+		// The minority commits first, then 40 + 30 out of 100 is a supermajority.
+		clients[2].post_block_commitment(commitment(1, 9)).await?;
+		clients[0].post_block_commitment(commitment(1, 1)).await?;
+		assert_eq!(clients[0].get_commitment_at_height(1).await?, None);
+		clients[1].post_block_commitment(commitment(1, 1)).await?;
+
+		let accepted = stream.next().await.expect("stream has ended")?;
+		assert_eq!(accepted, commitment(1, 1));
+		assert_eq!(clients[2].get_commitment_at_height(1).await?, Some(commitment(1, 1)));
+		assert_eq!(clients[2].get_posted_commitment_at_height(1).await?, Some(commitment(1, 9)));
+		assert_eq!(mcr.last_accepted_block_height(), 1);
+
+		let slashes = mcr.slashes();
+		assert_eq!(slashes.len(), 1);
+		assert_eq!(slashes[0].attester, clients[2].attester());
+		assert_eq!(slashes[0].commitment, commitment(1, 9));
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_batches_are_accepted_in_order() -> Result<(), anyhow::Error> {
+		let (mcr, clients) = simulator(&[50, 50]);
+		let mut stream = clients[1].stream_block_commitments().await?;
+
+		let batch = vec![commitment(1, 1), commitment(2, 2), commitment(3, 3)];
+		clients[0].post_block_commitment_batch(batch.clone()).await?;
+		assert_eq!(mcr.last_accepted_block_height(), 0);
+		// The second attester lags behind, each height is accepted once both committed.
+		clients[1].post_block_commitment(commitment(2, 2)).await?;
+		assert_eq!(mcr.last_accepted_block_height(), 0);
+		clients[1]
+			.post_block_commitment_batch(vec![commitment(1, 1), commitment(3, 3)])
+			.await?;
+
+		for expected in batch {
+			assert_eq!(stream.next().await.expect("stream has ended")?, expected);
+		}
+		assert_eq!(mcr.max_tolerable_block_height(), 13);
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_reverted_transactions_leave_no_state() -> Result<(), anyhow::Error> {
+		let (mcr, clients) = simulator(&[100]);
+		let stranger = mcr.client(Address::with_last_byte(0xff));
+		assert!(stranger.post_block_commitment(commitment(1, 1)).await.is_err());
+
+		clients[0].post_block_commitment(commitment(1, 1)).await?;
+		let error = clients[0].post_block_commitment(commitment(1, 2)).await.unwrap_err();
+		assert_eq!(
+			error.downcast::<McrSimulatorError>()?,
+			McrSimulatorError::AttesterAlreadyCommitted {
+				attester: clients[0].attester(),
+				height: 1
+			}
+		);
+
+		// Height 13 is past the tolerance once 2 is accepted, so the whole batch reverts.
+		let batch = vec![commitment(2, 2), commitment(13, 13)];
+		assert!(clients[0].post_block_commitment_batch(batch).await.is_err());
+		assert_eq!(clients[0].get_posted_commitment_at_height(2).await?, None);
+		assert_eq!(mcr.last_accepted_block_height(), 1);
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_stake_changes_apply_from_next_epoch() -> Result<(), anyhow::Error> {
+		let (mcr, clients) = simulator(&[50, 50]);
+		let attester1 = clients[0].attester();
+		let attester2 = clients[1].attester();
+		mcr.unstake(attester2, 50);
+		assert_eq!(mcr.current_epoch_stake(attester2), 50);
+
+		// The stake of the second attester still counts in this epoch.
+		clients[0].post_block_commitment(commitment(1, 1)).await?;
+		assert_eq!(mcr.last_accepted_block_height(), 0);
+		clients[1].post_block_commitment(commitment(1, 1)).await?;
+		assert_eq!(mcr.last_accepted_block_height(), 1);
+
+		// The next height is assigned to the next epoch, where the first attester holds all
+		// the stake. Ticking on the height rolls the contract over to that epoch.
+		mcr.advance_epochs(1);
+		clients[0].post_block_commitment(commitment(2, 2)).await?;
+		assert_eq!(mcr.current_epoch(), 1);
+		assert_eq!(mcr.current_epoch_stake(attester1), 50);
+		assert_eq!(mcr.current_epoch_stake(attester2), 0);
+		assert_eq!(mcr.last_accepted_block_height(), 2);
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_force_commitment_drops_accepted_commitments() -> Result<(), anyhow::Error> {
+		let (mcr, clients) = simulator(&[100]);
+		clients[0]
+			.post_block_commitment_batch(vec![commitment(1, 1), commitment(2, 2)])
+			.await?;
+		assert_eq!(mcr.last_accepted_block_height(), 2);
+
+		let admin = mcr.admin_client(Address::with_last_byte(0xaa));
+		admin.post_block_commitment(commitment(5, 5)).await?;
+		assert_eq!(mcr.last_accepted_block_height(), 5);
+		assert_eq!(mcr.accepted_commitment_at(2), None);
+		assert_eq!(mcr.accepted_commitment_at(5), Some(commitment(5, 5)));
+		assert_eq!(mcr.max_tolerable_block_height(), 15);
+		Ok(())
+	}
+}
@@ -10,6 +10,21 @@ pub struct Config {
 	pub batch_timeout: u64,
 	#[serde(default = "default_transaction_send_retries")]
 	pub transaction_send_retries: u32,
+	/// Time to wait for the receipt of a transaction before it is resubmitted
+	/// with the same nonce and a higher gas price, in seconds
+	#[serde(default = "default_transaction_receipt_timeout")]
+	pub transaction_receipt_timeout: u64,
+	/// Increase of the gas price of a resubmitted transaction, in percent
+	#[serde(default = "default_gas_price_bump_percent")]
+	pub gas_price_bump_percent: u64,
+	/// Time a posted commitment can stay unsettled before its posting is checked
+	/// on the contract, in milliseconds
+	#[serde(default = "default_resubmit_timeout")]
+	pub resubmit_timeout: u64,
+	/// File keeping the commitments not settled yet across restarts.
+	/// The commitments are only kept in memory when not set.
+	#[serde(default)]
+	pub commitment_outbox_path: Option<String>,
 }
 
 env_short_default!(default_gas_limit, u64, 10_000_000_000_000_000 as u64);
@@ -18,12 +33,22 @@ env_short_default!(default_batch_timeout, u64, 2000 as u64);
 
 env_short_default!(default_transaction_send_retries, u32, 10 as u32);
 
+env_short_default!(default_transaction_receipt_timeout, u64, 120 as u64);
+
+env_short_default!(default_gas_price_bump_percent, u64, 15 as u64);
+
+env_short_default!(default_resubmit_timeout, u64, 60_000 as u64);
+
 impl Default for Config {
 	fn default() -> Self {
 		Config {
 			gas_limit: default_gas_limit(),
 			batch_timeout: default_batch_timeout(),
 			transaction_send_retries: default_transaction_send_retries(),
+			transaction_receipt_timeout: default_transaction_receipt_timeout(),
+			gas_price_bump_percent: default_gas_price_bump_percent(),
+			resubmit_timeout: default_resubmit_timeout(),
+			commitment_outbox_path: None,
 		}
 	}
 }
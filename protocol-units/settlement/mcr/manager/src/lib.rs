@@ -2,6 +2,7 @@ use movement_types::block::{BlockCommitment, BlockCommitmentEvent};
 use tokio_stream::Stream;
 
 mod manager;
+pub mod outbox;
 
 pub use manager::Manager as McrSettlementManager;
 
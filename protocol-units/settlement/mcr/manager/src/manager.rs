@@ -1,3 +1,4 @@
+use crate::outbox::{now_ms, CommitmentOutbox};
 use crate::{BlockCommitmentEvent, CommitmentEventStream, McrSettlementManagerOperations};
 
 use mcr_settlement_client::McrSettlementClientOperations;
@@ -8,11 +9,12 @@ use async_stream::stream;
 use async_trait::async_trait;
 use futures::future::{self, Either};
 use tokio::sync::mpsc;
-use tokio::time;
+use tokio::time::{self, Instant, Sleep};
 use tokio_stream::StreamExt;
+use tracing::{info, warn};
 
-use std::collections::BTreeMap;
 use std::mem;
+use std::pin::Pin;
 use std::time::Duration;
 
 /// Public handle for the MCR settlement manager.
@@ -26,14 +28,23 @@ impl Manager {
 	/// Returns the handle with the public API and the stream to receive commitment events.
 	/// The stream needs to be polled to drive the MCR settlement client and
 	/// process the commitments.
+	///
+	/// The commitments not settled yet are kept in the outbox file of the config, if any.
+	/// Those left by a previous run are reconciled with the contract when the stream starts.
 	pub fn new<C: McrSettlementClientOperations + Send + 'static>(
 		client: C,
 		config: &Config,
-	) -> (Self, CommitmentEventStream) {
+	) -> Result<(Self, CommitmentEventStream), anyhow::Error> {
+		let outbox = match &config.transactions.commitment_outbox_path {
+			Some(path) => CommitmentOutbox::open(path)?,
+			None => CommitmentOutbox::in_memory(),
+		};
 		let batch_timeout = Duration::from_millis(config.transactions.batch_timeout);
+		let resubmit_timeout = Duration::from_millis(config.transactions.resubmit_timeout);
 		let (sender, receiver) = mpsc::channel(16);
-		let event_stream = process_commitments(receiver, client, batch_timeout);
-		(Self { sender }, event_stream)
+		let event_stream =
+			process_commitments(receiver, client, outbox, batch_timeout, resubmit_timeout);
+		Ok((Self { sender }, event_stream))
 	}
 }
 
@@ -48,52 +59,209 @@ impl McrSettlementManagerOperations for Manager {
 	}
 }
 
+/// The outcome of checking on the contract a commitment the settlement went past.
+enum CatchUp {
+	/// The height is settled on the contract.
+	Settled(BlockCommitmentEvent),
+	/// The commitment is posted, the height is not settled yet.
+	Posted,
+	/// The commitment is not posted, nor the height settled.
+	NotPosted,
+}
+
+/// Check a commitment at a height the settlement stream went past.
+/// The event is read from the contract, as the stream won't deliver it.
+async fn catch_up<C: McrSettlementClientOperations>(
+	client: &C,
+	block_commitment: &BlockCommitment,
+) -> Result<CatchUp, anyhow::Error> {
+	let height = block_commitment.height();
+	if let Some(accepted) = client.get_commitment_at_height(height).await? {
+		let event = if accepted.commitment() == block_commitment.commitment() {
+			BlockCommitmentEvent::Accepted(accepted)
+		} else {
+			BlockCommitmentEvent::Rejected {
+				height,
+				reason: BlockCommitmentRejectionReason::InvalidCommitment,
+			}
+		};
+		return Ok(CatchUp::Settled(event));
+	}
+	match client.get_posted_commitment_at_height(height).await? {
+		Some(_) => Ok(CatchUp::Posted),
+		None => Ok(CatchUp::NotPosted),
+	}
+}
+
+/// Check the commitments of the outbox on the contract.
+/// The settled ones are removed from the outbox and their events returned,
+/// with the commitments to post again.
+async fn reconcile<C: McrSettlementClientOperations>(
+	client: &C,
+	outbox: &mut CommitmentOutbox,
+	commitments: Vec<BlockCommitment>,
+) -> Result<(Vec<BlockCommitmentEvent>, Vec<BlockCommitment>), anyhow::Error> {
+	let mut events = Vec::new();
+	let mut to_post = Vec::new();
+	for block_commitment in commitments {
+		match catch_up(client, &block_commitment).await? {
+			CatchUp::Settled(event) => {
+				outbox.remove(block_commitment.height()).await?;
+				events.push(event);
+			}
+			// Wait for the height to be settled.
+			CatchUp::Posted => {}
+			CatchUp::NotPosted => to_post.push(block_commitment),
+		}
+	}
+	Ok((events, to_post))
+}
+
+/// Post a batch of commitments and record them as posted in the outbox.
+/// Returns whether the batch was posted.
+async fn post_batch<C: McrSettlementClientOperations>(
+	client: &C,
+	outbox: &mut CommitmentOutbox,
+	batch: &[BlockCommitment],
+) -> Result<bool, anyhow::Error> {
+	if batch.is_empty() {
+		return Ok(true);
+	}
+	if let Err(e) = client.post_block_commitment_batch(batch.to_vec()).await {
+		// The commitments stay in the outbox to be posted again.
+		warn!("Failed to post a batch of {} commitments: {:?}", batch.len(), e);
+		return Ok(false);
+	}
+	let heights: Vec<u64> = batch.iter().map(|c| c.height()).collect();
+	outbox.mark_posted(&heights).await?;
+	Ok(true)
+}
+
+type BatchReady = Either<future::Pending<()>, Pin<Box<Sleep>>>;
+
+/// Add the commitments to the next batch, unless they are in it already.
+fn requeue(
+	batch_acc: &mut Vec<BlockCommitment>,
+	batch_ready: &mut BatchReady,
+	commitments: Vec<BlockCommitment>,
+	batch_timeout: Duration,
+) {
+	for block_commitment in commitments {
+		let height = block_commitment.height();
+		if batch_acc.iter().any(|c| c.height() == height) {
+			continue;
+		}
+		// If this commitment starts a new batch, start the timeout
+		if batch_acc.is_empty() {
+			*batch_ready = Either::Right(Box::pin(time::sleep(batch_timeout)));
+		}
+		batch_acc.push(block_commitment);
+	}
+}
+
 fn process_commitments<C: McrSettlementClientOperations + Send + 'static>(
 	mut receiver: mpsc::Receiver<BlockCommitment>,
 	client: C,
+	mut outbox: CommitmentOutbox,
 	batch_timeout: Duration,
+	resubmit_timeout: Duration,
 ) -> CommitmentEventStream {
 	// Can't mix try_stream! and select!, see https://github.com/tokio-rs/async-stream/issues/63
 	Box::pin(stream! {
 		let mut settlement_stream = client.stream_block_commitments().await?;
 		let mut max_height = client.get_max_tolerable_block_height().await?;
 		let mut ahead_of_settlement = false;
-		let mut commitments_to_settle = BTreeMap::new();
-		let mut batch_acc = Vec::new();
-		let mut batch_ready = Either::Left(future::pending::<()>());
-		loop {
+		// The highest height settled on the stream.
+		let mut settled_height = 0;
+		let mut batch_acc: Vec<BlockCommitment> = Vec::new();
+		let mut batch_ready: BatchReady = Either::Left(future::pending::<()>());
+		let mut resubmit_check =
+			time::interval_at(Instant::now() + resubmit_timeout, resubmit_timeout);
+
+		// Reconcile the commitments left by a previous run with the contract.
+		if !outbox.is_empty() {
+			info!("Reconciling {} commitments of the outbox", outbox.len());
+			let commitments: Vec<BlockCommitment> =
+				outbox.entries().map(|entry| entry.commitment.clone()).collect();
+			let (events, to_post) = reconcile(&client, &mut outbox, commitments).await?;
+			for event in events {
+				yield Ok(event);
+			}
+			let to_post = to_post.into_iter().filter(|c| c.height() <= max_height).collect();
+			requeue(&mut batch_acc, &mut batch_ready, to_post, batch_timeout);
+		}
+
+		'process: loop {
 			tokio::select! {
 				Some(block_commitment) = receiver.recv(), if !ahead_of_settlement => {
-					commitments_to_settle.insert(
-						block_commitment.height(),
-						block_commitment.commitment().clone(),
-					);
+					if block_commitment.height() <= settled_height {
+						// The settlement is ahead of this node, the height may be settled already.
+						match catch_up(&client, &block_commitment).await {
+							Ok(CatchUp::Settled(event)) => {
+								yield Ok(event);
+								continue 'process;
+							}
+							// Posted before a restart, wait for the height to be settled.
+							Ok(CatchUp::Posted) => {
+								let height = block_commitment.height();
+								let saved = match outbox.insert(block_commitment).await {
+									Ok(()) => outbox.mark_posted(&[height]).await,
+									Err(e) => Err(e),
+								};
+								if let Err(e) = saved {
+									yield Err(e);
+									break 'process;
+								}
+								continue 'process;
+							}
+							Ok(CatchUp::NotPosted) => {}
+							Err(e) => {
+								yield Err(e);
+								break 'process;
+							}
+						}
+					}
+					if let Err(e) = outbox.insert(block_commitment.clone()).await {
+						yield Err(e);
+						break;
+					}
 					if block_commitment.height() > max_height {
 						// Can't post this commitment to the contract yet.
 						// Post the previously accumulated commitments as a batch
 						// and pause reading from input.
 						ahead_of_settlement = true;
-						let batch = mem::replace(&mut batch_acc, Vec::new());
-						if let Err(e) = client.post_block_commitment_batch(batch).await {
-							yield Err(e);
-							break;
+						let batch = mem::take(&mut batch_acc);
+						match post_batch(&client, &mut outbox, &batch).await {
+							Ok(true) => {}
+							// Post the batch again with this commitment.
+							Ok(false) => {
+								requeue(&mut batch_acc, &mut batch_ready, batch, batch_timeout)
+							}
+							Err(e) => {
+								yield Err(e);
+								break;
+							}
 						}
 					}
-					// If this commitment starts a new batch, start the timeout
-					if batch_acc.is_empty() {
-						batch_ready = Either::Right(Box::pin(time::sleep(batch_timeout)));
-					}
-					batch_acc.push(block_commitment);
+					let commitments = vec![block_commitment];
+					requeue(&mut batch_acc, &mut batch_ready, commitments, batch_timeout);
 				}
 				_ = &mut batch_ready => {
 					// Batch timeout has expired, post the commitments we have now
-					let batch = mem::replace(&mut batch_acc, Vec::new());
-					if let Err(e) = client.post_block_commitment_batch(batch).await {
-						yield Err(e);
-						break;
-					}
+					let batch = mem::take(&mut batch_acc);
 					// Disable the batch timeout
 					batch_ready = Either::Left(future::pending::<()>());
+					match post_batch(&client, &mut outbox, &batch).await {
+						Ok(true) => {}
+						// Post the batch again after the timeout.
+						Ok(false) => {
+							requeue(&mut batch_acc, &mut batch_ready, batch, batch_timeout)
+						}
+						Err(e) => {
+							yield Err(e);
+							break;
+						}
+					}
 				}
 				Some(res) = settlement_stream.next() => {
 					let settled_commitment = match res {
@@ -105,8 +273,16 @@ fn process_commitments<C: McrSettlementClientOperations + Send + 'static>(
 					};
 
 					let height = settled_commitment.height();
-					if let Some(commitment) = commitments_to_settle.remove(&height) {
-						let event = if commitment == settled_commitment.commitment() {
+					settled_height = settled_height.max(height);
+					let commitment = match outbox.remove(height).await {
+						Ok(commitment) => commitment,
+						Err(e) => {
+							yield Err(e);
+							break;
+						}
+					};
+					if let Some(commitment) = commitment {
+						let event = if commitment.commitment() == settled_commitment.commitment() {
 							BlockCommitmentEvent::Accepted(settled_commitment)
 						} else {
 							BlockCommitmentEvent::Rejected {
@@ -115,11 +291,26 @@ fn process_commitments<C: McrSettlementClientOperations + Send + 'static>(
 							}
 						};
 						yield Ok(event);
-					} else if let Some((&lh, _)) = commitments_to_settle.last_key_value() {
-						if lh < height {
-							// Settlement has left some commitments behind, but the client could
-							// deliver them of order?
-							todo!("Handle falling behind on settlement")
+					}
+
+					// The settlement went past the commitments below this height,
+					// the stream won't deliver them. Read their outcome on the contract.
+					let behind: Vec<BlockCommitment> = outbox
+						.entries()
+						.take_while(|entry| entry.commitment.height() < height)
+						.map(|entry| entry.commitment.clone())
+						.collect();
+					match reconcile(&client, &mut outbox, behind).await {
+						Ok((events, to_post)) => {
+							for event in events {
+								yield Ok(event);
+							}
+							// The commitments were lost, post them again with the next batch.
+							requeue(&mut batch_acc, &mut batch_ready, to_post, batch_timeout);
+						}
+						Err(e) => {
+							yield Err(e);
+							break;
 						}
 					}
 					// Remove back-pressure if we can proceed settling new blocks.
@@ -137,6 +328,40 @@ fn process_commitments<C: McrSettlementClientOperations + Send + 'static>(
 						}
 					}
 				}
+				_ = resubmit_check.tick() => {
+					// Check the commitments posted long ago and not settled yet, their
+					// transaction may have been dropped. The ones left unposted at startup
+					// are posted once the contract accepts their height.
+					let posted_before =
+						now_ms().saturating_sub(resubmit_timeout.as_millis() as u64);
+					let stale: Vec<BlockCommitment> = outbox
+						.entries()
+						.filter(|entry| entry.commitment.height() <= max_height)
+						.filter(|entry| entry.posted_at_ms.map_or(true, |at| at <= posted_before))
+						.filter(|entry| {
+							!batch_acc.iter().any(|c| c.height() == entry.commitment.height())
+						})
+						.map(|entry| entry.commitment.clone())
+						.collect();
+					if stale.is_empty() {
+						continue 'process;
+					}
+					match reconcile(&client, &mut outbox, stale).await {
+						Ok((events, to_post)) => {
+							for event in events {
+								yield Ok(event);
+							}
+							if !to_post.is_empty() {
+								info!("Posting again {} lost commitments", to_post.len());
+							}
+							requeue(&mut batch_acc, &mut batch_ready, to_post, batch_timeout);
+						}
+						Err(e) => {
+							yield Err(e);
+							break;
+						}
+					}
+				}
 				else => break
 			}
 		}
@@ -154,7 +379,7 @@ mod tests {
 		let config = Config::default();
 		let mut client = McrSettlementClient::new();
 		client.block_lead_tolerance = 1;
-		let (manager, mut event_stream) = Manager::new(client.clone(), &config);
+		let (manager, mut event_stream) = Manager::new(client.clone(), &config)?;
 		let commitment = BlockCommitment::new(1, Default::default(), Commitment::new([1; 32]));
 		manager.post_block_commitment(commitment.clone()).await?;
 		let commitment2 = BlockCommitment::new(2, Default::default(), Commitment::new([2; 32]));
@@ -171,7 +396,7 @@ mod tests {
 		let config = Config::default();
 		let mut client = McrSettlementClient::new();
 		client.block_lead_tolerance = 1;
-		let (manager, mut event_stream) = Manager::new(client.clone(), &config);
+		let (manager, mut event_stream) = Manager::new(client.clone(), &config)?;
 		let commitment = BlockCommitment::new(1, Default::default(), Commitment::new([1; 32]));
 		client
 			.override_block_commitment(BlockCommitment::new(
@@ -202,7 +427,7 @@ mod tests {
 		let mut client = McrSettlementClient::new();
 		client.block_lead_tolerance = 2;
 		client.pause_after(2).await;
-		let (manager, mut event_stream) = Manager::new(client.clone(), &config);
+		let (manager, mut event_stream) = Manager::new(client.clone(), &config)?;
 
 		let commitment1 = BlockCommitment::new(1, Default::default(), Commitment::new([1; 32]));
 		manager.post_block_commitment(commitment1.clone()).await?;
@@ -236,12 +461,65 @@ mod tests {
 		Ok(())
 	}
 
+	#[tokio::test]
+	async fn test_catch_up_after_falling_behind_settlement() -> Result<(), anyhow::Error> {
+		let mut config = Config::default();
+		config.transactions.batch_timeout = 100;
+		let client = McrSettlementClient::new();
+		// The commitments posted by this node are not streamed until resumed.
+		client.pause_after(0).await;
+		// Other validators settled the heights before this node could post its commitments.
+		let commitment1 = BlockCommitment::new(1, Default::default(), Commitment::new([1; 32]));
+		client.override_block_commitment(commitment1.clone()).await;
+		client
+			.override_block_commitment(BlockCommitment::new(
+				2,
+				Default::default(),
+				Commitment::new([7; 32]),
+			))
+			.await;
+		let commitment3 = BlockCommitment::new(3, Default::default(), Commitment::new([3; 32]));
+		client.accept_block_commitment(commitment3.clone()).await;
+		let (manager, mut event_stream) = Manager::new(client.clone(), &config)?;
+
+		manager.post_block_commitment(commitment1.clone()).await?;
+		let commitment2 = BlockCommitment::new(2, Default::default(), Commitment::new([2; 32]));
+		manager.post_block_commitment(commitment2).await?;
+		manager.post_block_commitment(commitment3.clone()).await?;
+
+		// The outcome of the heights the settlement went past is read from the contract.
+		let event = event_stream.next().await.expect("stream has ended")?;
+		assert_eq!(event, BlockCommitmentEvent::Accepted(commitment1));
+		let event = event_stream.next().await.expect("stream has ended")?;
+		assert_eq!(
+			event,
+			BlockCommitmentEvent::Rejected {
+				height: 2,
+				reason: BlockCommitmentRejectionReason::InvalidCommitment,
+			}
+		);
+		let event = event_stream.next().await.expect("stream has ended")?;
+		assert_eq!(event, BlockCommitmentEvent::Accepted(commitment3));
+
+		// The normal batching resumes with the next commitments.
+		client.resume().await;
+		let commitment4 = BlockCommitment::new(4, Default::default(), Commitment::new([4; 32]));
+		manager.post_block_commitment(commitment4.clone()).await?;
+		let item = time::timeout(Duration::from_secs(2), event_stream.next())
+			.await
+			.expect("no timeout");
+		let event = item.expect("stream has ended")?;
+		assert_eq!(event, BlockCommitmentEvent::Accepted(commitment4));
+
+		Ok(())
+	}
+
 	#[tokio::test]
 	async fn test_batch_timeout() -> Result<(), anyhow::Error> {
 		let mut config = Config::default();
 		config.transactions.batch_timeout = 100;
 		let client = McrSettlementClient::new();
-		let (manager, mut event_stream) = Manager::new(client.clone(), &config);
+		let (manager, mut event_stream) = Manager::new(client.clone(), &config)?;
 
 		let commitment1 = BlockCommitment::new(1, Default::default(), Commitment::new([1; 32]));
 		manager.post_block_commitment(commitment1.clone()).await?;
@@ -267,4 +545,57 @@ mod tests {
 
 		Ok(())
 	}
+
+	#[tokio::test]
+	async fn test_failed_batch_is_posted_again() -> Result<(), anyhow::Error> {
+		let mut config = Config::default();
+		config.transactions.batch_timeout = 100;
+		let client = McrSettlementClient::new();
+		client.fail_next_posts(1).await;
+		let (manager, mut event_stream) = Manager::new(client.clone(), &config)?;
+
+		let commitment = BlockCommitment::new(1, Default::default(), Commitment::new([1; 32]));
+		manager.post_block_commitment(commitment.clone()).await?;
+		let item = time::timeout(Duration::from_secs(2), event_stream.next())
+			.await
+			.expect("no timeout");
+		let event = item.expect("stream has ended")?;
+		assert_eq!(event, BlockCommitmentEvent::Accepted(commitment));
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_outbox_is_reconciled_on_restart() -> Result<(), anyhow::Error> {
+		let dir = tempfile::tempdir()?;
+		let path = dir.path().join("outbox.json");
+		let mut config = Config::default();
+		config.transactions.batch_timeout = 100;
+		config.transactions.commitment_outbox_path = Some(path.to_string_lossy().into_owned());
+
+		// A previous run posted the first commitment and stopped before posting the second one.
+		let commitment1 = BlockCommitment::new(1, Default::default(), Commitment::new([1; 32]));
+		let commitment2 = BlockCommitment::new(2, Default::default(), Commitment::new([2; 32]));
+		{
+			let mut outbox = CommitmentOutbox::open(&path)?;
+			outbox.insert(commitment1.clone()).await?;
+			outbox.insert(commitment2.clone()).await?;
+			outbox.mark_posted(&[1]).await?;
+		}
+		// The first height was settled while the node was down.
+		let client = McrSettlementClient::new();
+		client.override_block_commitment(commitment1.clone()).await;
+		let (_manager, mut event_stream) = Manager::new(client.clone(), &config)?;
+
+		let event = event_stream.next().await.expect("stream has ended")?;
+		assert_eq!(event, BlockCommitmentEvent::Accepted(commitment1));
+		let item = time::timeout(Duration::from_secs(2), event_stream.next())
+			.await
+			.expect("no timeout");
+		let event = item.expect("stream has ended")?;
+		assert_eq!(event, BlockCommitmentEvent::Accepted(commitment2));
+		assert!(CommitmentOutbox::open(&path)?.is_empty());
+
+		Ok(())
+	}
 }
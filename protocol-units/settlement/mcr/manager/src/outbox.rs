@@ -0,0 +1,322 @@
+//! The commitments of the manager not settled yet.
+//!
+//! The outbox is kept in an append-only log, one JSON record per line, so the commitments
+//! survive a restart of the node and are reconciled with the contract when the manager starts
+//! again. Each change appends and syncs its record only. The log is compacted into a snapshot
+//! of the entries when it holds mostly stale records, by replacing it with a synced temporary
+//! file, so a crash leaves either the old or the new log. A record torn by a crash is dropped
+//! when the log is opened.
+
+use movement_types::block::BlockCommitment;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Number of records under which the log is never compacted.
+const MIN_COMPACTION_RECORDS: usize = 128;
+
+/// A commitment waiting to be settled.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutboxEntry {
+	pub commitment: BlockCommitment,
+	/// When the commitment was last posted, in milliseconds since the epoch.
+	pub posted_at_ms: Option<u64>,
+}
+
+/// A change of the outbox, as saved in the log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+enum OutboxRecord {
+	Insert(OutboxEntry),
+	Posted { heights: Vec<u64>, at_ms: u64 },
+	Remove(u64),
+}
+
+impl OutboxRecord {
+	fn apply(self, entries: &mut BTreeMap<u64, OutboxEntry>) {
+		match self {
+			OutboxRecord::Insert(entry) => {
+				entries.insert(entry.commitment.height(), entry);
+			}
+			OutboxRecord::Posted { heights, at_ms } => {
+				for height in heights {
+					if let Some(entry) = entries.get_mut(&height) {
+						entry.posted_at_ms = Some(at_ms);
+					}
+				}
+			}
+			OutboxRecord::Remove(height) => {
+				entries.remove(&height);
+			}
+		}
+	}
+}
+
+/// The commitments not settled yet, by height.
+#[derive(Debug, Default)]
+pub struct CommitmentOutbox {
+	path: Option<PathBuf>,
+	entries: BTreeMap<u64, OutboxEntry>,
+	/// Number of records in the log.
+	records: usize,
+}
+
+pub(crate) fn now_ms() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+impl CommitmentOutbox {
+	/// An outbox kept in memory only.
+	pub fn in_memory() -> Self {
+		Self::default()
+	}
+
+	/// Open the outbox saved in the log, or create an empty one. An outbox saved by the
+	/// previous versions as a JSON array is rewritten as a log.
+	pub fn open(path: impl AsRef<Path>) -> Result<Self, anyhow::Error> {
+		let path = path.as_ref().to_path_buf();
+		let bytes = match fs::read(&path) {
+			Ok(bytes) => bytes,
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+			Err(e) => return Err(e.into()),
+		};
+		let read_error =
+			|e| anyhow::anyhow!("Failed to read the commitment outbox {:?}: {:?}", path, e);
+
+		let mut entries = BTreeMap::new();
+		let mut records = 0;
+		// A new log is created, and a log without its last newline is rewritten before
+		// records are appended to it.
+		let mut rewrite = bytes.last() != Some(&b'\n');
+		if bytes.trim_ascii_start().starts_with(b"[") {
+			let legacy: Vec<OutboxEntry> = serde_json::from_slice(&bytes).map_err(read_error)?;
+			entries = legacy.into_iter().map(|entry| (entry.commitment.height(), entry)).collect();
+			rewrite = true;
+		} else {
+			let mut lines = bytes.split(|byte| *byte == b'\n').peekable();
+			while let Some(line) = lines.next() {
+				if line.trim_ascii().is_empty() {
+					continue;
+				}
+				match serde_json::from_slice::<OutboxRecord>(line) {
+					Ok(record) => {
+						record.apply(&mut entries);
+						records += 1;
+					}
+					// Only the last record can be torn by a crash, it wasn't acknowledged.
+					Err(e) if lines.peek().is_none() => {
+						warn!("Dropping the torn last record of the commitment outbox: {e}");
+						rewrite = true;
+					}
+					Err(e) => return Err(read_error(e)),
+				}
+			}
+		}
+
+		let mut outbox = Self { path: Some(path.clone()), entries, records };
+		if rewrite {
+			write_synced(&path, &outbox.snapshot()?)?;
+		}
+		Ok(outbox)
+	}
+
+	pub fn get(&self, height: u64) -> Option<&OutboxEntry> {
+		self.entries.get(&height)
+	}
+
+	/// The entries, ordered by height.
+	pub fn entries(&self) -> impl Iterator<Item = &OutboxEntry> {
+		self.entries.values()
+	}
+
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	/// Add a commitment not posted yet, it replaces the commitment at the same height.
+	pub async fn insert(&mut self, commitment: BlockCommitment) -> Result<(), anyhow::Error> {
+		self.save(OutboxRecord::Insert(OutboxEntry { commitment, posted_at_ms: None }))
+			.await
+	}
+
+	/// Record the commitments at the heights as posted now.
+	pub async fn mark_posted(&mut self, heights: &[u64]) -> Result<(), anyhow::Error> {
+		let heights: Vec<u64> = heights
+			.iter()
+			.copied()
+			.filter(|height| self.entries.contains_key(height))
+			.collect();
+		if heights.is_empty() {
+			return Ok(());
+		}
+		self.save(OutboxRecord::Posted { heights, at_ms: now_ms() }).await
+	}
+
+	/// Remove the commitment at the height, once the height is settled.
+	pub async fn remove(&mut self, height: u64) -> Result<Option<BlockCommitment>, anyhow::Error> {
+		let Some(entry) = self.entries.get(&height) else {
+			return Ok(None);
+		};
+		let commitment = entry.commitment.clone();
+		self.save(OutboxRecord::Remove(height)).await?;
+		Ok(Some(commitment))
+	}
+
+	/// Apply the record and append it to the log, or compact the log if it holds mostly
+	/// stale records.
+	async fn save(&mut self, record: OutboxRecord) -> Result<(), anyhow::Error> {
+		record.clone().apply(&mut self.entries);
+		let Some(path) = self.path.clone() else {
+			return Ok(());
+		};
+		self.records += 1;
+		if self.records > MIN_COMPACTION_RECORDS && self.records > 2 * self.entries.len() {
+			let bytes = self.snapshot()?;
+			return tokio::task::spawn_blocking(move || write_synced(&path, &bytes)).await?;
+		}
+		let mut bytes = serde_json::to_vec(&record)?;
+		bytes.push(b'\n');
+		tokio::task::spawn_blocking(move || append_synced(&path, &bytes)).await?
+	}
+
+	/// The log of the entries only, one insert record each.
+	fn snapshot(&mut self) -> Result<Vec<u8>, anyhow::Error> {
+		let mut bytes = Vec::new();
+		for entry in self.entries.values() {
+			bytes.extend(serde_json::to_vec(&OutboxRecord::Insert(entry.clone()))?);
+			bytes.push(b'\n');
+		}
+		self.records = self.entries.len();
+		Ok(bytes)
+	}
+}
+
+/// Append the bytes to the file and sync them.
+fn append_synced(path: &Path, bytes: &[u8]) -> Result<(), anyhow::Error> {
+	let mut file = fs::OpenOptions::new().append(true).open(path)?;
+	file.write_all(bytes)?;
+	file.sync_data()?;
+	Ok(())
+}
+
+/// Replace the file with the bytes. A temporary file is written and synced first, then renamed
+/// over the file, and the directory is synced so the rename survives a crash.
+fn write_synced(path: &Path, bytes: &[u8]) -> Result<(), anyhow::Error> {
+	let mut temporary = path.as_os_str().to_owned();
+	temporary.push(".tmp");
+	let mut file = fs::File::create(&temporary)?;
+	file.write_all(bytes)?;
+	file.sync_all()?;
+	fs::rename(&temporary, path)?;
+	let directory = match path.parent() {
+		Some(directory) if !directory.as_os_str().is_empty() => directory,
+		_ => Path::new("."),
+	};
+	fs::File::open(directory)?.sync_all()?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use movement_types::block::{Commitment, Id};
+
+	#[tokio::test]
+	async fn test_outbox_survives_reopening() -> Result<(), anyhow::Error> {
+		let dir = tempfile::tempdir()?;
+		let path = dir.path().join("outbox.json");
+		let commitment1 = BlockCommitment::new(1, Id::test(), Commitment::new([1; 32]));
+		let commitment2 = BlockCommitment::new(2, Id::test(), Commitment::new([2; 32]));
+
+		let mut outbox = CommitmentOutbox::open(&path)?;
+		assert!(outbox.is_empty());
+		outbox.insert(commitment1.clone()).await?;
+		outbox.insert(commitment2.clone()).await?;
+		outbox.mark_posted(&[1]).await?;
+		assert_eq!(outbox.remove(3).await?, None);
+
+		let mut outbox = CommitmentOutbox::open(&path)?;
+		assert_eq!(outbox.len(), 2);
+		assert!(outbox.get(1).unwrap().posted_at_ms.is_some());
+		assert_eq!(outbox.get(2).unwrap().posted_at_ms, None);
+		assert_eq!(outbox.remove(1).await?, Some(commitment1));
+
+		let outbox = CommitmentOutbox::open(&path)?;
+		let commitments: Vec<_> = outbox.entries().map(|entry| entry.commitment.clone()).collect();
+		assert_eq!(commitments, vec![commitment2]);
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_outbox_appends_records_and_compacts_the_log() -> Result<(), anyhow::Error> {
+		let dir = tempfile::tempdir()?;
+		let path = dir.path().join("outbox.json");
+		let line_count = || fs::read_to_string(&path).unwrap().lines().count();
+
+		let mut outbox = CommitmentOutbox::open(&path)?;
+		let commitment = BlockCommitment::new(1, Id::test(), Commitment::new([1; 32]));
+		outbox.insert(commitment.clone()).await?;
+		outbox.mark_posted(&[1]).await?;
+		assert_eq!(line_count(), 2);
+
+		for height in 2..=MIN_COMPACTION_RECORDS as u64 {
+			outbox
+				.insert(BlockCommitment::new(height, Id::test(), Commitment::new([2; 32])))
+				.await?;
+			outbox.remove(height).await?;
+		}
+		assert!(line_count() < MIN_COMPACTION_RECORDS);
+
+		let outbox = CommitmentOutbox::open(&path)?;
+		assert_eq!(outbox.len(), 1);
+		assert_eq!(outbox.get(1).unwrap().commitment, commitment);
+		assert!(outbox.get(1).unwrap().posted_at_ms.is_some());
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_outbox_drops_a_torn_record() -> Result<(), anyhow::Error> {
+		let dir = tempfile::tempdir()?;
+		let path = dir.path().join("outbox.json");
+		let commitment1 = BlockCommitment::new(1, Id::test(), Commitment::new([1; 32]));
+		let commitment2 = BlockCommitment::new(2, Id::test(), Commitment::new([2; 32]));
+
+		let mut outbox = CommitmentOutbox::open(&path)?;
+		outbox.insert(commitment1.clone()).await?;
+		let mut file = fs::OpenOptions::new().append(true).open(&path)?;
+		file.write_all(b"{\"Remove\":")?;
+
+		let mut outbox = CommitmentOutbox::open(&path)?;
+		assert_eq!(outbox.len(), 1);
+		outbox.insert(commitment2.clone()).await?;
+
+		let outbox = CommitmentOutbox::open(&path)?;
+		let commitments: Vec<_> = outbox.entries().map(|entry| entry.commitment.clone()).collect();
+		assert_eq!(commitments, vec![commitment1, commitment2]);
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_outbox_migrates_the_json_array() -> Result<(), anyhow::Error> {
+		let dir = tempfile::tempdir()?;
+		let path = dir.path().join("outbox.json");
+		let entry = OutboxEntry {
+			commitment: BlockCommitment::new(1, Id::test(), Commitment::new([1; 32])),
+			posted_at_ms: Some(10),
+		};
+		fs::write(&path, serde_json::to_vec_pretty(&vec![&entry])?)?;
+
+		let mut outbox = CommitmentOutbox::open(&path)?;
+		assert_eq!(outbox.get(1), Some(&entry));
+		outbox.remove(1).await?;
+		assert!(CommitmentOutbox::open(&path)?.is_empty());
+		Ok(())
+	}
+}
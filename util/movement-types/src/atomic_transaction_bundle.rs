@@ -46,20 +46,116 @@ pub struct TransactionEntry {
 	data: Transaction,
 }
 
+impl TransactionEntry {
+	pub fn new(consumer_id: Id, data: Transaction) -> Self {
+		Self { consumer_id, data }
+	}
+
+	pub fn consumer_id(&self) -> Id {
+		self.consumer_id
+	}
+
+	pub fn data(&self) -> &Transaction {
+		&self.data
+	}
+}
+
+/// Transactions of several consumers sequenced atomically: a block contains all of them,
+/// contiguously, or none.
+///
+/// A bundle of more than one entry is carried in a block as a single transaction, whose data is
+/// the tagged BCS encoding of the bundle, and which the block metadata marks as a bundle.
+/// A bundle of one entry for the default consumer is carried as the entry itself.
 #[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct AtomicTransactionBundle {
 	sequencer_id: Id,
 	transactions: Vec<TransactionEntry>,
 }
 
+impl AtomicTransactionBundle {
+	/// Prefix of the data of the block transactions carrying a bundle. The sequencers refuse
+	/// plain transactions with this prefix.
+	pub const TAG: &'static [u8] = b"movement::AtomicTransactionBundle\0";
+
+	/// Whether the data of the transaction has the prefix of a bundle.
+	pub fn is_tagged(transaction: &Transaction) -> bool {
+		transaction.data().starts_with(Self::TAG)
+	}
+
+	pub fn new(sequencer_id: Id, transactions: Vec<TransactionEntry>) -> Self {
+		Self { sequencer_id, transactions }
+	}
+
+	pub fn sequencer_id(&self) -> Id {
+		self.sequencer_id
+	}
+
+	pub fn transactions(&self) -> &[TransactionEntry] {
+		&self.transactions
+	}
+
+	pub fn len(&self) -> usize {
+		self.transactions.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.transactions.is_empty()
+	}
+
+	/// The transactions of the consumer, in the bundle order.
+	pub fn consumer_transactions<'a>(
+		&'a self,
+		consumer_id: &'a Id,
+	) -> impl Iterator<Item = &'a Transaction> + 'a {
+		self.transactions
+			.iter()
+			.filter(move |entry| entry.consumer_id == *consumer_id)
+			.map(|entry| &entry.data)
+	}
+
+	/// Decodes the bundle carried by a block transaction.
+	/// Returns `None` if the transaction doesn't have the prefix of a bundle. Only the
+	/// transactions marked as bundles in the block metadata carry one.
+	pub fn from_block_transaction(
+		transaction: &Transaction,
+	) -> Option<Result<Self, anyhow::Error>> {
+		let encoded = transaction.data().strip_prefix(Self::TAG)?;
+		Some(bcs::from_bytes(encoded).map_err(anyhow::Error::from))
+	}
+
+	/// Number of transactions a block transaction stands for.
+	pub fn block_transaction_size(transaction: &Transaction) -> usize {
+		match Self::from_block_transaction(transaction) {
+			Some(Ok(bundle)) => bundle.len(),
+			_ => 1,
+		}
+	}
+}
+
 impl TryFrom<AtomicTransactionBundle> for Transaction {
 	type Error = anyhow::Error;
 
-	fn try_from(value: AtomicTransactionBundle) -> Result<Self, Self::Error> {
-		if value.transactions.len() == 1 {
-			Ok(value.transactions[0].data.clone())
-		} else {
-			Err(anyhow::anyhow!("AtomicTransactionBundle must contain exactly one transaction"))
+	/// Converts the bundle to the transaction carrying it in a block.
+	/// The transaction has the highest priority and the first sequence number of the entries.
+	fn try_from(mut value: AtomicTransactionBundle) -> Result<Self, Self::Error> {
+		match value.transactions.len() {
+			0 => Err(anyhow::anyhow!("AtomicTransactionBundle must contain a transaction")),
+			// The entry of another consumer keeps the bundle, which carries its consumer id.
+			1 if value.transactions[0].consumer_id == Id::default() => {
+				Ok(value.transactions.remove(0).data)
+			}
+			_ => {
+				let application_priority = value
+					.transactions
+					.iter()
+					.map(|entry| entry.data.application_priority())
+					.min()
+					.unwrap_or_default();
+				let sequence_number = value.transactions[0].data.sequence_number();
+				let mut data = AtomicTransactionBundle::TAG.to_vec();
+				data.extend(bcs::to_bytes(&value)?);
+				Ok(Transaction::new(data, application_priority, sequence_number))
+			}
 		}
 	}
 }
@@ -72,3 +168,86 @@ impl From<Transaction> for AtomicTransactionBundle {
 		}
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::block::{self, Block, BlockMetadata};
+	use std::collections::BTreeSet;
+
+	#[test]
+	fn test_bundle_round_trip_through_block_transaction() -> Result<(), anyhow::Error> {
+		let rollup_a = Id::new([1; 32]);
+		let rollup_b = Id::new([2; 32]);
+		let bundle = AtomicTransactionBundle::new(
+			Id::default(),
+			vec![
+				TransactionEntry::new(rollup_a, Transaction::new(vec![1], 3, 0)),
+				TransactionEntry::new(rollup_b, Transaction::new(vec![2], 1, 0)),
+				TransactionEntry::new(rollup_a, Transaction::new(vec![3], 3, 1)),
+			],
+		);
+
+		let transaction = Transaction::try_from(bundle.clone())?;
+		assert_eq!(transaction.application_priority(), 1);
+		assert_eq!(AtomicTransactionBundle::block_transaction_size(&transaction), 3);
+		let decoded = AtomicTransactionBundle::from_block_transaction(&transaction)
+			.ok_or(anyhow::anyhow!("Not a bundle"))??;
+		assert_eq!(decoded, bundle);
+		let rollup_a_data: Vec<&[u8]> =
+			decoded.consumer_transactions(&rollup_a).map(|tx| tx.data()).collect();
+		assert_eq!(rollup_a_data, vec![&[1u8][..], &[3u8][..]]);
+
+		// A single entry bundle is the transaction itself.
+		let single = Transaction::new(vec![4], 0, 0);
+		let transaction = Transaction::try_from(AtomicTransactionBundle::from(single.clone()))?;
+		assert_eq!(transaction, single);
+		assert!(AtomicTransactionBundle::from_block_transaction(&transaction).is_none());
+
+		// Unless it is for another consumer.
+		let single_bundle = AtomicTransactionBundle::new(
+			Id::default(),
+			vec![TransactionEntry::new(rollup_a, single.clone())],
+		);
+		let transaction = Transaction::try_from(single_bundle.clone())?;
+		let decoded = AtomicTransactionBundle::from_block_transaction(&transaction)
+			.ok_or(anyhow::anyhow!("Not a bundle"))??;
+		assert_eq!(decoded, single_bundle);
+		assert_eq!(decoded.consumer_transactions(&rollup_a).collect::<Vec<_>>(), vec![&single]);
+
+		assert!(Transaction::try_from(AtomicTransactionBundle::default()).is_err());
+		Ok(())
+	}
+
+	#[test]
+	fn test_block_decodes_only_the_marked_bundles() -> Result<(), anyhow::Error> {
+		let rollup_a = Id::new([1; 32]);
+		let bundle = Transaction::try_from(AtomicTransactionBundle::new(
+			Id::default(),
+			vec![
+				TransactionEntry::new(rollup_a, Transaction::new(vec![1], 0, 0)),
+				TransactionEntry::new(rollup_a, Transaction::new(vec![2], 0, 1)),
+			],
+		))?;
+		// Tagged like a bundle, but not marked in the metadata.
+		let unmarked = Transaction::new(bundle.data().to_vec(), 0, 1);
+		let mut malformed_data = AtomicTransactionBundle::TAG.to_vec();
+		malformed_data.push(0xff);
+		let malformed = Transaction::new(malformed_data, 0, 2);
+
+		let block = Block::new(
+			BlockMetadata::with_atomic_bundles(BTreeSet::from([bundle.id(), malformed.id()])),
+			block::Id::default(),
+			BTreeSet::from([bundle, unmarked.clone(), malformed]),
+		);
+		let rollup_a_data: Vec<Vec<u8>> = block
+			.consumer_transactions(&rollup_a)
+			.iter()
+			.map(|tx| tx.data().to_vec())
+			.collect();
+		assert_eq!(rollup_a_data, vec![vec![1], vec![2]]);
+		// The malformed bundle is skipped, the unmarked one is a plain transaction.
+		assert_eq!(block.consumer_transactions(&Id::default()), vec![unmarked]);
+		Ok(())
+	}
+}
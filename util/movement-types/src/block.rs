@@ -1,12 +1,13 @@
-use crate::transaction::Transaction;
+use crate::atomic_transaction_bundle::{self, AtomicTransactionBundle};
+use crate::transaction::{self, Transaction};
 use aptos_types::state_proof::StateProof;
 use serde::{Deserialize, Serialize};
-use std::collections::btree_set;
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashSet};
 use std::fmt;
 use std::result::Result;
+use std::slice;
 
-pub type Transactions<'a> = btree_set::Iter<'a, Transaction>;
+pub type Transactions<'a> = slice::Iter<'a, Transaction>;
 
 #[derive(Debug, thiserror::Error)]
 pub enum BlockError {
@@ -62,29 +63,57 @@ impl fmt::Display for Id {
 pub enum BlockMetadata {
 	#[default]
 	BlockMetadata,
+	/// The ids of the block transactions carrying an [`AtomicTransactionBundle`].
+	AtomicBundles(BTreeSet<transaction::Id>),
+}
+
+impl BlockMetadata {
+	/// Metadata marking the transactions carrying a bundle, the default one if there are none.
+	pub fn with_atomic_bundles(bundles: BTreeSet<transaction::Id>) -> Self {
+		if bundles.is_empty() {
+			BlockMetadata::BlockMetadata
+		} else {
+			BlockMetadata::AtomicBundles(bundles)
+		}
+	}
+
+	/// Whether the transaction with this id carries a bundle.
+	pub fn is_atomic_bundle(&self, id: &transaction::Id) -> bool {
+		match self {
+			BlockMetadata::BlockMetadata => false,
+			BlockMetadata::AtomicBundles(bundles) => bundles.contains(id),
+		}
+	}
 }
 
 #[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Block {
 	metadata: BlockMetadata,
 	parent: Id,
-	transactions: BTreeSet<Transaction>,
+	// In execution order. Serialized as a sequence, like the set the blocks used to hold.
+	transactions: Vec<Transaction>,
 	id: Id,
 	timestamp: u64,
 }
 
 impl Block {
-	pub fn new(metadata: BlockMetadata, parent: Id, transactions: BTreeSet<Transaction>) -> Self {
+	/// A block of the transactions in the given order, a transaction given twice is kept once.
+	pub fn new(
+		metadata: BlockMetadata,
+		parent: Id,
+		transactions: impl IntoIterator<Item = Transaction>,
+	) -> Self {
+		let mut ids = HashSet::new();
+		let transactions: Vec<_> = transactions
+			.into_iter()
+			.filter(|transaction| ids.insert(transaction.id()))
+			.collect();
 		let timestamp = chrono::Utc::now().timestamp_micros() as u64;
 		let id = Self::generate_id_with_block_data(parent, timestamp, &transactions);
 		Self { metadata, parent, transactions, id, timestamp }
 	}
 
-	fn generate_id_with_block_data(
-		parent: Id,
-		timestamp: u64,
-		transactions: &BTreeSet<Transaction>,
-	) -> Id {
+	fn generate_id_with_block_data(parent: Id, timestamp: u64, transactions: &[Transaction]) -> Id {
 		let mut hasher = blake3::Hasher::new();
 		hasher.update(parent.as_bytes());
 		hasher.update(&timestamp.to_le_bytes());
@@ -95,7 +124,7 @@ impl Block {
 		id
 	}
 
-	pub fn into_parts(self) -> (BlockMetadata, Id, BTreeSet<Transaction>, Id) {
+	pub fn into_parts(self) -> (BlockMetadata, Id, Vec<Transaction>, Id) {
 		(self.metadata, self.parent, self.transactions, self.id)
 	}
 
@@ -111,6 +140,30 @@ impl Block {
 		self.transactions.iter()
 	}
 
+	/// The sub-sequence of the block transactions of a consumer. The transactions of a bundle
+	/// are in the bundle order, the ones outside of bundles belong to the default consumer.
+	///
+	/// Only the transactions marked as bundles in the metadata are decoded, a bundle that can't
+	/// be decoded is skipped.
+	pub fn consumer_transactions(
+		&self,
+		consumer_id: &atomic_transaction_bundle::Id,
+	) -> Vec<Transaction> {
+		let mut transactions = Vec::new();
+		for transaction in &self.transactions {
+			if self.metadata.is_atomic_bundle(&transaction.id()) {
+				if let Some(Ok(bundle)) =
+					AtomicTransactionBundle::from_block_transaction(transaction)
+				{
+					transactions.extend(bundle.consumer_transactions(consumer_id).cloned());
+				}
+			} else if *consumer_id == atomic_transaction_bundle::Id::default() {
+				transactions.push(transaction.clone());
+			}
+		}
+		transactions
+	}
+
 	pub fn timestamp(&self) -> u64 {
 		self.timestamp
 	}
@@ -120,20 +173,19 @@ impl Block {
 	}
 
 	pub fn test() -> Self {
-		Self::new(
-			BlockMetadata::BlockMetadata,
-			Id::test(),
-			BTreeSet::from_iter(vec![Transaction::test()]),
-		)
+		Self::new(BlockMetadata::BlockMetadata, Id::test(), vec![Transaction::test()])
 	}
 
 	pub fn add_transaction(&mut self, transaction: Transaction) -> Result<(), BlockError> {
-		self.transactions.insert(transaction);
+		if !self.transactions.iter().any(|other| other.id() == transaction.id()) {
+			self.transactions.push(transaction);
+		}
 		Ok(())
 	}
 
 	pub fn collapse(blocks: Vec<Block>) -> Block {
-		let mut transactions = BTreeSet::new();
+		let mut transactions = Vec::new();
+		let mut bundles = BTreeSet::new();
 		let parent = if let Some(first_block) = blocks.first() {
 			first_block.parent
 		} else {
@@ -141,12 +193,13 @@ impl Block {
 		};
 
 		for block in blocks {
-			for transaction in block.transactions {
-				transactions.insert(transaction);
+			if let BlockMetadata::AtomicBundles(block_bundles) = block.metadata {
+				bundles.extend(block_bundles);
 			}
+			transactions.extend(block.transactions);
 		}
 
-		Block::new(BlockMetadata::BlockMetadata, parent, transactions)
+		Block::new(BlockMetadata::with_atomic_bundles(bundles), parent, transactions)
 	}
 
 	pub fn verify_id(&self) -> bool {
@@ -277,8 +330,9 @@ pub mod test {
 		let new_transaction = super::Transaction::new(vec![4, 5, 6], 0, 0);
 		diff_block1.add_transaction(new_transaction.clone()).unwrap();
 		let collapsed = super::Block::collapse(vec![block1, diff_block1]);
+		// the transactions keep the order of the blocks
 		assert_eq!(collapsed.transactions().count(), 2);
-		assert_eq!(collapsed.transactions().next(), Some(&new_transaction));
-		assert_eq!(collapsed.transactions().nth(1), test.transactions().next());
+		assert_eq!(collapsed.transactions().next(), test.transactions().next());
+		assert_eq!(collapsed.transactions().nth(1), Some(&new_transaction));
 	}
 }
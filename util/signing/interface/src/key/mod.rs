@@ -215,6 +215,13 @@ impl Key {
 	}
 }
 
+impl ToCanonicalString for Key {
+	/// Returns the canonical string of the key, the inverse of [Key::try_from_canonical_string].
+	fn to_canonical_string(&self) -> String {
+		format!("/{}", self.to_delimited_canonical_string("/"))
+	}
+}
+
 impl TryFromCanonicalString for Key {
 	/// Gets a key from a canonical string.
 	/// Example canonical string: "movement/prod/full_node/mcr_settlement/signer/validator/0"
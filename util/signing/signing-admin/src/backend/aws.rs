@@ -1,8 +1,25 @@
 use anyhow::{Context, Result};
 use aws_config;
 use aws_sdk_kms::{Client as KmsClient};
-use aws_sdk_kms::types::Tag;
+use aws_sdk_kms::types::{KeyState as KmsKeyState, Tag};
+use movement_signer::{
+        cryptography::secp256k1::Secp256k1,
+        key::{Key, ToCanonicalString, TryFromCanonicalString},
+        Signing,
+};
+use movement_signer_aws_kms::{cryptography::AwsKmsCryptographySpec, hsm::AwsKms};
+use movement_signer_loader::identifiers::{aws_kms, SignerIdentifier};
+use std::collections::HashMap;
 use super::SigningBackend;
+use crate::key::{KeyCurve, KeyFilter, KeyState, ManagedKey};
+
+/// The tags recording the parts of the [Key] of a created key, in the order of its canonical
+/// string.
+const KEY_TAGS: [&str; 5] = ["org", "environment", "software_unit", "usage", "allowed_roles"];
+const KEY_NAME_TAG: &str = "key_name";
+const APP_REPLICA_TAG: &str = "app_replica";
+/// Prefix of the description of a created key, followed by the canonical string of the key.
+const DESCRIPTION_PREFIX: &str = "Signing key ";
 
 pub struct AwsBackend;
 
@@ -16,7 +33,7 @@ impl AwsBackend {
                 Ok(KmsClient::new(&aws_config))
         }
 
-        async fn create_key(client: &KmsClient) -> Result<String> {
+        async fn create_signing_key(client: &KmsClient) -> Result<String> {
                 let response = client
                         .create_key()
                         .description("Key for signing and verification")
@@ -38,6 +55,119 @@ impl AwsBackend {
                         .and_then(|meta| Some(meta.key_id().to_string()))
                         .context("Failed to retrieve key ID from AWS response")
         }
+
+        /// The signer loader uses the key name as the AWS KMS key id, so the key held by AWS KMS
+        /// replaces the requested key name with the key id.
+        fn kms_key(key: &Key, key_id: String) -> Key {
+                KeyFilter::from(key).key(key_id, key.app_replica().cloned())
+        }
+
+        fn identifier(key: &Key) -> SignerIdentifier {
+                SignerIdentifier::AwsKms(aws_kms::AwsKms { create: false, key: key.clone() })
+        }
+
+        fn key_tags(key: &Key) -> Result<Vec<Tag>> {
+                let values = [
+                        key.org().to_canonical_string(),
+                        key.environment().to_canonical_string(),
+                        key.software_unit().to_canonical_string(),
+                        key.usage().to_canonical_string(),
+                        key.allowed_roles().to_canonical_string(),
+                ];
+                let app_replica = key.app_replica().cloned().unwrap_or_else(|| "0".to_string());
+                KEY_TAGS
+                        .into_iter()
+                        .zip(values)
+                        .chain([
+                                (KEY_NAME_TAG, key.key_name().to_string()),
+                                (APP_REPLICA_TAG, app_replica),
+                        ])
+                        .map(|(tag_key, tag_value)| {
+                                Tag::builder()
+                                        .tag_key(tag_key)
+                                        .tag_value(tag_value)
+                                        .build()
+                                        .context("Failed to build AWS KMS tag")
+                        })
+                        .collect()
+        }
+
+        /// Reads the key back from the tags of the AWS KMS key, keys not created by
+        /// [SigningBackend::create_key] have no key.
+        async fn tagged_key(client: &KmsClient, key_id: &str) -> Result<Option<Key>> {
+                let response = client
+                        .list_resource_tags()
+                        .key_id(key_id)
+                        .send()
+                        .await
+                        .context("Failed to list AWS KMS key tags")?;
+                let tags: HashMap<&str, &str> = response
+                        .tags()
+                        .iter()
+                        .map(|tag| (tag.tag_key(), tag.tag_value()))
+                        .collect();
+
+                let mut parts = Vec::new();
+                for tag_key in KEY_TAGS.into_iter().chain([APP_REPLICA_TAG]) {
+                        match tags.get(tag_key) {
+                                Some(value) => parts.push(*value),
+                                None => return Ok(None),
+                        }
+                }
+                let canonical_string = format!(
+                        "/{}/{}/{}/{}/{}/{}/{}",
+                        parts[0], parts[1], parts[2], parts[3], parts[4], key_id, parts[5]
+                );
+                Ok(Key::try_from_canonical_string(&canonical_string).ok())
+        }
+
+        fn key_state(state: &KmsKeyState) -> KeyState {
+                match state {
+                        KmsKeyState::Enabled => KeyState::Enabled,
+                        KmsKeyState::Disabled => KeyState::Disabled,
+                        KmsKeyState::PendingDeletion => KeyState::PendingDeletion,
+                        other => KeyState::Other(other.as_str().to_string()),
+                }
+        }
+
+        /// The managed key of the AWS KMS key, if it matches the filter. The description of the
+        /// key is checked first, so the tags are only read for the keys created for the filter.
+        async fn listed_key(
+                client: &KmsClient,
+                key_id: &str,
+                filter: &KeyFilter,
+        ) -> Result<Option<ManagedKey>> {
+                let response = client
+                        .describe_key()
+                        .key_id(key_id)
+                        .send()
+                        .await
+                        .context("Failed to describe AWS KMS key")?;
+                let metadata = response
+                        .key_metadata()
+                        .context("Failed to retrieve key metadata from AWS response")?;
+                let described_key = metadata
+                        .description()
+                        .and_then(|description| description.strip_prefix(DESCRIPTION_PREFIX))
+                        .and_then(|canonical_string| {
+                                Key::try_from_canonical_string(canonical_string).ok()
+                        });
+                if !described_key.is_some_and(|key| filter.matches(&key)) {
+                        return Ok(None);
+                }
+
+                let Some(key) = Self::tagged_key(client, key_id).await? else {
+                        return Ok(None);
+                };
+                if !filter.matches(&key) {
+                        return Ok(None);
+                }
+                let state = metadata
+                        .key_state()
+                        .map(Self::key_state)
+                        .context("Failed to retrieve key state from AWS response")?;
+                Ok(Some(ManagedKey { identifier: Self::identifier(&key), key, state }))
+        }
 }
 
 #[async_trait::async_trait]
@@ -52,7 +182,7 @@ impl SigningBackend for AwsBackend {
                         format!("alias/{}", key_id)
                 };
 
-                let new_key_id = Self::create_key(&client).await?;
+                let new_key_id = Self::create_signing_key(&client).await?;
                 client
                         .update_alias()
                         .alias_name(&full_alias)
@@ -63,4 +193,97 @@ impl SigningBackend for AwsBackend {
                 
                 Ok(())
         }
+
+        async fn create_key(&self, key: &Key, curve: KeyCurve) -> Result<ManagedKey> {
+                if curve != KeyCurve::Secp256k1 {
+                        anyhow::bail!("AWS KMS signing keys only support the secp256k1 curve");
+                }
+                let client = Self::create_client().await?;
+
+                let response = client
+                        .create_key()
+                        .description(format!("{DESCRIPTION_PREFIX}{}", key.to_canonical_string()))
+                        .key_spec(Secp256k1::key_spec())
+                        .key_usage(Secp256k1::key_usage_type())
+                        .set_tags(Some(Self::key_tags(key)?))
+                        .send()
+                        .await
+                        .context("Failed to create key with AWS KMS")?;
+                let key_id = response
+                        .key_metadata()
+                        .map(|meta| meta.key_id().to_string())
+                        .context("Failed to retrieve key ID from AWS response")?;
+
+                let key = Self::kms_key(key, key_id);
+                Ok(ManagedKey { identifier: Self::identifier(&key), key, state: KeyState::Enabled })
+        }
+
+        async fn list_keys(&self, filter: &KeyFilter) -> Result<Vec<ManagedKey>> {
+                let client = Self::create_client().await?;
+
+                let mut keys = Vec::new();
+                let mut marker = None;
+                loop {
+                        let response = client
+                                .list_keys()
+                                .set_marker(marker.take())
+                                .send()
+                                .await
+                                .context("Failed to list AWS KMS keys")?;
+                        for entry in response.keys() {
+                                let Some(key_id) = entry.key_id() else {
+                                        continue;
+                                };
+                                match Self::listed_key(&client, key_id, filter).await {
+                                        Ok(Some(managed_key)) => keys.push(managed_key),
+                                        Ok(None) => {}
+                                        // AWS managed keys, keys the caller can't read and keys
+                                        // pending deletion can fail, they are not listed.
+                                        Err(e) => eprintln!("Skipping AWS KMS key {key_id}: {e:#}"),
+                                }
+                        }
+                        match response.next_marker() {
+                                Some(next_marker) if response.truncated() => {
+                                        marker = Some(next_marker.to_string())
+                                }
+                                _ => break,
+                        }
+                }
+                Ok(keys)
+        }
+
+        async fn signer_identifier(&self, key: &Key) -> Result<SignerIdentifier> {
+                Ok(Self::identifier(key))
+        }
+
+        async fn public_key(&self, key: &Key) -> Result<Vec<u8>> {
+                let client = Self::create_client().await?;
+                let signer = AwsKms::<Secp256k1>::new(client, key.key_name().to_string());
+                let public_key =
+                        signer.public_key().await.context("Failed to get public key from AWS KMS")?;
+                Ok(public_key.as_bytes().to_vec())
+        }
+
+        async fn disable_key(&self, key: &Key) -> Result<()> {
+                let client = Self::create_client().await?;
+                client
+                        .disable_key()
+                        .key_id(key.key_name())
+                        .send()
+                        .await
+                        .context("Failed to disable AWS KMS key")?;
+                Ok(())
+        }
+
+        async fn schedule_key_deletion(&self, key: &Key, pending_window_days: i32) -> Result<()> {
+                let client = Self::create_client().await?;
+                client
+                        .schedule_key_deletion()
+                        .key_id(key.key_name())
+                        .pending_window_in_days(pending_window_days)
+                        .send()
+                        .await
+                        .context("Failed to schedule AWS KMS key deletion")?;
+                Ok(())
+        }
 }
@@ -0,0 +1,235 @@
+use super::SigningBackend;
+use crate::key::{KeyCurve, KeyFilter, KeyState, ManagedKey};
+use anyhow::{Context, Result};
+use movement_signer::{
+        cryptography::{ed25519::Ed25519, secp256k1::Secp256k1},
+        key::{Key, ToCanonicalString, TryFromCanonicalString},
+        Signing,
+};
+use movement_signer_loader::identifiers::{local::Local, SignerIdentifier};
+use movement_signer_loader::Load;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+/// A local key, saved as JSON in the directory of the backend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LocalKeyFile {
+        key: String,
+        curve: KeyCurve,
+        private_key_hex: String,
+        enabled: bool,
+}
+
+impl LocalKeyFile {
+        fn identifier(&self) -> SignerIdentifier {
+                SignerIdentifier::Local(Local {
+                        private_key_hex_bytes: self.private_key_hex.clone(),
+                })
+        }
+
+        fn managed_key(&self) -> Result<ManagedKey> {
+                let key = Key::try_from_canonical_string(&self.key)
+                        .map_err(|e| anyhow::anyhow!(e))?;
+                let state = if self.enabled { KeyState::Enabled } else { KeyState::Disabled };
+                Ok(ManagedKey { key, identifier: self.identifier(), state })
+        }
+}
+
+/// Generates keys locally, the private key is part of the signer identifier of a local key.
+///
+/// The key files hold the private keys, they are readable by their owner only.
+/// Disabling a local key only marks it in the directory, the signer identifier still loads.
+pub struct LocalBackend {
+        dir: PathBuf,
+}
+
+impl LocalBackend {
+        pub fn new(dir: impl Into<PathBuf>) -> Self {
+                Self { dir: dir.into() }
+        }
+
+        pub fn try_from_env() -> Result<Self> {
+                let dir = std::env::var("LOCAL_KEYS_DIR")
+                        .context("Missing LOCAL_KEYS_DIR environment variable")?;
+                Ok(Self::new(dir))
+        }
+
+        fn path(&self, key: &Key) -> PathBuf {
+                self.dir.join(format!("{}.json", key.to_delimited_canonical_string("-")))
+        }
+
+        fn read(path: &Path) -> Result<LocalKeyFile> {
+                let bytes = fs::read(path)
+                        .with_context(|| format!("Failed to read local key {:?}", path))?;
+                serde_json::from_slice(&bytes)
+                        .with_context(|| format!("Failed to parse local key {:?}", path))
+        }
+
+        /// Writes the key file, a new file fails if the key already exists so a key is never
+        /// overwritten by a concurrent creation.
+        fn write(&self, path: &Path, file: &LocalKeyFile, create_new: bool) -> Result<()> {
+                fs::create_dir_all(&self.dir)
+                        .context("Failed to create the local keys directory")?;
+                let mut options = fs::OpenOptions::new();
+                if create_new {
+                        options.write(true).create_new(true);
+                } else {
+                        options.write(true).truncate(true);
+                }
+                #[cfg(unix)]
+                options.mode(0o600);
+                let mut key_file = match options.open(path) {
+                        Ok(key_file) => key_file,
+                        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                                anyhow::bail!("Local key {} already exists", file.key)
+                        }
+                        Err(e) => {
+                                return Err(e).with_context(|| {
+                                        format!("Failed to open local key {:?}", path)
+                                })
+                        }
+                };
+                // The mode only applies to new files.
+                #[cfg(unix)]
+                key_file.set_permissions(fs::Permissions::from_mode(0o600))
+                        .with_context(|| format!("Failed to restrict local key {:?}", path))?;
+                key_file.write_all(&serde_json::to_vec_pretty(file)?)
+                        .with_context(|| format!("Failed to write local key {:?}", path))
+        }
+}
+
+#[async_trait::async_trait]
+impl SigningBackend for LocalBackend {
+        async fn rotate_key(&self, _key_id: &str) -> Result<()> {
+                anyhow::bail!("Local keys cannot be rotated, create a new key instead")
+        }
+
+        async fn create_key(&self, key: &Key, curve: KeyCurve) -> Result<ManagedKey> {
+                let path = self.path(key);
+                let private_key_hex = match curve {
+                        KeyCurve::Ed25519 => hex::encode(
+                                ed25519_dalek::SigningKey::generate(&mut rand::thread_rng())
+                                        .to_bytes(),
+                        ),
+                        KeyCurve::Secp256k1 => hex::encode(
+                                k256::ecdsa::SigningKey::random(&mut rand::thread_rng()).to_bytes(),
+                        ),
+                };
+                let file = LocalKeyFile {
+                        key: key.to_canonical_string(),
+                        curve,
+                        private_key_hex,
+                        enabled: true,
+                };
+                self.write(&path, &file, true)?;
+                file.managed_key()
+        }
+
+        async fn list_keys(&self, filter: &KeyFilter) -> Result<Vec<ManagedKey>> {
+                let entries = match fs::read_dir(&self.dir) {
+                        Ok(entries) => entries,
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+                        Err(e) => return Err(e).context("Failed to read the local keys directory"),
+                };
+
+                let mut keys = Vec::new();
+                for entry in entries {
+                        let path = entry?.path();
+                        if path.extension().map_or(true, |extension| extension != "json") {
+                                continue;
+                        }
+                        let managed_key = Self::read(&path)?.managed_key()?;
+                        if filter.matches(&managed_key.key) {
+                                keys.push(managed_key);
+                        }
+                }
+                keys.sort_by_key(|managed_key| managed_key.key.to_canonical_string());
+                Ok(keys)
+        }
+
+        async fn signer_identifier(&self, key: &Key) -> Result<SignerIdentifier> {
+                Ok(Self::read(&self.path(key))?.identifier())
+        }
+
+        async fn public_key(&self, key: &Key) -> Result<Vec<u8>> {
+                let file = Self::read(&self.path(key))?;
+                let identifier = file.identifier();
+                let public_key = match file.curve {
+                        KeyCurve::Ed25519 => {
+                                let signer: movement_signer_loader::LoadedSigner<Ed25519> =
+                                        identifier.load().await?;
+                                signer.public_key().await?.as_bytes().to_vec()
+                        }
+                        KeyCurve::Secp256k1 => {
+                                let signer: movement_signer_loader::LoadedSigner<Secp256k1> =
+                                        identifier.load().await?;
+                                signer.public_key().await?.as_bytes().to_vec()
+                        }
+                };
+                Ok(public_key)
+        }
+
+        async fn disable_key(&self, key: &Key) -> Result<()> {
+                let path = self.path(key);
+                let mut file = Self::read(&path)?;
+                file.enabled = false;
+                self.write(&path, &file, false)
+        }
+
+        /// Local keys are deleted right away.
+        async fn schedule_key_deletion(&self, key: &Key, _pending_window_days: i32) -> Result<()> {
+                let path = self.path(key);
+                fs::remove_file(&path)
+                        .with_context(|| format!("Failed to delete local key {:?}", path))
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        #[tokio::test]
+        async fn test_local_key_lifecycle() -> Result<()> {
+                let dir = tempfile::tempdir()?;
+                let backend = LocalBackend::new(dir.path());
+                let filter = KeyFilter::try_from_canonical_string(
+                        "/movement/prod/fullNode/mcr_settlement/signer",
+                )
+                .map_err(|e| anyhow::anyhow!(e))?;
+                let validator = filter.key("validator".to_string(), Some("0".to_string()));
+                let da = filter.key("da".to_string(), Some("0".to_string()));
+
+                let created = backend.create_key(&validator, KeyCurve::Secp256k1).await?;
+                assert_eq!(created.key, validator);
+                #[cfg(unix)]
+                assert_eq!(
+                        fs::metadata(backend.path(&validator))?.permissions().mode() & 0o777,
+                        0o600
+                );
+                assert!(backend.create_key(&validator, KeyCurve::Ed25519).await.is_err());
+                backend.create_key(&da, KeyCurve::Ed25519).await?;
+
+                let keys = backend.list_keys(&filter).await?;
+                assert_eq!(
+                        keys.iter().map(|managed_key| &managed_key.key).collect::<Vec<_>>(),
+                        vec![&da, &validator]
+                );
+                assert_eq!(
+                        backend.signer_identifier(&validator).await?.to_canonical_string(),
+                        created.identifier.to_canonical_string()
+                );
+                assert_eq!(backend.public_key(&validator).await?.len(), 65);
+                assert_eq!(backend.public_key(&da).await?.len(), 32);
+
+                backend.disable_key(&validator).await?;
+                backend.schedule_key_deletion(&da, 7).await?;
+                let keys = backend.list_keys(&filter).await?;
+                assert_eq!(keys.len(), 1);
+                assert_eq!(keys[0].state, KeyState::Disabled);
+                Ok(())
+        }
+}
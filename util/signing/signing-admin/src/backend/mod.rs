@@ -1,21 +1,63 @@
 pub mod aws;
+pub mod local;
 pub mod vault;
 
 use anyhow::Result;
 use async_trait::async_trait;
 use aws::AwsBackend;
+use local::LocalBackend;
+use movement_signer::key::Key;
+use movement_signer_loader::identifiers::SignerIdentifier;
 use vault::VaultBackend;
+use crate::key::{KeyCurve, KeyFilter, ManagedKey};
 
 /// The trait that all signing backends must implement.
 #[async_trait]
 pub trait SigningBackend {
         async fn rotate_key(&self, key_id: &str) -> Result<()>;
+
+        /// Creates a key, the returned key is the one to load the signer with.
+        async fn create_key(&self, key: &Key, curve: KeyCurve) -> Result<ManagedKey>;
+
+        /// Lists the keys matching the filter.
+        async fn list_keys(&self, filter: &KeyFilter) -> Result<Vec<ManagedKey>>;
+
+        /// Returns the identifier to load the signer of the key with.
+        async fn signer_identifier(&self, key: &Key) -> Result<SignerIdentifier>;
+
+        /// Returns the raw bytes of the public key.
+        async fn public_key(&self, key: &Key) -> Result<Vec<u8>>;
+
+        async fn disable_key(&self, key: &Key) -> Result<()>;
+
+        /// Schedules the deletion of the key, for backends deleting keys right away the window is
+        /// ignored.
+        async fn schedule_key_deletion(&self, key: &Key, pending_window_days: i32) -> Result<()>;
 }
 
 /// Enum to represent the different backends.
 pub enum Backend {
         Aws(AwsBackend),
         Vault(VaultBackend),
+        Local(LocalBackend),
+}
+
+impl Backend {
+        /// Builds the backend from its name, the local backend keeps its keys in the directory
+        /// of the LOCAL_KEYS_DIR environment variable.
+        pub fn from_name(name: &str) -> Result<Self> {
+                match name {
+                        "aws" => Ok(Backend::Aws(AwsBackend::new())),
+                        "vault" => Ok(Backend::Vault(VaultBackend::new())),
+                        "local" => Ok(Backend::Local(LocalBackend::try_from_env()?)),
+                        _ => Err(anyhow::anyhow!("Unsupported backend: {}", name)),
+                }
+        }
+
+        /// Whether the backend deletes keys right away instead of scheduling their deletion.
+        pub fn deletes_keys_immediately(&self) -> bool {
+                matches!(self, Backend::Vault(_) | Backend::Local(_))
+        }
 }
 
 /// Implement the SigningBackend trait for the Backend enum.
@@ -25,6 +67,59 @@ impl SigningBackend for Backend {
                 match self {
                         Backend::Aws(aws) => aws.rotate_key(key_id).await,
                         Backend::Vault(vault) => vault.rotate_key(key_id).await,
+                        Backend::Local(local) => local.rotate_key(key_id).await,
+                }
+        }
+
+        async fn create_key(&self, key: &Key, curve: KeyCurve) -> Result<ManagedKey> {
+                match self {
+                        Backend::Aws(aws) => aws.create_key(key, curve).await,
+                        Backend::Vault(vault) => vault.create_key(key, curve).await,
+                        Backend::Local(local) => local.create_key(key, curve).await,
+                }
+        }
+
+        async fn list_keys(&self, filter: &KeyFilter) -> Result<Vec<ManagedKey>> {
+                match self {
+                        Backend::Aws(aws) => aws.list_keys(filter).await,
+                        Backend::Vault(vault) => vault.list_keys(filter).await,
+                        Backend::Local(local) => local.list_keys(filter).await,
+                }
+        }
+
+        async fn signer_identifier(&self, key: &Key) -> Result<SignerIdentifier> {
+                match self {
+                        Backend::Aws(aws) => aws.signer_identifier(key).await,
+                        Backend::Vault(vault) => vault.signer_identifier(key).await,
+                        Backend::Local(local) => local.signer_identifier(key).await,
+                }
+        }
+
+        async fn public_key(&self, key: &Key) -> Result<Vec<u8>> {
+                match self {
+                        Backend::Aws(aws) => aws.public_key(key).await,
+                        Backend::Vault(vault) => vault.public_key(key).await,
+                        Backend::Local(local) => local.public_key(key).await,
+                }
+        }
+
+        async fn disable_key(&self, key: &Key) -> Result<()> {
+                match self {
+                        Backend::Aws(aws) => aws.disable_key(key).await,
+                        Backend::Vault(vault) => vault.disable_key(key).await,
+                        Backend::Local(local) => local.disable_key(key).await,
+                }
+        }
+
+        async fn schedule_key_deletion(&self, key: &Key, pending_window_days: i32) -> Result<()> {
+                match self {
+                        Backend::Aws(aws) => aws.schedule_key_deletion(key, pending_window_days).await,
+                        Backend::Vault(vault) => {
+                                vault.schedule_key_deletion(key, pending_window_days).await
+                        }
+                        Backend::Local(local) => {
+                                local.schedule_key_deletion(key, pending_window_days).await
+                        }
                 }
         }
 }
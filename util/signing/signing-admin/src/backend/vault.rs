@@ -1,7 +1,14 @@
 use anyhow::{Context, Result};
+use movement_signer::{cryptography::ed25519::Ed25519, key::Key, Signing};
+use movement_signer_hashicorp_vault::{
+        cryptography::HashiCorpVaultCryptographySpec, hsm::HashiCorpVault,
+};
+use movement_signer_loader::identifiers::{hashi_corp_vault, SignerIdentifier};
+use vaultrs::api::transit::requests::{CreateKeyRequest, UpdateKeyConfigurationRequest};
 use vaultrs::client::{VaultClient, VaultClientSettingsBuilder};
-use vaultrs::transit::key::rotate;
+use vaultrs::transit::key::{self as transit_key, rotate};
 use super::SigningBackend;
+use crate::key::{KeyCurve, KeyFilter, KeyState, ManagedKey};
 
 pub struct VaultBackend;
 
@@ -19,14 +26,120 @@ impl VaultBackend {
                         .context("Failed to build Vault client settings")?;
                 VaultClient::new(settings).context("Failed to create Vault client")
         }
+
+        async fn client_from_env() -> Result<VaultClient> {
+                let vault_url = std::env::var("VAULT_URL").context("Missing VAULT_URL environment variable")?;
+                let token = std::env::var("VAULT_TOKEN").context("Missing VAULT_TOKEN environment variable")?;
+                Self::create_client(&vault_url, &token).await
+        }
+
+        /// The transit mount of the keys, the one of the signer loader.
+        fn mount_name() -> String {
+                std::env::var("VAULT_MOUNT_NAME").unwrap_or_else(|_| "transit".to_string())
+        }
+
+        /// The signer loader names the Vault key after the key, with "-" delimiting its parts.
+        fn key_name(key: &Key) -> String {
+                key.to_delimited_canonical_string("-")
+        }
+
+        fn identifier(key: &Key) -> SignerIdentifier {
+                SignerIdentifier::HashiCorpVault(hashi_corp_vault::HashiCorpVault {
+                        create: false,
+                        key: key.clone(),
+                })
+        }
 }
 
 #[async_trait::async_trait]
 impl SigningBackend for VaultBackend {
         async fn rotate_key(&self, key_id: &str) -> Result<()> {
-                let vault_url = std::env::var("VAULT_URL").context("Missing VAULT_URL environment variable")?;
-                let token = std::env::var("VAULT_TOKEN").context("Missing VAULT_TOKEN environment variable")?;
-                let client = Self::create_client(&vault_url, &token).await?;
+                let client = Self::client_from_env().await?;
                 rotate(&client, "transit", key_id).await.context("Failed to rotate key in Vault")
         }
+
+        async fn create_key(&self, key: &Key, curve: KeyCurve) -> Result<ManagedKey> {
+                if curve != KeyCurve::Ed25519 {
+                        anyhow::bail!("Vault transit signing keys only support the ed25519 curve");
+                }
+                let client = Self::client_from_env().await?;
+
+                transit_key::create(
+                        &client,
+                        &Self::mount_name(),
+                        &Self::key_name(key),
+                        Some(
+                                CreateKeyRequest::builder().key_type(Ed25519::key_type()).derived(false),
+                        ),
+                )
+                .await
+                .context("Failed to create key in Vault")?;
+
+                Ok(ManagedKey {
+                        key: key.clone(),
+                        identifier: Self::identifier(key),
+                        state: KeyState::Enabled,
+                })
+        }
+
+        /// The key name and app replica of a Vault key are split on the last "-", the app replica
+        /// of a listed key must not contain a "-".
+        async fn list_keys(&self, filter: &KeyFilter) -> Result<Vec<ManagedKey>> {
+                let client = Self::client_from_env().await?;
+                let response = transit_key::list(&client, &Self::mount_name())
+                        .await
+                        .context("Failed to list keys in Vault")?;
+
+                let prefix = format!("{}-", filter.to_delimited_canonical_string("-"));
+                let mut keys = Vec::new();
+                for name in response.keys {
+                        let Some((key_name, app_replica)) =
+                                name.strip_prefix(&prefix).and_then(|rest| rest.rsplit_once('-'))
+                        else {
+                                continue;
+                        };
+                        let key = filter.key(key_name.to_string(), Some(app_replica.to_string()));
+                        let identifier = Self::identifier(&key);
+                        keys.push(ManagedKey { key, identifier, state: KeyState::Enabled });
+                }
+                Ok(keys)
+        }
+
+        async fn signer_identifier(&self, key: &Key) -> Result<SignerIdentifier> {
+                Ok(Self::identifier(key))
+        }
+
+        async fn public_key(&self, key: &Key) -> Result<Vec<u8>> {
+                let client = Self::client_from_env().await?;
+                let signer =
+                        HashiCorpVault::<Ed25519>::new(client, Self::key_name(key), Self::mount_name());
+                let public_key =
+                        signer.public_key().await.context("Failed to get public key from Vault")?;
+                Ok(public_key.as_bytes().to_vec())
+        }
+
+        /// Vault transit keys can't be disabled. A compromised key can only be deleted, for good,
+        /// and there is no way back for the operators once it is.
+        async fn disable_key(&self, _key: &Key) -> Result<()> {
+                anyhow::bail!("Vault transit keys cannot be disabled")
+        }
+
+        /// Vault deletes transit keys right away and permanently, the window is ignored.
+        async fn schedule_key_deletion(&self, key: &Key, _pending_window_days: i32) -> Result<()> {
+                let client = Self::client_from_env().await?;
+                let mount_name = Self::mount_name();
+                let key_name = Self::key_name(key);
+
+                transit_key::update(
+                        &client,
+                        &mount_name,
+                        &key_name,
+                        Some(UpdateKeyConfigurationRequest::builder().deletion_allowed(true)),
+                )
+                .await
+                .context("Failed to allow the deletion of the key in Vault")?;
+                transit_key::delete(&client, &mount_name, &key_name)
+                        .await
+                        .context("Failed to delete key in Vault")
+        }
 }
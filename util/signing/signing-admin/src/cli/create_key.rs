@@ -0,0 +1,28 @@
+use anyhow::{Context, Result};
+use movement_signer::key::ToCanonicalString;
+use signing_admin::{
+        backend::{Backend, SigningBackend},
+        key::KeyCurve,
+};
+
+pub async fn create_key(
+        canonical_string: String,
+        curve: KeyCurve,
+        backend_name: String,
+        show_signer_identifier: bool,
+) -> Result<()> {
+        let backend = Backend::from_name(&backend_name)?;
+        let key = super::parse_key(&canonical_string)?;
+
+        let managed_key =
+                backend.create_key(&key, curve).await.context("Failed to create the key")?;
+        let public_key = backend.public_key(&key).await.context("Failed to get the public key")?;
+
+        println!("{}", managed_key.key.to_canonical_string());
+        println!("0x{}", hex::encode(public_key));
+        // The signer identifier to configure the application with.
+        if show_signer_identifier {
+                println!("{}", managed_key.identifier.to_canonical_string());
+        }
+        Ok(())
+}
@@ -0,0 +1,29 @@
+use anyhow::{Context, Result};
+use movement_signer::key::{ToCanonicalString, TryFromCanonicalString};
+use signing_admin::{
+        backend::{Backend, SigningBackend},
+        key::KeyFilter,
+};
+
+pub async fn list_keys(filter: String, backend_name: String) -> Result<()> {
+        let backend = Backend::from_name(&backend_name)?;
+        let filter =
+                KeyFilter::try_from_canonical_string(&filter).map_err(|e| anyhow::anyhow!(e))?;
+
+        let keys = backend.list_keys(&filter).await.context("Failed to list the keys")?;
+
+        for managed_key in keys {
+                // The public key of a disabled key can't be read from every backend.
+                let public_key = match backend.public_key(&managed_key.key).await {
+                        Ok(public_key) => format!("0x{}", hex::encode(public_key)),
+                        Err(_) => "-".to_string(),
+                };
+                println!(
+                        "{}\t{}\t{}",
+                        managed_key.key.to_canonical_string(),
+                        managed_key.state,
+                        public_key
+                );
+        }
+        Ok(())
+}
@@ -1,6 +1,14 @@
+use anyhow::Result;
 use clap::{Parser, Subcommand};
+use movement_signer::key::{Key, TryFromCanonicalString};
+use signing_admin::key::KeyCurve;
 
+pub mod create_key;
+pub mod disable_key;
+pub mod list_keys;
+pub mod public_key;
 pub mod rotate_key;
+pub mod schedule_key_deletion;
 
 #[derive(Parser, Debug)]
 #[clap(name = "signing-admin", about = "CLI for managing signing keys")]
@@ -21,4 +29,75 @@ pub enum Commands {
                 #[clap(long, help = "Backend to use (e.g., 'vault', 'aws')")]
                 backend: String,
         },
+        CreateKey {
+                #[clap(
+                        long,
+                        help = "Canonical string of the key (e.g., '/movement/prod/fullNode/mcr_settlement/signer/validator/0')"
+                )]
+                canonical_string: String,
+
+                #[clap(long, value_enum, help = "Curve of the key")]
+                curve: KeyCurve,
+
+                #[clap(long, help = "Backend to use (e.g., 'vault', 'aws', 'local')")]
+                backend: String,
+
+                #[clap(
+                        long,
+                        help = "Print the signer identifier to configure the application with, the identifier of a local key holds its private key"
+                )]
+                show_signer_identifier: bool,
+        },
+        ListKeys {
+                #[clap(
+                        long,
+                        help = "Org, environment, software unit, usage and roles of the keys (e.g., '/movement/prod/fullNode/mcr_settlement/signer')"
+                )]
+                filter: String,
+
+                #[clap(long, help = "Backend to use (e.g., 'vault', 'aws', 'local')")]
+                backend: String,
+        },
+        PublicKey {
+                #[clap(long, help = "Canonical string of the key, as printed by create-key")]
+                canonical_string: String,
+
+                #[clap(long, help = "Backend to use (e.g., 'vault', 'aws', 'local')")]
+                backend: String,
+        },
+        DisableKey {
+                #[clap(long, help = "Canonical string of the key, as printed by create-key")]
+                canonical_string: String,
+
+                #[clap(
+                        long,
+                        help = "Backend to use (e.g., 'aws', 'local'), Vault keys can't be disabled, only deleted for good"
+                )]
+                backend: String,
+        },
+        ScheduleKeyDeletion {
+                #[clap(long, help = "Canonical string of the key, as printed by create-key")]
+                canonical_string: String,
+
+                #[clap(
+                        long,
+                        default_value_t = 30,
+                        help = "Days before AWS KMS deletes the key (7 to 30)"
+                )]
+                pending_window_days: i32,
+
+                #[clap(
+                        long,
+                        help = "Delete the key right away and permanently, required for Vault and local keys which can't be scheduled for deletion"
+                )]
+                now: bool,
+
+                #[clap(long, help = "Backend to use (e.g., 'vault', 'aws', 'local')")]
+                backend: String,
+        },
+}
+
+/// Parses the canonical string of a key.
+pub fn parse_key(canonical_string: &str) -> Result<Key> {
+        Key::try_from_canonical_string(canonical_string).map_err(|e| anyhow::anyhow!(e))
 }
@@ -0,0 +1,14 @@
+use anyhow::{Context, Result};
+use movement_signer::key::ToCanonicalString;
+use signing_admin::backend::{Backend, SigningBackend};
+
+pub async fn public_key(canonical_string: String, backend_name: String) -> Result<()> {
+        let backend = Backend::from_name(&backend_name)?;
+        let key = super::parse_key(&canonical_string)?;
+
+        let public_key = backend.public_key(&key).await.context("Failed to get the public key")?;
+
+        println!("{}", key.to_canonical_string());
+        println!("0x{}", hex::encode(public_key));
+        Ok(())
+}
@@ -0,0 +1,28 @@
+use anyhow::{Context, Result};
+use movement_signer::key::ToCanonicalString;
+use signing_admin::backend::{Backend, SigningBackend};
+
+pub async fn schedule_key_deletion(
+        canonical_string: String,
+        pending_window_days: i32,
+        now: bool,
+        backend_name: String,
+) -> Result<()> {
+        let backend = Backend::from_name(&backend_name)?;
+        let key = super::parse_key(&canonical_string)?;
+        if backend.deletes_keys_immediately() && !now {
+                anyhow::bail!(
+                        "The {backend_name} backend deletes keys right away and permanently, pass --now to delete the key"
+                );
+        }
+
+        // Read the public key first, the key may be deleted right away.
+        let public_key = backend.public_key(&key).await.context("Failed to get the public key")?;
+        backend.schedule_key_deletion(&key, pending_window_days)
+                .await
+                .context("Failed to schedule the key deletion")?;
+
+        println!("{}", key.to_canonical_string());
+        println!("0x{}", hex::encode(public_key));
+        Ok(())
+}
@@ -0,0 +1,148 @@
+use movement_signer::key::{
+        AllowedRoles, Environment, Key, Organization, SoftwareUnit, ToCanonicalString,
+        TryFromCanonicalString, Usage,
+};
+use movement_signer_loader::identifiers::SignerIdentifier;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The curve of a key created by a backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyCurve {
+        Ed25519,
+        Secp256k1,
+}
+
+/// The keys sharing the org, environment, software unit, usage and roles of a [Key].
+///
+/// Canonical string: "/movement/prod/fullNode/mcr_settlement/signer"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyFilter {
+        pub org: Organization,
+        pub environment: Environment,
+        pub software_unit: SoftwareUnit,
+        pub usage: Usage,
+        pub allowed_roles: AllowedRoles,
+}
+
+impl KeyFilter {
+        pub fn matches(&self, key: &Key) -> bool {
+                self.org == *key.org()
+                        && self.environment == *key.environment()
+                        && self.software_unit == *key.software_unit()
+                        && self.usage == *key.usage()
+                        && self.allowed_roles == *key.allowed_roles()
+        }
+
+        /// Return a delimited canonical string representation of the filter, the prefix of the
+        /// delimited canonical strings of the matching keys.
+        pub fn to_delimited_canonical_string(&self, delimiter: &str) -> String {
+                [
+                        self.org.to_canonical_string(),
+                        self.environment.to_canonical_string(),
+                        self.software_unit.to_canonical_string(),
+                        self.usage.to_canonical_string(),
+                        self.allowed_roles.to_canonical_string(),
+                ]
+                .join(delimiter)
+        }
+
+        /// Builds the key with the parts of the filter.
+        pub fn key(&self, key_name: String, app_replica: Option<String>) -> Key {
+                Key::new(
+                        self.org.clone(),
+                        self.environment.clone(),
+                        self.software_unit.clone(),
+                        self.usage.clone(),
+                        self.allowed_roles.clone(),
+                        key_name,
+                        app_replica,
+                )
+        }
+}
+
+impl From<&Key> for KeyFilter {
+        fn from(key: &Key) -> Self {
+                Self {
+                        org: key.org().clone(),
+                        environment: key.environment().clone(),
+                        software_unit: key.software_unit().clone(),
+                        usage: key.usage().clone(),
+                        allowed_roles: key.allowed_roles().clone(),
+                }
+        }
+}
+
+impl TryFromCanonicalString for KeyFilter {
+        fn try_from_canonical_string(s: &str) -> Result<Self, String> {
+                let parts: Vec<&str> = s.trim_end_matches('/').split('/').collect();
+                if parts.len() != 6 || !parts[0].is_empty() {
+                        return Err(format!(
+                                "invalid key filter, bad number of elements {:?}: '{}'",
+                                parts, s
+                        ));
+                }
+
+                Ok(Self {
+                        org: Organization::try_from_canonical_string(parts[1])?,
+                        environment: Environment::try_from_canonical_string(parts[2])?,
+                        software_unit: SoftwareUnit::try_from_canonical_string(parts[3])?,
+                        usage: Usage::try_from_canonical_string(parts[4])?,
+                        allowed_roles: AllowedRoles::try_from_canonical_string(parts[5])?,
+                })
+        }
+}
+
+/// The state of a key in its backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyState {
+        Enabled,
+        Disabled,
+        PendingDeletion,
+        Other(String),
+}
+
+impl fmt::Display for KeyState {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                        KeyState::Enabled => write!(f, "enabled"),
+                        KeyState::Disabled => write!(f, "disabled"),
+                        KeyState::PendingDeletion => write!(f, "pending_deletion"),
+                        KeyState::Other(state) => write!(f, "{}", state),
+                }
+        }
+}
+
+/// A key held by a backend, with the identifier to load its signer.
+#[derive(Debug, Clone)]
+pub struct ManagedKey {
+        pub key: Key,
+        pub identifier: SignerIdentifier,
+        pub state: KeyState,
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        #[test]
+        fn test_key_filter_matches_keys() -> Result<(), String> {
+                let filter = KeyFilter::try_from_canonical_string(
+                        "/movement/prod/fullNode/mcr_settlement/signer",
+                )?;
+                let key = Key::try_from_canonical_string(
+                        "/movement/prod/fullNode/mcr_settlement/signer/validator/0",
+                )?;
+                assert!(filter.matches(&key));
+                assert_eq!(KeyFilter::from(&key), filter);
+                assert_eq!(filter.key("validator".to_string(), Some("0".to_string())), key);
+
+                let other = Key::try_from_canonical_string(
+                        "/movement/prod/fullNode/mcr_settlement/auditor/validator/0",
+                )?;
+                assert!(!filter.matches(&other));
+                assert!(KeyFilter::try_from_canonical_string("movement/prod/fullNode").is_err());
+                Ok(())
+        }
+}
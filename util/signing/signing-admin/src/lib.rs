@@ -1,4 +1,5 @@
 pub mod application;
 pub mod backend;
+pub mod key;
 pub mod key_manager;
 
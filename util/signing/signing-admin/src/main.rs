@@ -15,6 +15,43 @@ async fn main() -> Result<()> {
                 } => {
                         cli::rotate_key::rotate_key(canonical_string, application_url, backend).await?;
                 }
+                cli::Commands::CreateKey {
+                        canonical_string,
+                        curve,
+                        backend,
+                        show_signer_identifier,
+                } => {
+                        cli::create_key::create_key(
+                                canonical_string,
+                                curve,
+                                backend,
+                                show_signer_identifier,
+                        )
+                        .await?;
+                }
+                cli::Commands::ListKeys { filter, backend } => {
+                        cli::list_keys::list_keys(filter, backend).await?;
+                }
+                cli::Commands::PublicKey { canonical_string, backend } => {
+                        cli::public_key::public_key(canonical_string, backend).await?;
+                }
+                cli::Commands::DisableKey { canonical_string, backend } => {
+                        cli::disable_key::disable_key(canonical_string, backend).await?;
+                }
+                cli::Commands::ScheduleKeyDeletion {
+                        canonical_string,
+                        pending_window_days,
+                        now,
+                        backend,
+                } => {
+                        cli::schedule_key_deletion::schedule_key_deletion(
+                                canonical_string,
+                                pending_window_days,
+                                now,
+                                backend,
+                        )
+                        .await?;
+                }
         }
 
         Ok(())
@@ -1,4 +1,4 @@
-use movement_signer::key::{Key, TryFromCanonicalString};
+use movement_signer::key::{Key, ToCanonicalString, TryFromCanonicalString};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
@@ -7,6 +7,16 @@ pub struct AwsKms {
 	pub key: Key,
 }
 
+impl ToCanonicalString for AwsKms {
+	fn to_canonical_string(&self) -> String {
+		if self.create {
+			format!("create::{}", self.key.to_canonical_string())
+		} else {
+			self.key.to_canonical_string()
+		}
+	}
+}
+
 impl TryFromCanonicalString for AwsKms {
 	fn try_from_canonical_string(s: &str) -> Result<Self, String> {
 		// split on the "::"
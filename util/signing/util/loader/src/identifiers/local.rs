@@ -1,4 +1,4 @@
-use movement_signer::key::TryFromCanonicalString;
+use movement_signer::key::{ToCanonicalString, TryFromCanonicalString};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
@@ -6,6 +6,12 @@ pub struct Local {
 	pub private_key_hex_bytes: String,
 }
 
+impl ToCanonicalString for Local {
+	fn to_canonical_string(&self) -> String {
+		self.private_key_hex_bytes.clone()
+	}
+}
+
 impl TryFromCanonicalString for Local {
 	fn try_from_canonical_string(s: &str) -> Result<Self, String> {
 		Ok(Local { private_key_hex_bytes: s.to_string() })
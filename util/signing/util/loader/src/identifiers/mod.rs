@@ -3,7 +3,10 @@ pub mod hashi_corp_vault;
 pub mod local;
 
 use anyhow::anyhow;
-use movement_signer::{cryptography::Curve, key::TryFromCanonicalString};
+use movement_signer::{
+	cryptography::Curve,
+	key::{ToCanonicalString, TryFromCanonicalString},
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
@@ -34,6 +37,22 @@ impl SignerIdentifier {
 	}
 }
 
+impl ToCanonicalString for SignerIdentifier {
+	/// Returns the canonical string of the identifier, as accepted by
+	/// [SignerIdentifier::try_from_canonical_string].
+	fn to_canonical_string(&self) -> String {
+		match self {
+			SignerIdentifier::Local(local) => format!("local::{}", local.to_canonical_string()),
+			SignerIdentifier::AwsKms(aws_kms) => {
+				format!("aws_kms::{}", aws_kms.to_canonical_string())
+			}
+			SignerIdentifier::HashiCorpVault(hashi_corp_vault) => {
+				format!("hashi_corp_vault::{}", hashi_corp_vault.to_canonical_string())
+			}
+		}
+	}
+}
+
 impl TryFromCanonicalString for SignerIdentifier {
 	fn try_from_canonical_string(s: &str) -> Result<Self, String> {
 		// split on the first "::"
@@ -74,3 +93,22 @@ where
 		Self { signer_identifier, __curve_marker: std::marker::PhantomData }
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_canonical_string_round_trip() -> Result<(), String> {
+		for s in [
+			"local::0123456789abcdef",
+			"aws_kms::/movement/prod/fullNode/mcr_settlement/signer/validator/0",
+			"aws_kms::create::/movement/prod/fullNode/mcr_settlement/signer/validator/0",
+			"hashi_corp_vault::/movement/devnet/fullNode/mcr_settlement/auditor/validator/1",
+		] {
+			let identifier = SignerIdentifier::try_from_canonical_string(s)?;
+			assert_eq!(identifier.to_canonical_string(), s);
+		}
+		Ok(())
+	}
+}